@@ -3,11 +3,19 @@
 
 pub mod proto;
 
+use crypto::HashValue;
 use failure::prelude::*;
 use logger::prelude::*;
 use mempool::MempoolAddTransactionStatus;
 use proto_conv::{FromProto, IntoProto};
-use types::vm_error::VMStatus;
+use std::convert::TryFrom;
+use types::{
+    account_address::AccountAddress,
+    committed_block::{CommitNotification, CommittedBlock},
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::SignedTransaction,
+    vm_error::VMStatus,
+};
 
 /// AC response status of submit_transaction to clients.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -18,6 +26,8 @@ pub enum AdmissionControlStatus {
     Blacklisted(String),
     /// The transaction is rejected, e.g. due to incorrect signature.
     Rejected(String),
+    /// The sender or the client connection submitted too many transactions recently.
+    QuotaExceeded(String),
 }
 
 impl IntoProto for AdmissionControlStatus {
@@ -38,6 +48,10 @@ impl IntoProto for AdmissionControlStatus {
                 admission_control_status.set_message(msg);
                 admission_control_status.set_code(ProtoStatusCode::Rejected)
             }
+            AdmissionControlStatus::QuotaExceeded(msg) => {
+                admission_control_status.set_message(msg);
+                admission_control_status.set_code(ProtoStatusCode::QuotaExceeded)
+            }
         }
         admission_control_status
     }
@@ -58,6 +72,10 @@ impl FromProto for AdmissionControlStatus {
                 let msg = proto_admission_control_status.take_message();
                 AdmissionControlStatus::Rejected(msg)
             }
+            ProtoStatusCode::QuotaExceeded => {
+                let msg = proto_admission_control_status.take_message();
+                AdmissionControlStatus::QuotaExceeded(msg)
+            }
         };
         Ok(ret)
     }
@@ -66,7 +84,8 @@ impl FromProto for AdmissionControlStatus {
 /// Rust structure for SubmitTransactionResponse protobuf definition.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SubmitTransactionResponse {
-    /// AC status returned to client if any - it can be one of: accepted, blacklisted, or rejected.
+    /// AC status returned to client if any - it can be one of: accepted, blacklisted, rejected or
+    /// quota exceeded.
     pub ac_status: Option<AdmissionControlStatus>,
     /// Mempool error status if any.
     pub mempool_error: Option<MempoolAddTransactionStatus>,
@@ -74,6 +93,14 @@ pub struct SubmitTransactionResponse {
     pub vm_error: Option<VMStatus>,
     /// The id of validator associated with this AC.
     pub validator_id: Vec<u8>,
+    /// Sequence number of the sender's next transaction, if the transaction reached mempool.
+    pub account_sequence_number: u64,
+    /// Distance between the transaction's sequence number and the sender's, if the transaction
+    /// reached mempool.
+    pub sequence_number_gap: u64,
+    /// Time (seconds since the epoch) mempool drops the transaction if not included by then, if
+    /// the transaction reached mempool.
+    pub expiration_time: u64,
 }
 
 impl IntoProto for SubmitTransactionResponse {
@@ -91,6 +118,9 @@ impl IntoProto for SubmitTransactionResponse {
             error!("No status is available in SubmitTransactionResponse!");
         }
         proto.set_validator_id(self.validator_id);
+        proto.set_account_sequence_number(self.account_sequence_number);
+        proto.set_sequence_number_gap(self.sequence_number_gap);
+        proto.set_expiration_time(self.expiration_time);
         proto
     }
 }
@@ -122,6 +152,87 @@ impl FromProto for SubmitTransactionResponse {
             mempool_error,
             vm_error,
             validator_id: object.take_validator_id(),
+            account_sequence_number: object.get_account_sequence_number(),
+            sequence_number_gap: object.get_sequence_number_gap(),
+            expiration_time: object.get_expiration_time(),
         })
     }
 }
+
+/// Rust structure for SubscribeToCommittedBlocksResponse protobuf definition.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubscribeToCommittedBlocksResponse {
+    /// The notification about the commits.
+    pub notification: CommitNotification,
+}
+
+impl From<CommitNotification> for SubscribeToCommittedBlocksResponse {
+    fn from(notification: CommitNotification) -> Self {
+        Self { notification }
+    }
+}
+
+impl IntoProto for SubscribeToCommittedBlocksResponse {
+    type ProtoType = crate::proto::admission_control::SubscribeToCommittedBlocksResponse;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        match self.notification {
+            CommitNotification::Block(block) => {
+                let mut committed_block = crate::proto::admission_control::CommittedBlock::new();
+                committed_block.set_block_id(block.block_id.to_vec());
+                committed_block.set_round(block.round);
+                if let Some(proposer) = block.proposer {
+                    committed_block.set_proposer(proposer.to_vec());
+                }
+                committed_block.set_transactions(::protobuf::RepeatedField::from_vec(
+                    block
+                        .transactions
+                        .into_iter()
+                        .map(IntoProto::into_proto)
+                        .collect(),
+                ));
+                committed_block
+                    .set_ledger_info_with_sigs(block.ledger_info_with_sigs.into_proto());
+                proto.set_block(committed_block);
+            }
+            CommitNotification::Gap(ledger_info_with_sigs) => {
+                proto.set_gap(ledger_info_with_sigs.into_proto())
+            }
+        }
+        proto
+    }
+}
+
+impl FromProto for SubscribeToCommittedBlocksResponse {
+    type ProtoType = crate::proto::admission_control::SubscribeToCommittedBlocksResponse;
+
+    fn from_proto(mut object: Self::ProtoType) -> Result<Self> {
+        let notification = if object.has_block() {
+            let mut block = object.take_block();
+            let proposer = if block.get_proposer().is_empty() {
+                None
+            } else {
+                Some(AccountAddress::try_from(block.take_proposer())?)
+            };
+            CommitNotification::Block(CommittedBlock {
+                block_id: HashValue::from_slice(block.get_block_id())?,
+                round: block.get_round(),
+                proposer,
+                transactions: block
+                    .take_transactions()
+                    .into_iter()
+                    .map(SignedTransaction::from_proto)
+                    .collect::<Result<Vec<_>>>()?,
+                ledger_info_with_sigs: LedgerInfoWithSignatures::from_proto(
+                    block.take_ledger_info_with_sigs(),
+                )?,
+            })
+        } else if object.has_gap() {
+            CommitNotification::Gap(LedgerInfoWithSignatures::from_proto(object.take_gap())?)
+        } else {
+            bail!("Commit notification is missing");
+        };
+        Ok(Self { notification })
+    }
+}
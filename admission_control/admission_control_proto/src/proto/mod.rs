@@ -3,7 +3,7 @@
 
 #![allow(bare_trait_objects)]
 
-use mempool::proto::shared::mempool_status;
+use mempool::proto::shared::{mempool_status, transaction_event};
 use types::proto::*;
 
 /// Auto generated proto src files
@@ -1,7 +1,9 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::admission_control_service::AdmissionControlService;
+use crate::{
+    admission_control_service::AdmissionControlService, submission_quota::SubmissionQuotas,
+};
 use admission_control_proto::proto::admission_control_grpc;
 use config::config::NodeConfig;
 use debug_interface::{node_debug_service::NodeDebugService, proto::node_debug_interface_grpc};
@@ -95,7 +97,11 @@ impl AdmissionControlNode {
             self.node_config
                 .admission_control
                 .need_to_check_mempool_before_validation,
-        );
+        )
+        .with_submission_quotas(SubmissionQuotas::from_config(
+            &self.node_config.admission_control,
+        ))
+        .with_max_submit_batch_size(self.node_config.admission_control.max_submit_batch_size);
         let service = admission_control_grpc::create_admission_control(handle);
 
         let _ac_service_handle = spawn_service_thread(
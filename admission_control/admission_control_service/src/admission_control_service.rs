@@ -5,34 +5,63 @@
 //! from external clients (such as wallets) and performs necessary processing before sending them to
 //! next step.
 
-use crate::OP_COUNTERS;
+use crate::{submission_quota::SubmissionQuotas, OP_COUNTERS};
 use admission_control_proto::{
     proto::{
-        admission_control::{SubmitTransactionRequest, SubmitTransactionResponse},
+        admission_control::{
+            GetTransactionStatusRequest, GetTransactionStatusResponse, SubmitTransactionRequest,
+            SubmitTransactionResponse, SubmitTransactionsRequest, SubmitTransactionsResponse,
+            SubscribeToCommittedBlocksRequest,
+            SubscribeToCommittedBlocksResponse as ProtoSubscribeToCommittedBlocksResponse,
+            SubscribeToTransactionEventsRequest, TransactionStatusCode,
+        },
         admission_control_grpc::AdmissionControl,
     },
-    AdmissionControlStatus,
+    AdmissionControlStatus, SubscribeToCommittedBlocksResponse,
 };
+use crypto::{hash::CryptoHash, HashValue};
 use failure::prelude::*;
 use futures::future::Future;
-use futures03::executor::block_on;
-use grpc_helpers::provide_grpc_response;
+use futures03::{compat::Stream01CompatExt, executor::block_on, stream::StreamExt};
+use grpc_helpers::{provide_grpc_response, provide_grpc_stream};
+use grpcio::{RpcStatus, RpcStatusCode};
 use logger::prelude::*;
 use mempool::proto::{
-    mempool::{AddTransactionWithValidationRequest, HealthCheckRequest},
+    mempool::{
+        AddTransactionWithValidationRequest, AddTransactionWithValidationResponse,
+        AddTransactionsWithValidationRequest, GetTransactionStatusRequest as MempoolStatusRequest,
+        HealthCheckRequest, MempoolTransactionState,
+        SubscribeToTransactionEventsRequest as MempoolEventsRequest,
+    },
     mempool_client::MempoolClientTrait,
-    shared::mempool_status::{
-        MempoolAddTransactionStatus,
-        MempoolAddTransactionStatusCode::{self, MempoolIsFull},
+    shared::{
+        mempool_status::{
+            MempoolAddTransactionStatus,
+            MempoolAddTransactionStatusCode::{self, MempoolIsFull},
+        },
+        transaction_event::TransactionEvent,
     },
 };
 use metrics::counters::SVC_COUNTERS;
 use proto_conv::{FromProto, IntoProto};
-use std::sync::Arc;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use storage_client::StorageRead;
 use types::{
-    proto::get_with_proof::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse},
+    account_address::AccountAddress,
+    committed_block::CommittedBlockSource,
+    get_with_proof::{RequestItem, ResponseItem},
+    proto::{
+        get_with_proof::{UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse},
+        transaction::SignedTransaction as ProtoSignedTransaction,
+    },
     transaction::SignedTransaction,
+    vm_error::VMStatus,
 };
 use vm_validator::vm_validator::{get_account_state, TransactionValidation};
 
@@ -40,6 +69,10 @@ use vm_validator::vm_validator::{get_account_state, TransactionValidation};
 #[path = "unit_tests/admission_control_service_test.rs"]
 mod admission_control_service_test;
 
+/// Max number of transactions submitted in a single SubmitTransactions request, unless configured
+/// otherwise.
+pub const DEFAULT_MAX_SUBMIT_BATCH_SIZE: usize = 1000;
+
 /// Struct implementing trait (service handle) AdmissionControlService.
 #[derive(Clone)]
 pub struct AdmissionControlService<M, V> {
@@ -52,12 +85,23 @@ pub struct AdmissionControlService<M, V> {
     /// Flag indicating whether we need to check mempool before validation, drop txn if check
     /// fails.
     need_to_check_mempool_before_validation: bool,
+    /// Source of the committed blocks streamed to the subscribers, None if the node does not run
+    /// consensus.
+    committed_blocks: Option<Arc<dyn CommittedBlockSource>>,
+    /// Limits on the submissions of a single sender or client connection, shared by the clones
+    /// of the service.
+    submission_quotas: Arc<Mutex<SubmissionQuotas>>,
+    /// Max number of transactions submitted in a single SubmitTransactions request.
+    max_submit_batch_size: usize,
+    /// Threads validating the transactions of the SubmitTransactions requests, as many as there
+    /// are CPUs, shared by the clones of the service.
+    validation_pool: Arc<ThreadPool>,
 }
 
 impl<M: 'static, V> AdmissionControlService<M, V>
 where
     M: MempoolClientTrait,
-    V: TransactionValidation,
+    V: TransactionValidation + 'static,
 {
     /// Constructs a new AdmissionControlService instance.
     pub fn new(
@@ -71,49 +115,196 @@ where
             storage_read_client,
             vm_validator,
             need_to_check_mempool_before_validation,
+            committed_blocks: None,
+            submission_quotas: Arc::new(Mutex::new(SubmissionQuotas::default())),
+            max_submit_batch_size: DEFAULT_MAX_SUBMIT_BATCH_SIZE,
+            validation_pool: Arc::new(
+                ThreadPoolBuilder::new()
+                    .thread_name(|index| format!("ac-validation-{}", index))
+                    .build()
+                    .expect("[admission control] failed to create the validation pool"),
+            ),
         }
     }
 
+    /// Sets the max number of transactions submitted in a single SubmitTransactions request.
+    pub fn with_max_submit_batch_size(mut self, max_submit_batch_size: usize) -> Self {
+        self.max_submit_batch_size = max_submit_batch_size;
+        self
+    }
+
+    /// Limits the submissions of a single sender or client connection.
+    pub fn with_submission_quotas(mut self, submission_quotas: SubmissionQuotas) -> Self {
+        self.submission_quotas = Arc::new(Mutex::new(submission_quotas));
+        self
+    }
+
+    /// Also streams the blocks committed by the node's consensus to the subscribers.
+    pub fn with_committed_blocks(
+        mut self,
+        committed_blocks: Arc<dyn CommittedBlockSource>,
+    ) -> Self {
+        self.committed_blocks = Some(committed_blocks);
+        self
+    }
+
     /// Validate transaction signature, then via VM, and add it to Mempool if it passes VM check.
     pub(crate) fn submit_transaction_inner(
         &self,
-        req: SubmitTransactionRequest,
+        mut req: SubmitTransactionRequest,
     ) -> Result<SubmitTransactionResponse> {
         // Drop requests first if mempool is full (validator is lagging behind) so not to consume
         // unnecessary resources.
         if !self.can_send_txn_to_mempool()? {
-            debug!("Mempool is full");
-            OP_COUNTERS.inc_by("submit_txn.rejected.mempool_full", 1);
-            let mut response = SubmitTransactionResponse::new();
-            let mut status = MempoolAddTransactionStatus::new();
-            status.set_code(MempoolIsFull);
-            status.set_message("Mempool is full".to_string());
-            response.set_mempool_status(status);
+            return Ok(Self::mempool_full_response());
+        }
+
+        let signed_txn = match Self::decode_txn(req.get_signed_txn()) {
+            Ok(signed_txn) => signed_txn,
+            Err(response) => return Ok(response),
+        };
+        let validation_status = Self::validate_txn(&self.vm_validator, signed_txn.clone())?;
+        if let Some(response) = self.check_validated_txn(&signed_txn, validation_status) {
             return Ok(response);
         }
+        let account_state = block_on(get_account_state(
+            self.storage_read_client.clone(),
+            signed_txn.sender(),
+        ));
+        let add_transaction_request =
+            Self::add_transaction_request(req.take_signed_txn(), &signed_txn, account_state.ok());
+        self.add_txn_to_mempool(add_transaction_request)
+    }
+
+    /// Same as `submit_transaction_inner` for each transaction of the batch, but validates them
+    /// in parallel and adds them to Mempool at once.
+    pub(crate) fn submit_transactions_inner(
+        &self,
+        mut req: SubmitTransactionsRequest,
+        connection: &str,
+    ) -> Result<SubmitTransactionsResponse> {
+        let signed_txn_protos = req.take_signed_txns().into_vec();
+        ensure!(
+            signed_txn_protos.len() <= self.max_submit_batch_size,
+            "Too many transactions in the batch: {}, max {}",
+            signed_txn_protos.len(),
+            self.max_submit_batch_size
+        );
+        OP_COUNTERS.inc_by("submit_txns.requested", signed_txn_protos.len());
+        if !self.can_send_txn_to_mempool()? {
+            let responses = vec![Some(Self::mempool_full_response()); signed_txn_protos.len()];
+            return Ok(Self::batch_response(responses));
+        }
+        let mut responses = vec![None; signed_txn_protos.len()];
 
-        let signed_txn_proto = req.get_signed_txn();
+        let mut decoded_txns = vec![];
+        for (index, signed_txn_proto) in signed_txn_protos.into_iter().enumerate() {
+            if let Some(response) = self.check_connection_quota(connection) {
+                responses[index] = Some(response);
+                continue;
+            }
+            match Self::decode_txn(&signed_txn_proto) {
+                Ok(signed_txn) => decoded_txns.push((index, signed_txn_proto, signed_txn)),
+                Err(response) => responses[index] = Some(response),
+            }
+        }
 
-        let signed_txn = match SignedTransaction::from_proto(signed_txn_proto.clone()) {
-            Ok(t) => t,
-            Err(e) => {
-                security_log(SecurityEvent::InvalidTransactionAC)
-                    .error(&e)
-                    .data(&signed_txn_proto)
-                    .log();
-                let mut response = SubmitTransactionResponse::new();
-                response.set_ac_status(
-                    AdmissionControlStatus::Rejected("submit txn rejected".to_string())
-                        .into_proto(),
-                );
-                OP_COUNTERS.inc_by("submit_txn.rejected.invalid_txn", 1);
-                return Ok(response);
+        let validation_statuses = self.validate_txns(
+            decoded_txns
+                .iter()
+                .map(|(_, _, signed_txn)| signed_txn.clone())
+                .collect(),
+        );
+        // the transactions of a sender are all checked against the same account state
+        let mut account_states = HashMap::new();
+        let mut add_transaction_requests = vec![];
+        let mut indexes = vec![];
+        for ((index, signed_txn_proto, signed_txn), validation_status) in
+            decoded_txns.into_iter().zip(validation_statuses)
+        {
+            // A transaction that cannot be validated is rejected on its own, the rest of the
+            // batch goes on.
+            let validation_status = match validation_status {
+                Ok(validation_status) => validation_status,
+                Err(_) => {
+                    responses[index] = Some(Self::validation_error_response());
+                    continue;
+                }
+            };
+            if let Some(response) = self.check_validated_txn(&signed_txn, validation_status) {
+                responses[index] = Some(response);
+                continue;
             }
-        };
+            let account_state = *account_states
+                .entry(signed_txn.sender())
+                .or_insert_with(|| {
+                    block_on(get_account_state(
+                        self.storage_read_client.clone(),
+                        signed_txn.sender(),
+                    ))
+                    .ok()
+                });
+            add_transaction_requests.push(Self::add_transaction_request(
+                signed_txn_proto,
+                &signed_txn,
+                account_state,
+            ));
+            indexes.push(index);
+        }
 
-        let gas_cost = signed_txn.max_gas_amount();
-        let validation_status = self
-            .vm_validator
+        if !add_transaction_requests.is_empty() {
+            let mempool_responses = self.add_txns_to_mempool(add_transaction_requests)?;
+            for (index, response) in indexes.into_iter().zip(mempool_responses) {
+                responses[index] = Some(response);
+            }
+        }
+        Ok(Self::batch_response(responses))
+    }
+
+    fn batch_response(
+        responses: Vec<Option<SubmitTransactionResponse>>,
+    ) -> SubmitTransactionsResponse {
+        let mut response = SubmitTransactionsResponse::new();
+        response.set_responses(
+            responses
+                .into_iter()
+                .map(|response| response.expect("[admission control] missing response"))
+                .collect(),
+        );
+        response
+    }
+
+    fn mempool_full_response() -> SubmitTransactionResponse {
+        debug!("Mempool is full");
+        OP_COUNTERS.inc_by("submit_txn.rejected.mempool_full", 1);
+        let mut response = SubmitTransactionResponse::new();
+        let mut status = MempoolAddTransactionStatus::new();
+        status.set_code(MempoolIsFull);
+        status.set_message("Mempool is full".to_string());
+        response.set_mempool_status(status);
+        response
+    }
+
+    /// Decodes the submitted transaction, returns the response to reply with if it is invalid.
+    fn decode_txn(
+        signed_txn_proto: &ProtoSignedTransaction,
+    ) -> std::result::Result<SignedTransaction, SubmitTransactionResponse> {
+        SignedTransaction::from_proto(signed_txn_proto.clone()).map_err(|e| {
+            security_log(SecurityEvent::InvalidTransactionAC)
+                .error(&e)
+                .data(&signed_txn_proto)
+                .log();
+            let mut response = SubmitTransactionResponse::new();
+            response.set_ac_status(
+                AdmissionControlStatus::Rejected("submit txn rejected".to_string()).into_proto(),
+            );
+            OP_COUNTERS.inc_by("submit_txn.rejected.invalid_txn", 1);
+            response
+        })
+    }
+
+    fn validate_txn(vm_validator: &V, signed_txn: SignedTransaction) -> Result<Option<VMStatus>> {
+        vm_validator
             .validate_transaction(signed_txn.clone())
             .wait()
             .map_err(|e| {
@@ -122,7 +313,37 @@ where
                     .data(&signed_txn)
                     .log();
                 e
-            })?;
+            })
+    }
+
+    /// Validates the transactions via VM on the validation pool of the service, so that the
+    /// concurrent requests don't use more threads than there are CPUs.
+    fn validate_txns(&self, signed_txns: Vec<SignedTransaction>) -> Vec<Result<Option<VMStatus>>> {
+        let vm_validator = &self.vm_validator;
+        self.validation_pool.install(|| {
+            signed_txns
+                .into_par_iter()
+                .map(|signed_txn| Self::validate_txn(vm_validator, signed_txn))
+                .collect()
+        })
+    }
+
+    fn validation_error_response() -> SubmitTransactionResponse {
+        OP_COUNTERS.inc_by("submit_txn.rejected.validation_error", 1);
+        let mut response = SubmitTransactionResponse::new();
+        response.set_ac_status(
+            AdmissionControlStatus::Rejected("txn validation failed".to_string()).into_proto(),
+        );
+        response
+    }
+
+    /// Checks the outcome of the VM validation and the quota of the sender, returns the response
+    /// to reply with if the transaction is rejected.
+    fn check_validated_txn(
+        &self,
+        signed_txn: &SignedTransaction,
+        validation_status: Option<VMStatus>,
+    ) -> Option<SubmitTransactionResponse> {
         if let Some(validation_status) = validation_status {
             let mut response = SubmitTransactionResponse::new();
             OP_COUNTERS.inc_by("submit_txn.vm_validation.failure", 1);
@@ -131,20 +352,69 @@ where
                 validation_status, signed_txn
             );
             response.set_vm_status(validation_status.into_proto());
-            return Ok(response);
+            return Some(response);
         }
         let sender = signed_txn.sender();
-        let account_state = block_on(get_account_state(self.storage_read_client.clone(), sender));
+        // Only checked once the signature is verified by the VM, so that one cannot exhaust the
+        // quota of someone else's account.
+        if !self
+            .submission_quotas
+            .lock()
+            .expect("[admission control] failed to acquire submission quotas lock")
+            .try_submit_for_account(sender, Instant::now())
+        {
+            debug!("Submission quota exceeded for account {}", sender);
+            OP_COUNTERS.inc_by("submit_txn.rejected.account_quota", 1);
+            return Some(Self::quota_exceeded_response(format!(
+                "Too many transactions submitted recently by {}",
+                sender
+            )));
+        }
+        None
+    }
+
+    /// Builds the Mempool request for a transaction that passed the checks.
+    fn add_transaction_request(
+        signed_txn_proto: ProtoSignedTransaction,
+        signed_txn: &SignedTransaction,
+        account_state: Option<(u64, u64)>,
+    ) -> AddTransactionWithValidationRequest {
         let mut add_transaction_request = AddTransactionWithValidationRequest::new();
-        add_transaction_request.signed_txn = req.signed_txn.clone();
-        add_transaction_request.set_max_gas_cost(gas_cost);
+        add_transaction_request.set_signed_txn(signed_txn_proto);
+        add_transaction_request.set_max_gas_cost(signed_txn.max_gas_amount());
 
-        if let Ok((sequence_number, balance)) = account_state {
+        if let Some((sequence_number, balance)) = account_state {
             add_transaction_request.set_account_balance(balance);
             add_transaction_request.set_latest_sequence_number(sequence_number);
         }
+        add_transaction_request
+    }
 
-        self.add_txn_to_mempool(add_transaction_request)
+    /// Records a submission through the client connection, returns the response to reply with if
+    /// the connection exceeds its quota.
+    pub(crate) fn check_connection_quota(
+        &self,
+        connection: &str,
+    ) -> Option<SubmitTransactionResponse> {
+        if self
+            .submission_quotas
+            .lock()
+            .expect("[admission control] failed to acquire submission quotas lock")
+            .try_submit_for_connection(connection, Instant::now())
+        {
+            return None;
+        }
+        debug!("Submission quota exceeded for connection {}", connection);
+        OP_COUNTERS.inc_by("submit_txn.rejected.connection_quota", 1);
+        Some(Self::quota_exceeded_response(
+            "Too many transactions submitted recently through this connection".to_string(),
+        ))
+    }
+
+    fn quota_exceeded_response(message: String) -> SubmitTransactionResponse {
+        let mut response = SubmitTransactionResponse::new();
+        response.set_ac_status(AdmissionControlStatus::QuotaExceeded(message).into_proto());
+        response
     }
 
     fn can_send_txn_to_mempool(&self) -> Result<bool> {
@@ -166,27 +436,173 @@ where
     ) -> Result<SubmitTransactionResponse> {
         match &self.mempool_client {
             Some(mempool_client) => {
-                let mut mempool_result =
+                let mempool_result =
                     mempool_client.add_transaction_with_validation(&add_transaction_request)?;
-
                 debug!("[GRPC] Done with transaction submission request");
-                let mut response = SubmitTransactionResponse::new();
-                if mempool_result.get_status().get_code() == MempoolAddTransactionStatusCode::Valid
-                {
-                    OP_COUNTERS.inc_by("submit_txn.txn_accepted", 1);
-                    response.set_ac_status(AdmissionControlStatus::Accepted.into_proto());
-                } else {
-                    debug!(
-                        "txn failed in mempool, status: {:?}, txn: {:?}",
-                        mempool_result,
-                        add_transaction_request.get_signed_txn()
+                Ok(Self::mempool_response(
+                    &add_transaction_request,
+                    mempool_result,
+                ))
+            }
+            None => Err(format_err!("Mempool is not initialized")),
+        }
+    }
+
+    /// Add signed transactions to mempool at once, once they pass vm check
+    fn add_txns_to_mempool(
+        &self,
+        add_transaction_requests: Vec<AddTransactionWithValidationRequest>,
+    ) -> Result<Vec<SubmitTransactionResponse>> {
+        match &self.mempool_client {
+            Some(mempool_client) => {
+                let mut req = AddTransactionsWithValidationRequest::new();
+                req.set_transactions(add_transaction_requests.into());
+                let mempool_results = mempool_client
+                    .add_transactions_with_validation(&req)?
+                    .take_responses();
+                ensure!(
+                    mempool_results.len() == req.get_transactions().len(),
+                    "Mempool returned {} results for {} transactions",
+                    mempool_results.len(),
+                    req.get_transactions().len()
+                );
+                debug!("[GRPC] Done with transactions submission request");
+                Ok(req
+                    .get_transactions()
+                    .iter()
+                    .zip(mempool_results.into_iter())
+                    .map(|(add_transaction_request, mempool_result)| {
+                        Self::mempool_response(add_transaction_request, mempool_result)
+                    })
+                    .collect())
+            }
+            None => Err(format_err!("Mempool is not initialized")),
+        }
+    }
+
+    fn mempool_response(
+        add_transaction_request: &AddTransactionWithValidationRequest,
+        mut mempool_result: AddTransactionWithValidationResponse,
+    ) -> SubmitTransactionResponse {
+        let mut response = SubmitTransactionResponse::new();
+        response.set_account_sequence_number(mempool_result.get_account_sequence_number());
+        response.set_sequence_number_gap(mempool_result.get_sequence_number_gap());
+        response.set_expiration_time(mempool_result.get_expiration_time());
+        if mempool_result.get_status().get_code() == MempoolAddTransactionStatusCode::Valid {
+            OP_COUNTERS.inc_by("submit_txn.txn_accepted", 1);
+            response.set_ac_status(AdmissionControlStatus::Accepted.into_proto());
+        } else {
+            debug!(
+                "txn failed in mempool, status: {:?}, txn: {:?}",
+                mempool_result,
+                add_transaction_request.get_signed_txn()
+            );
+            OP_COUNTERS.inc_by("submit_txn.mempool.failure", 1);
+            response.set_mempool_status(mempool_result.take_status());
+        }
+        response
+    }
+
+    /// Look the transaction up in Mempool (if any), then check in Storage whether it is committed.
+    fn get_transaction_status_inner(
+        &self,
+        req: GetTransactionStatusRequest,
+    ) -> Result<GetTransactionStatusResponse> {
+        let mut response = GetTransactionStatusResponse::new();
+        let hash = if req.get_hash().is_empty() {
+            None
+        } else {
+            Some(HashValue::from_slice(req.get_hash())?)
+        };
+        let mempool_status = match &self.mempool_client {
+            Some(mempool_client) => {
+                let mut mempool_req = MempoolStatusRequest::new();
+                mempool_req.set_sender(req.get_sender().to_vec());
+                mempool_req.set_sequence_number(req.get_sequence_number());
+                mempool_req.set_hash(req.get_hash().to_vec());
+                Some(mempool_client.get_transaction_status(&mempool_req)?)
+            }
+            None => None,
+        };
+        let (sender, sequence_number) = match (hash, &mempool_status) {
+            (None, _) => (
+                AccountAddress::try_from(req.get_sender())?,
+                req.get_sequence_number(),
+            ),
+            (Some(_), Some(status)) if status.get_state() != MempoolTransactionState::Unknown => (
+                AccountAddress::try_from(status.get_sender())?,
+                status.get_sequence_number(),
+            ),
+            // Storage can't look the transactions up by hash.
+            (Some(_), _) => return Ok(response),
+        };
+        response.set_sender(sender.into());
+        response.set_sequence_number(sequence_number);
+
+        let (mut response_items, ledger_info_with_sigs, _) =
+            self.storage_read_client.update_to_latest_ledger(
+                0,
+                vec![RequestItem::GetAccountTransactionBySequenceNumber {
+                    account: sender,
+                    sequence_number,
+                    fetch_events: false,
+                }],
+            )?;
+        response.set_ledger_version(ledger_info_with_sigs.ledger_info().version());
+        let committed = match response_items.pop() {
+            Some(ResponseItem::GetAccountTransactionBySequenceNumber {
+                signed_transaction_with_proof,
+                ..
+            }) => signed_transaction_with_proof,
+            _ => bail!("Unexpected response from storage"),
+        };
+        if let Some(committed) = committed {
+            let committed_hash = committed.signed_transaction.hash();
+            match hash {
+                Some(hash) if hash != committed_hash => {
+                    response.set_code(TransactionStatusCode::Discarded);
+                    response.set_hash(hash.to_vec());
+                    response.set_message(
+                        "Another transaction with the same sequence number is committed"
+                            .to_string(),
                     );
-                    OP_COUNTERS.inc_by("submit_txn.mempool.failure", 1);
-                    response.set_mempool_status(mempool_result.take_status());
                 }
-                Ok(response)
+                _ => {
+                    response.set_code(TransactionStatusCode::Committed);
+                    response.set_hash(committed_hash.to_vec());
+                    response.set_version(committed.version);
+                    // Storage doesn't keep the VM status of the committed transactions.
+                    response.set_gas_used(committed.proof.transaction_info().gas_used());
+                }
             }
-            None => Err(format_err!("Mempool is not initialized")),
+            return Ok(response);
+        }
+
+        if let Some(mut mempool_status) = mempool_status {
+            let code = match mempool_status.get_state() {
+                MempoolTransactionState::Ready => TransactionStatusCode::Ready,
+                MempoolTransactionState::Parked => TransactionStatusCode::Parked,
+                MempoolTransactionState::Expiring => TransactionStatusCode::Expiring,
+                MempoolTransactionState::Discarded => TransactionStatusCode::Discarded,
+                // Not committed as far as Storage knows.
+                MempoolTransactionState::Committed | MempoolTransactionState::Unknown => {
+                    return Ok(response);
+                }
+            };
+            response.set_code(code);
+            response.set_hash(mempool_status.take_hash());
+            response.set_expiration_time(mempool_status.get_expiration_time());
+            response.set_message(mempool_status.take_message());
+        }
+        Ok(response)
+    }
+
+    /// Status of a failed Mempool call, forwarded to the client as is if Mempool replied with one,
+    /// e.g. RESOURCE_EXHAUSTED for a subscriber that does not keep up with the events.
+    fn mempool_error_status(e: grpcio::Error) -> RpcStatus {
+        match e {
+            grpcio::Error::RpcFailure(status) => status,
+            e => RpcStatus::new(RpcStatusCode::Internal, Some(e.to_string())),
         }
     }
 
@@ -211,7 +627,7 @@ where
 impl<M: 'static, V> AdmissionControl for AdmissionControlService<M, V>
 where
     M: MempoolClientTrait,
-    V: TransactionValidation,
+    V: TransactionValidation + 'static,
 {
     /// Submit a transaction to the validator this AC instance connecting to.
     /// The specific transaction will be first validated by VM and then passed
@@ -226,7 +642,26 @@ where
         let _timer = SVC_COUNTERS.req(&ctx);
         let resp = match self.mempool_client {
             None => Err(format_err!("Node doesn't accept write requests")),
-            Some(_) => self.submit_transaction_inner(req),
+            Some(_) => match self.check_connection_quota(&ctx.peer()) {
+                Some(response) => Ok(response),
+                None => self.submit_transaction_inner(req),
+            },
+        };
+        provide_grpc_response(resp, ctx, sink);
+    }
+
+    /// Submit multiple transactions at once, each of them processed as by `submit_transaction`.
+    fn submit_transactions(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: SubmitTransactionsRequest,
+        sink: ::grpcio::UnarySink<SubmitTransactionsResponse>,
+    ) {
+        debug!("[GRPC] AdmissionControl::submit_transactions");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = match self.mempool_client {
+            None => Err(format_err!("Node doesn't accept write requests")),
+            Some(_) => self.submit_transactions_inner(req, &ctx.peer()),
         };
         provide_grpc_response(resp, ctx, sink);
     }
@@ -248,4 +683,100 @@ where
         let resp = self.update_to_latest_ledger_inner(req);
         provide_grpc_response(resp, ctx, sink);
     }
+
+    /// Reports whether the transaction is pending in Mempool, committed or discarded.
+    fn get_transaction_status(
+        &mut self,
+        ctx: grpcio::RpcContext<'_>,
+        req: GetTransactionStatusRequest,
+        sink: grpcio::UnarySink<GetTransactionStatusResponse>,
+    ) {
+        debug!("[GRPC] AdmissionControl::get_transaction_status");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let resp = self.get_transaction_status_inner(req);
+        provide_grpc_response(resp, ctx, sink);
+    }
+
+    /// Streams the commit notifications from now on to the client. The subscription fails with
+    /// RESOURCE_EXHAUSTED in case the client does not keep up with the commits.
+    fn subscribe_to_committed_blocks(
+        &mut self,
+        ctx: grpcio::RpcContext<'_>,
+        _req: SubscribeToCommittedBlocksRequest,
+        sink: grpcio::ServerStreamingSink<ProtoSubscribeToCommittedBlocksResponse>,
+    ) {
+        debug!("[GRPC] AdmissionControl::subscribe_to_committed_blocks");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        match &self.committed_blocks {
+            Some(committed_blocks) => {
+                OP_COUNTERS.inc_by("subscribe_to_committed_blocks.subscribed", 1);
+                let notifications =
+                    committed_blocks
+                        .subscribe()
+                        .map(|notification| match notification {
+                            Ok(notification) => {
+                                Ok(SubscribeToCommittedBlocksResponse::from(notification)
+                                    .into_proto())
+                            }
+                            Err(lagged) => {
+                                OP_COUNTERS.inc_by("subscribe_to_committed_blocks.lagged", 1);
+                                Err(RpcStatus::new(
+                                    RpcStatusCode::ResourceExhausted,
+                                    Some(lagged.to_string()),
+                                ))
+                            }
+                        });
+                provide_grpc_stream(&ctx, sink, notifications);
+            }
+            None => {
+                let status = RpcStatus::new(
+                    RpcStatusCode::Unimplemented,
+                    Some("Node doesn't run consensus".to_string()),
+                );
+                ctx.spawn(sink.fail(status).map_err(|e| {
+                    error!("Failed to reply to the subscription: {:?}", e);
+                }));
+            }
+        }
+    }
+
+    /// Streams the lifecycle events of the transactions in Mempool from now on to the client. The
+    /// subscription fails with RESOURCE_EXHAUSTED in case the client does not keep up with the
+    /// events, or Mempool has too many subscribers already.
+    fn subscribe_to_transaction_events(
+        &mut self,
+        ctx: grpcio::RpcContext<'_>,
+        req: SubscribeToTransactionEventsRequest,
+        sink: grpcio::ServerStreamingSink<TransactionEvent>,
+    ) {
+        debug!("[GRPC] AdmissionControl::subscribe_to_transaction_events");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let events = match &self.mempool_client {
+            Some(mempool_client) => {
+                let mut mempool_req = MempoolEventsRequest::new();
+                mempool_req.set_sender(req.get_sender().to_vec());
+                mempool_client
+                    .subscribe_to_transaction_events(&mempool_req)
+                    .map_err(Self::mempool_error_status)
+            }
+            None => Err(RpcStatus::new(
+                RpcStatusCode::Unimplemented,
+                Some("Node doesn't run mempool".to_string()),
+            )),
+        };
+        match events {
+            Ok(events) => {
+                OP_COUNTERS.inc_by("subscribe_to_transaction_events.subscribed", 1);
+                let events = events
+                    .compat()
+                    .map(|event| event.map_err(Self::mempool_error_status));
+                provide_grpc_stream(&ctx, sink, events);
+            }
+            Err(status) => {
+                ctx.spawn(sink.fail(status).map_err(|e| {
+                    error!("Failed to reply to the subscription: {:?}", e);
+                }));
+            }
+        }
+    }
 }
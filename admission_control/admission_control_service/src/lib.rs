@@ -14,6 +14,8 @@
 pub mod admission_control_node;
 /// AC gRPC service.
 pub mod admission_control_service;
+/// Limits on the submissions of a single sender or client connection.
+pub mod submission_quota;
 use lazy_static::lazy_static;
 use metrics::OpMetrics;
 
@@ -0,0 +1,116 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Limits on the number of transactions a single sender, or a single client connection, can
+//! submit within a sliding window, so that one client cannot monopolize a public AC.
+
+use config::config::AdmissionControlConfig;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+use types::account_address::AccountAddress;
+
+/// Counts the submissions of each key within the last `window`
+struct SlidingWindowCounter<K> {
+    // max number of submissions within the window, 0 for no limit
+    limit: usize,
+    window: Duration,
+    // times of the submissions within the window, oldest first
+    submissions: HashMap<K, VecDeque<Instant>>,
+    last_cleanup: Instant,
+}
+
+impl<K: Eq + Hash> SlidingWindowCounter<K> {
+    fn new(limit: usize, window: Duration, now: Instant) -> Self {
+        Self {
+            limit,
+            window,
+            submissions: HashMap::new(),
+            last_cleanup: now,
+        }
+    }
+
+    /// Records a submission of the key unless it reached the limit
+    fn try_record(&mut self, key: K, now: Instant) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        self.cleanup(now);
+        let window = self.window;
+        let submissions = self.submissions.entry(key).or_insert_with(VecDeque::new);
+        while submissions
+            .front()
+            .map_or(false, |time| *time + window <= now)
+        {
+            submissions.pop_front();
+        }
+        if submissions.len() >= self.limit {
+            return false;
+        }
+        submissions.push_back(now);
+        true
+    }
+
+    /// Forgets the keys without submissions in the window, once per window
+    fn cleanup(&mut self, now: Instant) {
+        if now < self.last_cleanup + self.window {
+            return;
+        }
+        let window = self.window;
+        self.submissions.retain(|_, submissions| {
+            submissions
+                .back()
+                .map_or(false, |time| now < *time + window)
+        });
+        self.last_cleanup = now;
+    }
+}
+
+/// Per sender and per client connection submission quotas
+pub struct SubmissionQuotas {
+    per_account: SlidingWindowCounter<AccountAddress>,
+    per_connection: SlidingWindowCounter<String>,
+}
+
+impl SubmissionQuotas {
+    /// Quotas of at most `max_per_account` transactions per sender and `max_per_connection`
+    /// transactions per client connection within `window`, 0 meaning no limit.
+    pub fn new(max_per_account: usize, max_per_connection: usize, window: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            per_account: SlidingWindowCounter::new(max_per_account, window, now),
+            per_connection: SlidingWindowCounter::new(max_per_connection, window, now),
+        }
+    }
+
+    /// Quotas configured for the AC.
+    pub fn from_config(config: &AdmissionControlConfig) -> Self {
+        Self::new(
+            config.max_submissions_per_account,
+            config.max_submissions_per_connection,
+            Duration::from_millis(config.submission_quota_window_ms),
+        )
+    }
+
+    /// Records a submission of the sender, returns false if it exceeds its quota.
+    pub fn try_submit_for_account(&mut self, sender: AccountAddress, now: Instant) -> bool {
+        self.per_account.try_record(sender, now)
+    }
+
+    /// Records a submission through the client connection (gRPC peer), returns false if it
+    /// exceeds its quota.
+    pub fn try_submit_for_connection(&mut self, connection: &str, now: Instant) -> bool {
+        if self.per_connection.limit == 0 {
+            return true;
+        }
+        self.per_connection.try_record(connection.to_string(), now)
+    }
+}
+
+impl Default for SubmissionQuotas {
+    fn default() -> Self {
+        Self::new(0, 0, Duration::from_secs(0))
+    }
+}
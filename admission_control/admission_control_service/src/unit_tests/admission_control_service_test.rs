@@ -4,24 +4,31 @@
 use crate::{
     admission_control_service::{
         AdmissionControlService, SubmitTransactionRequest,
-        SubmitTransactionResponse as ProtoSubmitTransactionResponse,
+        SubmitTransactionResponse as ProtoSubmitTransactionResponse, SubmitTransactionsRequest,
     },
+    submission_quota::SubmissionQuotas,
     unit_tests::LocalMockMempool,
 };
-use admission_control_proto::{AdmissionControlStatus, SubmitTransactionResponse};
+use admission_control_proto::{
+    proto::admission_control::{GetTransactionStatusRequest, TransactionStatusCode},
+    AdmissionControlStatus, SubmitTransactionResponse,
+};
 
 use assert_matches::assert_matches;
-use crypto::{ed25519::*, hash::CryptoHash, test_utils::TEST_SEED, SigningKey};
+use crypto::{ed25519::*, hash::CryptoHash, test_utils::TEST_SEED, HashValue, SigningKey};
 use mempool::proto::shared::mempool_status::MempoolAddTransactionStatusCode;
 use proto_conv::FromProto;
 use protobuf::{Message, UnknownFields};
 use rand::SeedableRng;
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use storage_service::mocks::mock_storage_client::MockStorageReadClient;
 use types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     test_helpers::transaction_test_helpers::get_test_signed_txn,
-    transaction::RawTransactionBytes,
+    transaction::{RawTransactionBytes, SignedTransaction},
     vm_error::{ExecutionStatus, VMStatus, VMValidationStatus},
 };
 use vm_validator::mocks::mock_vm_validator::MockVMValidator;
@@ -277,3 +284,217 @@ fn test_submit_txn_inner_mempool() {
         AdmissionControlStatus::Accepted,
     );
 }
+
+#[test]
+fn test_submit_txn_sequence_number_gap() {
+    let ac_service = create_ac_service_for_ut();
+    let keypair = compat::generate_keypair(None);
+    let signed_txn = get_test_signed_txn(
+        AccountAddress::new([103; ADDRESS_LENGTH]),
+        3,
+        keypair.0,
+        keypair.1,
+        None,
+    );
+    let expiration_time = SignedTransaction::from_proto(signed_txn.clone())
+        .unwrap()
+        .expiration_time();
+    let mut req = SubmitTransactionRequest::new();
+    req.set_signed_txn(signed_txn);
+    let response =
+        SubmitTransactionResponse::from_proto(ac_service.submit_transaction_inner(req).unwrap())
+            .unwrap();
+    assert_eq!(
+        response.ac_status.unwrap(),
+        AdmissionControlStatus::Accepted,
+    );
+    assert_eq!(response.account_sequence_number, 0);
+    assert_eq!(response.sequence_number_gap, 3);
+    assert_eq!(response.expiration_time, expiration_time.as_secs());
+}
+
+#[test]
+fn test_get_transaction_status() {
+    let ac_service = create_ac_service_for_ut();
+    let sender = AccountAddress::new([0; ADDRESS_LENGTH]);
+    let status = |sequence_number| {
+        let mut req = GetTransactionStatusRequest::new();
+        req.set_sender(sender.into());
+        req.set_sequence_number(sequence_number);
+        ac_service.get_transaction_status_inner(req).unwrap()
+    };
+
+    // The first transaction is committed in storage.
+    let response = status(0);
+    assert_eq!(response.get_code(), TransactionStatusCode::Committed);
+    assert_eq!(response.get_version(), 0);
+    assert_eq!(response.get_ledger_version(), 7);
+    // The second one is in mempool.
+    let response = status(1);
+    assert_eq!(response.get_code(), TransactionStatusCode::Ready);
+    assert_eq!(response.get_sequence_number(), 1);
+    assert_eq!(status(2).get_code(), TransactionStatusCode::Unknown);
+
+    // Storage can't look the transactions up by hash.
+    let mut req = GetTransactionStatusRequest::new();
+    req.set_hash(HashValue::random().to_vec());
+    let response = ac_service.get_transaction_status_inner(req).unwrap();
+    assert_eq!(response.get_code(), TransactionStatusCode::Unknown);
+}
+
+#[test]
+fn test_account_submission_quota() {
+    let ac_service = create_ac_service_for_ut().with_submission_quotas(SubmissionQuotas::new(
+        2,
+        0,
+        Duration::from_secs(3600),
+    ));
+    let keypair = compat::generate_keypair(None);
+    let submit = |sender, sequence_number| {
+        let mut req = SubmitTransactionRequest::new();
+        req.set_signed_txn(get_test_signed_txn(
+            sender,
+            sequence_number,
+            keypair.0.clone(),
+            keypair.1.clone(),
+            None,
+        ));
+        SubmitTransactionResponse::from_proto(ac_service.submit_transaction_inner(req).unwrap())
+            .unwrap()
+            .ac_status
+    };
+    let sender = AccountAddress::new([103; ADDRESS_LENGTH]);
+    assert_eq!(submit(sender, 0), Some(AdmissionControlStatus::Accepted));
+    assert_eq!(submit(sender, 1), Some(AdmissionControlStatus::Accepted));
+    assert_matches!(
+        submit(sender, 2),
+        Some(AdmissionControlStatus::QuotaExceeded(_))
+    );
+
+    // the transactions rejected by the VM don't count
+    let unknown_sender = AccountAddress::new([0; ADDRESS_LENGTH]);
+    for sequence_number in 0..3 {
+        assert_eq!(submit(unknown_sender, sequence_number), None);
+    }
+}
+
+#[test]
+fn test_connection_submission_quota() {
+    let ac_service = create_ac_service_for_ut().with_submission_quotas(SubmissionQuotas::new(
+        0,
+        2,
+        Duration::from_secs(3600),
+    ));
+    let connection = "ipv4:127.0.0.1:50000";
+    assert!(ac_service.check_connection_quota(connection).is_none());
+    assert!(ac_service.check_connection_quota(connection).is_none());
+    let response = SubmitTransactionResponse::from_proto(
+        ac_service.check_connection_quota(connection).unwrap(),
+    )
+    .unwrap();
+    assert_matches!(
+        response.ac_status.unwrap(),
+        AdmissionControlStatus::QuotaExceeded(_)
+    );
+    assert!(ac_service
+        .check_connection_quota("ipv4:127.0.0.1:50001")
+        .is_none());
+
+    // clones of the service share the quotas
+    assert!(ac_service
+        .clone()
+        .check_connection_quota(connection)
+        .is_some());
+}
+
+#[test]
+fn test_submission_quota_window() {
+    let window = Duration::from_secs(10);
+    let mut quotas = SubmissionQuotas::new(2, 0, window);
+    let sender = AccountAddress::random();
+    let start = Instant::now();
+    assert!(quotas.try_submit_for_account(sender, start));
+    assert!(quotas.try_submit_for_account(sender, start + Duration::from_secs(5)));
+    assert!(!quotas.try_submit_for_account(sender, start + Duration::from_secs(9)));
+    // the first submission leaves the window
+    assert!(quotas.try_submit_for_account(sender, start + window));
+    assert!(!quotas.try_submit_for_account(sender, start + window));
+    // no limit on the connections
+    for _ in 0..10 {
+        assert!(quotas.try_submit_for_connection("ipv4:127.0.0.1:50000", start));
+    }
+}
+
+#[test]
+fn test_submit_txns_inner() {
+    let ac_service = create_ac_service_for_ut()
+        .with_submission_quotas(SubmissionQuotas::new(2, 0, Duration::from_secs(3600)))
+        .with_max_submit_batch_size(10);
+    let keypair = compat::generate_keypair(None);
+    let signed_txn = |sender, sequence_number| {
+        get_test_signed_txn(
+            AccountAddress::new([sender; ADDRESS_LENGTH]),
+            sequence_number,
+            keypair.0.clone(),
+            keypair.1.clone(),
+            None,
+        )
+    };
+    let mut invalid_txn = signed_txn(103, 0);
+    invalid_txn.set_sender_signature(vec![]);
+
+    let mut req = SubmitTransactionsRequest::new();
+    for txn in vec![
+        signed_txn(103, 0),
+        signed_txn(0, 0),
+        invalid_txn,
+        signed_txn(100, 0),
+        signed_txn(103, 1),
+        signed_txn(103, 2),
+    ] {
+        req.mut_signed_txns().push(txn);
+    }
+    let responses: Vec<_> = ac_service
+        .submit_transactions_inner(req.clone(), "ipv4:127.0.0.1:50000")
+        .unwrap()
+        .take_responses()
+        .into_iter()
+        .map(|response| SubmitTransactionResponse::from_proto(response).unwrap())
+        .collect();
+    assert_eq!(responses.len(), 6);
+    assert_eq!(
+        responses[0].ac_status,
+        Some(AdmissionControlStatus::Accepted)
+    );
+    assert_eq!(
+        responses[1].vm_error,
+        Some(VMStatus::Validation(
+            VMValidationStatus::SendingAccountDoesNotExist("TEST".to_string())
+        ))
+    );
+    assert_matches!(
+        responses[2].ac_status,
+        Some(AdmissionControlStatus::Rejected(_))
+    );
+    assert_eq!(
+        responses[3].mempool_error.as_ref().unwrap().code,
+        MempoolAddTransactionStatusCode::InsufficientBalance
+    );
+    assert_eq!(
+        responses[4].ac_status,
+        Some(AdmissionControlStatus::Accepted)
+    );
+    assert_matches!(
+        responses[5].ac_status,
+        Some(AdmissionControlStatus::QuotaExceeded(_))
+    );
+
+    // too many transactions
+    for sequence_number in 0..5 {
+        req.mut_signed_txns()
+            .push(signed_txn(103, sequence_number + 3));
+    }
+    assert!(ac_service
+        .submit_transactions_inner(req, "ipv4:127.0.0.1:50000")
+        .is_err());
+}
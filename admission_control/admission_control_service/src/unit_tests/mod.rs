@@ -1,10 +1,13 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crypto::HashValue;
 use mempool::proto::{
     mempool::{
         AddTransactionWithValidationRequest, AddTransactionWithValidationResponse,
-        HealthCheckRequest, HealthCheckResponse,
+        AddTransactionsWithValidationRequest, AddTransactionsWithValidationResponse,
+        GetTransactionStatusRequest, GetTransactionStatusResponse, HealthCheckRequest,
+        HealthCheckResponse, MempoolTransactionState,
     },
     mempool_client::MempoolClientTrait,
     shared::mempool_status::{MempoolAddTransactionStatus, MempoolAddTransactionStatusCode},
@@ -53,6 +56,24 @@ impl MempoolClientTrait for LocalMockMempool {
             status.set_code(MempoolAddTransactionStatusCode::MempoolIsFull);
         }
         resp.set_status(status);
+        resp.set_account_sequence_number(req.get_latest_sequence_number());
+        resp.set_sequence_number_gap(
+            signed_txn
+                .sequence_number()
+                .saturating_sub(req.get_latest_sequence_number()),
+        );
+        resp.set_expiration_time(signed_txn.expiration_time().as_secs());
+        Ok(resp)
+    }
+    fn add_transactions_with_validation(
+        &self,
+        req: &AddTransactionsWithValidationRequest,
+    ) -> ::grpcio::Result<AddTransactionsWithValidationResponse> {
+        let mut resp = AddTransactionsWithValidationResponse::new();
+        for txn_req in req.get_transactions() {
+            resp.mut_responses()
+                .push(self.add_transaction_with_validation(txn_req)?);
+        }
         Ok(resp)
     }
     fn health_check(&self, _req: &HealthCheckRequest) -> ::grpcio::Result<HealthCheckResponse> {
@@ -64,4 +85,18 @@ impl MempoolClientTrait for LocalMockMempool {
         ret.set_is_healthy(duration_ms > 500 || duration_ms < 300);
         Ok(ret)
     }
+    // Mempool only holds the transactions of sequence number 1, ready to be included in a block.
+    fn get_transaction_status(
+        &self,
+        req: &GetTransactionStatusRequest,
+    ) -> ::grpcio::Result<GetTransactionStatusResponse> {
+        let mut ret = GetTransactionStatusResponse::new();
+        if req.get_hash().is_empty() && req.get_sequence_number() == 1 {
+            ret.set_state(MempoolTransactionState::Ready);
+            ret.set_sender(req.get_sender().to_vec());
+            ret.set_sequence_number(1);
+            ret.set_hash(HashValue::zero().to_vec());
+        }
+        Ok(ret)
+    }
 }
@@ -17,6 +17,8 @@ use std::{
     time::{Duration, Instant},
 };
 
+pub mod publisher;
+
 #[cfg(test)]
 mod test;
 
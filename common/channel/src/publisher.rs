@@ -0,0 +1,103 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Publishes messages to any number of subscribers over bounded channels, without ever waiting
+//! for them: a subscriber that lets its channel fill up is disconnected.
+
+use failure::prelude::*;
+use futures::channel::mpsc;
+use logger::prelude::*;
+
+#[cfg(test)]
+#[path = "publisher_test.rs"]
+mod publisher_test;
+
+/// Max number of the messages waiting to be consumed by a subscriber: the subscribers lagging
+/// behind further are disconnected so that they never slow down the publisher.
+pub const SUBSCRIBER_CHANNEL_SIZE: usize = 1_024;
+
+/// The subscriber did not keep up with the published messages and was disconnected.
+#[derive(Clone, Copy, Debug, Eq, Fail, PartialEq)]
+#[fail(display = "Subscriber lags behind by {} messages", _0)]
+pub struct SubscriberLagged(pub usize);
+
+/// What the subscribers receive: the published messages, ended by an error in case the
+/// subscriber is disconnected for lagging behind.
+pub type Subscription<T> = mpsc::Receiver<std::result::Result<T, SubscriberLagged>>;
+
+struct Subscriber<T> {
+    messages: mpsc::Sender<std::result::Result<T, SubscriberLagged>>,
+    // Never used before the subscriber lags behind: a sender always has room for one message of
+    // its own, so the disconnection is reported even though the channel is full.
+    lagged: mpsc::Sender<std::result::Result<T, SubscriberLagged>>,
+}
+
+/// Sends each published message to all the subscribers. Not synchronized, the owner wraps it in
+/// a lock if it is shared.
+pub struct Publisher<T> {
+    subscribers: Vec<Subscriber<T>>,
+}
+
+impl<T> Default for Publisher<T> {
+    fn default() -> Self {
+        Self {
+            subscribers: vec![],
+        }
+    }
+}
+
+impl<T: Clone> Publisher<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the stream of the messages published from now on.
+    pub fn subscribe(&mut self) -> Subscription<T> {
+        let (messages, receiver) = mpsc::channel(SUBSCRIBER_CHANNEL_SIZE);
+        let lagged = messages.clone();
+        self.subscribers.push(Subscriber { messages, lagged });
+        receiver
+    }
+
+    /// Sends the message to the subscribers and drops the ones that are gone or lag behind.
+    /// Returns the number of subscribers disconnected for lagging behind.
+    pub fn publish(&mut self, message: &T) -> usize {
+        let mut num_lagged = 0;
+        let mut active_subscribers = Vec::with_capacity(self.subscribers.len());
+        for mut subscriber in self.subscribers.drain(..) {
+            match subscriber.messages.try_send(Ok(message.clone())) {
+                Ok(()) => active_subscribers.push(subscriber),
+                Err(e) if e.is_full() => {
+                    warn!(
+                        "Subscriber lags behind by {} messages, disconnecting it",
+                        SUBSCRIBER_CHANNEL_SIZE
+                    );
+                    num_lagged += 1;
+                    let _ = subscriber
+                        .lagged
+                        .try_send(Err(SubscriberLagged(SUBSCRIBER_CHANNEL_SIZE)));
+                }
+                // The subscriber is gone.
+                Err(_) => (),
+            }
+        }
+        self.subscribers = active_subscribers;
+        num_lagged
+    }
+}
+
+impl<T> Publisher<T> {
+    /// Drops the subscribers that are gone, even if nothing was published to them since.
+    pub fn prune(&mut self) {
+        self.subscribers
+            .retain(|subscriber| !subscriber.messages.is_closed());
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+}
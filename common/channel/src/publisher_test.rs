@@ -0,0 +1,55 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::publisher::{Publisher, SubscriberLagged, SUBSCRIBER_CHANNEL_SIZE};
+use futures::{executor::block_on, StreamExt};
+
+#[test]
+fn test_publish() {
+    let mut publisher = Publisher::new();
+    let mut first = publisher.subscribe();
+    publisher.publish(&1);
+    let mut second = publisher.subscribe();
+    assert_eq!(publisher.publish(&2), 0);
+    drop(publisher);
+    assert_eq!(
+        block_on(first.by_ref().collect::<Vec<_>>()),
+        vec![Ok(1), Ok(2)]
+    );
+    assert_eq!(block_on(second.by_ref().collect::<Vec<_>>()), vec![Ok(2)]);
+}
+
+#[test]
+fn test_gone_subscribers_are_dropped() {
+    let mut publisher = Publisher::new();
+    let subscription = publisher.subscribe();
+    let _other_subscription = publisher.subscribe();
+    drop(subscription);
+    publisher.prune();
+    assert_eq!(publisher.len(), 1);
+    publisher.publish(&0);
+    assert_eq!(publisher.len(), 1);
+}
+
+#[test]
+fn test_lagging_subscriber_is_disconnected() {
+    let mut publisher = Publisher::new();
+    let mut lagging = publisher.subscribe();
+    // The channel has one slot per sender on top of its buffer.
+    for message in 0..=SUBSCRIBER_CHANNEL_SIZE {
+        assert_eq!(publisher.publish(&message), 0);
+    }
+    assert_eq!(publisher.publish(&(SUBSCRIBER_CHANNEL_SIZE + 1)), 1);
+    assert!(publisher.is_empty());
+    // The messages sent before the disconnection are still delivered, then the stream ends with
+    // the error despite the full channel.
+    let messages = block_on(lagging.by_ref().collect::<Vec<_>>());
+    assert_eq!(messages.len(), SUBSCRIBER_CHANNEL_SIZE + 2);
+    assert!(messages[..=SUBSCRIBER_CHANNEL_SIZE]
+        .iter()
+        .all(|message| message.is_ok()));
+    assert_eq!(
+        messages.last(),
+        Some(&Err(SubscriberLagged(SUBSCRIBER_CHANNEL_SIZE)))
+    );
+}
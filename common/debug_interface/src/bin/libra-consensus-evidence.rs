@@ -0,0 +1,39 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use debug_interface::NodeDebugClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "libra-consensus-evidence",
+    author = "Libra",
+    about = "Prints the double proposals and double votes detected by the consensus of a running node"
+)]
+struct Args {
+    /// Address of the node's debug interface
+    #[structopt(short = "a", long = "address", default_value = "localhost")]
+    pub address: String,
+    /// Port of the node's debug interface
+    #[structopt(short = "p", long = "port", default_value = "6191")]
+    pub port: u16,
+}
+
+fn main() {
+    let args = Args::from_args();
+    let client = NodeDebugClient::new(&args.address, args.port);
+    let response = client
+        .get_consensus_evidence()
+        .unwrap_or_else(|e| panic!("Failed to query consensus evidence: {}", e));
+
+    println!("Evidence ({}):", response.get_evidence().len());
+    for evidence in response.get_evidence() {
+        println!(
+            "  round {} {} {}",
+            evidence.get_round(),
+            evidence.get_kind(),
+            evidence.get_author(),
+        );
+        println!("    {}", evidence.get_description());
+    }
+}
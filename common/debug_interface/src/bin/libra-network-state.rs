@@ -0,0 +1,86 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use debug_interface::NodeDebugClient;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "libra-network-state",
+    author = "Libra",
+    about = "Prints the state of the network stack of a running node"
+)]
+struct Args {
+    /// Address of the node's debug interface
+    #[structopt(short = "a", long = "address", default_value = "localhost")]
+    pub address: String,
+    /// Port of the node's debug interface
+    #[structopt(short = "p", long = "port", default_value = "6191")]
+    pub port: u16,
+}
+
+fn main() {
+    let args = Args::from_args();
+    let client = NodeDebugClient::new(&args.address, args.port);
+    let state = client
+        .get_network_state()
+        .unwrap_or_else(|e| panic!("Failed to query network state: {}", e));
+
+    println!("Connected peers ({}):", state.get_connected_peers().len());
+    for peer in state.get_connected_peers() {
+        println!(
+            "  {} {} {} {}",
+            peer.get_peer_id(),
+            peer.get_address(),
+            peer.get_origin(),
+            peer.get_role(),
+        );
+        if peer.get_has_last_ping_rtt() {
+            println!(
+                "    last ping rtt: {}us, ping failures since: {}",
+                peer.get_last_ping_rtt_us(),
+                peer.get_ping_failures()
+            );
+        } else {
+            println!(
+                "    no successful ping, ping failures: {}",
+                peer.get_ping_failures()
+            );
+        }
+        let mut substreams_opened: Vec<_> = peer.get_substreams_opened().iter().collect();
+        substreams_opened.sort();
+        for (protocol, count) in substreams_opened {
+            println!(
+                "    {} substreams opened since connected: {}",
+                protocol, count
+            );
+        }
+    }
+
+    println!("Dials ({}):", state.get_dials().len());
+    for dial in state.get_dials() {
+        let next_dial = if dial.get_dial_queued() {
+            format!("next dial in {}ms", dial.get_next_dial_in_ms())
+        } else {
+            "no dial queued".to_string()
+        };
+        println!(
+            "  {} {} attempts: {}, backoff: {}ms, {}",
+            dial.get_peer_id(),
+            dial.get_address(),
+            dial.get_attempts(),
+            dial.get_backoff_ms(),
+            next_dial
+        );
+    }
+
+    println!(
+        "Discovery notes ({}):",
+        state.get_discovery_note_epochs().len()
+    );
+    let mut epochs: Vec<_> = state.get_discovery_note_epochs().iter().collect();
+    epochs.sort();
+    for (peer_id, epoch) in epochs {
+        println!("  {} epoch: {}", peer_id, epoch);
+    }
+}
@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::proto::{
-    node_debug_interface::{DumpJemallocHeapProfileRequest, GetNodeDetailsRequest},
+    node_debug_interface::{
+        DumpJemallocHeapProfileRequest, GetConsensusEvidenceRequest,
+        GetConsensusEvidenceResponse, GetNetworkStateRequest, GetNetworkStateResponse,
+        GetNodeDetailsRequest,
+    },
     node_debug_interface_grpc::NodeDebugInterfaceClient,
 };
 use failure::prelude::*;
@@ -69,4 +73,22 @@ impl NodeDebugClient {
 
         Ok(response.status_code)
     }
+
+    pub fn get_network_state(&self) -> Result<GetNetworkStateResponse> {
+        let response = self
+            .client
+            .get_network_state(&GetNetworkStateRequest::new())
+            .context("Unable to query network state")?;
+
+        Ok(response)
+    }
+
+    pub fn get_consensus_evidence(&self) -> Result<GetConsensusEvidenceResponse> {
+        let response = self
+            .client
+            .get_consensus_evidence(&GetConsensusEvidenceRequest::new())
+            .context("Unable to query consensus evidence")?;
+
+        Ok(response)
+    }
 }
@@ -8,22 +8,57 @@ use crate::{
     proto::{
         node_debug_interface::{
             DumpJemallocHeapProfileRequest, DumpJemallocHeapProfileResponse, Event,
-            GetEventsRequest, GetEventsResponse, GetNodeDetailsRequest, GetNodeDetailsResponse,
+            GetConsensusEvidenceRequest, GetConsensusEvidenceResponse, GetEventsRequest,
+            GetEventsResponse, GetNetworkStateRequest, GetNetworkStateResponse,
+            GetNodeDetailsRequest, GetNodeDetailsResponse,
         },
         node_debug_interface_grpc::NodeDebugInterface,
     },
 };
+use failure::prelude::*;
 use futures::Future;
+use grpcio::{RpcStatus, RpcStatusCode};
 use logger::prelude::*;
 use metrics::counters::COUNTER_ADMISSION_CONTROL_CANNOT_SEND_REPLY;
+use std::sync::Arc;
+
+/// Source of the network state returned by `GetNetworkState`.
+pub trait NetworkStateProvider: Send + Sync {
+    fn network_state(&self) -> GetNetworkStateResponse;
+}
+
+/// Source of the evidence returned by `GetConsensusEvidence`.
+pub trait ConsensusEvidenceProvider: Send + Sync {
+    fn consensus_evidence(&self) -> Result<GetConsensusEvidenceResponse>;
+}
 
 #[derive(Clone, Default)]
-pub struct NodeDebugService {}
+pub struct NodeDebugService {
+    network_state: Option<Arc<dyn NetworkStateProvider>>,
+    consensus_evidence: Option<Arc<dyn ConsensusEvidenceProvider>>,
+}
 
 impl NodeDebugService {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Creates a debug service which also reports the state of the node's network stack.
+    pub fn with_network_state(network_state: Arc<dyn NetworkStateProvider>) -> Self {
+        Self {
+            network_state: Some(network_state),
+            consensus_evidence: None,
+        }
+    }
+
+    /// Also reports the evidence of misbehaviors detected by the node's consensus.
+    pub fn with_consensus_evidence(
+        mut self,
+        consensus_evidence: Arc<dyn ConsensusEvidenceProvider>,
+    ) -> Self {
+        self.consensus_evidence = Some(consensus_evidence);
+        self
+    }
 }
 
 impl NodeDebugInterface for NodeDebugService {
@@ -74,6 +109,46 @@ impl NodeDebugInterface for NodeDebugService {
         let f = sink.success(resp).map_err(default_reply_error_logger);
         ctx.spawn(f)
     }
+
+    fn get_network_state(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetNetworkStateRequest,
+        sink: ::grpcio::UnarySink<GetNetworkStateResponse>,
+    ) {
+        trace!("[GRPC] get_network_state");
+        let f = match &self.network_state {
+            Some(network_state) => sink.success(network_state.network_state()),
+            None => sink.fail(RpcStatus::new(
+                RpcStatusCode::Unimplemented,
+                Some("This node does not run a network stack".to_string()),
+            )),
+        };
+        ctx.spawn(f.map_err(default_reply_error_logger))
+    }
+
+    fn get_consensus_evidence(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetConsensusEvidenceRequest,
+        sink: ::grpcio::UnarySink<GetConsensusEvidenceResponse>,
+    ) {
+        trace!("[GRPC] get_consensus_evidence");
+        let f = match &self.consensus_evidence {
+            Some(consensus_evidence) => match consensus_evidence.consensus_evidence() {
+                Ok(response) => sink.success(response),
+                Err(e) => sink.fail(RpcStatus::new(
+                    RpcStatusCode::Internal,
+                    Some(format!("Failed to read consensus evidence: {}", e)),
+                )),
+            },
+            None => sink.fail(RpcStatus::new(
+                RpcStatusCode::Unimplemented,
+                Some("This node does not run consensus".to_string()),
+            )),
+        };
+        ctx.spawn(f.map_err(default_reply_error_logger))
+    }
 }
 
 fn default_reply_error_logger<T: ::std::fmt::Debug>(e: T) {
@@ -3,7 +3,10 @@
 
 use failure::{prelude::*, Result};
 use futures::{compat::Future01CompatExt, future::Future, prelude::*};
-use futures_01::future::Future as Future01;
+use futures_01::{
+    future::{loop_fn, poll_fn, Either, Future as Future01, Loop},
+    Sink as Sink01, Stream as Stream01,
+};
 use grpcio::{ChannelBuilder, EnvBuilder, ServerBuilder};
 use logger::prelude::*;
 use metrics::counters::SVC_COUNTERS;
@@ -53,6 +56,33 @@ pub fn provide_grpc_response<ResponseType: std::fmt::Debug>(
     SVC_COUNTERS.resp(&ctx, success);
 }
 
+/// Streams the items to the client. The call completes once the stream ends, or fails with the
+/// given status as soon as the stream yields an error (e.g. RESOURCE_EXHAUSTED for a subscriber
+/// that does not keep up with the stream).
+pub fn provide_grpc_stream<T, S>(
+    ctx: &::grpcio::RpcContext<'_>,
+    sink: ::grpcio::ServerStreamingSink<T>,
+    stream: S,
+) where
+    T: Send + 'static,
+    S: Stream<Item = std::result::Result<T, ::grpcio::RpcStatus>> + Send + Unpin + 'static,
+{
+    let streaming = loop_fn((sink, stream.compat()), |(sink, stream)| {
+        stream.into_future().then(|next| match next {
+            Ok((Some(item), stream)) => Either::A(
+                sink.send((item, ::grpcio::WriteFlags::default()))
+                    .map(|sink| Loop::Continue((sink, stream))),
+            ),
+            Ok((None, _)) => {
+                let mut sink = sink;
+                Either::B(Either::A(poll_fn(move || sink.close()).map(Loop::Break)))
+            }
+            Err((status, _)) => Either::B(Either::B(sink.fail(status).map(Loop::Break))),
+        })
+    });
+    ctx.spawn(streaming.map_err(|e| debug!("Stream to the client ended: {:?}", e)));
+}
+
 pub fn spawn_service_thread(
     service: ::grpcio::Service,
     service_host_address: String,
@@ -140,7 +140,7 @@ impl<'a> MethodGen<'a> {
         let (sig, implemented) = match self.method_type().0 {
             util::MethodType::Unary => (self.unary(method_name), true),
             util::MethodType::ClientStreaming => (self.client_streaming(method_name), false),
-            util::MethodType::ServerStreaming => (self.server_streaming(method_name), false),
+            util::MethodType::ServerStreaming => (self.server_streaming(method_name), true),
             util::MethodType::Duplex => (self.duplex_streaming(method_name), false),
         };
 
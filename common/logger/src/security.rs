@@ -33,12 +33,18 @@ pub enum SecurityEvent {
     /// Consensus received an invalid vote
     InvalidConsensusVote,
 
+    /// Consensus received conflicting messages signed by the same validator for the same round
+    ConsensusEquivocation,
+
     /// Consensus received an invalid new round message
     InvalidConsensusRound,
 
     /// Consensus received an invalid sync info message
     InvalidSyncInfoMsg,
 
+    /// Consensus received a transaction batch that is not sent by its author
+    InvalidBatch,
+
     /// A block being committed or executed is invalid
     InvalidBlock,
 
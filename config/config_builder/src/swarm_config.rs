@@ -209,6 +209,7 @@ impl SwarmConfig {
             enable_encryption_and_authentication: template
                 .network
                 .enable_encryption_and_authentication,
+            transport: template.network.transport.clone(),
         };
         let mut config = NodeConfig {
             base: base_config,
@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils::{deserialize_whitelist, get_local_ip, serialize_whitelist};
+use crate::utils::{
+    deserialize_addresses, deserialize_whitelist, get_local_ip, serialize_addresses,
+    serialize_whitelist,
+};
 use parity_multiaddr::{Multiaddr, Protocol};
 use std::{
     collections::HashSet,
@@ -24,10 +27,16 @@ use toml;
 
 use failure::prelude::*;
 use proto_conv::FromProtoBytes;
-use types::transaction::{SignedTransaction, SCRIPT_HASH_LENGTH};
+use types::{
+    account_address::AccountAddress,
+    account_config,
+    transaction::{SignedTransaction, SCRIPT_HASH_LENGTH},
+};
 
 use crate::{
-    config::ConsensusProposerType::{FixedProposer, MultipleOrderedProposers, RotatingProposer},
+    config::ConsensusProposerType::{
+        FixedProposer, LeaderReputation, MultipleOrderedProposers, RotatingProposer,
+    },
     seed_peers::{SeedPeersConfig, SeedPeersConfigHelpers},
     trusted_peers::{
         deserialize_key, deserialize_opt_key, serialize_key, serialize_opt_key,
@@ -452,6 +461,16 @@ pub struct AdmissionControlConfig {
     pub address: String,
     pub admission_control_service_port: u16,
     pub need_to_check_mempool_before_validation: bool,
+    // max number of transactions of a single sender accepted within the quota window, 0 for no
+    // limit
+    pub max_submissions_per_account: usize,
+    // max number of transactions submitted through a single client connection within the quota
+    // window, 0 for no limit
+    pub max_submissions_per_connection: usize,
+    // length of the sliding window the submission quotas apply to
+    pub submission_quota_window_ms: u64,
+    // max number of transactions submitted in a single SubmitTransactions request
+    pub max_submit_batch_size: usize,
 }
 
 impl Default for AdmissionControlConfig {
@@ -460,6 +479,10 @@ impl Default for AdmissionControlConfig {
             address: "0.0.0.0".to_string(),
             admission_control_service_port: 8000,
             need_to_check_mempool_before_validation: false,
+            max_submissions_per_account: 0,
+            max_submissions_per_connection: 0,
+            submission_quota_window_ms: 60_000,
+            max_submit_batch_size: 1000,
         }
     }
 }
@@ -525,6 +548,9 @@ pub struct NetworkConfig {
     pub discovery_interval_ms: u64,
     pub connectivity_check_interval_ms: u64,
     pub enable_encryption_and_authentication: bool,
+    // Transport the connections are established over: "tcp", or "quic" which is always encrypted
+    // and authenticated. The listen and advertised addresses must be of the same kind.
+    pub transport: String,
     pub role: String,
     // peer_keypairs contains all the node's private keys,
     // it is filled later on from peer_keypairs_file.
@@ -552,6 +578,7 @@ impl Default for NetworkConfig {
             discovery_interval_ms: 1000,
             connectivity_check_interval_ms: 5000,
             enable_encryption_and_authentication: true,
+            transport: "tcp".to_string(),
             peer_keypairs_file: PathBuf::from("peer_keypairs.config.toml"),
             peer_keypairs: KeyPairs::default(),
             trusted_peers_file: PathBuf::from("trusted_peers.config.toml"),
@@ -572,6 +599,7 @@ impl Clone for NetworkConfig {
             discovery_interval_ms: self.discovery_interval_ms,
             connectivity_check_interval_ms: self.connectivity_check_interval_ms,
             enable_encryption_and_authentication: self.enable_encryption_and_authentication,
+            transport: self.transport.clone(),
             role: self.role.clone(),
             peer_keypairs: self.peer_keypairs.clone(),
             peer_keypairs_file: self.peer_keypairs_file.clone(),
@@ -583,24 +611,56 @@ impl Clone for NetworkConfig {
     }
 }
 
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum NetworkTransport {
+    // TCP, optionally upgraded with Noise depending on enable_encryption_and_authentication
+    Tcp,
+    // QUIC, with natively multiplexed streams
+    Quic,
+}
+
+impl NetworkConfig {
+    pub fn get_transport(&self) -> NetworkTransport {
+        match self.transport.as_str() {
+            "tcp" => NetworkTransport::Tcp,
+            "quic" => NetworkTransport::Quic,
+            &_ => unimplemented!("Invalid network transport: {}", self.transport),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 pub struct ConsensusConfig {
     max_block_size: u64,
+    // Share (in percent) of each proposed block reserved for the transactions of the mempool's
+    // privileged senders (e.g. the validator set updates), so that they can't be starved.
+    privileged_lane_percent: u64,
     proposer_type: String,
     contiguous_rounds: u32,
     max_pruned_blocks_in_mem: Option<u64>,
     pacemaker_initial_timeout_ms: Option<u64>,
+    // Max number of proposed blocks executed speculatively at the same time, 0 disables the
+    // speculative execution.
+    max_pending_executions: usize,
+    safety_rules: SafetyRulesConfig,
+    batching: BatchingConfig,
+    adaptive_pacemaker: AdaptivePacemakerConfig,
 }
 
 impl Default for ConsensusConfig {
     fn default() -> ConsensusConfig {
         ConsensusConfig {
             max_block_size: 100,
+            privileged_lane_percent: 10,
             proposer_type: "multiple_ordered_proposers".to_string(),
             contiguous_rounds: 2,
             max_pruned_blocks_in_mem: None,
             pacemaker_initial_timeout_ms: None,
+            max_pending_executions: 4,
+            safety_rules: SafetyRulesConfig::default(),
+            batching: BatchingConfig::default(),
+            adaptive_pacemaker: AdaptivePacemakerConfig::default(),
         }
     }
 }
@@ -613,6 +673,8 @@ pub enum ConsensusProposerType {
     RotatingProposer,
     // Multiple ordered proposers per round (primary, secondary, etc.)
     MultipleOrderedProposers,
+    // Weighted choice preferring the proposers active in the recently committed blocks
+    LeaderReputation,
 }
 
 impl ConsensusConfig {
@@ -621,6 +683,7 @@ impl ConsensusConfig {
             "fixed_proposer" => FixedProposer,
             "rotating_proposer" => RotatingProposer,
             "multiple_ordered_proposers" => MultipleOrderedProposers,
+            "leader_reputation" => LeaderReputation,
             &_ => unimplemented!("Invalid proposer type: {}", self.proposer_type),
         }
     }
@@ -633,6 +696,10 @@ impl ConsensusConfig {
         self.max_block_size
     }
 
+    pub fn privileged_lane_percent(&self) -> u64 {
+        self.privileged_lane_percent
+    }
+
     pub fn max_pruned_blocks_in_mem(&self) -> &Option<u64> {
         &self.max_pruned_blocks_in_mem
     }
@@ -640,6 +707,132 @@ impl ConsensusConfig {
     pub fn pacemaker_initial_timeout_ms(&self) -> &Option<u64> {
         &self.pacemaker_initial_timeout_ms
     }
+
+    pub fn max_pending_executions(&self) -> usize {
+        self.max_pending_executions
+    }
+
+    pub fn safety_rules(&self) -> &SafetyRulesConfig {
+        &self.safety_rules
+    }
+
+    pub fn batching(&self) -> &BatchingConfig {
+        &self.batching
+    }
+
+    pub fn adaptive_pacemaker(&self) -> &AdaptivePacemakerConfig {
+        &self.adaptive_pacemaker
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BatchingConfig {
+    // Whether the validators disseminate the transactions in batches ahead of the proposals, which
+    // then carry the digests of the batches instead of the transactions.
+    pub enabled: bool,
+    // Max number of transactions in a batch.
+    pub max_batch_size: u64,
+    // Interval between two batches pulled from mempool.
+    pub batch_interval_ms: u64,
+    // Max number of batches a block refers to.
+    pub max_batches_per_block: usize,
+    // Max number of uncommitted batches kept in memory, the oldest ones are dropped first.
+    pub max_batches_in_mem: usize,
+    // Max number of committed batches kept in memory to serve the peers lagging behind, the
+    // oldest ones are dropped first.
+    pub max_committed_batches_in_mem: usize,
+    // Timeout of fetching a missing batch from a peer.
+    pub batch_request_timeout_ms: u64,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> BatchingConfig {
+        BatchingConfig {
+            enabled: false,
+            max_batch_size: 100,
+            batch_interval_ms: 100,
+            max_batches_per_block: 100,
+            max_batches_in_mem: 10_000,
+            max_committed_batches_in_mem: 10_000,
+            batch_request_timeout_ms: 1_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AdaptivePacemakerConfig {
+    // Whether the round timeouts are derived from the observed QC formation latencies instead of
+    // the fixed pacemaker_initial_timeout_ms.
+    pub enabled: bool,
+    // Number of the most recent QC formation latencies the timeouts are derived from.
+    pub window_size: usize,
+    // Number of latencies to observe before adapting, pacemaker_initial_timeout_ms is used until
+    // then.
+    pub min_samples: usize,
+    // Percentile of the observed latencies (between 0 and 100) the round timeout is based on.
+    pub latency_percentile: f64,
+    // Added to the latency percentile to get the round timeout.
+    pub margin_ms: u64,
+    // Bounds of the round timeout, including the backoff of the rounds without commit.
+    pub min_timeout_ms: u64,
+    pub max_timeout_ms: u64,
+}
+
+impl Default for AdaptivePacemakerConfig {
+    fn default() -> AdaptivePacemakerConfig {
+        AdaptivePacemakerConfig {
+            enabled: false,
+            window_size: 100,
+            min_samples: 10,
+            latency_percentile: 90.0,
+            margin_ms: 500,
+            min_timeout_ms: 500,
+            max_timeout_ms: 60_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SafetyRulesConfig {
+    // Either "local" to run SafetyRules within the consensus process, or "remote" to talk to the
+    // standalone safety rules service listening on address:safety_rules_port.
+    backend: String,
+    pub address: String,
+    pub safety_rules_port: u16,
+    // Directory of the consensus state persisted by the standalone safety rules service.
+    pub dir: PathBuf,
+}
+
+impl Default for SafetyRulesConfig {
+    fn default() -> SafetyRulesConfig {
+        SafetyRulesConfig {
+            backend: "local".to_string(),
+            address: "localhost".to_string(),
+            safety_rules_port: 6186,
+            dir: PathBuf::from("safety_rules"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SafetyRulesBackend {
+    // SafetyRules runs within the consensus process
+    Local,
+    // SafetyRules runs in its own process and is reached over gRPC
+    Remote,
+}
+
+impl SafetyRulesConfig {
+    pub fn get_backend(&self) -> SafetyRulesBackend {
+        match self.backend.as_str() {
+            "local" => SafetyRulesBackend::Local,
+            "remote" => SafetyRulesBackend::Remote,
+            &_ => unimplemented!("Invalid safety rules backend: {}", self.backend),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -649,10 +842,45 @@ pub struct MempoolConfig {
     pub shared_mempool_tick_interval_ms: u64,
     pub shared_mempool_batch_size: usize,
     pub shared_mempool_max_concurrent_inbound_syncs: usize,
+    // peers (validators or full nodes) the local transactions are forwarded to, by order of
+    // preference. The local transactions are broadcast to all the connected peers if empty
+    pub upstream_peers: Vec<String>,
+    // how long to wait for an upstream peer to acknowledge a batch before sending it again
+    pub upstream_ack_timeout_ms: u64,
+    // number of consecutive unacknowledged batches after which an upstream peer is failed over
+    pub upstream_max_retries: usize,
+    // how long a failed over upstream peer is avoided, doubled with each consecutive failover
+    pub upstream_failover_backoff_ms: u64,
+    pub upstream_failover_max_backoff_ms: u64,
+    // interval between two checks of the pending transactions against storage, removing the
+    // committed ones. Only run if upstream peers are configured, as such nodes (e.g. full nodes)
+    // are not notified about the commits by consensus
+    pub committed_txns_check_interval_ms: u64,
     pub capacity: usize,
     // max number of transactions per user in Mempool
     pub capacity_per_user: usize,
+    // minimum gas price increase (in percent) for a transaction to replace the pending one with
+    // the same sequence number
+    pub replace_by_fee_min_bump_percent: u64,
+    // max distance between the sequence number of a new transaction and its account's current
+    // one: transactions further ahead are rejected rather than parked
+    pub max_sequence_number_gap: u64,
+    // senders (hex addresses) whose transactions, e.g. the validator set updates, get a reserved
+    // lane in the blocks and are never evicted in favor of other transactions
+    #[serde(deserialize_with = "deserialize_addresses")]
+    #[serde(serialize_with = "serialize_addresses")]
+    pub privileged_senders: Vec<AccountAddress>,
     pub sequence_cache_capacity: usize,
+    // number of recently removed (committed or discarded) transactions mempool remembers, to
+    // report their status
+    pub removed_txns_cache_capacity: usize,
+    // whether mempool journals its transactions on disk, to recover them after a restart
+    pub journal_enabled: bool,
+    pub journal_path: PathBuf,
+    // interval between two rewrites of the journal that drop the removed transactions
+    pub journal_compaction_interval_ms: u64,
+    // max number of clients following the lifecycle events of the transactions at once
+    pub max_event_subscribers: usize,
     pub system_transaction_timeout_secs: u64,
     pub system_transaction_gc_interval_ms: u64,
     pub mempool_service_port: u16,
@@ -666,9 +894,23 @@ impl Default for MempoolConfig {
             shared_mempool_tick_interval_ms: 50,
             shared_mempool_batch_size: 100,
             shared_mempool_max_concurrent_inbound_syncs: 100,
+            upstream_peers: vec![],
+            upstream_ack_timeout_ms: 2_000,
+            upstream_max_retries: 3,
+            upstream_failover_backoff_ms: 10_000,
+            upstream_failover_max_backoff_ms: 300_000,
+            committed_txns_check_interval_ms: 10_000,
             capacity: 10_000_000,
             capacity_per_user: 100,
+            replace_by_fee_min_bump_percent: 10,
+            max_sequence_number_gap: 100,
+            privileged_senders: vec![account_config::association_address()],
             sequence_cache_capacity: 1000,
+            removed_txns_cache_capacity: 10_000,
+            journal_enabled: false,
+            journal_path: PathBuf::from("mempool_journal"),
+            journal_compaction_interval_ms: 60_000,
+            max_event_subscribers: 1_000,
             system_transaction_timeout_secs: 86400,
             address: "localhost".to_string(),
             mempool_service_port: 6182,
@@ -864,6 +1106,11 @@ impl NodeConfigHelpers {
             config.metrics.dir = config.base.data_dir_path.join(&config.metrics.dir);
         }
         config.storage.dir = config.base.data_dir_path.join(config.storage.get_dir());
+        config.consensus.safety_rules.dir = config
+            .base
+            .data_dir_path
+            .join(&config.consensus.safety_rules.dir);
+        config.mempool.journal_path = config.base.data_dir_path.join(&config.mempool.journal_path);
         if config.execution.genesis_file_location == DISPOSABLE_DIR_MARKER {
             config.execution.genesis_file_location = config
                 .base
@@ -894,6 +1141,7 @@ impl NodeConfigHelpers {
         config.network.advertised_address = randomize_tcp_port(&config.network.advertised_address);
         config.network.listen_address = randomize_tcp_port(&config.network.listen_address);
         config.secret_service.secret_service_port = get_available_port();
+        config.consensus.safety_rules.safety_rules_port = get_available_port();
         config.storage.port = get_available_port();
     }
 }
@@ -26,3 +26,21 @@ fn verify_all_configs() {
         }
     }
 }
+
+#[test]
+fn verify_privileged_senders() {
+    let config = NodeConfigHelpers::get_single_node_test_config(false);
+    let config_string = toml::to_string(&config).unwrap();
+    let parsed = NodeConfig::parse(&config_string).unwrap();
+    assert_eq!(
+        parsed.mempool.privileged_senders,
+        vec![account_config::association_address()]
+    );
+
+    // invalid addresses are rejected when the config is loaded
+    let invalid_config_string = config_string.replace(
+        &format!("{:x}", account_config::association_address()),
+        "not an address",
+    );
+    assert!(NodeConfig::parse(&invalid_config_string).is_err());
+}
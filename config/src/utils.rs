@@ -6,10 +6,11 @@ use parity_multiaddr::{Multiaddr, Protocol};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::HashSet,
+    convert::TryFrom,
     hash::BuildHasher,
     net::{IpAddr, TcpListener, TcpStream},
 };
-use types::transaction::SCRIPT_HASH_LENGTH;
+use types::{account_address::AccountAddress, transaction::SCRIPT_HASH_LENGTH};
 
 /// Return an ephemeral, available port. On unix systems, the port returned will be in the
 /// TIME_WAIT state ensuring that the OS won't hand out this port for some grace period.
@@ -94,3 +95,32 @@ where
     let encoded_whitelist: Vec<String> = whitelist.iter().map(hex::encode).collect();
     encoded_whitelist.serialize(serializer)
 }
+
+pub fn deserialize_addresses<'de, D>(
+    deserializer: D,
+) -> ::std::result::Result<Vec<AccountAddress>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded_addresses: Vec<String> = Deserialize::deserialize(deserializer)?;
+    encoded_addresses
+        .into_iter()
+        .map(|address| {
+            AccountAddress::try_from(address).map_err(<D::Error as serde::de::Error>::custom)
+        })
+        .collect()
+}
+
+pub fn serialize_addresses<S>(
+    addresses: &[AccountAddress],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let encoded_addresses: Vec<String> = addresses
+        .iter()
+        .map(|address| format!("{:x}", address))
+        .collect();
+    encoded_addresses.serialize(serializer)
+}
@@ -13,6 +13,6 @@ fn main() {
     build_helpers::build_helpers::compile_proto(
         proto_root,
         vec![dependent_root],
-        false, /* generate_client_stub */
+        true, /* generate_client_stub */
     );
 }
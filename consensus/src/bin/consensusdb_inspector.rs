@@ -0,0 +1,38 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus::consensusdb_inspector::ConsensusDBInspector;
+use crypto::HashValue;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "consensusdb-inspector",
+    author = "Libra",
+    about = "Prints the data persisted in a ConsensusDB and the recovery decisions consensus \
+             would take on startup"
+)]
+struct Args {
+    /// Storage directory of the node (containing the consensusdb directory)
+    #[structopt(short = "d", long = "db-dir", parse(from_os_str))]
+    pub db_dir: PathBuf,
+    /// Block id (hex) of the latest ledger info in storage. Storage is assumed to have committed
+    /// the latest commit of consensus if not given
+    #[structopt(short = "r", long = "storage-root")]
+    pub storage_root: Option<String>,
+}
+
+fn main() {
+    let args = Args::from_args();
+    let root_from_storage = args.storage_root.map(|root| {
+        let bytes = hex::decode(&root).expect("Invalid hex storage root");
+        HashValue::from_slice(&bytes).expect("Invalid storage root")
+    });
+    let inspector = ConsensusDBInspector::open(&args.db_dir)
+        .unwrap_or_else(|e| panic!("Failed to open ConsensusDB: {}", e));
+    let report = inspector
+        .report(root_from_storage)
+        .unwrap_or_else(|e| panic!("Failed to read ConsensusDB: {}", e));
+    print!("{}", report);
+}
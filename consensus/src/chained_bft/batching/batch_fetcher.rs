@@ -0,0 +1,95 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    batching::BatchStore, common::Author, consensus_types::batch::BatchInfo,
+    network::ConsensusNetworkImpl,
+};
+use failure::prelude::*;
+use futures::future::join_all;
+use logger::prelude::*;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Brings the batches a block refers to from the peers.
+#[derive(Clone)]
+pub struct BatchFetcher {
+    batch_store: Arc<BatchStore>,
+    network: ConsensusNetworkImpl,
+    // Timeout of a single batch retrieval request.
+    request_timeout: Duration,
+}
+
+impl BatchFetcher {
+    pub fn new(
+        batch_store: Arc<BatchStore>,
+        network: ConsensusNetworkImpl,
+        request_timeout: Duration,
+    ) -> Self {
+        Self {
+            batch_store,
+            network,
+            request_timeout,
+        }
+    }
+
+    /// The deadline of a fetch that is not bound to a round: a single request to each peer.
+    pub fn default_deadline(&self) -> Instant {
+        Instant::now() + self.request_timeout
+    }
+
+    /// Adds the given batches missing from the batch store, trying the preferred peer (which is
+    /// expected to know all the batches of its proposal) first and then the author of the batch.
+    /// The missing batches are fetched concurrently, no request outlives the deadline.
+    /// Returns an error in case any of the batches could not be retrieved.
+    pub async fn fetch(
+        self,
+        batches: Vec<BatchInfo>,
+        preferred_peer: Option<Author>,
+        deadline: Instant,
+    ) -> Result<()> {
+        let fetches = self
+            .batch_store
+            .missing(&batches)
+            .into_iter()
+            .map(|info| self.clone().fetch_one(info, preferred_peer, deadline));
+        join_all(fetches).await.into_iter().collect()
+    }
+
+    async fn fetch_one(
+        mut self,
+        info: BatchInfo,
+        preferred_peer: Option<Author>,
+        deadline: Instant,
+    ) -> Result<()> {
+        let mut peers: Vec<Author> = preferred_peer.into_iter().collect();
+        if !peers.contains(&info.author()) {
+            peers.push(info.author());
+        }
+        for peer in peers {
+            let now = Instant::now();
+            ensure!(deadline > now, "Could not fetch {}: deadline reached", info);
+            let timeout = self.request_timeout.min(deadline.duration_since(now));
+            match self
+                .network
+                .request_batch(info.digest(), peer, timeout)
+                .await
+            {
+                Ok(batch) => {
+                    debug!("Fetched {} from {}", batch, peer.short_str());
+                    self.batch_store.insert(batch);
+                    return Ok(());
+                }
+                Err(e) => warn!(
+                    "Failed to fetch {} from {}: {:?}",
+                    info,
+                    peer.short_str(),
+                    e
+                ),
+            }
+        }
+        bail!("Could not fetch {}", info)
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        consensus_types::batch::{Batch, BatchInfo},
+        consensusdb::ConsensusDB,
+    },
+    counters,
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use logger::prelude::*;
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+#[cfg(test)]
+#[path = "batch_store_test.rs"]
+mod batch_store_test;
+
+/// Keeps the batches that are not committed yet, both the ones created by this validator and the
+/// ones disseminated by its peers. In case the store is full the oldest batch is dropped (it can
+/// still be fetched from its author if a proposal refers to it later). Once the block including
+/// a batch is committed, the batch is no longer proposed but it is kept for a while to serve the
+/// peers lagging behind.
+///
+/// The batches of the blocks being executed are pinned: they are needed again once the block is
+/// committed, hence they are never dropped. A pin is released when the batch is committed or when
+/// all the blocks referring to it are abandoned. The pinned batches are persisted along with the
+/// blocks in ConsensusDB, so that the blocks can be executed again after a restart.
+pub struct BatchStore {
+    inner: Mutex<BatchStoreInner>,
+    max_batches: usize,
+    // Max number of committed batches kept to serve the peers.
+    max_committed_batches: usize,
+    // Persists the pinned batches.
+    db: Option<Arc<ConsensusDB>>,
+}
+
+struct BatchStoreInner {
+    batches: HashMap<HashValue, Arc<Batch>>,
+    // Digests of the uncommitted batches in the order they were added.
+    order: VecDeque<HashValue>,
+    // Digests of the committed batches in the order they were committed.
+    committed: VecDeque<HashValue>,
+    // Number of executed blocks referring to each batch.
+    pinned: HashMap<HashValue, usize>,
+}
+
+impl BatchStore {
+    pub fn new(max_batches: usize) -> Self {
+        assert!(max_batches > 0);
+        Self {
+            inner: Mutex::new(BatchStoreInner {
+                batches: HashMap::new(),
+                order: VecDeque::new(),
+                committed: VecDeque::new(),
+                pinned: HashMap::new(),
+            }),
+            max_batches,
+            max_committed_batches: 0,
+            db: None,
+        }
+    }
+
+    /// Keeps up to the given number of committed batches to serve the peers.
+    pub fn with_committed_batches(mut self, max_committed_batches: usize) -> Self {
+        self.max_committed_batches = max_committed_batches;
+        self
+    }
+
+    /// Persists the pinned batches in the given ConsensusDB and recovers the batches it holds: the
+    /// ones of the blocks that are going to be executed again.
+    pub fn with_db(mut self, db: Arc<ConsensusDB>) -> Self {
+        let batches = db.get_batches().expect("unable to recover batches");
+        debug!("Recovered {} batches", batches.len());
+        self.db = Some(db);
+        for batch in batches {
+            self.insert(batch);
+        }
+        self
+    }
+
+    /// Adds the given batch, returns false in case the batch is already known.
+    pub fn insert(&self, batch: Batch) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let digest = batch.digest();
+        if inner.batches.contains_key(&digest) {
+            return false;
+        }
+        if inner.order.len() >= self.max_batches {
+            let BatchStoreInner {
+                batches,
+                order,
+                pinned,
+                ..
+            } = &mut *inner;
+            match order.iter().position(|digest| !pinned.contains_key(digest)) {
+                Some(index) => {
+                    let oldest = order.remove(index).expect("Index is in the order");
+                    warn!("Batch store is full, dropping batch {}", oldest);
+                    batches.remove(&oldest);
+                    // A recovered batch might still be persisted.
+                    self.delete_persisted(vec![oldest]);
+                }
+                None => warn!(
+                    "Batch store is full of pinned batches, keeping {} batches",
+                    order.len() + 1
+                ),
+            }
+        }
+        if inner.pinned.contains_key(&digest) {
+            self.persist(&batch);
+        }
+        inner.order.push_back(digest);
+        inner.batches.insert(digest, Arc::new(batch));
+        counters::NUM_BATCHES_IN_STORE.set(inner.batches.len() as i64);
+        true
+    }
+
+    pub fn get(&self, digest: HashValue) -> Option<Arc<Batch>> {
+        self.inner.lock().unwrap().batches.get(&digest).cloned()
+    }
+
+    /// Protects the given batches of an executed block from being dropped when the store is full
+    /// until they are committed or unpinned. The batches do not need to be in the store yet.
+    pub fn pin(&self, batches: &[BatchInfo]) {
+        let mut inner = self.inner.lock().unwrap();
+        for info in batches {
+            let digest = info.digest();
+            if inner.committed.contains(&digest) {
+                continue;
+            }
+            *inner.pinned.entry(digest).or_insert(0) += 1;
+            if let Some(batch) = inner.batches.get(&digest) {
+                self.persist(batch);
+            }
+        }
+    }
+
+    /// Releases the pins of the given batches of an abandoned block: the batches that are not
+    /// pinned by another block any more can be dropped again.
+    pub fn unpin(&self, batches: &[BatchInfo]) {
+        let mut inner = self.inner.lock().unwrap();
+        let mut released = vec![];
+        for info in batches {
+            let digest = info.digest();
+            if let Some(count) = inner.pinned.get_mut(&digest) {
+                *count -= 1;
+                if *count == 0 {
+                    inner.pinned.remove(&digest);
+                    released.push(digest);
+                }
+            }
+        }
+        if !released.is_empty() {
+            self.delete_persisted(released);
+        }
+    }
+
+    /// Returns the given batches that are not in the store.
+    pub fn missing(&self, batches: &[BatchInfo]) -> Vec<BatchInfo> {
+        let inner = self.inner.lock().unwrap();
+        batches
+            .iter()
+            .filter(|info| !inner.batches.contains_key(&info.digest()))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns all the uncommitted batches in the order they were added.
+    pub fn batches(&self) -> Vec<Arc<Batch>> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .order
+            .iter()
+            .filter_map(|digest| inner.batches.get(digest).cloned())
+            .collect()
+    }
+
+    /// Chooses the batches of a new proposal: the oldest batches that are not excluded (i.e., not
+    /// already included in the branch the proposal extends), up to the given number of
+    /// transactions and batches.
+    pub fn pull(
+        &self,
+        max_txns: u64,
+        max_batches: usize,
+        exclude: &HashSet<HashValue>,
+    ) -> Vec<BatchInfo> {
+        let inner = self.inner.lock().unwrap();
+        let mut num_txns = 0;
+        let mut pulled = vec![];
+        for digest in inner.order.iter().filter(|d| !exclude.contains(d)) {
+            let info = match inner.batches.get(digest) {
+                Some(batch) => batch.info(),
+                None => continue,
+            };
+            if num_txns + info.num_txns() > max_txns || pulled.len() >= max_batches {
+                break;
+            }
+            num_txns += info.num_txns();
+            pulled.push(info);
+        }
+        pulled
+    }
+
+    /// Returns the transactions of the given batches in order. All the batches must be in the
+    /// store.
+    pub fn txns<X: DeserializeOwned>(&self, batches: &[BatchInfo]) -> Result<Vec<X>> {
+        let mut txns = vec![];
+        for info in batches {
+            let batch = self
+                .get(info.digest())
+                .ok_or_else(|| format_err!("Batch {} is not available", info))?;
+            txns.extend(batch.txns::<X>()?);
+        }
+        Ok(txns)
+    }
+
+    /// Commits the given batches: they are unpinned and no longer proposed, the oldest committed
+    /// batches are dropped once there are more than the max number of committed batches.
+    pub fn commit(&self, digests: &[HashValue]) {
+        let mut inner = self.inner.lock().unwrap();
+        let BatchStoreInner {
+            batches,
+            order,
+            committed,
+            pinned,
+        } = &mut *inner;
+        let mut newly_committed = vec![];
+        for digest in digests {
+            if batches.contains_key(digest) && !committed.contains(digest) {
+                pinned.remove(digest);
+                committed.push_back(*digest);
+                newly_committed.push(*digest);
+            }
+        }
+        order.retain(|digest| !newly_committed.contains(digest));
+        while committed.len() > self.max_committed_batches {
+            if let Some(oldest) = committed.pop_front() {
+                batches.remove(&oldest);
+            }
+        }
+        counters::NUM_BATCHES_IN_STORE.set(batches.len() as i64);
+        // The committed blocks are pruned from ConsensusDB.
+        if !newly_committed.is_empty() {
+            self.delete_persisted(newly_committed);
+        }
+    }
+
+    fn persist(&self, batch: &Batch) {
+        if let Some(db) = self.db.as_ref() {
+            if let Err(e) = db.save_batch(batch) {
+                error!("Failed to persist batch {}: {:?}", batch, e);
+            }
+        }
+    }
+
+    fn delete_persisted(&self, digests: Vec<HashValue>) {
+        if let Some(db) = self.db.as_ref() {
+            if let Err(e) = db.delete_batches(digests) {
+                error!("Failed to delete persisted batches: {:?}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    batching::BatchStore, consensus_types::batch::Batch, consensusdb::ConsensusDB,
+};
+use crypto::HashValue;
+use std::{collections::HashSet, sync::Arc};
+use tempfile::tempdir;
+use types::account_address::AccountAddress;
+
+fn make_batches(author: AccountAddress, sizes: &[usize]) -> Vec<Batch> {
+    let mut next_txn = 0;
+    sizes
+        .iter()
+        .map(|size| {
+            let txns: Vec<usize> = (next_txn..next_txn + size).collect();
+            next_txn += size;
+            Batch::new(author, &txns)
+        })
+        .collect()
+}
+
+#[test]
+fn test_batch_digest() {
+    let author = AccountAddress::random();
+    let batch = Batch::new(author, &[1usize, 2, 3]);
+    assert_eq!(batch.digest(), Batch::new(author, &[1usize, 2, 3]).digest());
+    assert_ne!(batch.digest(), Batch::new(author, &[1usize, 2]).digest());
+    assert_ne!(
+        batch.digest(),
+        Batch::new(AccountAddress::random(), &[1usize, 2, 3]).digest()
+    );
+    assert_eq!(batch.num_txns(), 3);
+    assert_eq!(batch.txns::<usize>().unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_insert_and_missing() {
+    let store = BatchStore::new(10);
+    let batches = make_batches(AccountAddress::random(), &[1, 2, 3]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+
+    assert!(store.insert(batches[0].clone()));
+    assert!(!store.insert(batches[0].clone()));
+    assert!(store.insert(batches[2].clone()));
+    assert_eq!(store.get(infos[0].digest()).unwrap().as_ref(), &batches[0]);
+    assert!(store.get(infos[1].digest()).is_none());
+    assert_eq!(store.missing(&infos), vec![infos[1]]);
+
+    assert!(store.txns::<usize>(&infos).is_err());
+    store.insert(batches[1].clone());
+    assert_eq!(
+        store.txns::<usize>(&infos).unwrap(),
+        (0..6).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_pull() {
+    let store = BatchStore::new(10);
+    let batches = make_batches(AccountAddress::random(), &[2, 3, 4, 1]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+    for batch in batches {
+        store.insert(batch);
+    }
+
+    // The oldest batches first, up to the max number of transactions.
+    assert_eq!(store.pull(5, 10, &HashSet::new()), infos[0..2].to_vec());
+    assert_eq!(store.pull(4, 10, &HashSet::new()), infos[0..1].to_vec());
+    assert!(store.pull(1, 10, &HashSet::new()).is_empty());
+    assert_eq!(store.pull(100, 10, &HashSet::new()), infos);
+    // Up to the max number of batches.
+    assert_eq!(store.pull(100, 3, &HashSet::new()), infos[0..3].to_vec());
+
+    // The batches of the extended branch are excluded.
+    let exclude = vec![infos[0].digest(), infos[2].digest(), HashValue::random()]
+        .into_iter()
+        .collect();
+    assert_eq!(store.pull(100, 10, &exclude), vec![infos[1], infos[3]]);
+}
+
+#[test]
+fn test_commit_and_eviction() {
+    let store = BatchStore::new(3);
+    let batches = make_batches(AccountAddress::random(), &[1, 1, 1, 1]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+    for batch in batches.iter().take(3) {
+        store.insert(batch.clone());
+    }
+
+    store.commit(&[infos[1].digest()]);
+    assert_eq!(store.missing(&infos[0..3]), vec![infos[1]]);
+    assert_eq!(
+        store.pull(100, 10, &HashSet::new()),
+        vec![infos[0], infos[2]]
+    );
+
+    // The oldest batch is dropped once the store is full.
+    store.insert(batches[1].clone());
+    store.insert(batches[3].clone());
+    assert_eq!(store.missing(&infos), vec![infos[0]]);
+    assert_eq!(
+        store.pull(100, 10, &HashSet::new()),
+        vec![infos[2], infos[1], infos[3]]
+    );
+}
+
+#[test]
+fn test_pinned_batches_are_not_evicted() {
+    let store = BatchStore::new(2);
+    let batches = make_batches(AccountAddress::random(), &[1, 1, 1, 1]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+
+    // A block refers to the first batch, which is not in the store yet.
+    store.pin(&infos[0..1]);
+    store.insert(batches[0].clone());
+    store.insert(batches[1].clone());
+    // The oldest batch that is not pinned is dropped instead of the pinned one.
+    store.insert(batches[2].clone());
+    assert_eq!(store.missing(&infos[0..3]), vec![infos[1]]);
+
+    // Once all the batches are pinned the store keeps growing.
+    store.pin(&infos[2..3]);
+    store.insert(batches[3].clone());
+    assert!(store.missing(&infos[0..4]).contains(&infos[1]));
+    assert_eq!(store.batches().len(), 3);
+
+    // Committing a batch releases its pin.
+    store.commit(&[infos[0].digest()]);
+    store.insert(batches[1].clone());
+    assert_eq!(store.missing(&infos), vec![infos[0], infos[3]]);
+}
+
+#[test]
+fn test_committed_batches_are_kept() {
+    let store = BatchStore::new(10).with_committed_batches(1);
+    let batches = make_batches(AccountAddress::random(), &[1, 1, 1]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+    for batch in &batches {
+        store.insert(batch.clone());
+    }
+
+    // A committed batch is still served but no longer proposed.
+    store.commit(&[infos[0].digest()]);
+    assert!(store.missing(&infos).is_empty());
+    assert!(!store.insert(batches[0].clone()));
+    assert_eq!(
+        store.pull(100, 10, &HashSet::new()),
+        vec![infos[1], infos[2]]
+    );
+
+    // The oldest committed batch is dropped first.
+    store.commit(&[infos[1].digest()]);
+    assert_eq!(store.missing(&infos), vec![infos[0]]);
+    assert_eq!(store.pull(100, 10, &HashSet::new()), vec![infos[2]]);
+}
+
+#[test]
+fn test_unpin() {
+    let store = BatchStore::new(1);
+    let batches = make_batches(AccountAddress::random(), &[1, 1]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+
+    // Two blocks refer to the same batch.
+    store.pin(&infos[0..1]);
+    store.pin(&infos[0..1]);
+    store.insert(batches[0].clone());
+    store.unpin(&infos[0..1]);
+    store.insert(batches[1].clone());
+    assert_eq!(store.missing(&infos), vec![]);
+
+    // Once all the blocks are abandoned the batch can be dropped.
+    store.unpin(&infos[0..1]);
+    store.insert(Batch::new(AccountAddress::random(), &[1usize]));
+    assert_eq!(store.missing(&infos), vec![infos[0]]);
+}
+
+#[test]
+fn test_pinned_batches_are_persisted() {
+    let tmp_dir = tempdir().unwrap();
+    let db = Arc::new(ConsensusDB::new(&tmp_dir));
+    let batches = make_batches(AccountAddress::random(), &[1, 1, 1]);
+    let infos: Vec<_> = batches.iter().map(Batch::info).collect();
+    {
+        let store = BatchStore::new(10).with_db(Arc::clone(&db));
+        for batch in &batches {
+            store.insert(batch.clone());
+        }
+        store.pin(&infos[0..2]);
+        store.pin(&infos[2..3]);
+        // Neither the committed nor the abandoned batches are persisted.
+        store.commit(&[infos[1].digest()]);
+        store.unpin(&infos[2..3]);
+    }
+
+    let store = BatchStore::new(10).with_db(db);
+    assert_eq!(store.missing(&infos), vec![infos[1], infos[2]]);
+    assert_eq!(store.get(infos[0].digest()).unwrap().as_ref(), &batches[0]);
+}
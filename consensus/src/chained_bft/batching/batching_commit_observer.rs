@@ -0,0 +1,69 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{batching::BatchStore, consensus_types::batch::BatchInfo},
+    state_replication::CommitObserver,
+};
+use crypto::HashValue;
+use logger::prelude::*;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use types::{account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures};
+
+/// CommitObserver of the blocks referring to batches: the batches of a committed block are
+/// resolved to their transactions, which are passed to the inner CommitObserver. The observer is
+/// notified before the committed batches are removed from the batch store.
+pub struct BatchingCommitObserver<X> {
+    commit_observer: Arc<dyn CommitObserver<Payload = Vec<X>>>,
+    batch_store: Arc<BatchStore>,
+}
+
+impl<X> BatchingCommitObserver<X> {
+    pub fn new(
+        commit_observer: Arc<dyn CommitObserver<Payload = Vec<X>>>,
+        batch_store: Arc<BatchStore>,
+    ) -> Self {
+        Self {
+            commit_observer,
+            batch_store,
+        }
+    }
+}
+
+impl<X> CommitObserver for BatchingCommitObserver<X>
+where
+    X: DeserializeOwned + Send + Sync + 'static,
+{
+    type Payload = Vec<BatchInfo>;
+
+    fn on_commit(
+        &self,
+        block_id: HashValue,
+        round: u64,
+        proposer: Option<AccountAddress>,
+        batches: &Self::Payload,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) {
+        match self.batch_store.txns::<X>(batches) {
+            Ok(txns) => self.commit_observer.on_commit(
+                block_id,
+                round,
+                proposer,
+                &txns,
+                ledger_info_with_sigs,
+            ),
+            Err(e) => {
+                error!(
+                    "Failed to resolve the batches of committed block {}: {:?}",
+                    block_id, e
+                );
+                self.commit_observer.on_gap(ledger_info_with_sigs);
+            }
+        }
+    }
+
+    fn on_gap(&self, ledger_info_with_sigs: &LedgerInfoWithSignatures) {
+        self.commit_observer.on_gap(ledger_info_with_sigs);
+    }
+}
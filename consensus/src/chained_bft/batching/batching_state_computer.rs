@@ -0,0 +1,131 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        batching::{BatchFetcher, BatchStore},
+        consensus_types::batch::BatchInfo,
+        QuorumCert,
+    },
+    state_replication::{StateComputeResult, StateComputer},
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use futures::{Future, FutureExt};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use types::crypto_proxies::LedgerInfoWithSignatures;
+
+/// StateComputer of the blocks referring to batches: the batches of a block are resolved to their
+/// transactions, which are executed by the inner StateComputer. The batches missing from the
+/// batch store (e.g., the batches of the blocks retrieved while syncing up) are fetched from their
+/// authors first. The batches of an executed block are pinned in the batch store until they are
+/// committed, or until a commit abandons the block (i.e., the block is neither an ancestor nor a
+/// descendant of the committed block).
+pub struct BatchingStateComputer<X> {
+    state_computer: Arc<dyn StateComputer<Payload = Vec<X>>>,
+    batch_store: Arc<BatchStore>,
+    batch_fetcher: BatchFetcher,
+    // The blocks executed since the last commit in the order of execution (a parent is executed
+    // before its children): block id, parent block id and the pinned batches.
+    executed: Mutex<Vec<(HashValue, HashValue, Vec<BatchInfo>)>>,
+}
+
+impl<X> BatchingStateComputer<X> {
+    pub fn new(
+        state_computer: Arc<dyn StateComputer<Payload = Vec<X>>>,
+        batch_store: Arc<BatchStore>,
+        batch_fetcher: BatchFetcher,
+    ) -> Self {
+        Self {
+            state_computer,
+            batch_store,
+            batch_fetcher,
+            executed: Mutex::new(vec![]),
+        }
+    }
+
+    /// Releases the pins of the executed blocks abandoned by the commit of the given block, and
+    /// forgets about the committed ones, whose batches are unpinned once committed.
+    fn prune_executed(&self, committed_block_id: HashValue) {
+        let mut executed = self.executed.lock().unwrap();
+        let parents: HashMap<_, _> = executed
+            .iter()
+            .map(|(block_id, parent_id, _)| (*block_id, *parent_id))
+            .collect();
+        let mut committed = HashSet::new();
+        let mut block_id = committed_block_id;
+        committed.insert(block_id);
+        while let Some(parent_id) = parents.get(&block_id) {
+            committed.insert(*parent_id);
+            block_id = *parent_id;
+        }
+        let mut live = HashSet::new();
+        live.insert(committed_block_id);
+        let batch_store = &self.batch_store;
+        executed.retain(|(block_id, parent_id, batches)| {
+            if committed.contains(block_id) {
+                false
+            } else if live.contains(parent_id) {
+                live.insert(*block_id);
+                true
+            } else {
+                batch_store.unpin(batches);
+                false
+            }
+        });
+    }
+}
+
+impl<X> StateComputer for BatchingStateComputer<X>
+where
+    X: DeserializeOwned + Send + Sync + 'static,
+{
+    type Payload = Vec<BatchInfo>;
+
+    fn compute(
+        &self,
+        parent_block_id: HashValue,
+        block_id: HashValue,
+        batches: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = Result<StateComputeResult>> + Send>> {
+        let state_computer = Arc::clone(&self.state_computer);
+        let batch_store = Arc::clone(&self.batch_store);
+        let batch_fetcher = self.batch_fetcher.clone();
+        let batches = batches.clone();
+        batch_store.pin(&batches);
+        self.executed
+            .lock()
+            .unwrap()
+            .push((block_id, parent_block_id, batches.clone()));
+        async move {
+            let deadline = batch_fetcher.default_deadline();
+            batch_fetcher.fetch(batches.clone(), None, deadline).await?;
+            let txns = batch_store.txns::<X>(&batches)?;
+            state_computer
+                .compute(parent_block_id, block_id, &txns)
+                .await
+        }
+            .boxed()
+    }
+
+    fn commit(
+        &self,
+        commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.prune_executed(commit.ledger_info().consensus_block_id());
+        self.state_computer.commit(commit)
+    }
+
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        // The block tree is rebuilt from the synced blocks, which are executed again.
+        for (_, _, batches) in self.executed.lock().unwrap().drain(..) {
+            self.batch_store.unpin(&batches);
+        }
+        self.state_computer.sync_to(commit)
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        batching::{BatchFetcher, BatchStore},
+        common::Author,
+        consensus_types::batch::{Batch, BatchInfo},
+        network::ConsensusNetworkImpl,
+    },
+    counters,
+    state_replication::{StateComputeResult, TxnManager},
+    util::time_service::TimeService,
+};
+use failure::prelude::*;
+use futures::{future, Future, FutureExt};
+use logger::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use types::account_address::AccountAddress;
+
+#[cfg(test)]
+#[path = "batching_txn_manager_test.rs"]
+mod batching_txn_manager_test;
+
+/// TxnManager of the proposals referring to batches: wraps the TxnManager delivering the
+/// transactions (e.g., the mempool proxy).
+/// * The transactions are pulled from the inner TxnManager in batches, which are disseminated to
+/// the peers ahead of the proposals (see `generate_batch`). The privileged lane is reserved in
+/// each batch.
+/// * A proposal payload consists of the oldest uncommitted batches known to the proposer, up to
+/// the max block size and the max number of batches per block. Bigger proposals are rejected.
+/// * The committed batches are resolved to their transactions, which are committed to the inner
+/// TxnManager.
+pub struct BatchingTxnManager<X> {
+    author: Author,
+    txn_manager: Arc<dyn TxnManager<Payload = Vec<X>>>,
+    batch_store: Arc<BatchStore>,
+    batch_fetcher: BatchFetcher,
+    network: ConsensusNetworkImpl,
+    // Max number of transactions in a batch.
+    max_batch_size: u64,
+    // Number of transactions of a batch reserved for the privileged senders.
+    privileged_lane_size: u64,
+    // Max number of transactions in a block.
+    max_block_size: u64,
+    // Max number of batches a block refers to.
+    max_batches_per_block: usize,
+}
+
+impl<X> BatchingTxnManager<X>
+where
+    X: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    pub fn new(
+        author: Author,
+        txn_manager: Arc<dyn TxnManager<Payload = Vec<X>>>,
+        batch_store: Arc<BatchStore>,
+        batch_fetcher: BatchFetcher,
+        network: ConsensusNetworkImpl,
+        max_batch_size: u64,
+        max_block_size: u64,
+        max_batches_per_block: usize,
+    ) -> Self {
+        Self {
+            author,
+            txn_manager,
+            batch_store,
+            batch_fetcher,
+            network,
+            max_batch_size,
+            privileged_lane_size: 0,
+            max_block_size,
+            max_batches_per_block,
+        }
+    }
+
+    pub fn with_privileged_lane_size(mut self, privileged_lane_size: u64) -> Self {
+        self.privileged_lane_size = privileged_lane_size;
+        self
+    }
+
+    /// Checks the sizes of a proposed payload against the limits, the sizes are the ones claimed
+    /// by the proposer.
+    fn verify_payload_size(&self, batches: &[BatchInfo]) -> Result<()> {
+        ensure!(
+            batches.len() <= self.max_batches_per_block,
+            "Payload refers to {} batches, max is {}",
+            batches.len(),
+            self.max_batches_per_block
+        );
+        let num_txns = batches.iter().fold(0u64, |num_txns, info| {
+            num_txns.saturating_add(info.num_txns())
+        });
+        ensure!(
+            num_txns <= self.max_block_size,
+            "Payload has {} transactions, max is {}",
+            num_txns,
+            self.max_block_size
+        );
+        Ok(())
+    }
+
+    /// Pulls a batch of new transactions from the inner TxnManager, excluding the transactions of
+    /// all the known uncommitted batches, and broadcasts it to the peers. Returns None in case
+    /// there are no new transactions.
+    pub async fn generate_batch(&self) -> Result<Option<BatchInfo>> {
+        let pending_txns = self
+            .batch_store
+            .batches()
+            .iter()
+            .filter_map(|batch| match batch.txns::<X>() {
+                Ok(txns) => Some(txns),
+                Err(e) => {
+                    warn!("Failed to deserialize {}: {:?}", batch, e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let txns = self
+            .txn_manager
+            .pull_txns_with_privileged_lane(
+                self.max_batch_size,
+                self.privileged_lane_size,
+                pending_txns.iter().collect(),
+            )
+            .await?;
+        if txns.is_empty() {
+            return Ok(None);
+        }
+        let batch = Batch::new(self.author, &txns);
+        let info = batch.info();
+        debug!("Created {}", batch);
+        self.batch_store.insert(batch.clone());
+        self.network.clone().broadcast_batch(batch).await;
+        counters::BATCHES_CREATED_COUNT.inc();
+        Ok(Some(info))
+    }
+
+    /// Generates a new batch every given interval, runs until the executor is shut down.
+    pub async fn disseminate(
+        txn_manager: Arc<Self>,
+        time_service: Arc<dyn TimeService>,
+        interval: Duration,
+    ) {
+        loop {
+            if let Err(e) = txn_manager.generate_batch().await {
+                error!("Failed to generate a batch: {:?}", e);
+            }
+            time_service.sleep(interval).await;
+        }
+    }
+}
+
+impl<X> TxnManager for BatchingTxnManager<X>
+where
+    X: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    type Payload = Vec<BatchInfo>;
+
+    fn pull_txns(
+        &self,
+        max_size: u64,
+        exclude_payloads: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
+        let exclude = exclude_payloads
+            .into_iter()
+            .flatten()
+            .map(BatchInfo::digest)
+            .collect::<HashSet<_>>();
+        future::ok(
+            self.batch_store
+                .pull(max_size, self.max_batches_per_block, &exclude),
+        )
+        .boxed()
+    }
+
+    /// The batches are proposed oldest first, whatever their transactions pay, so the privileged
+    /// lane reserved in each batch carries over to the blocks.
+    fn pull_txns_with_privileged_lane(
+        &self,
+        max_size: u64,
+        _privileged_lane_size: u64,
+        exclude_payloads: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
+        self.pull_txns(max_size, exclude_payloads)
+    }
+
+    fn commit_txns<'a>(
+        &'a self,
+        batches: &Self::Payload,
+        compute_result: &StateComputeResult,
+        timestamp_usecs: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        // The batches of a committed block have been executed, hence they are in the store.
+        let txns = match self.batch_store.txns::<X>(batches) {
+            Ok(txns) => txns,
+            Err(e) => return future::err(e).boxed(),
+        };
+        let commit = self
+            .txn_manager
+            .commit_txns(&txns, compute_result, timestamp_usecs);
+        let digests = batches.iter().map(BatchInfo::digest).collect::<Vec<_>>();
+        async move {
+            self.batch_store.commit(&digests);
+            commit.await
+        }
+            .boxed()
+    }
+
+    fn fetch_payload<'a>(
+        &'a self,
+        batches: &Self::Payload,
+        peer: AccountAddress,
+        deadline: Instant,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        if let Err(e) = self.verify_payload_size(batches) {
+            return future::err(e).boxed();
+        }
+        let fetch = self
+            .batch_fetcher
+            .clone()
+            .fetch(batches.clone(), Some(peer), deadline);
+        let batches = batches.clone();
+        async move {
+            fetch.await?;
+            // The digest of a fetched batch covers its actual author and size.
+            for info in &batches {
+                let batch = self
+                    .batch_store
+                    .get(info.digest())
+                    .ok_or_else(|| format_err!("Batch {} is not available", info))?;
+                ensure!(
+                    batch.info() == *info,
+                    "Proposed {} does not match {}",
+                    info,
+                    batch
+                );
+            }
+            Ok(())
+        }
+            .boxed()
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        batching::{BatchFetcher, BatchStore, BatchingTxnManager},
+        common::Author,
+        consensus_types::batch::Batch,
+        epoch_manager::EpochManager,
+        network::ConsensusNetworkImpl,
+        test_utils::MockTransactionManager,
+    },
+    state_replication::{StateComputeResult, TxnManager},
+};
+use crypto::HashValue;
+use futures::executor::block_on;
+use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn create_txn_manager(
+    max_batch_size: u64,
+    max_block_size: u64,
+    max_batches_per_block: usize,
+) -> (
+    Author,
+    BatchingTxnManager<usize>,
+    Arc<MockTransactionManager>,
+    Arc<BatchStore>,
+) {
+    let signer = ValidatorSigner::random(None);
+    let author = signer.author();
+    // A single validator: the batches are not sent anywhere.
+    let validators = ValidatorVerifier::new_single(author, signer.public_key());
+    let epoch_mgr = Arc::new(EpochManager::new(0, validators));
+    let (network_reqs_tx, _) = channel::new_test(8);
+    let (_, consensus_rx) = channel::new_test(8);
+    let network = ConsensusNetworkImpl::new(
+        author,
+        ConsensusNetworkSender::new(network_reqs_tx),
+        ConsensusNetworkEvents::new(consensus_rx),
+        epoch_mgr,
+    );
+    let batch_store = Arc::new(BatchStore::new(100));
+    let mempool = Arc::new(MockTransactionManager::new());
+    let batch_fetcher = BatchFetcher::new(
+        Arc::clone(&batch_store),
+        network.clone(),
+        Duration::from_secs(1),
+    );
+    let txn_manager = BatchingTxnManager::new(
+        author,
+        mempool.clone(),
+        Arc::clone(&batch_store),
+        batch_fetcher,
+        network,
+        max_batch_size,
+        max_block_size,
+        max_batches_per_block,
+    );
+    (author, txn_manager, mempool, batch_store)
+}
+
+fn deadline() -> Instant {
+    Instant::now() + Duration::from_secs(1)
+}
+
+#[test]
+fn test_batches_are_proposed_and_committed() {
+    let (author, txn_manager, mempool, batch_store) = create_txn_manager(5, 100, 10);
+    let first = block_on(txn_manager.generate_batch()).unwrap().unwrap();
+    let second = block_on(txn_manager.generate_batch()).unwrap().unwrap();
+    assert_eq!(first.author(), author);
+    assert_eq!(first.num_txns(), 5);
+    assert_eq!(batch_store.batches().len(), 2);
+
+    // A proposal extending a block with the first batch refers to the second batch only.
+    let payload = block_on(txn_manager.pull_txns(100, vec![&vec![first]])).unwrap();
+    assert_eq!(payload, vec![second]);
+    let payload = block_on(txn_manager.pull_txns(9, vec![])).unwrap();
+    assert_eq!(payload, vec![first]);
+
+    // The batches are available locally.
+    block_on(txn_manager.fetch_payload(&vec![first, second], author, deadline())).unwrap();
+
+    let compute_result = StateComputeResult {
+        new_state_id: HashValue::zero(),
+        compute_status: vec![true; 10],
+        num_successful_txns: 10,
+        validators: None,
+    };
+    block_on(txn_manager.commit_txns(&vec![first, second], &compute_result, 0)).unwrap();
+    assert_eq!(mempool.get_committed_txns(), (0..10).collect::<Vec<_>>());
+    assert!(batch_store.batches().is_empty());
+}
+
+#[test]
+fn test_fetch_unavailable_batch() {
+    let (author, txn_manager, _, _) = create_txn_manager(5, 100, 10);
+    let unknown = Batch::new(author, &[42usize]).info();
+    // Nobody else knows the batch of this validator.
+    assert!(block_on(txn_manager.fetch_payload(&vec![unknown], author, deadline())).is_err());
+}
+
+#[test]
+fn test_oversized_payload_is_rejected() {
+    let (author, txn_manager, _, _) = create_txn_manager(5, 12, 2);
+    let batches: Vec<_> = (0..3)
+        .map(|_| block_on(txn_manager.generate_batch()).unwrap().unwrap())
+        .collect();
+
+    // A proposal never exceeds the limits.
+    let payload = block_on(txn_manager.pull_txns(100, vec![])).unwrap();
+    assert_eq!(payload, batches[0..2].to_vec());
+    block_on(txn_manager.fetch_payload(&payload, author, deadline())).unwrap();
+
+    // Too many batches.
+    assert!(block_on(txn_manager.fetch_payload(&batches, author, deadline())).is_err());
+    // Too many transactions.
+    let big = Batch::new(author, &(0..13).collect::<Vec<usize>>()).info();
+    assert!(block_on(txn_manager.fetch_payload(&vec![big], author, deadline())).is_err());
+}
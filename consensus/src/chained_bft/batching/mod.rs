@@ -0,0 +1,23 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decouples the dissemination of the transactions from the proposals: every validator
+//! periodically pulls a batch of transactions from its mempool and broadcasts it, the proposals
+//! then carry the digests of the batches (a `Vec<BatchInfo>` payload) instead of the transactions.
+//! A validator fetches the batches it misses before voting for a proposal, hence neither the size
+//! of a proposal nor the bandwidth of the leader bounds the throughput.
+//!
+//! The batching layer wraps the TxnManager, StateComputer and CommitObserver dealing with the
+//! transactions, the rest of consensus is oblivious to it.
+
+mod batch_fetcher;
+mod batch_store;
+mod batching_commit_observer;
+mod batching_state_computer;
+mod batching_txn_manager;
+
+pub use batch_fetcher::BatchFetcher;
+pub use batch_store::BatchStore;
+pub use batching_commit_observer::BatchingCommitObserver;
+pub use batching_state_computer::BatchingStateComputer;
+pub use batching_txn_manager::BatchingTxnManager;
@@ -4,14 +4,22 @@
 use crate::{
     chained_bft::{
         block_storage::{block_tree::BlockTree, BlockReader, InsertError, VoteReceptionResult},
-        common::{Payload, Round},
-        consensus_types::{block::Block, quorum_cert::QuorumCert},
+        common::{Author, Payload, Round},
+        consensus_types::{
+            block::{Block, BlockData},
+            quorum_cert::QuorumCert,
+        },
         persistent_storage::PersistentStorage,
         safety::vote_msg::VoteMsg,
     },
+    counters,
     state_replication::{ExecutedState, StateComputer},
 };
 use crypto::HashValue;
+use futures::{
+    channel::oneshot,
+    future::{FutureExt, Shared, TryFutureExt},
+};
 use logger::prelude::*;
 
 use crate::{chained_bft::persistent_storage::RecoveryData, state_replication::StateComputeResult};
@@ -19,9 +27,10 @@ use crypto::hash::CryptoHash;
 use mirai_annotations::checked_precondition;
 use std::{
     collections::{vec_deque::VecDeque, HashMap},
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
-use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+use tokio::runtime::TaskExecutor;
+use types::ledger_info::LedgerInfo;
 
 #[cfg(test)]
 #[path = "block_store_test.rs"]
@@ -36,6 +45,82 @@ pub enum NeedFetchResult {
     NeedFetch,
 }
 
+type ExecutionResult = failure::Result<StateComputeResult>;
+
+/// The execution of a proposed block started before the block is inserted.
+enum PendingExecution {
+    /// The execution is in flight in its own task, which sends the result and then signals the
+    /// completion to the executions of the children.
+    Running(
+        Round,
+        oneshot::Receiver<ExecutionResult>,
+        Shared<oneshot::Receiver<()>>,
+    ),
+    /// The execution has been awaited to make room for the newer ones.
+    Done(Round, ExecutionResult),
+}
+
+impl PendingExecution {
+    fn round(&self) -> Round {
+        match self {
+            PendingExecution::Running(round, ..) | PendingExecution::Done(round, _) => *round,
+        }
+    }
+
+    /// The result of an execution that is running, an execution whose task is dropped (e.g., at
+    /// shutdown) fails.
+    async fn result(execution: oneshot::Receiver<ExecutionResult>) -> ExecutionResult {
+        execution
+            .await
+            .unwrap_or_else(|_| Err(failure::err_msg("Speculative execution was dropped")))
+    }
+}
+
+#[derive(Default)]
+struct PendingExecutions {
+    executions: HashMap<HashValue, PendingExecution>,
+    // Ids of the running executions in the order they were started.
+    running: VecDeque<HashValue>,
+}
+
+impl PendingExecutions {
+    fn num_running(&self) -> usize {
+        self.running.len()
+    }
+
+    fn insert(&mut self, block_id: HashValue, execution: PendingExecution) {
+        if let PendingExecution::Running(..) = execution {
+            self.running.push_back(block_id);
+        }
+        self.executions.insert(block_id, execution);
+        counters::NUM_PENDING_EXECUTIONS.set(self.executions.len() as i64);
+    }
+
+    fn take(&mut self, block_id: HashValue) -> Option<PendingExecution> {
+        let execution = self.executions.remove(&block_id)?;
+        self.running.retain(|id| *id != block_id);
+        counters::NUM_PENDING_EXECUTIONS.set(self.executions.len() as i64);
+        Some(execution)
+    }
+
+    fn take_oldest_running(&mut self) -> Option<(HashValue, PendingExecution)> {
+        let block_id = *self.running.front()?;
+        self.take(block_id).map(|execution| (block_id, execution))
+    }
+
+    /// Drops the executions of the blocks that can not be inserted anymore.
+    fn prune(&mut self, root_round: Round) {
+        self.executions
+            .retain(|_, execution| execution.round() > root_round);
+        let PendingExecutions {
+            executions,
+            running,
+        } = self;
+        running.retain(|id| executions.contains_key(id));
+        counters::NUM_PENDING_EXECUTIONS.set(executions.len() as i64);
+    }
+}
+
 /// Responsible for maintaining all the blocks of payload and the dependencies of those blocks
 /// (parent and previous QC links).  It is expected to be accessed concurrently by multiple threads
 /// and is thread-safe.
@@ -52,24 +137,37 @@ pub enum NeedFetchResult {
 ///             | -> C1
 ///             | -------> C2
 ///             | -------------> D3
+///
+/// The execution of a proposed block can start speculatively before the block is inserted (see
+/// `execute_speculatively`), in which case the insertion waits for the result of the execution in
+/// flight instead of executing the block again.
 pub struct BlockStore<T> {
     inner: Arc<RwLock<BlockTree<T>>>,
-    validator_signer: ValidatorSigner,
+    // The author of the blocks proposed by this node.
+    author: Author,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     enforce_increasing_timestamps: bool,
     /// The persistent storage backing up the in-memory data structure, every write should go
     /// through this before in-memory tree.
     storage: Arc<dyn PersistentStorage<T>>,
+    pending_executions: Mutex<PendingExecutions>,
+    // Max number of speculative executions running at the same time, 0 disables the speculative
+    // execution.
+    max_pending_executions: usize,
+    // Runs the speculative executions.
+    executor: TaskExecutor,
 }
 
 impl<T: Payload> BlockStore<T> {
     pub async fn new(
         storage: Arc<dyn PersistentStorage<T>>,
         initial_data: RecoveryData<T>,
-        validator_signer: ValidatorSigner,
+        author: Author,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         enforce_increasing_timestamps: bool,
         max_pruned_blocks_in_mem: usize,
+        max_pending_executions: usize,
+        executor: TaskExecutor,
     ) -> Self {
         let (root, blocks, quorum_certs) = initial_data.take();
         let inner = Arc::new(RwLock::new(
@@ -84,13 +182,21 @@ impl<T: Payload> BlockStore<T> {
         ));
         BlockStore {
             inner,
-            validator_signer,
+            author,
             state_computer,
             enforce_increasing_timestamps,
             storage,
+            pending_executions: Mutex::new(PendingExecutions::default()),
+            max_pending_executions,
+            executor,
         }
     }
 
+    /// Builds the tree of the given blocks, which are executed again. The blocks that can't be
+    /// executed (e.g., their payload can't be retrieved any more) are left out along with their
+    /// descendants and QCs: they are retrieved from the peers again, or skipped by a state
+    /// synchronization once a peer sends a higher ledger info. They are kept in the storage,
+    /// which prunes them once the root moves past them.
     async fn build_block_tree(
         root: (Block<T>, QuorumCert, QuorumCert),
         blocks: Vec<Block<T>>,
@@ -104,10 +210,22 @@ impl<T: Payload> BlockStore<T> {
             .map(|qc| (qc.certified_block_id(), qc))
             .collect::<HashMap<_, _>>();
         for block in blocks {
-            let compute_res = state_computer
+            if !tree.block_exists(block.parent_id()) {
+                continue;
+            }
+            let compute_res = match state_computer
                 .compute(block.parent_id(), block.id(), block.get_payload())
                 .await
-                .expect("fail to rebuild scratchpad");
+            {
+                Ok(compute_res) => compute_res,
+                Err(e) => {
+                    warn!(
+                        "Failed to execute block {} while building the tree, skipping it: {:?}",
+                        block, e
+                    );
+                    continue;
+                }
+            };
             let version = tree
                 .get_state_for_block(block.parent_id())
                 .expect("parent state does not exist")
@@ -117,22 +235,29 @@ impl<T: Payload> BlockStore<T> {
                 state_id: compute_res.new_state_id,
                 version,
             };
-            // if this block is certified, ensure we agree with the certified state.
-            if let Some(qc) = quorum_certs.get(&block.id()) {
+            // if this block is committed, ensure we agree with the committed state.
+            if let Some(committed_state) = quorum_certs
+                .values()
+                .find(|qc| qc.committed_block_id() == Some(block.id()))
+                .and_then(|qc| qc.committed_state())
+            {
                 assert_eq!(
-                    qc.certified_state(),
+                    committed_state,
                     executed_state,
-                    "We have inconsistent executed state with Quorum Cert for block {}",
+                    "We have inconsistent executed state with the LedgerInfo committing block {}",
                     block.id()
                 );
             }
             tree.insert_block(block, executed_state, compute_res)
                 .expect("Block insertion failed while build the tree");
         }
-        quorum_certs.into_iter().for_each(|(_, qc)| {
-            tree.insert_quorum_cert(qc)
-                .expect("QuorumCert insertion failed while build the tree")
-        });
+        quorum_certs
+            .into_iter()
+            .filter(|(block_id, _)| tree.block_exists(*block_id))
+            .for_each(|(_, qc)| {
+                tree.insert_quorum_cert(qc)
+                    .expect("QuorumCert insertion failed while build the tree")
+            });
         tree
     }
 
@@ -150,6 +275,7 @@ impl<T: Payload> BlockStore<T> {
             self.inner.read().unwrap().max_pruned_blocks_in_mem(),
         )
         .await;
+        *self.pending_executions.lock().unwrap() = PendingExecutions::default();
         let to_remove = self.inner.read().unwrap().get_all_block_id();
         if let Err(e) = self.storage.prune_tree(to_remove) {
             // it's fine to fail here, the next restart will try to clean up dangling blocks again.
@@ -158,8 +284,9 @@ impl<T: Payload> BlockStore<T> {
         *self.inner.write().unwrap() = tree;
     }
 
-    pub fn signer(&self) -> &ValidatorSigner {
-        &self.validator_signer
+    #[cfg(test)]
+    pub fn num_pending_executions(&self) -> usize {
+        self.pending_executions.lock().unwrap().executions.len()
     }
 
     /// Execute and insert a block if it passes all validation tests.
@@ -187,14 +314,22 @@ impl<T: Payload> BlockStore<T> {
                 return Err(e);
             }
         };
-        let compute_res = self
-            .state_computer
-            .compute(parent_id, block.id(), block.get_payload())
-            .await
-            .map_err(|e| {
-                error!("Execution failure for block {}: {:?}", block, e);
-                InsertError::StateComputerError
-            })?;
+        let pending_execution = self.pending_executions.lock().unwrap().take(block.id());
+        let compute_res = match pending_execution {
+            Some(PendingExecution::Running(_, execution, _)) => {
+                PendingExecution::result(execution).await
+            }
+            Some(PendingExecution::Done(_, compute_res)) => compute_res,
+            None => {
+                self.state_computer
+                    .compute(parent_id, block.id(), block.get_payload())
+                    .await
+            }
+        }
+        .map_err(|e| {
+            error!("Execution failure for block {}: {:?}", block, e);
+            InsertError::StateComputerError
+        })?;
 
         let version = parent_exec_version + compute_res.num_successful_txns;
 
@@ -212,6 +347,77 @@ impl<T: Payload> BlockStore<T> {
             .map_err(|e| e.into())
     }
 
+    /// Starts the execution of a proposed block before its insertion, so that the execution runs
+    /// while the proposal is being processed (e.g., while the commit its certificates trigger is
+    /// persisted). The block is executed in case its parent is either inserted or being executed
+    /// itself: several speculative executions can be in flight at the same time. The execution is
+    /// spawned right away, its task asks the state computer to execute the block once the
+    /// execution of the parent (if still running) is complete.
+    ///
+    /// Once the max number of speculative executions is reached the function waits for the
+    /// oldest ones to complete first, which slows down the processing of the proposals in case
+    /// the execution falls behind.
+    pub async fn execute_speculatively(&self, block: &Block<T>) {
+        if self.max_pending_executions == 0
+            || block.round() <= self.root().round()
+            || self.block_exists(block.id())
+        {
+            return;
+        }
+        let parent_execution = {
+            let pending_executions = self.pending_executions.lock().unwrap();
+            if pending_executions.executions.contains_key(&block.id()) {
+                return;
+            }
+            match pending_executions.executions.get(&block.parent_id()) {
+                Some(PendingExecution::Running(_, _, done)) => Some(done.clone()),
+                Some(PendingExecution::Done(..)) => None,
+                None if self.block_exists(block.parent_id()) => None,
+                None => return,
+            }
+        };
+        loop {
+            let oldest = {
+                let mut pending_executions = self.pending_executions.lock().unwrap();
+                if pending_executions.num_running() < self.max_pending_executions {
+                    break;
+                }
+                pending_executions.take_oldest_running()
+            };
+            if let Some((block_id, PendingExecution::Running(round, execution, _))) = oldest {
+                counters::EXECUTION_BACK_PRESSURE_COUNT.inc();
+                let compute_res = PendingExecution::result(execution).await;
+                self.pending_executions
+                    .lock()
+                    .unwrap()
+                    .insert(block_id, PendingExecution::Done(round, compute_res));
+            }
+        }
+        debug!("Executing {} speculatively", block);
+        counters::SPECULATIVE_EXECUTION_COUNT.inc();
+        let state_computer = Arc::clone(&self.state_computer);
+        let (parent_id, block_id, payload) =
+            (block.parent_id(), block.id(), block.get_payload().clone());
+        let (result_tx, result_rx) = oneshot::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+        let task = async move {
+            // A block can only be executed on top of its executed parent: the state computer
+            // starts executing as soon as it is asked to, hence it is only asked once the
+            // execution of the parent is complete.
+            if let Some(parent_execution) = parent_execution {
+                let _ = parent_execution.await;
+            }
+            let execution = state_computer.compute(parent_id, block_id, &payload);
+            let _ = result_tx.send(execution.await);
+            let _ = done_tx.send(());
+        };
+        self.executor.spawn(task.boxed().unit_error().compat());
+        self.pending_executions.lock().unwrap().insert(
+            block.id(),
+            PendingExecution::Running(block.round(), result_rx, done_rx.shared()),
+        );
+    }
+
     /// Check if we're far away from this ledger info and need to sync.
     /// Returns false if we have this block in the tree or the root's round is higher than the
     /// block.
@@ -252,19 +458,26 @@ impl<T: Payload> BlockStore<T> {
 
     /// Validates quorum certificates and inserts it into block tree assuming dependencies exist.
     pub fn insert_single_quorum_cert(&self, qc: QuorumCert) -> Result<(), InsertError> {
-        // Ensure executed state is consistent with Quorum Cert, otherwise persist the quorum's
-        // state and hopefully we restart and agree with it.
-        let executed_state = self
-            .get_state_for_block(qc.certified_block_id())
-            .ok_or_else(|| InsertError::MissingParentBlock(qc.certified_block_id()))?;
-        assert_eq!(
-            executed_state,
-            qc.certified_state(),
-            "We have inconsistent executed state with the executed state from the quorum \
-             certificate for block {}, will kill this validator and rely on state synchronization \
-             to try to achieve consistent state with the quorum certificate.",
-            qc.certified_block_id(),
-        );
+        if !self.block_exists(qc.certified_block_id()) {
+            return Err(InsertError::MissingParentBlock(qc.certified_block_id()));
+        }
+        // The votes only certify the ordering: ensure the executed state of the block the QC
+        // commits is consistent with the one signed by the quorum, otherwise persist the QC and
+        // hopefully we restart and agree with it.
+        if let Some(committed_id) = qc.committed_block_id() {
+            if let (Some(executed_state), Some(committed_state)) =
+                (self.get_state_for_block(committed_id), qc.committed_state())
+            {
+                assert_eq!(
+                    executed_state, committed_state,
+                    "We have inconsistent executed state with the committed state from the quorum \
+                     certificate for block {}, will kill this validator and rely on state \
+                     synchronization to try to achieve consistent state with the quorum \
+                     certificate.",
+                    committed_id,
+                );
+            }
+        }
         self.storage
             .save_tree(vec![], vec![qc.clone()])
             .map_err(|_| InsertError::StorageFailure)?;
@@ -321,12 +534,16 @@ impl<T: Payload> BlockStore<T> {
             .write()
             .unwrap()
             .process_pruned_blocks(next_root_id, id_to_remove.clone());
+        let root_round = self.root().round();
+        self.pending_executions.lock().unwrap().prune(root_round);
         id_to_remove
     }
 
-    /// If block id information is found, returns the ledger info placeholder, otherwise, return
-    /// a placeholder with info of the genesis block.
-    pub fn ledger_info_placeholder(&self, id: Option<HashValue>) -> LedgerInfo {
+    /// If block id information is found, returns the ledger info placeholder of the given epoch,
+    /// otherwise, return a placeholder with info of the genesis block.
+    /// In case the block or one of its uncommitted ancestors carries a new validator set, the
+    /// placeholder carries it as well: committing the block ends the epoch.
+    pub fn ledger_info_placeholder(&self, id: Option<HashValue>, epoch: usize) -> LedgerInfo {
         let block_id = match id {
             None => return Self::zero_ledger_info_placeholder(),
             Some(id) => id,
@@ -343,20 +560,28 @@ impl<T: Payload> BlockStore<T> {
                 return Self::zero_ledger_info_placeholder();
             }
         };
-        LedgerInfo::new(
+        let next_validator_set = self
+            .path_from_root(Arc::clone(&block))
+            .unwrap_or_else(Vec::new)
+            .iter()
+            .filter_map(|block| self.get_compute_result(block.id()))
+            .find_map(|compute_result| compute_result.validators.clone());
+        let mut ledger_info = LedgerInfo::new(
             version,
             state_id,
             HashValue::zero(),
             block_id,
-            0, // TODO [Reconfiguration] use the real epoch number.
+            epoch as u64,
             block.timestamp_usecs(),
-        )
+        );
+        ledger_info.set_next_validator_set(next_validator_set);
+        ledger_info
     }
 
     /// Used in case we're using a ledger info just as a placeholder for signing the votes / QCs
     /// and there is no real block committed.
     /// It's all pretty much zeroes.
-    fn zero_ledger_info_placeholder() -> LedgerInfo {
+    pub fn zero_ledger_info_placeholder() -> LedgerInfo {
         LedgerInfo::new(
             0,
             HashValue::zero(),
@@ -448,7 +673,7 @@ impl<T: Payload> BlockReader for BlockStore<T> {
         payload: Self::Payload,
         round: Round,
         timestamp_usecs: u64,
-    ) -> Block<Self::Payload> {
+    ) -> BlockData<Self::Payload> {
         if self.enforce_increasing_timestamps {
             checked_precondition!(parent.timestamp_usecs() < timestamp_usecs);
         }
@@ -457,13 +682,13 @@ impl<T: Payload> BlockReader for BlockStore<T> {
             .expect("Parent for the newly created block is not certified!")
             .as_ref()
             .clone();
-        Block::make_block(
+        Block::make_block_data(
             parent.as_ref(),
             payload,
             round,
             timestamp_usecs,
             quorum_cert,
-            &self.validator_signer,
+            self.author,
         )
     }
 
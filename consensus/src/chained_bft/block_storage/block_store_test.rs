@@ -1,24 +1,41 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::{
-    block_storage::{BlockReader, BlockStore, InsertError, NeedFetchResult, VoteReceptionResult},
-    common::Author,
-    consensus_types::{
-        block::{block_test, Block},
-        quorum_cert::QuorumCert,
-    },
-    safety::vote_msg::VoteMsg,
-    test_utils::{
-        build_empty_tree, build_empty_tree_with_custom_signing, placeholder_certificate_for_block,
-        placeholder_ledger_info, TreeInserter,
+use crate::{
+    chained_bft::{
+        block_storage::{
+            BlockReader, BlockStore, InsertError, NeedFetchResult, VoteReceptionResult,
+        },
+        common::{Author, Round},
+        consensus_types::{
+            block::{block_test, Block},
+            quorum_cert::QuorumCert,
+        },
+        safety::vote_msg::VoteMsg,
+        test_utils::{
+            build_empty_tree, build_empty_tree_with_custom_author,
+            placeholder_certificate_for_block, placeholder_ledger_info, test_executor,
+            EmptyStorage, TreeInserter,
+        },
     },
+    state_replication::{StateComputeResult, StateComputer},
 };
-use crypto::{HashValue, PrivateKey};
-use futures::executor::block_on;
+use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue, PrivateKey};
+use futures::{channel::oneshot, executor::block_on, future, Future, FutureExt};
 use proptest::prelude::*;
-use std::{cmp::min, collections::HashSet, sync::Arc};
-use types::{account_address::AccountAddress, crypto_proxies::ValidatorSigner};
+use std::{
+    cmp::min,
+    collections::HashSet,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use types::{
+    account_address::AccountAddress,
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner},
+    ledger_info::LedgerInfo,
+};
 
 fn build_simple_tree() -> (Vec<Arc<Block<Vec<usize>>>>, Arc<BlockStore<Vec<usize>>>) {
     let block_store = build_empty_tree();
@@ -51,27 +68,28 @@ fn build_simple_tree() -> (Vec<Arc<Block<Vec<usize>>>>, Arc<BlockStore<Vec<usize
 #[test]
 fn test_block_store_create_block() {
     let block_store = build_empty_tree();
+    let signer = ValidatorSigner::random(None);
     let genesis = block_store.root();
     let a1 = block_store.create_block(Arc::clone(&genesis), vec![1], 1, 1);
     assert_eq!(a1.parent_id(), genesis.id());
     assert_eq!(a1.round(), 1);
     assert_eq!(a1.height(), 1);
     assert_eq!(a1.quorum_cert().certified_block_id(), genesis.id());
+    assert_eq!(a1.author(), signer.author());
 
-    let a1_ref = block_on(block_store.execute_and_insert_block(a1)).unwrap();
+    let a1_ref = block_on(block_store.execute_and_insert_block(a1.sign(&signer))).unwrap();
 
     // certify a1
     let vote_msg = VoteMsg::new(
         a1_ref.id(),
-        block_store.get_state_for_block(a1_ref.id()).unwrap(),
         a1_ref.round(),
         a1_ref.quorum_cert().certified_parent_block_id(),
         a1_ref.quorum_cert().certified_parent_block_round(),
         a1_ref.quorum_cert().certified_grandparent_block_id(),
         a1_ref.quorum_cert().certified_grandparent_block_round(),
-        block_store.signer().author(),
+        signer.author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        &signer,
     );
     block_store.insert_vote_and_qc(vote_msg, 1);
 
@@ -175,7 +193,7 @@ proptest! {
         let authors: HashSet<Author> = private_keys.iter().map(|private_key| AccountAddress::from_public_key(&private_key.public_key())).collect();
         let priv_key = private_keys.pop().expect("several keypairs generated");
         let signer = ValidatorSigner::new(None, priv_key);
-        let block_store = build_empty_tree_with_custom_signing(signer);
+        let block_store = build_empty_tree_with_custom_author(signer.author());
         for block in blocks {
             if block.round() > 0 && authors.contains(&block.author().unwrap()) {
                 let known_parent = block_store.block_exists(block.parent_id());
@@ -330,9 +348,9 @@ fn test_insert_vote() {
         AccountAddress::from_public_key(&my_signer.public_key()),
         my_signer.public_key(),
     ));
-    let block_store = build_empty_tree_with_custom_signing(my_signer);
+    let block_store = build_empty_tree_with_custom_author(my_signer.author());
     let genesis = block_store.root();
-    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(block_store.clone(), my_signer);
     let block = inserter.insert_block(genesis.as_ref(), 1);
 
     assert!(block_store.get_quorum_cert_for_block(block.id()).is_none());
@@ -340,7 +358,6 @@ fn test_insert_vote() {
     for (i, voter) in signers.iter().enumerate().take(10).skip(1) {
         let vote_msg = VoteMsg::new(
             block.id(),
-            block_store.get_state_for_block(block.id()).unwrap(),
             block.round(),
             block.quorum_cert().certified_parent_block_id(),
             block.quorum_cert().certified_parent_block_round(),
@@ -367,7 +384,6 @@ fn test_insert_vote() {
     let final_voter = &signers[0];
     let vote_msg = VoteMsg::new(
         block.id(),
-        block_store.get_state_for_block(block.id()).unwrap(),
         block.round(),
         block.quorum_cert().certified_parent_block_id(),
         block.quorum_cert().certified_parent_block_round(),
@@ -390,6 +406,61 @@ fn test_insert_vote() {
     assert_eq!(block_qc.certified_block_id(), block.id());
 }
 
+#[test]
+fn test_votes_with_different_commit_info_are_not_aggregated() {
+    let signers: Vec<_> = (0..3)
+        .map(|i| ValidatorSigner::random([i as u8; 32]))
+        .collect();
+    let block_store = build_empty_tree();
+    let genesis = block_store.root();
+    let mut inserter = TreeInserter::new(block_store.clone());
+    let block = inserter.insert_block(genesis.as_ref(), 1);
+
+    let vote = |voter: &ValidatorSigner, commit_info: LedgerInfo| {
+        VoteMsg::new(
+            block.id(),
+            block.round(),
+            block.quorum_cert().certified_parent_block_id(),
+            block.quorum_cert().certified_parent_block_round(),
+            block.quorum_cert().certified_grandparent_block_id(),
+            block.quorum_cert().certified_grandparent_block_round(),
+            voter.author(),
+            commit_info,
+            voter,
+        )
+    };
+    let other_commit_info = LedgerInfo::new(
+        1,
+        HashValue::random(),
+        HashValue::zero(),
+        HashValue::zero(),
+        0,
+        0,
+    );
+
+    // The votes certify the same block, but only the matching commit infos add up.
+    assert_eq!(
+        block_store.insert_vote_and_qc(vote(&signers[0], placeholder_ledger_info()), 2),
+        VoteReceptionResult::VoteAdded(1)
+    );
+    assert_eq!(
+        block_store.insert_vote_and_qc(vote(&signers[1], other_commit_info), 2),
+        VoteReceptionResult::VoteAdded(1)
+    );
+    match block_store.insert_vote_and_qc(vote(&signers[2], placeholder_ledger_info()), 2) {
+        VoteReceptionResult::NewQuorumCertificate(qc) => {
+            assert_eq!(qc.certified_block_id(), block.id());
+            assert_eq!(
+                qc.ledger_info()
+                    .ledger_info()
+                    .transaction_accumulator_hash(),
+                HashValue::zero()
+            );
+        }
+        _ => panic!("QC not formed!"),
+    }
+}
+
 #[test]
 fn test_illegal_timestamp() {
     let block_store = build_empty_tree();
@@ -402,7 +473,7 @@ fn test_illegal_timestamp() {
         // This timestamp is illegal, it is the same as genesis
         genesis.timestamp_usecs(),
         QuorumCert::certificate_for_genesis(),
-        block_store.signer(),
+        &ValidatorSigner::random(None),
     );
     let result = block_on(block_store.execute_and_insert_block(block_with_illegal_timestamp));
     assert!(result.is_err());
@@ -438,7 +509,7 @@ fn test_need_fetch_for_qc() {
     let a3 = inserter.insert_block(a2.as_ref(), 3);
     block_tree.prune_tree(a2.id());
     let need_fetch_qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 1,
         HashValue::zero(),
@@ -448,7 +519,7 @@ fn test_need_fetch_for_qc() {
     );
     let too_old_qc = QuorumCert::certificate_for_genesis();
     let can_insert_qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         a3.id(),
         a3.round(),
         a2.id(),
@@ -488,7 +559,7 @@ fn test_need_sync_for_qc() {
     let a3 = inserter.insert_block(a2.as_ref(), 3);
     block_tree.prune_tree(a3.id());
     let qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 3,
         HashValue::zero(),
@@ -501,7 +572,7 @@ fn test_need_sync_for_qc() {
         true
     );
     let qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 2,
         HashValue::zero(),
@@ -518,3 +589,158 @@ fn test_need_sync_for_qc() {
         false
     );
 }
+
+fn make_child_block(parent: &Block<Vec<usize>>, round: Round) -> Block<Vec<usize>> {
+    let signer = ValidatorSigner::random(None);
+    let parent_qc = placeholder_certificate_for_block(
+        vec![&signer],
+        parent.id(),
+        parent.round(),
+        parent.quorum_cert().certified_block_id(),
+        parent.quorum_cert().certified_block_round(),
+        parent.quorum_cert().certified_parent_block_id(),
+        parent.quorum_cert().certified_parent_block_round(),
+    );
+    Block::make_block(
+        parent,
+        vec![100 + round as usize],
+        round,
+        parent.timestamp_usecs() + 1,
+        parent_qc,
+        &signer,
+    )
+}
+
+#[test]
+fn test_speculative_execution() {
+    let block_store = build_empty_tree();
+    let genesis = block_store.root();
+    // Genesis -> A1 -> ... -> A6
+    let mut chain = vec![];
+    let mut parent = genesis.as_ref().clone();
+    for round in 1..=6 {
+        let block = make_child_block(&parent, round);
+        parent = block.clone();
+        chain.push(block);
+    }
+    // The parent of the orphan block is neither inserted nor executed.
+    let unknown = make_child_block(genesis.as_ref(), 10);
+    let orphan = make_child_block(&unknown, 11);
+
+    // A block is executed speculatively once its parent is either inserted or being executed,
+    // more than the max number of speculative executions in flight wait for the older ones.
+    for block in chain.iter().chain(std::iter::once(&orphan)) {
+        block_on(block_store.execute_speculatively(block));
+    }
+    assert_eq!(block_store.num_pending_executions(), chain.len());
+    block_on(block_store.execute_speculatively(&chain[0]));
+    assert_eq!(block_store.num_pending_executions(), chain.len());
+
+    // The insertions take the results of the speculative executions.
+    for (i, block) in chain.iter().enumerate() {
+        block_on(block_store.execute_and_insert_block(block.clone())).unwrap();
+        assert_eq!(block_store.num_pending_executions(), chain.len() - i - 1);
+    }
+    // Nothing is executed speculatively for the blocks that are already inserted.
+    block_on(block_store.execute_speculatively(&chain[5]));
+    assert_eq!(block_store.num_pending_executions(), 0);
+}
+
+#[test]
+fn test_speculative_execution_prune() {
+    let (blocks, block_store) = build_simple_tree();
+    // A competing block at the round of A2 and a child of A3.
+    let a2_prime = make_child_block(&blocks[1], 2);
+    let a4 = make_child_block(&blocks[3], 7);
+    block_on(block_store.execute_speculatively(&a2_prime));
+    block_on(block_store.execute_speculatively(&a4));
+    assert_eq!(block_store.num_pending_executions(), 2);
+
+    // Once A2 becomes the root the competing block can not be inserted anymore.
+    block_store.prune_tree(blocks[2].id());
+    assert_eq!(block_store.num_pending_executions(), 1);
+    block_on(block_store.execute_and_insert_block(a4)).unwrap();
+    assert_eq!(block_store.num_pending_executions(), 0);
+}
+
+/// Records the blocks it is asked to execute, the first execution only completes once released.
+struct GatedStateComputer {
+    requests: Mutex<Vec<HashValue>>,
+    gate: Mutex<Option<oneshot::Receiver<()>>>,
+}
+
+impl StateComputer for GatedStateComputer {
+    type Payload = Vec<usize>;
+
+    fn compute(
+        &self,
+        _parent_id: HashValue,
+        block_id: HashValue,
+        _transactions: &Self::Payload,
+    ) -> Pin<Box<dyn Future<Output = failure::Result<StateComputeResult>> + Send>> {
+        self.requests.lock().unwrap().push(block_id);
+        let gate = self.gate.lock().unwrap().take();
+        async move {
+            if let Some(gate) = gate {
+                let _ = gate.await;
+            }
+            Ok(StateComputeResult {
+                new_state_id: *ACCUMULATOR_PLACEHOLDER_HASH,
+                compute_status: vec![],
+                num_successful_txns: 0,
+                validators: None,
+            })
+        }
+            .boxed()
+    }
+
+    fn commit(
+        &self,
+        _commit: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = failure::Result<()>> + Send>> {
+        future::ok(()).boxed()
+    }
+
+    fn sync_to(
+        &self,
+        _commit: QuorumCert,
+    ) -> Pin<Box<dyn Future<Output = failure::Result<bool>> + Send>> {
+        future::ok(true).boxed()
+    }
+}
+
+#[test]
+fn test_speculative_execution_waits_for_parent() {
+    let (release, gate) = oneshot::channel();
+    let state_computer = Arc::new(GatedStateComputer {
+        requests: Mutex::new(vec![]),
+        gate: Mutex::new(Some(gate)),
+    });
+    let (storage, initial_data) = EmptyStorage::start_for_testing();
+    let block_store: BlockStore<Vec<usize>> = block_on(BlockStore::new(
+        storage,
+        initial_data,
+        ValidatorSigner::random(None).author(),
+        state_computer.clone(),
+        true,
+        10,
+        4,
+        test_executor(),
+    ));
+    let a1 = make_child_block(block_store.root().as_ref(), 1);
+    let a2 = make_child_block(&a1, 2);
+    block_on(block_store.execute_speculatively(&a1));
+    block_on(block_store.execute_speculatively(&a2));
+
+    // The child is not submitted to the state computer while its parent is being executed.
+    thread::sleep(Duration::from_millis(100));
+    assert!(!state_computer.requests.lock().unwrap().contains(&a2.id()));
+
+    release.send(()).unwrap();
+    block_on(block_store.execute_and_insert_block(a1.clone())).unwrap();
+    block_on(block_store.execute_and_insert_block(a2.clone())).unwrap();
+    assert_eq!(
+        *state_computer.requests.lock().unwrap(),
+        vec![a1.id(), a2.id()]
+    );
+}
@@ -12,7 +12,7 @@ use crate::{
     util::time_service::duration_since_epoch,
 };
 use canonical_serialization::CanonicalSerialize;
-use crypto::HashValue;
+use crypto::{hash::CryptoHash, HashValue};
 use logger::prelude::*;
 use mirai_annotations::checked_verify_eq;
 use serde::Serialize;
@@ -57,10 +57,11 @@ pub struct BlockTree<T> {
 
     /// `id_to_votes` might keep multiple LedgerInfos per proposed block in order
     /// to tolerate non-determinism in execution: given a proposal, a QuorumCertificate is going
-    /// to be collected only for all the votes that have identical state id.
-    /// The vote digest is a hash that covers both the proposal id and the state id.
+    /// to be collected only for all the votes that commit the same state.
+    /// The vote digest is the hash of the LedgerInfo, which covers both the vote and the
+    /// committed state.
     /// Thus, the structure of `id_to_votes` is as follows:
-    /// HashMap<proposed_block_id, HashMap<vote_digest, LedgerInfoWithSignatures>>
+    /// HashMap<proposed_block_id, HashMap<ledger_info_digest, LedgerInfoWithSignatures>>
     id_to_votes: HashMap<HashValue, HashMap<HashValue, LedgerInfoWithSignatures>>,
     /// Map of block id to its completed quorum certificate (2f + 1 votes)
     id_to_quorum_cert: HashMap<HashValue, Arc<QuorumCert>>,
@@ -101,7 +102,12 @@ where
         );
 
         let mut id_to_state = HashMap::new();
-        id_to_state.insert(root.id(), root_quorum_cert.certified_state());
+        id_to_state.insert(
+            root.id(),
+            root_ledger_info
+                .committed_state()
+                .expect("Root ledger info must commit the root"),
+        );
 
         let pruned_block_ids = VecDeque::with_capacity(max_pruned_blocks_in_mem);
 
@@ -263,10 +269,11 @@ where
             .entry(block_id)
             .or_insert_with(HashMap::new);
 
-        // Note that the digest covers not just the proposal id, but also the resulting
-        // state id as well as the round number. In other words, if two different voters have the
-        // same digest then they reached the same state following the same proposals.
-        let digest = vote_msg.vote_hash();
+        // Note that the digest covers the whole LedgerInfo: not just the vote (the proposal id,
+        // its round and its ancestors) but also the committed state. In other words, if two
+        // different voters have the same digest then they agree on the ordering of the same
+        // proposals and reached the same committed state.
+        let digest = vote_msg.ledger_info().hash();
         let li_with_sig = block_votes.entry(digest).or_insert_with(|| {
             LedgerInfoWithSignatures::new(vote_msg.ledger_info().clone(), HashMap::new())
         });
@@ -280,7 +287,6 @@ where
         if num_votes >= min_votes_for_qc {
             let quorum_cert = QuorumCert::new(
                 block_id,
-                vote_msg.executed_state(),
                 vote_msg.round(),
                 li_with_sig.clone(),
                 vote_msg.parent_block_id(),
@@ -3,7 +3,7 @@
 
 use crate::chained_bft::{
     common::{Author, Round},
-    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    consensus_types::{block::{Block, BlockData}, quorum_cert::QuorumCert},
 };
 use crypto::HashValue;
 use std::sync::Arc;
@@ -180,10 +180,9 @@ pub trait BlockReader: Send + Sync {
         block: Arc<Block<Self::Payload>>,
     ) -> Option<Vec<Arc<Block<Self::Payload>>>>;
 
-    /// Generates and returns a block with the given parent and payload.
+    /// Generates and returns the content of a block with the given parent and payload, to be
+    /// signed by SafetyRules.
     /// Note that it does not add the block to the tree, just generates it.
-    /// The main reason we want this function in the BlockStore is the fact that the signer required
-    /// for signing the newly created block is held by the block store.
     /// The function panics in the following cases:
     /// * If the parent or its quorum certificate are not present in the tree,
     /// * If the given round (which is typically calculated by Pacemaker) is not greater than that
@@ -194,7 +193,7 @@ pub trait BlockReader: Send + Sync {
         payload: Self::Payload,
         round: Round,
         timestamp_usecs: u64,
-    ) -> Block<Self::Payload>;
+    ) -> BlockData<Self::Payload>;
 
     /// Return the certified block with the highest round.
     fn highest_certified_block(&self) -> Arc<Block<Self::Payload>>;
@@ -3,44 +3,76 @@
 
 use crate::{
     chained_bft::{
-        chained_bft_smr::ChainedBftSMR, network::ConsensusNetworkImpl,
-        persistent_storage::PersistentStorage,
+        batching::{
+            BatchFetcher, BatchStore, BatchingCommitObserver, BatchingStateComputer,
+            BatchingTxnManager,
+        },
+        chained_bft_smr::ChainedBftSMR,
+        common::Payload,
+        consensus_types::batch::BatchInfo,
+        network::ConsensusNetworkImpl,
+        persistent_storage::{PersistentStorage, RecoveryData},
     },
+    committed_block_publisher::CommittedBlockPublisher,
     consensus_provider::ConsensusProvider,
-    counters,
     state_computer::ExecutionProxy,
     state_replication::StateMachineReplication,
     txn_manager::MempoolProxy,
+    util::time_service::ClockTimeService,
 };
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 
 use crate::chained_bft::{
-    chained_bft_smr::ChainedBftSMRConfig, common::Author, epoch_manager::EpochManager,
+    chained_bft_smr::{proposers_for_epoch, ChainedBftSMRConfig},
+    common::Author,
+    epoch_manager::EpochManager,
+    evidence_reporter::EvidenceReporter,
     persistent_storage::StorageWriteProxy,
 };
-use config::config::{ConsensusProposerType::FixedProposer, NodeConfig};
+use config::config::{BatchingConfig, NodeConfig, SafetyRulesBackend};
+use debug_interface::node_debug_service::ConsensusEvidenceProvider;
 use execution_proto::proto::execution_grpc::ExecutionClient;
 use failure::prelude::*;
+use futures::{FutureExt, TryFutureExt};
 use logger::prelude::*;
 use mempool::proto::mempool_grpc::MempoolClient;
 use state_synchronizer::StateSyncClient;
-use std::{convert::TryFrom, sync::Arc};
-use tokio::runtime;
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+use tokio::runtime::{self, TaskExecutor};
 use types::{
     account_address::AccountAddress,
+    committed_block::CommittedBlockSource,
     crypto_proxies::{ValidatorSigner, ValidatorVerifier},
     transaction::SignedTransaction,
 };
 
 struct InitialSetup {
     author: Author,
-    signer: ValidatorSigner,
+    signer: Option<ValidatorSigner>,
     validator: ValidatorVerifier,
 }
 
+/// The state machine replication of the node: the blocks either carry the transactions or refer
+/// to the batches disseminated ahead of the proposals.
+enum ConsensusSMR {
+    Transactions(ChainedBftSMR<Vec<SignedTransaction>>),
+    Batches {
+        smr: ChainedBftSMR<Vec<BatchInfo>>,
+        author: Author,
+        network: ConsensusNetworkImpl,
+        batch_store: Arc<BatchStore>,
+        executor: TaskExecutor,
+        config: BatchingConfig,
+        max_block_size: u64,
+        privileged_lane_size: u64,
+    },
+}
+
 /// Supports the implementation of ConsensusProvider using LibraBFT.
 pub struct ChainedBftProvider {
-    smr: ChainedBftSMR<Vec<SignedTransaction>>,
+    smr: ConsensusSMR,
+    evidence_reporter: Arc<dyn ConsensusEvidenceProvider>,
+    committed_blocks: Arc<CommittedBlockPublisher>,
     mempool_client: Arc<MempoolClient>,
     execution_client: Arc<ExecutionClient>,
     synchronizer_client: Arc<StateSyncClient>,
@@ -68,42 +100,103 @@ impl ChainedBftProvider {
             network_events,
             Arc::clone(&epoch_mgr),
         );
-        let proposer = {
-            let peers = epoch_mgr.validators().get_ordered_account_addresses();
-            if node_config.consensus.get_proposer_type() == FixedProposer {
-                vec![Self::choose_leader(peers)]
-            } else {
-                peers
-            }
-        };
+        let proposer = proposers_for_epoch(
+            epoch_mgr.validators().as_ref(),
+            node_config.consensus.get_proposer_type(),
+        );
         debug!("[Consensus] My peer: {:?}", initial_setup.author);
         debug!("[Consensus] Chosen proposer: {:?}", proposer);
         let config = ChainedBftSMRConfig::from_node_config(&node_config.consensus);
-        let (storage, initial_data) = StorageWriteProxy::start(node_config);
-        info!(
-            "Starting up the consensus state machine with recovery data - {:?}, {:?}",
-            initial_data.state(),
-            initial_data.highest_timeout_certificates()
-        );
-        let smr = ChainedBftSMR::new(
-            initial_setup.author,
-            initial_setup.signer,
-            proposer,
-            network,
-            runtime,
-            config,
-            storage,
-            initial_data,
-            epoch_mgr,
-        );
+        let batching_config = node_config.consensus.batching().clone();
+        let committed_blocks = Arc::new(CommittedBlockPublisher::new());
+        let (smr, evidence_reporter) = if batching_config.enabled {
+            let (storage, initial_data) = Self::start_storage(node_config);
+            let evidence_reporter: Arc<dyn ConsensusEvidenceProvider> = Arc::new(
+                EvidenceReporter::<Vec<BatchInfo>>::new(storage.consensus_db()),
+            );
+            // The batches of the recovered blocks are recovered along with them.
+            let batch_store = Arc::new(
+                BatchStore::new(batching_config.max_batches_in_mem)
+                    .with_committed_batches(batching_config.max_committed_batches_in_mem)
+                    .with_db(storage.consensus_db()),
+            );
+            let executor = runtime.executor();
+            let mut smr = ChainedBftSMR::new(
+                initial_setup.author,
+                initial_setup.signer,
+                proposer,
+                network.clone(),
+                Arc::clone(&batch_store),
+                runtime,
+                config,
+                storage,
+                initial_data,
+                epoch_mgr,
+            );
+            smr.set_commit_observer(Arc::new(BatchingCommitObserver::<SignedTransaction>::new(
+                committed_blocks.clone(),
+                Arc::clone(&batch_store),
+            )));
+            // The privileged lane is reserved in each batch.
+            let privileged_lane_size = (batching_config.max_batch_size
+                * node_config.consensus.privileged_lane_percent()
+                + 99)
+                / 100;
+            let smr = ConsensusSMR::Batches {
+                smr,
+                author: initial_setup.author,
+                network,
+                batch_store,
+                executor,
+                config: batching_config,
+                max_block_size: node_config.consensus.max_block_size(),
+                privileged_lane_size,
+            };
+            (smr, evidence_reporter)
+        } else {
+            let (storage, initial_data) = Self::start_storage(node_config);
+            let evidence_reporter: Arc<dyn ConsensusEvidenceProvider> = Arc::new(
+                EvidenceReporter::<Vec<SignedTransaction>>::new(storage.consensus_db()),
+            );
+            let mut smr = ChainedBftSMR::new(
+                initial_setup.author,
+                initial_setup.signer,
+                proposer,
+                network,
+                Arc::new(BatchStore::new(batching_config.max_batches_in_mem)),
+                runtime,
+                config,
+                storage,
+                initial_data,
+                epoch_mgr,
+            );
+            smr.set_commit_observer(committed_blocks.clone());
+            (ConsensusSMR::Transactions(smr), evidence_reporter)
+        };
         Self {
             smr,
+            evidence_reporter,
+            committed_blocks,
             mempool_client,
             execution_client,
             synchronizer_client,
         }
     }
 
+    /// Recovers the consensus data of the given payload type from ConsensusDB. The epoch it
+    /// carries is restored by the SMR once started.
+    fn start_storage<T: Payload>(
+        node_config: &NodeConfig,
+    ) -> (Arc<StorageWriteProxy>, RecoveryData<T>) {
+        let (storage, initial_data) = StorageWriteProxy::start(node_config);
+        info!(
+            "Starting up the consensus state machine with recovery data - {:?}, {:?}",
+            initial_data.state(),
+            initial_data.highest_timeout_certificates()
+        );
+        (storage, initial_data)
+    }
+
     /// Retrieve the initial "state" for consensus. This function is synchronous and returns after
     /// reading the local persistent store and retrieving the initial state from the executor.
     fn initialize_setup(node_config: &mut NodeConfig) -> InitialSetup {
@@ -112,22 +205,25 @@ impl ChainedBftProvider {
         let peer_id_str = node_config.network.peer_id.clone();
         let author =
             AccountAddress::try_from(peer_id_str).expect("Failed to parse peer id of a validator");
-        let private_key = node_config
-            .network
-            .peer_keypairs
-            .take_consensus_private()
-            .expect(
-            "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
-        );
-        let signer = ValidatorSigner::new(author, private_key);
+        // A remote SafetyRules holds the signing key: consensus doesn't read it.
+        let signer = match node_config.consensus.safety_rules().get_backend() {
+            SafetyRulesBackend::Local => {
+                let private_key = node_config
+                    .network
+                    .peer_keypairs
+                    .take_consensus_private()
+                    .expect(
+                    "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
+                );
+                Some(ValidatorSigner::new(author, private_key))
+            }
+            SafetyRulesBackend::Remote => None,
+        };
         let peers_with_public_keys = node_config
             .network
             .trusted_peers
             .get_trusted_consensus_peers();
         let validator = ValidatorVerifier::new(peers_with_public_keys);
-        counters::EPOCH_NUM.set(0); // No reconfiguration yet, so it is always zero
-        counters::CURRENT_EPOCH_NUM_VALIDATORS.set(validator.len() as i64);
-        counters::CURRENT_EPOCH_QUORUM_SIZE.set(validator.quorum_size() as i64);
         debug!("[Consensus]: quorum_size = {:?}", validator.quorum_size());
         InitialSetup {
             author,
@@ -135,14 +231,6 @@ impl ChainedBftProvider {
             validator,
         }
     }
-
-    /// Choose a proposer that is going to be the single leader (relevant for a mock fixed proposer
-    /// election only).
-    fn choose_leader(peers: Vec<Author>) -> Author {
-        // As it is just a tmp hack function, pick the max PeerId to be a proposer.
-        // TODO: VRF will be integrated later.
-        peers.into_iter().max().expect("No trusted peers found!")
-    }
 }
 
 impl ConsensusProvider for ChainedBftProvider {
@@ -153,11 +241,65 @@ impl ConsensusProvider for ChainedBftProvider {
             self.synchronizer_client.clone(),
         ));
         debug!("Starting consensus provider.");
-        self.smr.start(txn_manager, state_computer)
+        match &mut self.smr {
+            ConsensusSMR::Transactions(smr) => smr.start(txn_manager, state_computer),
+            ConsensusSMR::Batches {
+                smr,
+                author,
+                network,
+                batch_store,
+                executor,
+                config,
+                max_block_size,
+                privileged_lane_size,
+            } => {
+                let batch_fetcher = BatchFetcher::new(
+                    Arc::clone(batch_store),
+                    network.clone(),
+                    Duration::from_millis(config.batch_request_timeout_ms),
+                );
+                let txn_manager = Arc::new(
+                    BatchingTxnManager::<SignedTransaction>::new(
+                        *author,
+                        txn_manager,
+                        Arc::clone(batch_store),
+                        batch_fetcher.clone(),
+                        network.clone(),
+                        config.max_batch_size,
+                        *max_block_size,
+                        config.max_batches_per_block,
+                    )
+                    .with_privileged_lane_size(*privileged_lane_size),
+                );
+                let state_computer = Arc::new(BatchingStateComputer::new(
+                    state_computer,
+                    Arc::clone(batch_store),
+                    batch_fetcher,
+                ));
+                let disseminate = BatchingTxnManager::disseminate(
+                    Arc::clone(&txn_manager),
+                    Arc::new(ClockTimeService::new(executor.clone())),
+                    Duration::from_millis(config.batch_interval_ms),
+                );
+                executor.spawn(disseminate.boxed().unit_error().compat());
+                smr.start(txn_manager, state_computer)
+            }
+        }
     }
 
     fn stop(&mut self) {
-        self.smr.stop();
+        match &mut self.smr {
+            ConsensusSMR::Transactions(smr) => smr.stop(),
+            ConsensusSMR::Batches { smr, .. } => smr.stop(),
+        }
         debug!("Consensus provider stopped.");
     }
+
+    fn consensus_evidence(&self) -> Arc<dyn ConsensusEvidenceProvider> {
+        self.evidence_reporter.clone()
+    }
+
+    fn committed_blocks(&self) -> Arc<dyn CommittedBlockSource> {
+        self.committed_blocks.clone()
+    }
 }
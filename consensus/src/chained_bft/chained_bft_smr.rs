@@ -3,12 +3,16 @@
 
 use crate::{
     chained_bft::{
+        batching::BatchStore,
         block_storage::BlockStore,
         common::{Payload, Round},
         event_processor::EventProcessor,
         liveness::{
+            adaptive_time_interval::AdaptiveTimeInterval,
+            committed_history::CommittedHistory,
+            leader_reputation::{LeaderReputation, HISTORY_SIZE, WINDOW_SIZE},
             multi_proposer_election::MultiProposer,
-            pacemaker::{ExponentialTimeInterval, Pacemaker},
+            pacemaker::{ExponentialTimeInterval, Pacemaker, PacemakerTimeInterval},
             pacemaker_timeout_manager::HighestTimeoutCertificates,
             proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
@@ -16,10 +20,13 @@ use crate::{
         },
         network::ConsensusNetworkImpl,
         persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
-        safety::safety_rules::SafetyRules,
+        safety::{
+            local_safety_rules::LocalSafetyRules, remote_safety_rules::RemoteSafetyRules,
+            t_safety_rules::TSafetyRules,
+        },
     },
     counters,
-    state_replication::{StateComputer, StateMachineReplication, TxnManager},
+    state_replication::{CommitObserver, StateComputer, StateMachineReplication, TxnManager},
     util::time_service::{ClockTimeService, TimeService},
 };
 use channel;
@@ -33,16 +40,25 @@ use futures::{
 };
 
 use crate::chained_bft::{common::Author, epoch_manager::EpochManager};
-use config::config::{ConsensusConfig, ConsensusProposerType};
+use config::config::{
+    AdaptivePacemakerConfig, ConsensusConfig, ConsensusProposerType, SafetyRulesBackend,
+    SafetyRulesConfig,
+};
 use logger::prelude::*;
 use std::{sync::Arc, time::Duration};
 use tokio::runtime::{Runtime, TaskExecutor};
-use types::crypto_proxies::ValidatorSigner;
+use types::{
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    validator_set::ValidatorSet,
+};
 
 /// Consensus configuration derived from ConsensusConfig
+#[derive(Clone)]
 pub struct ChainedBftSMRConfig {
     /// Keep up to this number of committed blocks before cleaning them up from the block store.
     pub max_pruned_blocks_in_mem: usize,
+    /// Max number of proposed blocks executed speculatively at the same time.
+    pub max_pending_executions: usize,
     /// Initial timeout for pacemaker
     pub pacemaker_initial_timeout: Duration,
     /// Consensus proposer type
@@ -51,6 +67,13 @@ pub struct ChainedBftSMRConfig {
     pub contiguous_rounds: u32,
     /// Max block size (number of transactions) that consensus pulls from mempool
     pub max_block_size: u64,
+    /// Number of the transactions of a block reserved for the mempool's privileged senders
+    pub privileged_lane_size: u64,
+    /// Whether SafetyRules runs within consensus or as a standalone service
+    pub safety_rules: SafetyRulesConfig,
+    /// Whether the round timeouts adapt to the observed QC latencies (starting from
+    /// pacemaker_initial_timeout), and how
+    pub adaptive_pacemaker: AdaptivePacemakerConfig,
 }
 
 impl ChainedBftSMRConfig {
@@ -58,14 +81,34 @@ impl ChainedBftSMRConfig {
         let pacemaker_initial_timeout_ms = cfg.pacemaker_initial_timeout_ms().unwrap_or(1000);
         ChainedBftSMRConfig {
             max_pruned_blocks_in_mem: cfg.max_pruned_blocks_in_mem().unwrap_or(10000) as usize,
+            max_pending_executions: cfg.max_pending_executions(),
             pacemaker_initial_timeout: Duration::from_millis(pacemaker_initial_timeout_ms),
             proposer_type: cfg.get_proposer_type(),
             contiguous_rounds: cfg.contiguous_rounds(),
             max_block_size: cfg.max_block_size(),
+            privileged_lane_size: (cfg.max_block_size() * cfg.privileged_lane_percent() + 99) / 100,
+            safety_rules: cfg.safety_rules().clone(),
+            adaptive_pacemaker: cfg.adaptive_pacemaker().clone(),
         }
     }
 }
 
+/// Returns the proposers of an epoch with the given validators.
+pub fn proposers_for_epoch(
+    validators: &ValidatorVerifier,
+    proposer_type: ConsensusProposerType,
+) -> Vec<Author> {
+    let peers = validators.get_ordered_account_addresses();
+    if proposer_type == ConsensusProposerType::FixedProposer {
+        // Choose a proposer that is going to be the single leader (relevant for a mock fixed
+        // proposer election only): as it is just a tmp hack, pick the max PeerId.
+        // TODO: VRF will be integrated later.
+        vec![peers.into_iter().max().expect("No trusted peers found!")]
+    } else {
+        peers
+    }
+}
+
 /// ChainedBFTSMR is the one to generate the components (BlockStore, Proposer, etc.) and start the
 /// driver. ChainedBftSMR implements the StateMachineReplication, it is going to be used by
 /// ConsensusProvider for the e2e flow.
@@ -76,18 +119,23 @@ pub struct ChainedBftSMR<T> {
     runtime: Option<Runtime>,
     block_store: Option<Arc<BlockStore<T>>>,
     network: ConsensusNetworkImpl,
+    // Keeps the transaction batches disseminated by the peers, empty unless the payloads refer to
+    // batches.
+    batch_store: Arc<BatchStore>,
     config: ChainedBftSMRConfig,
     storage: Arc<dyn PersistentStorage<T>>,
     initial_data: Option<RecoveryData<T>>,
     epoch_mgr: Arc<EpochManager>,
+    commit_observer: Option<Arc<dyn CommitObserver<Payload = T>>>,
 }
 
 impl<T: Payload> ChainedBftSMR<T> {
     pub fn new(
         author: Author,
-        signer: ValidatorSigner,
+        signer: Option<ValidatorSigner>,
         proposers: Vec<Author>,
         network: ConsensusNetworkImpl,
+        batch_store: Arc<BatchStore>,
         runtime: Runtime,
         config: ChainedBftSMRConfig,
         storage: Arc<dyn PersistentStorage<T>>,
@@ -96,98 +144,107 @@ impl<T: Payload> ChainedBftSMR<T> {
     ) -> Self {
         Self {
             author,
-            signer: Some(signer),
+            signer,
             proposers,
             runtime: Some(runtime),
             block_store: None,
             network,
+            batch_store,
             config,
             storage,
             initial_data: Some(initial_data),
             epoch_mgr,
+            commit_observer: None,
         }
     }
 
+    /// Notifies the given observer about the committed blocks, to be called before `start`.
+    pub fn set_commit_observer(&mut self, commit_observer: Arc<dyn CommitObserver<Payload = T>>) {
+        self.commit_observer = Some(commit_observer);
+    }
+
     #[cfg(test)]
     pub fn block_store(&self) -> Option<Arc<BlockStore<T>>> {
         self.block_store.clone()
     }
 
-    fn create_pacemaker(
-        &self,
-        persistent_liveness_storage: Box<dyn PersistentLivenessStorage>,
-        time_service: Arc<dyn TimeService>,
-        timeout_sender: channel::Sender<Round>,
-        highest_timeout_certificate: HighestTimeoutCertificates,
-    ) -> Pacemaker {
-        // 1.5^6 ~= 11
-        // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            self.config.pacemaker_initial_timeout,
-            1.5,
-            6,
-        ));
-        Pacemaker::new(
-            persistent_liveness_storage,
-            time_interval,
-            time_service,
-            timeout_sender,
-            highest_timeout_certificate,
-        )
-    }
-
-    /// Create a proposer election handler based on proposers
-    fn create_proposer_election(&self) -> Box<dyn ProposerElection<T> + Send + Sync> {
-        assert!(!self.proposers.is_empty());
-        match self.config.proposer_type {
-            ConsensusProposerType::MultipleOrderedProposers => {
-                Box::new(MultiProposer::new(self.proposers.clone(), 2))
-            }
-            // We don't really have a fixed proposer!
-            _ => Box::new(RotatingProposer::new(
-                self.proposers.clone(),
-                self.config.contiguous_rounds,
-            )),
-        }
-    }
-
     fn start_event_processing(
         &mut self,
         executor: TaskExecutor,
+        epoch_components: EpochComponents<T>,
         mut event_processor: EventProcessor<T>,
         mut pacemaker_timeout_sender_rx: channel::Receiver<Round>,
     ) {
-        let mut network_receivers = self.network.start(&executor);
+        let mut network_receivers = self.network.start(&executor, Arc::clone(&self.batch_store));
         let fut = async move {
-            event_processor.start().await;
-            loop {
-                select! {
-                    proposal_msg = network_receivers.proposals.select_next_some() => {
-                        event_processor.process_proposal_msg(proposal_msg).await;
-                    }
-                    block_retrieval = network_receivers.block_retrieval.select_next_some() => {
-                        event_processor.process_block_retrieval(block_retrieval).await;
-                    }
-                    vote_msg = network_receivers.votes.select_next_some() => {
-                        event_processor.process_vote(vote_msg).await;
+            'epoch: loop {
+                event_processor.start().await;
+                loop {
+                    select! {
+                        proposal_msg = network_receivers.proposals.select_next_some() => {
+                            event_processor.process_proposal_msg(proposal_msg).await;
+                        }
+                        block_retrieval = network_receivers.block_retrieval.select_next_some() => {
+                            event_processor.process_block_retrieval(block_retrieval).await;
+                        }
+                        vote_msg = network_receivers.votes.select_next_some() => {
+                            event_processor.process_vote(vote_msg).await;
+                        }
+                        remote_timeout_msg = network_receivers.timeout_msgs.select_next_some() => {
+                            event_processor.process_remote_timeout_msg(remote_timeout_msg).await;
+                        }
+                        local_timeout_round = pacemaker_timeout_sender_rx.select_next_some() => {
+                            event_processor.process_local_timeout(local_timeout_round).await;
+                        }
+                        sync_info_msg = network_receivers.sync_info_msgs.select_next_some() => {
+                            event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await;
+                        }
+                        complete => {
+                            break 'epoch;
+                        }
                     }
-                    remote_timeout_msg = network_receivers.timeout_msgs.select_next_some() => {
-                        event_processor.process_remote_timeout_msg(remote_timeout_msg).await;
-                    }
-                    local_timeout_round = pacemaker_timeout_sender_rx.select_next_some() => {
-                        event_processor.process_local_timeout(local_timeout_round).await;
-                    }
-                    sync_info_msg = network_receivers.sync_info_msgs.select_next_some() => {
-                        event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await;
-                    }
-                    complete => {
-                        break;
+                    if let Some(epoch_change) = event_processor.take_epoch_change() {
+                        let (next_event_processor, next_timeout_receiver) = epoch_components
+                            .start_next_epoch(event_processor.into_safety_rules(), epoch_change);
+                        event_processor = next_event_processor;
+                        pacemaker_timeout_sender_rx = next_timeout_receiver;
+                        continue 'epoch;
                     }
                 }
             }
         };
         executor.spawn(fut.boxed().unit_error().compat());
     }
+
+    /// Switches the EpochManager to the latest epoch known to the storage: either the persisted
+    /// one or the one started by the root, in case the node stopped right after committing the
+    /// block ending the previous epoch.
+    fn recover_epoch(&mut self, initial_data: &RecoveryData<T>) {
+        let mut epoch_info = initial_data.epoch_info().cloned();
+        let root_ledger_info = initial_data.root_ledger_info();
+        let root_ledger_info = root_ledger_info.ledger_info().ledger_info();
+        if let Some(validators) = root_ledger_info.next_validator_set() {
+            let epoch = root_ledger_info.epoch_num() as usize + 1;
+            if epoch_info.as_ref().map_or(true, |(e, _)| *e < epoch) {
+                self.storage
+                    .save_epoch_info(epoch, validators)
+                    .expect("Failed to persist the new epoch");
+                epoch_info = Some((epoch, validators.clone()));
+            }
+        }
+        if let Some((epoch, validators)) = epoch_info {
+            if self
+                .epoch_mgr
+                .start_new_epoch(epoch, ValidatorVerifier::from(&validators))
+            {
+                block_on(self.network.update_eligible_nodes(&validators));
+                self.proposers = proposers_for_epoch(
+                    self.epoch_mgr.validators().as_ref(),
+                    self.config.proposer_type,
+                );
+            }
+        }
+    }
 }
 
 impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
@@ -209,14 +266,24 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             .initial_data
             .take()
             .expect("already started, initial data is None");
+        self.recover_epoch(&initial_data);
         let consensus_state = initial_data.state();
+        let committed_history = Arc::new(CommittedHistory::new(
+            initial_data.committed_history().to_vec(),
+            HISTORY_SIZE,
+        ));
         let highest_timeout_certificates = initial_data.highest_timeout_certificates().clone();
         if initial_data.need_sync() {
             loop {
                 // make sure we sync to the root state in case we're not
                 let status = block_on(state_computer.sync_to(initial_data.root_ledger_info()));
                 match status {
-                    Ok(true) => break,
+                    Ok(true) => {
+                        if let Some(commit_observer) = self.commit_observer.as_ref() {
+                            commit_observer.on_gap(initial_data.root_ledger_info().ledger_info());
+                        }
+                        break;
+                    }
                     Ok(false) => panic!(
                     "state synchronizer failure, this validator will be killed as it can not \
                  recover from this error.  After the validator is restarted, synchronization will \
@@ -232,71 +299,236 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             }
         }
 
-        // the signer is only stored in the SMR to be provided here
-        let signer = self
-            .signer
-            .take()
-            .expect("start called twice on the same Chained BFT SMR!");
+        // the signer is only stored in the SMR to be provided here, a remote SafetyRules holds
+        // it in its own process
+        let safety_rules: Box<dyn TSafetyRules<T>> = match self.config.safety_rules.get_backend() {
+            SafetyRulesBackend::Local => Box::new(LocalSafetyRules::new(
+                consensus_state,
+                self.signer
+                    .take()
+                    .expect("No signer for the local SafetyRules or start called twice!"),
+                Arc::clone(&self.storage),
+            )),
+            SafetyRulesBackend::Remote => Box::new(RemoteSafetyRules::new(
+                &self.config.safety_rules.address,
+                self.config.safety_rules.safety_rules_port,
+            )),
+        };
         let block_store = Arc::new(block_on(BlockStore::new(
             Arc::clone(&self.storage),
             initial_data,
-            signer,
+            self.author,
             Arc::clone(&state_computer),
             true,
             self.config.max_pruned_blocks_in_mem,
+            self.config.max_pending_executions,
+            executor.clone(),
         )));
 
         self.block_store = Some(Arc::clone(&block_store));
 
+        let epoch_components = EpochComponents {
+            author: self.author,
+            config: self.config.clone(),
+            block_store,
+            txn_manager,
+            state_computer,
+            network: self.network.clone(),
+            storage: Arc::clone(&self.storage),
+            time_service,
+            epoch_mgr: Arc::clone(&self.epoch_mgr),
+            commit_observer: self.commit_observer.clone(),
+            committed_history,
+        };
+        let (event_processor, timeout_receiver) = epoch_components.create_event_processor(
+            self.proposers.clone(),
+            safety_rules,
+            highest_timeout_certificates,
+        );
+
+        self.start_event_processing(
+            executor,
+            epoch_components,
+            event_processor,
+            timeout_receiver,
+        );
+
+        debug!("Chained BFT SMR started.");
+        Ok(())
+    }
+
+    /// Stop is synchronous: waits for all the worker threads to terminate.
+    fn stop(&mut self) {
+        if let Some(rt) = self.runtime.take() {
+            block_on(rt.shutdown_now().compat()).unwrap();
+            debug!("Chained BFT SMR stopped.")
+        }
+    }
+}
+
+/// The components shared by all the epochs, used to create the EventProcessor of an epoch along
+/// with the components that depend on the validator set (Pacemaker and ProposerElection).
+/// The BlockStore and SafetyRules are carried over: the first epoch after a reconfiguration
+/// extends the blocks of the previous one.
+struct EpochComponents<T> {
+    author: Author,
+    config: ChainedBftSMRConfig,
+    block_store: Arc<BlockStore<T>>,
+    txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
+    network: ConsensusNetworkImpl,
+    storage: Arc<dyn PersistentStorage<T>>,
+    time_service: Arc<dyn TimeService>,
+    epoch_mgr: Arc<EpochManager>,
+    commit_observer: Option<Arc<dyn CommitObserver<Payload = T>>>,
+    committed_history: Arc<CommittedHistory>,
+}
+
+impl<T: Payload> EpochComponents<T> {
+    fn create_pacemaker(
+        &self,
+        persistent_liveness_storage: Box<dyn PersistentLivenessStorage>,
+        timeout_sender: channel::Sender<Round>,
+        highest_timeout_certificate: HighestTimeoutCertificates,
+    ) -> Pacemaker {
+        // 1.5^6 ~= 11
+        // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
+        let time_interval: Box<dyn PacemakerTimeInterval> =
+            if self.config.adaptive_pacemaker.enabled {
+                Box::new(AdaptiveTimeInterval::new(
+                    self.config.pacemaker_initial_timeout,
+                    &self.config.adaptive_pacemaker,
+                    1.5,
+                    6,
+                ))
+            } else {
+                Box::new(ExponentialTimeInterval::new(
+                    self.config.pacemaker_initial_timeout,
+                    1.5,
+                    6,
+                ))
+            };
+        Pacemaker::new(
+            persistent_liveness_storage,
+            time_interval,
+            Arc::clone(&self.time_service),
+            timeout_sender,
+            highest_timeout_certificate,
+        )
+    }
+
+    /// Create a proposer election handler based on proposers
+    fn create_proposer_election(
+        &self,
+        proposers: Vec<Author>,
+    ) -> Box<dyn ProposerElection<T> + Send + Sync> {
+        assert!(!proposers.is_empty());
+        match self.config.proposer_type {
+            ConsensusProposerType::MultipleOrderedProposers => {
+                Box::new(MultiProposer::new(proposers, 2))
+            }
+            ConsensusProposerType::LeaderReputation => Box::new(LeaderReputation::new(
+                proposers,
+                Arc::clone(&self.committed_history),
+                WINDOW_SIZE,
+            )),
+            // We don't really have a fixed proposer!
+            _ => Box::new(RotatingProposer::new(
+                proposers,
+                self.config.contiguous_rounds,
+            )),
+        }
+    }
+
+    /// Creates the EventProcessor of the current epoch of the EpochManager along with the receiver
+    /// of its pacemaker timeouts.
+    fn create_event_processor(
+        &self,
+        proposers: Vec<Author>,
+        safety_rules: Box<dyn TSafetyRules<T>>,
+        highest_timeout_certificates: HighestTimeoutCertificates,
+    ) -> (EventProcessor<T>, channel::Receiver<Round>) {
         // txn manager is required both by proposal generator (to pull the proposers)
         // and by event processor (to update their status).
         let proposal_generator = ProposalGenerator::new(
-            block_store.clone(),
-            Arc::clone(&txn_manager),
-            time_service.clone(),
+            self.block_store.clone(),
+            Arc::clone(&self.txn_manager),
+            Arc::clone(&self.time_service),
             self.config.max_block_size,
             true,
-        );
-
-        let safety_rules = SafetyRules::new(consensus_state);
+        )
+        .with_privileged_lane_size(self.config.privileged_lane_size);
 
         let (timeout_sender, timeout_receiver) =
             channel::new(1_024, &counters::PENDING_PACEMAKER_TIMEOUTS);
         let pacemaker = self.create_pacemaker(
             self.storage.persistent_liveness_storage(),
-            time_service.clone(),
             timeout_sender,
             highest_timeout_certificates,
         );
 
-        let proposer_election = self.create_proposer_election();
-        let event_processor = EventProcessor::new(
+        let proposer_election = self.create_proposer_election(proposers);
+        let mut event_processor = EventProcessor::new(
             self.author,
-            Arc::clone(&block_store),
+            Arc::clone(&self.block_store),
             pacemaker,
             proposer_election,
             proposal_generator,
             safety_rules,
-            state_computer,
-            txn_manager,
+            Arc::clone(&self.state_computer),
+            Arc::clone(&self.txn_manager),
             self.network.clone(),
             Arc::clone(&self.storage),
-            time_service.clone(),
+            Arc::clone(&self.time_service),
             true,
             Arc::clone(&self.epoch_mgr),
         );
-
-        self.start_event_processing(executor, event_processor, timeout_receiver);
-
-        debug!("Chained BFT SMR started.");
-        Ok(())
+        if let Some(commit_observer) = self.commit_observer.as_ref() {
+            event_processor.set_commit_observer(Arc::clone(commit_observer));
+        }
+        event_processor.set_committed_history(Arc::clone(&self.committed_history));
+        (event_processor, timeout_receiver)
     }
 
-    /// Stop is synchronous: waits for all the worker threads to terminate.
-    fn stop(&mut self) {
-        if let Some(rt) = self.runtime.take() {
-            block_on(rt.shutdown_now().compat()).unwrap();
-            debug!("Chained BFT SMR stopped.")
+    /// Switches the EpochManager to the next epoch and restarts the components depending on the
+    /// validator set. SafetyRules is carried over so that rounds keep increasing and no round is
+    /// voted twice, the timeout certificates of the previous epoch are dropped.
+    /// In case the EpochManager is already past the given epoch, the components are restarted
+    /// with its current epoch: the EventProcessor that ended the epoch is stale either way.
+    fn start_next_epoch(
+        &self,
+        safety_rules: Box<dyn TSafetyRules<T>>,
+        (epoch, validators): (usize, ValidatorSet),
+    ) -> (EventProcessor<T>, channel::Receiver<Round>) {
+        if self
+            .epoch_mgr
+            .start_new_epoch(epoch, ValidatorVerifier::from(&validators))
+        {
+            // A node restarting with the validators of a past epoch can't make progress.
+            self.storage
+                .save_epoch_info(epoch, &validators)
+                .expect("Failed to persist the new epoch");
+        } else {
+            warn!(
+                "Epoch manager is already at epoch {}, ignoring the switch to epoch {}",
+                self.epoch_mgr.epoch(),
+                epoch
+            );
         }
+        let epoch = self.epoch_mgr.epoch();
+        let proposers = proposers_for_epoch(
+            self.epoch_mgr.validators().as_ref(),
+            self.config.proposer_type,
+        );
+        info!(
+            "Restarting event processing for epoch {}, proposers: {:?}",
+            epoch,
+            proposers.iter().map(Author::short_str).collect::<Vec<_>>()
+        );
+        self.create_event_processor(
+            proposers,
+            safety_rules,
+            HighestTimeoutCertificates::default(),
+        )
     }
 }
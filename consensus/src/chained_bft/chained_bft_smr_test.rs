@@ -3,6 +3,7 @@
 
 use crate::{
     chained_bft::{
+        batching::BatchStore,
         block_storage::BlockReader,
         chained_bft_smr::{ChainedBftSMR, ChainedBftSMRConfig},
         common::Author,
@@ -15,7 +16,7 @@ use crate::{
     state_replication::StateMachineReplication,
 };
 use channel;
-use crypto::hash::CryptoHash;
+use crypto::{hash::CryptoHash, x25519};
 use futures::{channel::mpsc, executor::block_on, prelude::*};
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 use proto_conv::FromProto;
@@ -27,12 +28,20 @@ use crate::chained_bft::{
     persistent_storage::RecoveryData,
     test_utils::{consensus_runtime, with_smr_id},
 };
-use config::config::ConsensusProposerType::{
-    self, FixedProposer, MultipleOrderedProposers, RotatingProposer,
+use config::config::{
+    AdaptivePacemakerConfig,
+    ConsensusProposerType::{
+        self, FixedProposer, LeaderReputation, MultipleOrderedProposers, RotatingProposer,
+    },
+    SafetyRulesConfig,
 };
 use std::{collections::HashMap, time::Duration};
 use tokio::runtime;
-use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
+use types::{
+    crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier},
+    validator_public_keys::ValidatorPublicKeys,
+    validator_set::ValidatorSet,
+};
 
 /// Auxiliary struct that is preparing SMR for the test
 struct SMRNode {
@@ -44,6 +53,7 @@ struct SMRNode {
     smr_id: usize,
     smr: ChainedBftSMR<TestPayload>,
     commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    state_computer: Arc<MockStateComputer>,
     mempool: Arc<MockTransactionManager>,
     mempool_notif_receiver: mpsc::Receiver<usize>,
     storage: Arc<MockStorage<TestPayload>>,
@@ -81,16 +91,21 @@ impl SMRNode {
 
         let config = ChainedBftSMRConfig {
             max_pruned_blocks_in_mem: 10000,
+            max_pending_executions: 4,
             pacemaker_initial_timeout: Duration::from_secs(3),
             proposer_type,
             contiguous_rounds: 2,
             max_block_size: 50,
+            privileged_lane_size: 5,
+            safety_rules: SafetyRulesConfig::default(),
+            adaptive_pacemaker: AdaptivePacemakerConfig::default(),
         };
         let mut smr = ChainedBftSMR::new(
             author,
-            signer.clone(),
+            Some(signer.clone()),
             proposer.clone(),
             network,
+            Arc::new(BatchStore::new(1_000)),
             runtime,
             config,
             storage.clone(),
@@ -101,11 +116,9 @@ impl SMRNode {
         let mut mp = MockTransactionManager::new();
         let commit_receiver = mp.take_commit_receiver();
         let mempool = Arc::new(mp);
-        smr.start(
-            mempool.clone(),
-            Arc::new(MockStateComputer::new(commit_cb_sender.clone())),
-        )
-        .expect("Failed to start SMR!");
+        let state_computer = Arc::new(MockStateComputer::new(commit_cb_sender.clone()));
+        smr.start(mempool.clone(), state_computer.clone())
+            .expect("Failed to start SMR!");
         Self {
             author,
             signer,
@@ -115,6 +128,7 @@ impl SMRNode {
             smr_id,
             smr,
             commit_cb_receiver,
+            state_computer,
             mempool,
             mempool_notif_receiver: commit_receiver,
             storage,
@@ -164,7 +178,7 @@ impl SMRNode {
         let proposer = {
             match proposer_type {
                 FixedProposer => vec![peers[0]],
-                RotatingProposer | MultipleOrderedProposers => peers,
+                RotatingProposer | MultipleOrderedProposers | LeaderReputation => peers,
             }
         };
         let mut nodes = vec![];
@@ -302,6 +316,12 @@ fn happy_path_with_multi_proposer() {
     basic_full_round(2, 2, MultipleOrderedProposers);
 }
 
+#[test]
+/// Basic happy path with the leader reputation proposer election
+fn happy_path_with_leader_reputation() {
+    basic_full_round(2, 2, LeaderReputation);
+}
+
 /// Verify the basic e2e flow: blocks are committed, txn manager is notified, block tree is
 /// pruned, restart the node and we can still continue.
 #[test]
@@ -400,6 +420,69 @@ fn basic_commit_and_restart() {
     });
 }
 
+#[test]
+/// A committed block carrying a new validator set ends the epoch: consensus restarts with the
+/// new validators and keeps committing blocks.
+fn basic_reconfiguration() {
+    let runtime = consensus_runtime();
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let nodes = SMRNode::start_num_nodes(2, 2, &mut playground, RotatingProposer);
+    let validator_set = ValidatorSet::new(
+        nodes
+            .iter()
+            .map(|node| {
+                let (_, network_identity_public_key) = x25519::compat::generate_keypair(None);
+                ValidatorPublicKeys::new(
+                    node.author,
+                    node.signer.public_key(),
+                    node.signer.public_key(),
+                    network_identity_public_key,
+                )
+            })
+            .collect(),
+    );
+    // Nothing is delivered yet: the first proposed block is the one carrying the validator set.
+    for node in nodes.iter() {
+        node.state_computer
+            .reconfigure_next_block(validator_set.clone());
+    }
+
+    block_on(async {
+        while nodes[0].epoch_mgr.epoch() == 0 {
+            playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+        }
+        assert_eq!(
+            nodes[0]
+                .epoch_mgr
+                .validators()
+                .get_ordered_account_addresses(),
+            ValidatorVerifier::from(&validator_set).get_ordered_account_addresses()
+        );
+        let root_heights: Vec<_> = nodes
+            .iter()
+            .map(|node| node.smr.block_store().unwrap().root().height())
+            .collect();
+
+        // The first round of the new epoch might time out for the validators that learn about
+        // the end of the epoch from its first proposal.
+        for _ in 0..6 {
+            playground
+                .wait_for_messages(1, NetworkPlayground::votes_only)
+                .await;
+        }
+        for (node, root_height) in nodes.iter().zip(root_heights) {
+            assert!(
+                node.smr.block_store().unwrap().root().height() > root_height,
+                "node {} did not commit in the new epoch",
+                node.smr_id
+            );
+        }
+        assert_eq!(nodes[0].epoch_mgr.epoch(), 1);
+    });
+}
+
 #[test]
 fn basic_block_retrieval() {
     let runtime = consensus_runtime();
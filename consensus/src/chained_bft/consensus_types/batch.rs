@@ -0,0 +1,200 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::common::Author;
+use canonical_serialization::{
+    CanonicalDeserialize, CanonicalDeserializer, CanonicalSerialize, CanonicalSerializer,
+    SimpleSerializer,
+};
+use crypto::{
+    hash::{BatchHasher, CryptoHash, CryptoHasher},
+    HashValue,
+};
+use failure::prelude::*;
+use network::proto::Batch as ProtoBatch;
+use proto_conv::{FromProto, IntoProto};
+use rmp_serde::{from_slice, to_vec_named};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+};
+
+/// A batch of transactions disseminated by a validator ahead of the proposals that include it.
+/// Consensus only orders the digests of the batches: the transactions are kept serialized until
+/// the block referring to the batch is executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    /// The validator that pulled the transactions from its mempool.
+    author: Author,
+    /// The number of transactions in the batch.
+    num_txns: u64,
+    /// The serialized transactions.
+    payload: Vec<u8>,
+    /// The digest covering all the fields above.
+    digest: HashValue,
+}
+
+impl Batch {
+    pub fn new<X: Serialize>(author: Author, txns: &[X]) -> Self {
+        let payload = to_vec_named(txns).expect("fail to serialize batch");
+        Self::new_internal(author, txns.len() as u64, payload)
+    }
+
+    fn new_internal(author: Author, num_txns: u64, payload: Vec<u8>) -> Self {
+        let mut batch = Self {
+            author,
+            num_txns,
+            payload,
+            digest: HashValue::zero(),
+        };
+        batch.digest = batch.hash();
+        batch
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+
+    /// The reference to this batch carried by the proposals.
+    pub fn info(&self) -> BatchInfo {
+        BatchInfo {
+            author: self.author,
+            digest: self.digest,
+            num_txns: self.num_txns,
+        }
+    }
+
+    /// Deserializes the transactions of the batch.
+    pub fn txns<X: DeserializeOwned>(&self) -> Result<Vec<X>> {
+        let txns: Vec<X> = from_slice(&self.payload)?;
+        ensure!(
+            txns.len() as u64 == self.num_txns,
+            "Batch {} carries {} transactions instead of {}",
+            self.digest,
+            txns.len(),
+            self.num_txns
+        );
+        Ok(txns)
+    }
+}
+
+impl Display for Batch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.info())
+    }
+}
+
+impl CryptoHash for Batch {
+    type Hasher = BatchHasher;
+
+    fn hash(&self) -> HashValue {
+        let bytes =
+            SimpleSerializer::<Vec<u8>>::serialize(self).expect("batch serialization failed");
+        let mut state = Self::Hasher::default();
+        state.write(bytes.as_ref());
+        state.finish()
+    }
+}
+
+impl CanonicalSerialize for Batch {
+    fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> Result<()> {
+        serializer
+            .encode_struct(&self.author)?
+            .encode_u64(self.num_txns)?
+            .encode_bytes(&self.payload)?;
+        Ok(())
+    }
+}
+
+impl IntoProto for Batch {
+    type ProtoType = ProtoBatch;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_author(self.author.into());
+        proto.set_num_txns(self.num_txns);
+        proto.set_payload(self.payload.into());
+        proto
+    }
+}
+
+impl FromProto for Batch {
+    type ProtoType = ProtoBatch;
+
+    fn from_proto(object: Self::ProtoType) -> Result<Self> {
+        let author = Author::try_from(object.get_author())?;
+        Ok(Self::new_internal(
+            author,
+            object.get_num_txns(),
+            object.get_payload().to_vec(),
+        ))
+    }
+}
+
+/// The reference to a batch carried by a proposal instead of the transactions themselves: a
+/// block payload is a vector of BatchInfo when the transactions are disseminated in batches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchInfo {
+    author: Author,
+    digest: HashValue,
+    num_txns: u64,
+}
+
+impl BatchInfo {
+    /// The validator that created the batch, which is always able to serve it.
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+}
+
+impl Display for BatchInfo {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[batch {} from {} with {} txns]",
+            self.digest.short_str(),
+            self.author.short_str(),
+            self.num_txns
+        )
+    }
+}
+
+impl CanonicalSerialize for BatchInfo {
+    fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> Result<()> {
+        serializer
+            .encode_struct(&self.author)?
+            .encode_bytes(self.digest.as_ref())?
+            .encode_u64(self.num_txns)?;
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for BatchInfo {
+    fn deserialize(deserializer: &mut impl CanonicalDeserializer) -> Result<Self> {
+        let author = deserializer.decode_struct::<Author>()?;
+        let digest = HashValue::from_slice(&deserializer.decode_bytes()?)?;
+        let num_txns = deserializer.decode_u64()?;
+        Ok(Self {
+            author,
+            digest,
+            num_txns,
+        })
+    }
+}
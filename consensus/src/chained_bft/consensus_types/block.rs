@@ -133,7 +133,6 @@ where
         // carrying information about version `0`.
         let genesis_quorum_cert = QuorumCert::new(
             ancestor_id,
-            state,
             0,
             LedgerInfoWithSignatures::new(
                 LedgerInfo::new(
@@ -182,34 +181,16 @@ where
         quorum_cert: QuorumCert,
         validator_signer: &ValidatorSigner,
     ) -> Self {
-        let block_internal = BlockSerializer {
+        BlockData {
             parent_id,
-            payload: &payload,
-            round,
-            height,
-            timestamp_usecs,
-            quorum_cert: &quorum_cert,
-            author: Some(validator_signer.author()),
-        };
-
-        let id = block_internal.hash();
-        let signature = validator_signer
-            .sign_message(id)
-            .expect("Failed to sign message");
-
-        Block {
-            id,
             payload,
-            parent_id,
             round,
             height,
             timestamp_usecs,
             quorum_cert,
-            block_source: BlockSource::Proposal {
-                author: validator_signer.author(),
-                signature: signature.into(),
-            },
+            author: validator_signer.author(),
         }
+        .sign(validator_signer)
     }
 
     pub fn make_block(
@@ -220,23 +201,44 @@ where
         quorum_cert: QuorumCert,
         validator_signer: &ValidatorSigner,
     ) -> Self {
+        Self::make_block_data(
+            parent_block,
+            payload,
+            round,
+            timestamp_usecs,
+            quorum_cert,
+            validator_signer.author(),
+        )
+        .sign(validator_signer)
+    }
+
+    /// Same as `make_block`, but the returned proposal is still to be signed by the given author
+    /// (proposals are signed by SafetyRules, which holds the consensus signing key).
+    pub fn make_block_data(
+        parent_block: &Block<T>,
+        payload: T,
+        round: Round,
+        timestamp_usecs: u64,
+        quorum_cert: QuorumCert,
+        author: Author,
+    ) -> BlockData<T> {
         // A block must carry a QC to its parent.
         checked_precondition_eq!(quorum_cert.certified_block_id(), parent_block.id());
         checked_precondition!(round > parent_block.round());
 
-        // This precondition guards the addition overflow caused by passing
-        // parent_block.height() + 1 to new_internal.
+        // This precondition guards the addition overflow caused by computing
+        // parent_block.height() + 1.
         checked_precondition!(parent_block.height() < std::u64::MAX);
-        Block::new_internal(
+        BlockData {
+            parent_id: parent_block.id(),
             payload,
-            parent_block.id(),
             round,
             // Height is always parent's height + 1 because it's just the position in the chain.
-            parent_block.height() + 1,
+            height: parent_block.height() + 1,
             timestamp_usecs,
             quorum_cert,
-            validator_signer,
-        )
+            author,
+        }
     }
 
     /// The NIL blocks are special: they're not carrying any real payload and are generated
@@ -383,6 +385,91 @@ where
     }
 }
 
+/// The content of a proposal before it is signed by its author: the block id is the hash of the
+/// content, the signature covers the block id.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockData<T> {
+    parent_id: HashValue,
+    payload: T,
+    round: Round,
+    height: Height,
+    timestamp_usecs: u64,
+    quorum_cert: QuorumCert,
+    author: Author,
+}
+
+impl<T> BlockData<T>
+where
+    T: CanonicalSerialize,
+{
+    pub fn id(&self) -> HashValue {
+        BlockSerializer {
+            parent_id: self.parent_id,
+            payload: &self.payload,
+            round: self.round,
+            height: self.height,
+            timestamp_usecs: self.timestamp_usecs,
+            quorum_cert: &self.quorum_cert,
+            author: Some(self.author),
+        }
+        .hash()
+    }
+
+    pub fn parent_id(&self) -> HashValue {
+        self.parent_id
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    pub fn quorum_cert(&self) -> &QuorumCert {
+        &self.quorum_cert
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    /// Signs the block id with the given signer, which must be the author of the proposal.
+    pub fn sign(self, validator_signer: &ValidatorSigner) -> Block<T> {
+        checked_precondition_eq!(validator_signer.author(), self.author);
+        let id = self.id();
+        let signature = validator_signer
+            .sign_message(id)
+            .expect("Failed to sign message");
+        Block {
+            id,
+            parent_id: self.parent_id,
+            payload: self.payload,
+            round: self.round,
+            height: self.height,
+            timestamp_usecs: self.timestamp_usecs,
+            quorum_cert: self.quorum_cert,
+            block_source: BlockSource::Proposal {
+                author: self.author,
+                signature: signature.into(),
+            },
+        }
+    }
+}
+
+impl<T> Display for BlockData<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[round: {:02}, parent_id: {}, author: {}]",
+            self.round,
+            self.parent_id,
+            self.author.short_str()
+        )
+    }
+}
+
 impl<T> CryptoHash for Block<T>
 where
     T: canonical_serialization::CanonicalSerialize,
@@ -0,0 +1,90 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Payload, Round},
+    consensus_types::block::Block,
+    safety::vote_msg::VoteMsg,
+};
+use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The kinds of misbehavior: an author may be caught both proposing and voting twice in the same
+/// round, each one is a separate piece of evidence.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, FromPrimitive, ToPrimitive)]
+#[repr(u8)]
+pub enum EvidenceKind {
+    DoubleProposal = 0,
+    DoubleVote = 1,
+}
+
+/// Proof of a validator misbehavior: two conflicting messages signed by the same author for the
+/// same round. Both messages carry the author's signature, hence anyone knowing the validator set
+/// can check the evidence independently.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum Evidence<T> {
+    /// Two different blocks proposed by the same author in the same round.
+    DoubleProposal { first: Block<T>, second: Block<T> },
+    /// Two votes of the same author for different blocks in the same round.
+    DoubleVote { first: VoteMsg, second: VoteMsg },
+}
+
+impl<T: Payload> Evidence<T> {
+    /// The author of the conflicting messages.
+    pub fn author(&self) -> Author {
+        match self {
+            Evidence::DoubleProposal { first, .. } => first
+                .author()
+                .expect("Double proposal evidence must carry authored blocks"),
+            Evidence::DoubleVote { first, .. } => first.author(),
+        }
+    }
+
+    /// The round of the conflicting messages.
+    pub fn round(&self) -> Round {
+        match self {
+            Evidence::DoubleProposal { first, .. } => first.round(),
+            Evidence::DoubleVote { first, .. } => first.round(),
+        }
+    }
+
+    /// The kind of misbehavior.
+    pub fn evidence_kind(&self) -> EvidenceKind {
+        match self {
+            Evidence::DoubleProposal { .. } => EvidenceKind::DoubleProposal,
+            Evidence::DoubleVote { .. } => EvidenceKind::DoubleVote,
+        }
+    }
+
+    /// A short name of the kind of misbehavior.
+    pub fn kind(&self) -> &'static str {
+        match self.evidence_kind() {
+            EvidenceKind::DoubleProposal => "double_proposal",
+            EvidenceKind::DoubleVote => "double_vote",
+        }
+    }
+}
+
+impl<T: Payload> Display for Evidence<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Evidence::DoubleProposal { first, second } => write!(
+                f,
+                "DoubleProposal: [author: {}, round: {:02}, first: {}, second: {}]",
+                self.author().short_str(),
+                self.round(),
+                first,
+                second
+            ),
+            Evidence::DoubleVote { first, second } => write!(
+                f,
+                "DoubleVote: [author: {}, round: {:02}, first: {}, second: {}]",
+                self.author().short_str(),
+                self.round(),
+                first,
+                second
+            ),
+        }
+    }
+}
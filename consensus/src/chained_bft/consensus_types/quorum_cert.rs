@@ -29,8 +29,6 @@ use types::{
 pub struct QuorumCert {
     /// The id of a block that is certified by this QuorumCertificate.
     certified_block_id: HashValue,
-    /// The execution state of the corresponding block.
-    certified_state: ExecutedState,
     /// The round of a certified block.
     certified_block_round: Round,
     /// The signed LedgerInfo of a committed block that carries the data about the certified block.
@@ -58,7 +56,6 @@ impl Display for QuorumCert {
 impl QuorumCert {
     pub fn new(
         block_id: HashValue,
-        state: ExecutedState,
         round: Round,
         signed_ledger_info: LedgerInfoWithSignatures,
         certified_parent_block_id: HashValue,
@@ -68,7 +65,6 @@ impl QuorumCert {
     ) -> Self {
         QuorumCert {
             certified_block_id: block_id,
-            certified_state: state,
             certified_block_round: round,
             signed_ledger_info,
             certified_parent_block_id,
@@ -82,10 +78,6 @@ impl QuorumCert {
         self.certified_block_id
     }
 
-    pub fn certified_state(&self) -> ExecutedState {
-        self.certified_state
-    }
-
     pub fn certified_block_round(&self) -> Round {
         self.certified_block_round
     }
@@ -119,6 +111,16 @@ impl QuorumCert {
         }
     }
 
+    /// The executed state of the committed block, signed by the quorum in the LedgerInfo.
+    pub fn committed_state(&self) -> Option<ExecutedState> {
+        self.committed_block_id()?;
+        let ledger_info = self.ledger_info().ledger_info();
+        Some(ExecutedState {
+            state_id: ledger_info.transaction_accumulator_hash(),
+            version: ledger_info.version(),
+        })
+    }
+
     /// QuorumCert for the genesis block:
     /// - the ID of the block is predetermined by the `GENESIS_BLOCK_ID` constant.
     /// - the accumulator root hash of the LedgerInfo is set to `ACCUMULATOR_PLACEHOLDER_HASH`
@@ -127,7 +129,6 @@ impl QuorumCert {
     pub fn certificate_for_genesis() -> QuorumCert {
         let genesis_digest = VoteMsg::vote_digest(
             *GENESIS_BLOCK_ID,
-            ExecutedState::state_for_genesis(),
             0,
             *GENESIS_BLOCK_ID,
             0,
@@ -150,7 +151,6 @@ impl QuorumCert {
         signatures.insert(signer.author(), signature);
         QuorumCert::new(
             *GENESIS_BLOCK_ID,
-            ExecutedState::state_for_genesis(),
             0,
             LedgerInfoWithSignatures::new(li, signatures),
             *GENESIS_BLOCK_ID,
@@ -166,7 +166,6 @@ impl QuorumCert {
     ) -> ::std::result::Result<(), VoteMsgVerificationError> {
         let vote_hash = VoteMsg::vote_digest(
             self.certified_block_id,
-            self.certified_state,
             self.certified_block_round,
             self.certified_parent_block_id,
             self.certified_parent_block_round,
@@ -177,10 +176,7 @@ impl QuorumCert {
             return Err(VoteMsgVerificationError::ConsensusDataMismatch);
         }
         // Genesis is implicitly agreed upon, it doesn't have real signatures.
-        if self.certified_block_round == 0
-            && self.certified_block_id == *GENESIS_BLOCK_ID
-            && self.certified_state == ExecutedState::state_for_genesis()
-        {
+        if self.certified_block_round == 0 && self.certified_block_id == *GENESIS_BLOCK_ID {
             return Ok(());
         }
         self.ledger_info()
@@ -195,8 +191,6 @@ impl IntoProto for QuorumCert {
     fn into_proto(self) -> Self::ProtoType {
         let mut proto = Self::ProtoType::new();
         proto.set_block_id(self.certified_block_id.into());
-        proto.set_state_id(self.certified_state.state_id.into());
-        proto.set_version(self.certified_state.version);
         proto.set_round(self.certified_block_round);
         proto.set_signed_ledger_info(self.signed_ledger_info.into_proto());
         proto.set_parent_block_id(self.certified_parent_block_id.into());
@@ -212,8 +206,6 @@ impl FromProto for QuorumCert {
 
     fn from_proto(object: Self::ProtoType) -> Result<Self> {
         let certified_block_id = HashValue::from_slice(object.get_block_id())?;
-        let state_id = HashValue::from_slice(object.get_state_id())?;
-        let version = object.get_version();
         let certified_block_round = object.get_round();
         let signed_ledger_info =
             LedgerInfoWithSignatures::from_proto(object.get_signed_ledger_info().clone())?;
@@ -225,7 +217,6 @@ impl FromProto for QuorumCert {
 
         Ok(QuorumCert {
             certified_block_id,
-            certified_state: ExecutedState { state_id, version },
             certified_block_round,
             signed_ledger_info,
             certified_parent_block_id,
@@ -53,3 +53,107 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks::<i64>().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_put_get_evidence() {
+    let tmp_dir = tempdir().unwrap();
+    let db = ConsensusDB::new(&tmp_dir);
+    let author = Author::random();
+
+    assert!(db.get_evidence().unwrap().is_empty());
+
+    assert!(db
+        .save_evidence(2, EvidenceKind::DoubleProposal, author, vec![0x02])
+        .unwrap());
+    assert!(db
+        .save_evidence(1, EvidenceKind::DoubleProposal, author, vec![0x01])
+        .unwrap());
+    assert_eq!(db.get_evidence().unwrap(), vec![vec![0x01], vec![0x02]]);
+
+    // A later piece of evidence of the same kind for the same author and round is dropped.
+    assert!(!db
+        .save_evidence(1, EvidenceKind::DoubleProposal, author, vec![0x03])
+        .unwrap());
+    assert_eq!(db.get_evidence().unwrap(), vec![vec![0x01], vec![0x02]]);
+
+    // A double vote in the same round is another misbehavior.
+    assert!(db
+        .save_evidence(1, EvidenceKind::DoubleVote, author, vec![0x04])
+        .unwrap());
+    assert_eq!(
+        db.get_evidence().unwrap(),
+        vec![vec![0x01], vec![0x04], vec![0x02]]
+    );
+}
+
+#[test]
+fn test_epoch_info() {
+    let tmp_dir = tempdir().unwrap();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    assert!(db.get_epoch_info().unwrap().is_none());
+    db.save_epoch_info(vec![0x01]).unwrap();
+    db.save_epoch_info(vec![0x02, 0x03]).unwrap();
+    assert_eq!(db.get_epoch_info().unwrap(), Some(vec![0x02, 0x03]));
+    // The epoch info is kept apart from the consensus state.
+    assert!(db.get_state().unwrap().is_none());
+}
+
+#[test]
+fn test_batches() {
+    let tmp_dir = tempdir().unwrap();
+    let db = ConsensusDB::new(&tmp_dir);
+    let author = Author::random();
+    let batches = vec![Batch::new(author, &[1u64]), Batch::new(author, &[2u64, 3])];
+
+    assert!(db.get_batches().unwrap().is_empty());
+    for batch in &batches {
+        db.save_batch(batch).unwrap();
+    }
+    let mut saved = db.get_batches().unwrap();
+    saved.sort_by_key(Batch::digest);
+    let mut expected = batches.clone();
+    expected.sort_by_key(Batch::digest);
+    assert_eq!(saved, expected);
+
+    db.delete_batches(vec![batches[0].digest(), HashValue::random()])
+        .unwrap();
+    assert_eq!(db.get_batches().unwrap(), vec![batches[1].clone()]);
+}
+
+#[test]
+fn test_committed_blocks() {
+    let tmp_dir = tempdir().unwrap();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    assert!(db.get_committed_blocks().unwrap().is_empty());
+    db.save_committed_blocks(vec![(2, vec![0x02]), (1, vec![0x01])], vec![])
+        .unwrap();
+    assert_eq!(
+        db.get_committed_blocks().unwrap(),
+        vec![vec![0x01], vec![0x02]]
+    );
+
+    // The pruned rounds are deleted along with the new blocks being written.
+    db.save_committed_blocks(vec![(4, vec![0x04])], vec![1])
+        .unwrap();
+    assert_eq!(
+        db.get_committed_blocks().unwrap(),
+        vec![vec![0x02], vec![0x04]]
+    );
+}
+
+#[test]
+fn test_open_readonly() {
+    let tmp_dir = tempdir().unwrap();
+    assert!(ConsensusDB::open_readonly(&tmp_dir).is_err());
+
+    {
+        let db = ConsensusDB::new(&tmp_dir);
+        db.save_state(vec![0x01, 0x02, 0x03]).unwrap();
+    }
+
+    let db = ConsensusDB::open_readonly(&tmp_dir).unwrap();
+    assert_eq!(db.get_state().unwrap(), Some(vec![0x01, 0x02, 0x03]));
+    assert!(db.save_state(vec![0x04]).is_err());
+}
@@ -6,10 +6,15 @@ mod consensusdb_test;
 mod schema;
 
 use crate::chained_bft::{
-    common::Payload,
-    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    common::{Author, Payload, Round},
+    consensus_types::{
+        batch::Batch, block::Block, evidence::EvidenceKind, quorum_cert::QuorumCert,
+    },
     consensusdb::schema::{
+        batch::BatchSchema,
         block::BlockSchema,
+        committed_block::CommittedBlockSchema,
+        evidence::{EvidenceKey, EvidenceSchema},
         quorum_certificate::QCSchema,
         single_entry::{SingleEntryKey, SingleEntrySchema},
     },
@@ -17,7 +22,10 @@ use crate::chained_bft::{
 use crypto::HashValue;
 use failure::prelude::*;
 use logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{
+    BATCH_CF_NAME, BLOCK_CF_NAME, COMMITTED_BLOCK_CF_NAME, EVIDENCE_CF_NAME, QC_CF_NAME,
+    SINGLE_ENTRY_CF_NAME,
+};
 use schemadb::{
     ColumnFamilyOptions, ColumnFamilyOptionsMap, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME,
 };
@@ -25,6 +33,8 @@ use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
 type HighestTimeoutCertificates = Vec<u8>;
 type ConsensusStateData = Vec<u8>;
+type EvidenceData = Vec<u8>;
+type CommittedBlockData = Vec<u8>;
 
 pub struct ConsensusDB {
     db: DB,
@@ -32,22 +42,9 @@ pub struct ConsensusDB {
 
 impl ConsensusDB {
     pub fn new<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
-        let cf_opts_map: ColumnFamilyOptionsMap = [
-            (
-                /* UNUSED CF = */ DEFAULT_CF_NAME,
-                ColumnFamilyOptions::default(),
-            ),
-            (BLOCK_CF_NAME, ColumnFamilyOptions::default()),
-            (QC_CF_NAME, ColumnFamilyOptions::default()),
-            (SINGLE_ENTRY_CF_NAME, ColumnFamilyOptions::default()),
-        ]
-        .iter()
-        .cloned()
-        .collect();
-
         let path = db_root_path.as_ref().join("consensusdb");
         let instant = Instant::now();
-        let db = DB::open(path.clone(), cf_opts_map).unwrap_or_else(|e| {
+        let db = DB::open(path.clone(), Self::cf_opts_map()).unwrap_or_else(|e| {
             panic!("ConsensusDB open failed due to {:?}, unable to continue", e)
         });
 
@@ -60,6 +57,32 @@ impl ConsensusDB {
         Self { db }
     }
 
+    /// Opens an existing ConsensusDB in read-only mode, e.g. to inspect the data persisted by a
+    /// crashed node.
+    pub fn open_readonly<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        let path = db_root_path.as_ref().join("consensusdb");
+        let db = DB::open_readonly(path, Self::cf_opts_map())?;
+        Ok(Self { db })
+    }
+
+    fn cf_opts_map() -> ColumnFamilyOptionsMap {
+        [
+            (
+                /* UNUSED CF = */ DEFAULT_CF_NAME,
+                ColumnFamilyOptions::default(),
+            ),
+            (BATCH_CF_NAME, ColumnFamilyOptions::default()),
+            (BLOCK_CF_NAME, ColumnFamilyOptions::default()),
+            (COMMITTED_BLOCK_CF_NAME, ColumnFamilyOptions::default()),
+            (EVIDENCE_CF_NAME, ColumnFamilyOptions::default()),
+            (QC_CF_NAME, ColumnFamilyOptions::default()),
+            (SINGLE_ENTRY_CF_NAME, ColumnFamilyOptions::default()),
+        ]
+        .iter()
+        .cloned()
+        .collect()
+    }
+
     pub fn get_data<T: Payload>(
         &self,
     ) -> Result<(
@@ -108,6 +131,12 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    pub fn save_epoch_info(&self, epoch_info: Vec<u8>) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<SingleEntrySchema>(&SingleEntryKey::EpochInfo, &epoch_info)?;
+        self.commit(batch)
+    }
+
     pub fn save_blocks_and_quorum_certificates<T: Payload>(
         &self,
         block_data: Vec<Block<T>>,
@@ -145,6 +174,91 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist the evidence of a misbehavior of `author` at `round` unless there is already one
+    /// of the same kind, returns whether the evidence was written.
+    pub fn save_evidence(
+        &self,
+        round: Round,
+        kind: EvidenceKind,
+        author: Author,
+        evidence: EvidenceData,
+    ) -> Result<bool> {
+        let key = EvidenceKey {
+            round,
+            kind,
+            author,
+        };
+        if self.db.get::<EvidenceSchema>(&key)?.is_some() {
+            return Ok(false);
+        }
+        let mut batch = SchemaBatch::new();
+        batch.put::<EvidenceSchema>(&key, &evidence)?;
+        self.commit(batch)?;
+        Ok(true)
+    }
+
+    /// Get all the evidence of misbehaviors, ordered by round.
+    pub fn get_evidence(&self) -> Result<Vec<EvidenceData>> {
+        let mut iter = self.db.iter::<EvidenceSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|entry| entry.map(|(_key, evidence)| evidence))
+            .collect::<Result<Vec<EvidenceData>>>()
+    }
+
+    /// Persist the given transaction batch.
+    pub fn save_batch(&self, batch: &Batch) -> Result<()> {
+        let mut batch_data = SchemaBatch::new();
+        batch_data.put::<BatchSchema>(&batch.digest(), batch)?;
+        self.commit(batch_data)
+    }
+
+    /// Delete the transaction batches of the given digests atomically.
+    pub fn delete_batches(&self, digests: Vec<HashValue>) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        digests
+            .iter()
+            .map(|digest| batch.delete::<BatchSchema>(digest))
+            .collect::<Result<()>>()?;
+        self.commit(batch)
+    }
+
+    /// Get all the persisted transaction batches.
+    pub fn get_batches(&self) -> Result<Vec<Batch>> {
+        let mut iter = self.db.iter::<BatchSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|entry| entry.map(|(_digest, batch)| batch))
+            .collect::<Result<Vec<Batch>>>()
+    }
+
+    /// Persist the info of newly committed blocks and delete the ones of the given rounds
+    /// atomically.
+    pub fn save_committed_blocks(
+        &self,
+        committed_blocks: Vec<(Round, CommittedBlockData)>,
+        pruned_rounds: Vec<Round>,
+    ) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        pruned_rounds
+            .iter()
+            .map(|round| batch.delete::<CommittedBlockSchema>(round))
+            .collect::<Result<()>>()?;
+        committed_blocks
+            .iter()
+            .map(|(round, data)| batch.put::<CommittedBlockSchema>(round, data))
+            .collect::<Result<()>>()?;
+        self.commit(batch)
+    }
+
+    /// Get the info of all the committed blocks kept, ordered by round.
+    pub fn get_committed_blocks(&self) -> Result<Vec<CommittedBlockData>> {
+        let mut iter = self
+            .db
+            .iter::<CommittedBlockSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|entry| entry.map(|(_round, data)| data))
+            .collect::<Result<Vec<CommittedBlockData>>>()
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<()> {
@@ -158,11 +272,16 @@ impl ConsensusDB {
     }
 
     /// Get latest consensus state (we only store the latest state).
-    fn get_state(&self) -> Result<Option<Vec<u8>>> {
+    pub fn get_state(&self) -> Result<Option<Vec<u8>>> {
         self.db
             .get::<SingleEntrySchema>(&SingleEntryKey::ConsensusState)
     }
 
+    /// Get the latest epoch and its validator set (we only store the latest epoch).
+    pub fn get_epoch_info(&self) -> Result<Option<Vec<u8>>> {
+        self.db.get::<SingleEntrySchema>(&SingleEntryKey::EpochInfo)
+    }
+
     /// Get all consensus blocks.
     fn get_blocks<T: Payload>(&self) -> Result<HashMap<HashValue, Block<T>>> {
         let mut iter = self.db.iter::<BlockSchema<T>>(ReadOptions::default())?;
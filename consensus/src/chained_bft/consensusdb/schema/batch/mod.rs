@@ -0,0 +1,46 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the transaction batches referred to by the
+//! blocks of ConsensusDB.
+//!
+//! Serialized batch bytes identified by the batch digest.
+//! ```text
+//! |<---key---->|<---value--->|
+//! |   digest   |    batch    |
+//! ```
+
+use super::BATCH_CF_NAME;
+use crate::chained_bft::consensus_types::batch::Batch;
+use crypto::HashValue;
+use failure::prelude::*;
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+
+define_schema!(BatchSchema, HashValue, Batch, BATCH_CF_NAME);
+
+impl KeyCodec<BatchSchema> for HashValue {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(HashValue::from_slice(data)?)
+    }
+}
+
+impl ValueCodec<BatchSchema> for Batch {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.clone().into_proto_bytes()?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_proto_bytes(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,12 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use schemadb::schema::assert_encode_decode;
+use types::account_address::AccountAddress;
+
+#[test]
+fn test_batch_schema() {
+    let batch = Batch::new(AccountAddress::random(), &[1u64, 2, 3]);
+    assert_encode_decode::<BatchSchema>(&batch.digest(), &batch);
+}
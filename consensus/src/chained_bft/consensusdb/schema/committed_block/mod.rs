@@ -0,0 +1,55 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the history of committed blocks used by the
+//! leader reputation (their author and voters).
+//!
+//! Serialized committed block info bytes identified by the round of the committed block.
+//! ```text
+//! |<--key-->|<-------value------->|
+//! |  round  | committed block info |
+//! ```
+
+use super::{ensure_slice_len_eq, COMMITTED_BLOCK_CF_NAME};
+use crate::chained_bft::common::Round;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    CommittedBlockSchema,
+    Round,
+    Vec<u8>,
+    COMMITTED_BLOCK_CF_NAME
+);
+
+impl KeyCodec<CommittedBlockSchema> for Round {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        // Big endian keeps the committed blocks ordered by round.
+        let mut encoded = vec![];
+        encoded.write_u64::<BigEndian>(*self)?;
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Round>())?;
+        Ok((&data[..]).read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<CommittedBlockSchema> for Vec<u8> {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.clone())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(data.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test;
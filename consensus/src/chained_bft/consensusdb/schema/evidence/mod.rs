@@ -0,0 +1,81 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the evidence of validator misbehaviors.
+//!
+//! Serialized evidence bytes identified by the round, the kind of misbehavior and the author of
+//! the conflicting messages. One piece of evidence per author, kind and round is enough to prove a
+//! misbehavior, only the first one is kept.
+//! ```text
+//! |<----------key---------->|<-----value----->|
+//! |  round | kind | author  | evidence bytes  |
+//! ```
+
+use super::EVIDENCE_CF_NAME;
+use crate::chained_bft::{
+    common::{Author, Round},
+    consensus_types::evidence::EvidenceKind,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use num_traits::{FromPrimitive, ToPrimitive};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+
+define_schema!(EvidenceSchema, EvidenceKey, Vec<u8>, EVIDENCE_CF_NAME);
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct EvidenceKey {
+    pub round: Round,
+    pub kind: EvidenceKind,
+    pub author: Author,
+}
+
+impl KeyCodec<EvidenceSchema> for EvidenceKey {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        // Big endian keeps the evidence ordered by round.
+        let mut encoded = vec![];
+        encoded.write_u64::<BigEndian>(self.round)?;
+        encoded.write_u8(
+            self.kind
+                .to_u8()
+                .ok_or_else(|| format_err!("ToPrimitive failed."))?,
+        )?;
+        encoded.extend_from_slice(self.author.as_ref());
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        let author_offset = size_of::<Round>() + size_of::<u8>();
+        ensure!(
+            data.len() > author_offset,
+            "Unexpected data len {}",
+            data.len()
+        );
+        let round = (&data[..size_of::<Round>()]).read_u64::<BigEndian>()?;
+        let kind = EvidenceKind::from_u8((&data[size_of::<Round>()..author_offset]).read_u8()?)
+            .ok_or_else(|| format_err!("FromPrimitive failed."))?;
+        let author = Author::try_from(&data[author_offset..])?;
+        Ok(EvidenceKey {
+            round,
+            kind,
+            author,
+        })
+    }
+}
+
+impl ValueCodec<EvidenceSchema> for Vec<u8> {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.clone())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(data.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,20 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use schemadb::schema::assert_encode_decode;
+use types::account_address::AccountAddress;
+
+#[test]
+fn test_evidence_schema() {
+    for kind in &[EvidenceKind::DoubleProposal, EvidenceKind::DoubleVote] {
+        assert_encode_decode::<EvidenceSchema>(
+            &EvidenceKey {
+                round: 7,
+                kind: *kind,
+                author: AccountAddress::random(),
+            },
+            &vec![1u8, 2u8, 3u8],
+        );
+    }
+}
@@ -1,14 +1,20 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod batch;
 pub(crate) mod block;
+pub(crate) mod committed_block;
+pub(crate) mod evidence;
 pub(crate) mod quorum_certificate;
 pub(crate) mod single_entry;
 
 use failure::prelude::*;
 use schemadb::ColumnFamilyName;
 
+pub(super) const BATCH_CF_NAME: ColumnFamilyName = "batch";
 pub(super) const BLOCK_CF_NAME: ColumnFamilyName = "block";
+pub(super) const COMMITTED_BLOCK_CF_NAME: ColumnFamilyName = "committed_block";
+pub(super) const EVIDENCE_CF_NAME: ColumnFamilyName = "evidence";
 pub(super) const QC_CF_NAME: ColumnFamilyName = "quorum_certificate";
 pub(super) const SINGLE_ENTRY_CF_NAME: ColumnFamilyName = "single_entry";
 
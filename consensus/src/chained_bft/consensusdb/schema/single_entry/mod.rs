@@ -35,6 +35,8 @@ pub enum SingleEntryKey {
     ConsensusState = 0,
     // Used to store the highest timeout certificates
     HighestTimeoutCertificates = 1,
+    // Used to store the current epoch and its validator set
+    EpochInfo = 2,
 }
 
 impl KeyCodec<SingleEntrySchema> for SingleEntryKey {
@@ -1,30 +1,69 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::counters;
+use logger::prelude::*;
 use std::sync::{Arc, RwLock};
 use types::crypto_proxies::ValidatorVerifier;
 
+struct EpochState {
+    epoch: usize,
+    validators: Arc<ValidatorVerifier>,
+}
+
 /// Manages the current epoch and validator set to provide quorum size/voting power and signature
 /// verification.
 pub struct EpochManager {
-    #[allow(dead_code)]
-    epoch: usize,
-    validators: RwLock<Arc<ValidatorVerifier>>,
+    state: RwLock<EpochState>,
 }
 
 impl EpochManager {
     pub fn new(epoch: usize, validators: ValidatorVerifier) -> Self {
+        Self::update_counters(epoch, &validators);
         Self {
-            epoch,
-            validators: RwLock::new(Arc::new(validators)),
+            state: RwLock::new(EpochState {
+                epoch,
+                validators: Arc::new(validators),
+            }),
         }
     }
 
+    pub fn epoch(&self) -> usize {
+        self.state.read().unwrap().epoch
+    }
+
     pub fn quorum_size(&self) -> usize {
-        self.validators.read().unwrap().quorum_size()
+        self.state.read().unwrap().validators.quorum_size()
     }
 
     pub fn validators(&self) -> Arc<ValidatorVerifier> {
-        Arc::clone(&self.validators.read().unwrap())
+        Arc::clone(&self.state.read().unwrap().validators)
+    }
+
+    /// Switches to the given epoch and its validator set. The switch is ignored in case the
+    /// manager is already at this epoch or at a later one: returns whether the switch happened.
+    pub fn start_new_epoch(&self, epoch: usize, validators: ValidatorVerifier) -> bool {
+        let mut state = self.state.write().unwrap();
+        if epoch <= state.epoch {
+            return false;
+        }
+        info!(
+            "Starting epoch {} with {} validators, quorum size {}",
+            epoch,
+            validators.len(),
+            validators.quorum_size()
+        );
+        Self::update_counters(epoch, &validators);
+        *state = EpochState {
+            epoch,
+            validators: Arc::new(validators),
+        };
+        true
+    }
+
+    fn update_counters(epoch: usize, validators: &ValidatorVerifier) {
+        counters::EPOCH_NUM.set(epoch as i64);
+        counters::CURRENT_EPOCH_NUM_VALIDATORS.set(validators.len() as i64);
+        counters::CURRENT_EPOCH_QUORUM_SIZE.set(validators.quorum_size() as i64);
     }
 }
@@ -0,0 +1,136 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        common::{Author, Payload, Round},
+        consensus_types::{
+            block::Block,
+            evidence::{Evidence, EvidenceKind},
+        },
+        persistent_storage::PersistentStorage,
+        safety::vote_msg::VoteMsg,
+    },
+    counters,
+};
+use logger::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+#[cfg(test)]
+#[path = "equivocation_detector_test.rs"]
+mod equivocation_detector_test;
+
+/// Proposals and votes are only tracked for the rounds within this distance of the current
+/// round: the older ones are dropped, and the ones too far in the future are not recorded in
+/// order to bound the memory a faulty validator can make us use.
+pub const EQUIVOCATION_WINDOW: Round = 10;
+
+/// Keeps the first proposal and the first vote of every author for the recent rounds, in order to
+/// detect the conflicting ones. The evidence of a misbehavior is persisted, logged and counted.
+/// The detection is best effort: a replica only sees the votes sent to it and the proposals that
+/// reach it.
+pub struct EquivocationDetector<T> {
+    storage: Arc<dyn PersistentStorage<T>>,
+    proposals: BTreeMap<Round, HashMap<Author, Block<T>>>,
+    votes: BTreeMap<Round, HashMap<Author, VoteMsg>>,
+}
+
+impl<T: Payload> EquivocationDetector<T> {
+    pub fn new(storage: Arc<dyn PersistentStorage<T>>) -> Self {
+        Self {
+            storage,
+            proposals: BTreeMap::new(),
+            votes: BTreeMap::new(),
+        }
+    }
+
+    /// Records a (verified) proposal and returns the evidence of a double proposal in case its
+    /// author already proposed a different block in the same round and this misbehavior was not
+    /// reported yet.
+    pub fn check_proposal(
+        &mut self,
+        proposal: &Block<T>,
+        current_round: Round,
+    ) -> Option<Evidence<T>> {
+        let author = proposal.author()?;
+        if !Self::is_tracked(proposal.round(), current_round) {
+            return None;
+        }
+        self.prune(current_round);
+        let first = self
+            .proposals
+            .entry(proposal.round())
+            .or_insert_with(HashMap::new)
+            .entry(author)
+            .or_insert_with(|| proposal.clone());
+        if first.id() == proposal.id() {
+            return None;
+        }
+        let evidence = Evidence::DoubleProposal {
+            first: first.clone(),
+            second: proposal.clone(),
+        };
+        self.report(evidence)
+    }
+
+    /// Records a (verified) vote and returns the evidence of a double vote in case its author
+    /// already voted for a different block in the same round and this misbehavior was not
+    /// reported yet.
+    pub fn check_vote(&mut self, vote: &VoteMsg, current_round: Round) -> Option<Evidence<T>> {
+        if !Self::is_tracked(vote.round(), current_round) {
+            return None;
+        }
+        self.prune(current_round);
+        let first = self
+            .votes
+            .entry(vote.round())
+            .or_insert_with(HashMap::new)
+            .entry(vote.author())
+            .or_insert_with(|| vote.clone());
+        if first.proposed_block_id() == vote.proposed_block_id() {
+            return None;
+        }
+        let evidence = Evidence::DoubleVote {
+            first: first.clone(),
+            second: vote.clone(),
+        };
+        self.report(evidence)
+    }
+
+    fn is_tracked(round: Round, current_round: Round) -> bool {
+        round.saturating_add(EQUIVOCATION_WINDOW) >= current_round
+            && round <= current_round.saturating_add(EQUIVOCATION_WINDOW)
+    }
+
+    /// Drops the messages of the rounds that fell out of the window.
+    fn prune(&mut self, current_round: Round) {
+        let lowest_round = current_round.saturating_sub(EQUIVOCATION_WINDOW);
+        self.proposals = self.proposals.split_off(&lowest_round);
+        self.votes = self.votes.split_off(&lowest_round);
+    }
+
+    /// Persists, logs and counts the evidence unless the same misbehavior of the author in that
+    /// round was already reported (e.g. a third conflicting message, or the same one resent).
+    fn report(&self, evidence: Evidence<T>) -> Option<Evidence<T>> {
+        match self.storage.save_evidence(evidence.clone()) {
+            Ok(true) => (),
+            Ok(false) => return None,
+            // Still report it: the misbehavior is real even if it could not be persisted.
+            Err(e) => error!("Failed to persist evidence {}: {:?}", evidence, e),
+        }
+        match evidence.evidence_kind() {
+            EvidenceKind::DoubleProposal => counters::DOUBLE_PROPOSAL_COUNT.inc(),
+            EvidenceKind::DoubleVote => counters::DOUBLE_VOTE_COUNT.inc(),
+        }
+        security_log(SecurityEvent::ConsensusEquivocation)
+            .error(evidence.kind())
+            .data(evidence.author())
+            .data(evidence.round())
+            .data(&evidence)
+            .log();
+        Some(evidence)
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockReader,
+    consensus_types::{block::Block, evidence::Evidence},
+    equivocation_detector::EquivocationDetector,
+    safety::vote_msg::VoteMsg,
+    test_utils::{
+        build_empty_tree, placeholder_ledger_info, MockStorage, TestPayload, TreeInserter,
+    },
+};
+use std::sync::Arc;
+use types::crypto_proxies::ValidatorSigner;
+
+fn make_vote(block: &Block<TestPayload>, voter: &ValidatorSigner) -> VoteMsg {
+    VoteMsg::new(
+        block.id(),
+        block.round(),
+        block.quorum_cert().certified_parent_block_id(),
+        block.quorum_cert().certified_parent_block_round(),
+        block.quorum_cert().certified_grandparent_block_id(),
+        block.quorum_cert().certified_grandparent_block_round(),
+        voter.author(),
+        placeholder_ledger_info(),
+        voter,
+    )
+}
+
+#[test]
+fn test_double_proposal_is_detected_and_persisted() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let mut detector = EquivocationDetector::<TestPayload>::new(storage.clone());
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(genesis.as_ref(), 1);
+    let b1 = inserter.insert_block(genesis.as_ref(), 1);
+    let a2 = inserter.insert_block(a1.as_ref(), 2);
+
+    assert_eq!(detector.check_proposal(a1.as_ref(), 1), None);
+    // The very same proposal is not a misbehavior.
+    assert_eq!(detector.check_proposal(a1.as_ref(), 1), None);
+    assert_eq!(detector.check_proposal(a2.as_ref(), 1), None);
+
+    let expected = Evidence::DoubleProposal {
+        first: a1.as_ref().clone(),
+        second: b1.as_ref().clone(),
+    };
+    assert_eq!(
+        detector.check_proposal(b1.as_ref(), 1),
+        Some(expected.clone())
+    );
+    assert_eq!(
+        *storage.shared_storage.evidence.lock().unwrap(),
+        vec![expected]
+    );
+}
+
+#[test]
+fn test_double_vote_is_detected_and_persisted() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let mut detector = EquivocationDetector::<TestPayload>::new(storage.clone());
+    let voter = ValidatorSigner::random(None);
+    let another_voter = ValidatorSigner::random(None);
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(genesis.as_ref(), 1);
+    let b1 = inserter.insert_block(genesis.as_ref(), 1);
+
+    let vote_a1 = make_vote(a1.as_ref(), &voter);
+    let vote_b1 = make_vote(b1.as_ref(), &voter);
+    let another_vote_b1 = make_vote(b1.as_ref(), &another_voter);
+
+    assert_eq!(detector.check_vote(&vote_a1, 1), None);
+    // Votes attached to timeouts are duplicates of the original ones.
+    assert_eq!(detector.check_vote(&vote_a1, 1), None);
+    assert_eq!(detector.check_vote(&another_vote_b1, 1), None);
+
+    let expected = Evidence::DoubleVote {
+        first: vote_a1,
+        second: vote_b1.clone(),
+    };
+    assert_eq!(detector.check_vote(&vote_b1, 1), Some(expected.clone()));
+    assert_eq!(
+        *storage.shared_storage.evidence.lock().unwrap(),
+        vec![expected]
+    );
+}
+
+#[test]
+fn test_messages_out_of_window_are_not_tracked() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let mut detector = EquivocationDetector::<TestPayload>::new(Arc::clone(&storage));
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(genesis.as_ref(), 1);
+    let b1 = inserter.insert_block(genesis.as_ref(), 1);
+    let a20 = inserter.insert_block(genesis.as_ref(), 20);
+    let b20 = inserter.insert_block(genesis.as_ref(), 20);
+
+    assert_eq!(detector.check_proposal(a1.as_ref(), 1), None);
+    // Too far in the future to be recorded.
+    assert_eq!(detector.check_proposal(a20.as_ref(), 1), None);
+    assert_eq!(detector.check_proposal(b20.as_ref(), 20), None);
+    // Dropped once the current round moved on.
+    assert_eq!(detector.check_proposal(b1.as_ref(), 20), None);
+    assert!(storage.shared_storage.evidence.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_evidence_is_reported_once_per_kind() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let mut detector = EquivocationDetector::<TestPayload>::new(Arc::clone(&storage));
+    let signer = inserter.signer().clone();
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(genesis.as_ref(), 1);
+    let b1 = inserter.insert_block(genesis.as_ref(), 1);
+    let c1 = inserter.insert_block(genesis.as_ref(), 1);
+
+    assert_eq!(detector.check_proposal(a1.as_ref(), 1), None);
+    assert!(detector.check_proposal(b1.as_ref(), 1).is_some());
+    // Neither a resent conflicting proposal nor a third one is a new misbehavior.
+    assert_eq!(detector.check_proposal(b1.as_ref(), 1), None);
+    assert_eq!(detector.check_proposal(c1.as_ref(), 1), None);
+
+    // The same author voting twice in that round is reported on its own.
+    let vote_a1 = make_vote(a1.as_ref(), &signer);
+    let vote_b1 = make_vote(b1.as_ref(), &signer);
+    assert_eq!(detector.check_vote(&vote_a1, 1), None);
+    assert!(detector.check_vote(&vote_b1, 1).is_some());
+
+    let evidence = storage.shared_storage.evidence.lock().unwrap();
+    assert_eq!(
+        evidence.iter().map(Evidence::kind).collect::<Vec<_>>(),
+        vec!["double_proposal", "double_vote"]
+    );
+}
@@ -14,35 +14,45 @@ use crate::{
             proposal_msg::ProposalMsg,
             quorum_cert::QuorumCert,
             sync_info::SyncInfo,
-            timeout_msg::{PacemakerTimeout, PacemakerTimeoutCertificate, TimeoutMsg},
+            timeout_msg::{PacemakerTimeoutCertificate, TimeoutMsg},
         },
         epoch_manager::EpochManager,
+        equivocation_detector::EquivocationDetector,
         liveness::{
+            committed_history::{CommittedBlockInfo, CommittedHistory},
             pacemaker::{NewRoundEvent, NewRoundReason, Pacemaker},
             proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
         },
         network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
         persistent_storage::PersistentStorage,
-        safety::{safety_rules::SafetyRules, vote_msg::VoteMsg},
+        safety::{t_safety_rules::TSafetyRules, vote_msg::VoteMsg},
         sync_manager::{SyncManager, SyncMgrContext},
     },
     counters,
-    state_replication::{StateComputer, TxnManager},
+    state_replication::{CommitObserver, StateComputer, TxnManager},
     util::time_service::{
         duration_since_epoch, wait_if_possible, TimeService, WaitingError, WaitingSuccess,
     },
 };
+use futures::Future;
 use logger::prelude::*;
 use network::proto::BlockRetrievalStatus;
-use std::{sync::Arc, time::Duration};
+use std::{pin::Pin, sync::Arc, time::Duration};
 use termion::color::*;
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::{crypto_proxies::LedgerInfoWithSignatures, validator_set::ValidatorSet};
 
 #[cfg(test)]
 #[path = "event_processor_test.rs"]
 mod event_processor_test;
 
+/// A commit whose state is being persisted by the state computer.
+struct PendingCommit<T> {
+    block: Arc<Block<T>>,
+    finality_proof: LedgerInfoWithSignatures,
+    commit: Pin<Box<dyn Future<Output = failure::Result<()>> + Send>>,
+}
+
 /// Consensus SMR is working in an event based fashion: EventProcessor is responsible for
 /// processing the individual events (e.g., process_new_round, process_proposal, process_vote,
 /// etc.). It is exposing the async processing functions for each event type.
@@ -54,17 +64,27 @@ pub struct EventProcessor<T> {
     pacemaker: Pacemaker,
     proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
     proposal_generator: ProposalGenerator<T>,
-    safety_rules: SafetyRules,
+    safety_rules: Box<dyn TSafetyRules<T>>,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     txn_manager: Arc<dyn TxnManager<Payload = T>>,
     network: ConsensusNetworkImpl,
     storage: Arc<dyn PersistentStorage<T>>,
     sync_manager: SyncManager<T>,
+    equivocation_detector: EquivocationDetector<T>,
     time_service: Arc<dyn TimeService>,
     enforce_increasing_timestamps: bool,
     // Cache of the last sent vote message.
     last_vote_sent: Option<(VoteMsg, Round)>,
     epoch_mgr: Arc<EpochManager>,
+    // The epoch this EventProcessor is processing events for.
+    epoch: usize,
+    // Next epoch and its validators, set once a block carrying a new validator set is committed.
+    epoch_change: Option<(usize, ValidatorSet)>,
+    // The last commit, finished at the end of the processing of the event that triggered it.
+    pending_commit: Option<PendingCommit<T>>,
+    commit_observer: Option<Arc<dyn CommitObserver<Payload = T>>>,
+    // Records the committed blocks for the leader reputation.
+    committed_history: Option<Arc<CommittedHistory>>,
 }
 
 impl<T: Payload> EventProcessor<T> {
@@ -74,7 +94,7 @@ impl<T: Payload> EventProcessor<T> {
         pacemaker: Pacemaker,
         proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
         proposal_generator: ProposalGenerator<T>,
-        safety_rules: SafetyRules,
+        safety_rules: Box<dyn TSafetyRules<T>>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         network: ConsensusNetworkImpl,
@@ -83,12 +103,14 @@ impl<T: Payload> EventProcessor<T> {
         enforce_increasing_timestamps: bool,
         epoch_mgr: Arc<EpochManager>,
     ) -> Self {
+        let equivocation_detector = EquivocationDetector::new(Arc::clone(&storage));
         let sync_manager = SyncManager::new(
             Arc::clone(&block_store),
             Arc::clone(&storage),
             network.clone(),
             Arc::clone(&state_computer),
         );
+        let epoch = epoch_mgr.epoch();
         Self {
             author,
             block_store,
@@ -101,13 +123,31 @@ impl<T: Payload> EventProcessor<T> {
             network,
             storage,
             sync_manager,
+            equivocation_detector,
             time_service,
             enforce_increasing_timestamps,
             last_vote_sent: None,
             epoch_mgr,
+            epoch,
+            epoch_change: None,
+            pending_commit: None,
+            commit_observer: None,
+            committed_history: None,
         }
     }
 
+    /// Notifies the given observer about the committed blocks.
+    pub fn set_commit_observer(&mut self, commit_observer: Arc<dyn CommitObserver<Payload = T>>) {
+        self.sync_manager
+            .set_commit_observer(Arc::clone(&commit_observer));
+        self.commit_observer = Some(commit_observer);
+    }
+
+    /// Records the committed blocks in the given history and persists them.
+    pub fn set_committed_history(&mut self, committed_history: Arc<CommittedHistory>) {
+        self.committed_history = Some(committed_history);
+    }
+
     /// Leader:
     ///
     /// This event is triggered by a new quorum certificate at the previous round or a
@@ -141,7 +181,7 @@ impl<T: Payload> EventProcessor<T> {
         }
 
         // Proposal generator will ensure that at most one proposal is generated per round
-        let proposal = match self
+        let proposal_data = match self
             .proposal_generator
             .generate_proposal(
                 new_round_event.round,
@@ -153,6 +193,13 @@ impl<T: Payload> EventProcessor<T> {
                 error!("Error while generating proposal: {:?}", e);
                 return;
             }
+            Ok(proposal_data) => proposal_data,
+        };
+        let proposal = match self.safety_rules.sign_proposal(proposal_data).await {
+            Err(e) => {
+                error!("Failed to sign the proposal: {:?}", e);
+                return;
+            }
             Ok(proposal) => proposal,
         };
         let mut network = self.network.clone();
@@ -181,14 +228,21 @@ impl<T: Payload> EventProcessor<T> {
 
     /// Process a ProposalMsg, pre_process would bring all the dependencies and filter out invalid
     /// proposal, process_proposed_block would execute and decide whether to vote for it.
+    /// The commit triggered by the certificates of the proposal is persisted while the proposed
+    /// block is executed and voted for.
     pub async fn process_proposal_msg(&mut self, proposal_msg: ProposalMsg<T>) {
         if let Some(block) = self.pre_process_proposal(proposal_msg).await {
-            self.process_proposed_block(block).await
+            // The certificates carried by the proposal might have ended the epoch.
+            if self.epoch_change.is_none() {
+                self.process_proposed_block(block).await
+            }
         }
+        self.finish_pending_commit().await;
     }
 
     /// The function is responsible for processing the incoming proposals and the Quorum
     /// Certificate.
+    /// 0. start executing the proposed block in case its parent is known
     /// 1. sync up to the SyncInfo including committing to the committed state the HLI carries
     /// and fetch all the blocks from the committed state to the HQC
     /// 2. forwarding the proposals to the ProposerElection queue,
@@ -220,6 +274,11 @@ impl<T: Payload> EventProcessor<T> {
             );
             return None;
         }
+        self.equivocation_detector
+            .check_proposal(&proposal_msg.proposal, current_round);
+        self.block_store
+            .execute_speculatively(&proposal_msg.proposal)
+            .await;
         if let Err(e) = self
             .sync_up(&proposal_msg.sync_info, proposal_msg.proposer(), true)
             .await
@@ -265,18 +324,19 @@ impl<T: Payload> EventProcessor<T> {
             .is_err()
         {
             warn!("Stop timeout msg processing because of sync up error.");
-            return;
-        };
-        if let Some(vote) = timeout_msg.pacemaker_timeout().vote_msg() {
-            self.add_vote(vote.clone(), self.epoch_mgr.quorum_size())
-                .await;
-        }
-        if let Some(new_round_event) = self.pacemaker.process_remote_timeout(
-            timeout_msg.pacemaker_timeout().clone(),
-            self.epoch_mgr.quorum_size(),
-        ) {
-            self.process_new_round_event(new_round_event).await;
+        } else if self.epoch_change.is_none() {
+            if let Some(vote) = timeout_msg.pacemaker_timeout().vote_msg() {
+                self.add_vote(vote.clone(), self.epoch_mgr.quorum_size())
+                    .await;
+            }
+            if let Some(new_round_event) = self.pacemaker.process_remote_timeout(
+                timeout_msg.pacemaker_timeout().clone(),
+                self.epoch_mgr.quorum_size(),
+            ) {
+                self.process_new_round_event(new_round_event).await;
+            }
         }
+        self.finish_pending_commit().await;
     }
 
     /// In case some peer's round or HQC is stale, send a SyncInfo message to that peer.
@@ -341,6 +401,9 @@ impl<T: Payload> EventProcessor<T> {
                 current_hqc_round,
                 sync_info.hqc_round(),
             );
+            // The state computer syncs up to the new committed state once the previous commit is
+            // persisted.
+            self.finish_pending_commit().await;
             let deadline = self.pacemaker.current_round_deadline();
             let sync_mgr_context = SyncMgrContext::new(sync_info, author);
             self.sync_manager
@@ -355,6 +418,26 @@ impl<T: Payload> EventProcessor<T> {
                     e
                 })?;
             debug!("Caught up to HQC at round {}", sync_info.hqc_round());
+            // State sync might have committed the block ending the current epoch: the new root
+            // is then certified by the highest ledger info of the sync info.
+            // SafetyRules follows the epoch changes via the certificates it is updated with.
+            if let Err(e) = self
+                .safety_rules
+                .update(sync_info.highest_ledger_info())
+                .await
+            {
+                error!(
+                    "Failed to update SafetyRules with {}: {:?}",
+                    sync_info.highest_ledger_info(),
+                    e
+                );
+            }
+            let highest_ledger_info = sync_info.highest_ledger_info().ledger_info();
+            if highest_ledger_info.ledger_info().consensus_block_id()
+                == self.block_store.root().id()
+            {
+                self.process_epoch_change(highest_ledger_info).await;
+            }
         }
 
         self.process_certificates(
@@ -373,6 +456,7 @@ impl<T: Payload> EventProcessor<T> {
         if let Err(e) = self.sync_up(&sync_info, peer, false).await {
             error!("Fail to process sync info: {:?}", e);
         }
+        self.finish_pending_commit().await;
     }
 
     /// The replica stops voting for this round and saves its consensus state.  Voting is halted
@@ -383,11 +467,17 @@ impl<T: Payload> EventProcessor<T> {
         if !self.pacemaker.process_local_timeout(round) {
             return;
         }
-        let last_vote_round = self.safety_rules.consensus_state().last_vote_round();
+        let voted_at_round = match self.safety_rules.consensus_state().await {
+            Ok(state) => state.last_vote_round() == round,
+            Err(e) => {
+                error!("Failed to retrieve the consensus state: {:?}", e);
+                false
+            }
+        };
         warn!(
             "Round {} timed out: {}, expected round proposer was {:?}, broadcasting new round to all replicas",
             round,
-            if last_vote_round == round { "already executed and voted at this round" } else { "will try to generate a backup vote" },
+            if voted_at_round { "already executed and voted at this round" } else { "will try to generate a backup vote" },
             self.proposer_election.get_valid_proposers(round).iter().map(|p| p.short_str()).collect::<Vec<String>>(),
         );
 
@@ -409,28 +499,32 @@ impl<T: Payload> EventProcessor<T> {
             }
         };
 
-        // Stop voting at this round, persist the consensus state to support restarting from
-        // a recent round (i.e. > the last vote round)  and then send the SyncInfo
-        let consensus_state = self.safety_rules.increase_last_vote_round(round);
-
-        if let Some(consensus_state) = consensus_state {
-            if let Err(e) = self.storage.save_consensus_state(consensus_state) {
-                error!("Failed to persist consensus state after increasing the last vote round due to {:?}", e);
-                return;
-            }
-        }
-
-        self.network
-            .broadcast_timeout_msg(TimeoutMsg::new(
+        // Stop voting at this round (SafetyRules persists the consensus state to support
+        // restarting from a recent round, i.e. > the last vote round) and then send the SyncInfo
+        let timeout_msg = match self
+            .safety_rules
+            .sign_timeout(
                 SyncInfo::new(
                     self.block_store.highest_quorum_cert().as_ref().clone(),
                     self.block_store.highest_ledger_info().as_ref().clone(),
                     self.pacemaker.highest_timeout_certificate(),
                 ),
-                PacemakerTimeout::new(round, self.block_store.signer(), vote_msg_to_attach),
-                self.block_store.signer(),
-            ))
-            .await;
+                round,
+                vote_msg_to_attach,
+            )
+            .await
+        {
+            Ok(timeout_msg) => timeout_msg,
+            Err(e) => {
+                error!(
+                    "Failed to sign the timeout after increasing the last vote round due to {:?}",
+                    e
+                );
+                return;
+            }
+        };
+
+        self.network.broadcast_timeout_msg(timeout_msg).await;
     }
 
     async fn gen_backup_vote(&mut self, round: Round) -> failure::Result<VoteMsg> {
@@ -460,7 +554,9 @@ impl<T: Payload> EventProcessor<T> {
         qc: &QuorumCert,
         tc: Option<&PacemakerTimeoutCertificate>,
     ) {
-        self.safety_rules.update(qc);
+        if let Err(e) = self.safety_rules.update(qc).await {
+            error!("Failed to update SafetyRules with {}: {:?}", qc, e);
+        }
 
         let mut highest_committed_proposal_round = None;
         if let Some(new_commit) = qc.committed_block_id() {
@@ -473,6 +569,10 @@ impl<T: Payload> EventProcessor<T> {
                 self.process_commit(block, finality_proof).await;
             }
         }
+        if self.epoch_change.is_some() {
+            // The epoch is over: no new round is started with the current validators.
+            return;
+        }
         if let Some(new_round_event) = self.pacemaker.process_certificates(
             qc.certified_block_round(),
             highest_committed_proposal_round,
@@ -569,13 +669,33 @@ impl<T: Payload> EventProcessor<T> {
     }
 
     /// The function generates a VoteMsg for a given proposed_block:
-    /// * first execute the block and add it to the block store
-    /// * then verify the voting rules
-    /// * save the updated state to consensus DB
-    /// * return a VoteMsg with the LedgerInfo to be committed in case the vote gathers QC.
+    /// * first fetch the transactions the payload refers to in case they are not available
+    /// * then execute the block and add it to the block store
+    /// * then ask SafetyRules to verify the voting rules, persist its updated state and return a
+    ///   VoteMsg with the LedgerInfo to be committed in case the vote gathers QC.
     ///
     /// This function assumes that it might be called from different tasks concurrently.
     async fn execute_and_vote(&mut self, proposed_block: Block<T>) -> failure::Result<VoteMsg> {
+        // The payload might refer to transactions that have not been disseminated to this
+        // validator yet: bring them from the proposer.
+        if let Some(proposer) = proposed_block.author() {
+            self.txn_manager
+                .fetch_payload(
+                    proposed_block.get_payload(),
+                    proposer,
+                    self.pacemaker.current_round_deadline(),
+                )
+                .await
+                .map_err(|e| {
+                    warn!(
+                        "Failed to fetch the payload of {} from {}: {:?}",
+                        proposed_block,
+                        proposer.short_str(),
+                        e
+                    );
+                    e
+                })?;
+        }
         let block = self
             .sync_manager
             .execute_and_insert_block(proposed_block)
@@ -598,41 +718,19 @@ impl<T: Payload> EventProcessor<T> {
         self.wait_before_vote_if_needed(block.timestamp_usecs())
             .await?;
 
-        let vote_info = self
-            .safety_rules
-            .voting_rule(Arc::clone(&block))
+        // The grandparent of the block gets committed in case the vote gathers a QC and the
+        // commit rule holds: SafetyRules decides whether to sign this commit.
+        let commit_ledger_info = self.block_store.ledger_info_placeholder(
+            Some(block.quorum_cert().certified_parent_block_id()),
+            self.epoch,
+        );
+        self.safety_rules
+            .construct_and_sign_vote(Arc::clone(&block), commit_ledger_info)
+            .await
             .map_err(|e| {
                 debug!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e);
                 e
-            })?;
-        self.storage
-            .save_consensus_state(vote_info.consensus_state().clone())
-            .map_err(|e| {
-                debug!("Fail to persist consensus state: {:?}", e);
-                e
-            })?;
-
-        let proposal_id = vote_info.proposal_id();
-        let executed_state = self
-            .block_store
-            .get_state_for_block(proposal_id)
-            .expect("Block proposed_block: no execution state found for inserted block.");
-
-        let ledger_info_placeholder = self
-            .block_store
-            .ledger_info_placeholder(vote_info.potential_commit_id());
-        Ok(VoteMsg::new(
-            proposal_id,
-            executed_state,
-            block.round(),
-            vote_info.parent_block_id(),
-            vote_info.parent_block_round(),
-            vote_info.grandparent_block_id(),
-            vote_info.grandparent_block_round(),
-            self.author,
-            ledger_info_placeholder,
-            self.block_store.signer(),
-        ))
+            })
     }
 
     /// Upon new vote:
@@ -661,6 +759,7 @@ impl<T: Payload> EventProcessor<T> {
         }
 
         self.add_vote(vote, self.epoch_mgr.quorum_size()).await;
+        self.finish_pending_commit().await;
     }
 
     /// Add a vote. Fetch missing dependencies if required.
@@ -675,6 +774,9 @@ impl<T: Payload> EventProcessor<T> {
         let deadline = self.pacemaker.current_round_deadline();
         let preferred_peer = vote.author();
         // TODO [Reconfiguration] Verify epoch of the vote message.
+        // A conflicting vote is reported but still added: a QC needs 2f + 1 votes anyway.
+        self.equivocation_detector
+            .check_vote(&vote, self.pacemaker.current_round());
         // Add the vote and check whether it completes a new QC.
         if let VoteReceptionResult::NewQuorumCertificate(qc) =
             self.block_store.insert_vote(vote, quorum_size)
@@ -702,18 +804,25 @@ impl<T: Payload> EventProcessor<T> {
     }
 
     /// Upon (potentially) new commit:
-    /// 0. Verify that this commit is newer than the current root.
-    /// 1. Notify state computer with the finality proof.
-    /// 2. After the state is finalized, update the txn manager with the status of the committed
-    /// transactions.
-    /// 3. Prune the tree.
+    /// 0. Verify that this commit is newer than the current root and the pending commit.
+    /// 1. Wait for the pending commit to finish: the commits are persisted one at a time and in
+    /// order, which also slows down the event processing in case committing falls behind.
+    /// 2. Notify state computer with the finality proof. The new commit becomes pending: the event
+    /// processing goes on (e.g., the next block is executed and voted for) while the state is
+    /// being persisted.
+    /// 3. In case a committed block carries a new validator set, finish the commit right away to
+    /// end the current epoch.
     async fn process_commit(
-        &self,
+        &mut self,
         committed_block: Arc<Block<T>>,
         finality_proof: LedgerInfoWithSignatures,
     ) {
         // First make sure that this commit is new.
-        if committed_block.round() <= self.block_store.root().round() {
+        let last_committed_round = match self.pending_commit.as_ref() {
+            Some(pending_commit) => pending_commit.block.round(),
+            None => self.block_store.root().round(),
+        };
+        if committed_block.round() <= last_committed_round {
             return;
         }
 
@@ -724,7 +833,34 @@ impl<T: Payload> EventProcessor<T> {
             committed_block.id()
         );
 
-        if let Err(e) = self.state_computer.commit(finality_proof).await {
+        self.finish_pending_commit().await;
+        let commit = self.state_computer.commit(finality_proof.clone());
+        let reconfiguration = finality_proof.ledger_info().next_validator_set().is_some();
+        self.pending_commit = Some(PendingCommit {
+            block: committed_block,
+            finality_proof,
+            commit,
+        });
+        if reconfiguration {
+            self.finish_pending_commit().await;
+        }
+    }
+
+    /// Once the pending commit is persisted:
+    /// 1. Notify the commit observer and update the txn manager with the status of the committed
+    /// transactions.
+    /// 2. Prune the tree.
+    /// 3. In case a committed block carries a new validator set, end the current epoch.
+    async fn finish_pending_commit(&mut self) {
+        let PendingCommit {
+            block: committed_block,
+            finality_proof,
+            commit,
+        } = match self.pending_commit.take() {
+            Some(pending_commit) => pending_commit,
+            None => return,
+        };
+        if let Err(e) = commit.await {
             // We assume that state computer cannot enter an inconsistent state that might
             // violate safety of the protocol. Specifically, an executor service is going to panic
             // if it fails to persist the commit requests, which would crash the whole process
@@ -738,6 +874,7 @@ impl<T: Payload> EventProcessor<T> {
         // At this moment the new state is persisted and we can notify the clients.
         // Multiple blocks might be committed at once: notify about all the transactions in the
         // path from the old root to the new root.
+        let mut committed_blocks = vec![];
         for committed in self
             .block_store
             .path_from_root(Arc::clone(&committed_block))
@@ -752,6 +889,15 @@ impl<T: Payload> EventProcessor<T> {
                 .block_store
                 .get_compute_result(committed.id())
                 .expect("Compute result of a pending block is unknown");
+            if let Some(commit_observer) = self.commit_observer.as_ref() {
+                commit_observer.on_commit(
+                    committed.id(),
+                    committed.round(),
+                    committed.author(),
+                    committed.get_payload(),
+                    &finality_proof,
+                );
+            }
             if let Err(e) = self
                 .txn_manager
                 .commit_txns(
@@ -763,7 +909,18 @@ impl<T: Payload> EventProcessor<T> {
             {
                 error!("Failed to notify mempool: {:?}", e);
             }
+            committed_blocks.push(CommittedBlockInfo::from_block(committed.as_ref()));
         }
+        if let Some(committed_history) = self.committed_history.as_ref() {
+            let pruned_rounds = committed_history.append(committed_blocks.clone());
+            if let Err(e) = self
+                .storage
+                .save_committed_blocks(committed_blocks, pruned_rounds)
+            {
+                error!("Failed to persist the committed history: {:?}", e);
+            }
+        }
+        self.process_epoch_change(&finality_proof).await;
         counters::LAST_COMMITTED_ROUND.set(committed_block.round() as i64);
         debug!("{}Committed{} {}", Fg(Blue), Fg(Reset), *committed_block);
         event!("committed",
@@ -774,6 +931,27 @@ impl<T: Payload> EventProcessor<T> {
         self.block_store.prune_tree(committed_block.id());
     }
 
+    /// In case the committed ledger info carries a new validator set, end the current epoch:
+    /// the eligible peers are updated and the next epoch is handed over by `take_epoch_change`.
+    async fn process_epoch_change(&mut self, ledger_info: &LedgerInfoWithSignatures) {
+        if self.epoch_change.is_some() {
+            return;
+        }
+        let ledger_info = ledger_info.ledger_info();
+        let validators = match ledger_info.next_validator_set() {
+            Some(validators) if ledger_info.epoch_num() == self.epoch as u64 => validators,
+            _ => return,
+        };
+        info!(
+            "Block {} ends epoch {} with a new validator set of size {}, updating eligible peers",
+            ledger_info.consensus_block_id().short_str(),
+            self.epoch,
+            validators.payload().len()
+        );
+        self.network.update_eligible_nodes(validators).await;
+        self.epoch_change = Some((self.epoch + 1, validators.clone()));
+    }
+
     /// Retrieve a n chained blocks from the block store starting from
     /// an initial parent id, returning with <n (as many as possible) if
     /// id or its ancestors can not be found.
@@ -823,7 +1001,18 @@ impl<T: Payload> EventProcessor<T> {
 
     /// Inspect the current consensus state.
     #[cfg(test)]
-    pub fn consensus_state(&self) -> ConsensusState {
-        self.safety_rules.consensus_state()
+    pub fn consensus_state(&self) -> failure::Result<ConsensusState> {
+        futures::executor::block_on(self.safety_rules.consensus_state())
+    }
+
+    /// Returns SafetyRules, to be handed over to the EventProcessor of the next epoch.
+    pub fn into_safety_rules(self) -> Box<dyn TSafetyRules<T>> {
+        self.safety_rules
+    }
+
+    /// Returns the next epoch and its validators in case a block committed by this EventProcessor
+    /// ended the current epoch. The EventProcessor must not be used once the epoch is over.
+    pub fn take_epoch_change(&mut self) -> Option<(usize, ValidatorSet)> {
+        self.epoch_change.take()
     }
 }
@@ -25,8 +25,7 @@ use crate::{
         network_tests::NetworkPlayground,
         persistent_storage::{PersistentStorage, RecoveryData},
         safety::{
-            safety_rules::{ConsensusState, SafetyRules},
-            vote_msg::VoteMsg,
+            local_safety_rules::LocalSafetyRules, safety_rules::ConsensusState, vote_msg::VoteMsg,
         },
         test_utils::{
             consensus_runtime, placeholder_certificate_for_block, placeholder_ledger_info,
@@ -64,19 +63,22 @@ struct NodeSetup {
 
 impl NodeSetup {
     fn build_empty_store(
-        signer: ValidatorSigner,
+        author: Author,
         storage: Arc<dyn PersistentStorage<TestPayload>>,
         initial_data: RecoveryData<TestPayload>,
+        executor: TaskExecutor,
     ) -> Arc<BlockStore<TestPayload>> {
         let (commit_cb_sender, _commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
 
         Arc::new(block_on(BlockStore::new(
             storage,
             initial_data,
-            signer,
+            author,
             Arc::new(MockStateComputer::new(commit_cb_sender)),
             true,
             10, // max pruned blocks in mem
+            4,  // max pending executions
+            executor,
         )))
     }
 
@@ -157,7 +159,12 @@ impl NodeSetup {
         );
         let consensus_state = initial_data.state();
 
-        let block_store = Self::build_empty_store(signer.clone(), storage.clone(), initial_data);
+        let block_store = Self::build_empty_store(
+            signer.author(),
+            storage.clone(),
+            initial_data,
+            executor.clone(),
+        );
         let time_service = Arc::new(ClockTimeService::new(executor.clone()));
         let proposal_generator = ProposalGenerator::new(
             block_store.clone(),
@@ -166,7 +173,11 @@ impl NodeSetup {
             1,
             true,
         );
-        let safety_rules = SafetyRules::new(consensus_state);
+        let safety_rules = Box::new(LocalSafetyRules::new(
+            consensus_state,
+            signer.clone(),
+            storage.clone(),
+        ));
 
         let pacemaker = Self::create_pacemaker(time_service.clone());
 
@@ -258,15 +269,14 @@ fn basic_new_rank_event_test() {
         // should be a child of a1 and carry its QC.
         let vote_msg = VoteMsg::new(
             a1.id(),
-            node.block_store.get_state_for_block(a1.id()).unwrap(),
             a1.round(),
             a1.quorum_cert().certified_parent_block_id(),
             a1.quorum_cert().certified_parent_block_round(),
             a1.quorum_cert().certified_grandparent_block_id(),
             a1.quorum_cert().certified_grandparent_block_round(),
-            node.block_store.signer().author(),
+            node.signer.author(),
             placeholder_ledger_info(),
-            node.block_store.signer(),
+            &node.signer,
         );
         node.block_store.insert_vote_and_qc(vote_msg, 0);
         node.event_processor
@@ -317,7 +327,7 @@ fn process_successful_proposal_test() {
             1,
             1,
             genesis_qc.clone(),
-            node.block_store.signer(),
+            &node.signer,
         );
         let proposal_id = proposal.id();
         node.event_processor.process_proposed_block(proposal).await;
@@ -357,7 +367,7 @@ fn process_old_proposal_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let new_block_id = new_block.id();
     let old_block = Block::make_block(
@@ -366,7 +376,7 @@ fn process_old_proposal_test() {
         1,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let old_block_id = old_block.id();
     block_on(async move {
@@ -408,7 +418,7 @@ fn process_round_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_skip_round = Block::make_block(
         genesis.as_ref(),
@@ -416,7 +426,7 @@ fn process_round_mismatch_test() {
         2,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     block_on(async move {
         let bad_proposal = ProposalMsg::<TestPayload> {
@@ -455,7 +465,8 @@ fn process_new_round_msg_test() {
     let genesis = non_proposer.block_store.root();
     let block_0 = non_proposer
         .block_store
-        .create_block(genesis, vec![1], 1, 1);
+        .create_block(genesis, vec![1], 1, 1)
+        .sign(&non_proposer.signer);
     let block_0_id = block_0.id();
     block_on(
         non_proposer
@@ -541,7 +552,7 @@ fn process_proposer_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_incorrect_proposer = Block::make_block(
         genesis.as_ref(),
@@ -549,7 +560,7 @@ fn process_proposer_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        incorrect_proposer.block_store.signer(),
+        &incorrect_proposer.signer,
     );
     block_on(async move {
         let bad_proposal = ProposalMsg::<TestPayload> {
@@ -594,7 +605,7 @@ fn process_timeout_certificate_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_skip_round = Block::make_block(
         genesis.as_ref(),
@@ -602,7 +613,7 @@ fn process_timeout_certificate_test() {
         2,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let tc =
         PacemakerTimeoutCertificate::new(1, vec![PacemakerTimeout::new(1, &node.signer, None)]);
@@ -645,15 +656,14 @@ fn process_votes_basic_test() {
         inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), genesis.as_ref(), 1);
     let vote_msg = VoteMsg::new(
         a1.id(),
-        node.block_store.get_state_for_block(a1.id()).unwrap(),
         a1.round(),
         a1.quorum_cert().certified_parent_block_id(),
         a1.quorum_cert().certified_parent_block_round(),
         a1.quorum_cert().certified_grandparent_block_id(),
         a1.quorum_cert().certified_parent_block_round(),
-        node.block_store.signer().author(),
+        node.signer.author(),
         placeholder_ledger_info(),
-        node.block_store.signer(),
+        &node.signer,
     );
     block_on(async move {
         node.event_processor.process_vote(vote_msg).await;
@@ -683,7 +693,7 @@ fn process_block_retrieval() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_id = block.id();
 
@@ -788,7 +798,7 @@ fn basic_restart_test() {
     // verify after restart we recover the data
     node = node.restart(&mut playground, runtime.executor());
     assert_eq!(
-        node.event_processor.consensus_state(),
+        node.event_processor.consensus_state().unwrap(),
         ConsensusState::new(num_proposals, num_proposals - 2),
     );
     for block in proposals {
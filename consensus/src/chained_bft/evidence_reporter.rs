@@ -0,0 +1,45 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Payload, consensus_types::evidence::Evidence, consensusdb::ConsensusDB,
+};
+use debug_interface::{
+    node_debug_service::ConsensusEvidenceProvider,
+    proto::node_debug_interface::{ConsensusEvidence, GetConsensusEvidenceResponse},
+};
+use failure::Result;
+use rmp_serde::from_slice;
+use std::{marker::PhantomData, sync::Arc};
+
+/// Reports the evidence persisted in ConsensusDB through the debug interface.
+pub struct EvidenceReporter<T> {
+    db: Arc<ConsensusDB>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Payload> EvidenceReporter<T> {
+    pub fn new(db: Arc<ConsensusDB>) -> Self {
+        Self {
+            db,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Payload> ConsensusEvidenceProvider for EvidenceReporter<T> {
+    fn consensus_evidence(&self) -> Result<GetConsensusEvidenceResponse> {
+        let mut response = GetConsensusEvidenceResponse::new();
+        for bytes in self.db.get_evidence()? {
+            let evidence: Evidence<T> = from_slice(&bytes)?;
+            let mut entry = ConsensusEvidence::new();
+            entry.set_kind(evidence.kind().to_string());
+            entry.set_author(evidence.author().to_string());
+            entry.set_round(evidence.round());
+            entry.set_description(evidence.to_string());
+            entry.set_evidence(bytes);
+            response.mut_evidence().push(entry);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{chained_bft::liveness::pacemaker::PacemakerTimeInterval, counters};
+use config::config::AdaptivePacemakerConfig;
+use std::{collections::VecDeque, time::Duration};
+
+/// Round durations derived from the recently observed QC formation latencies: the base duration
+/// is a percentile of the latencies plus a margin, and it increases exponentially in the rounds
+/// without commit (like `ExponentialTimeInterval`). All the durations are kept within the given
+/// bounds. The initial base duration is used until enough latencies are observed.
+pub struct AdaptiveTimeInterval {
+    // Base duration used until min_samples latencies are observed.
+    initial_base_ms: u64,
+    // Latencies (in ms) of the most recent QCs, the oldest first.
+    latencies_ms: VecDeque<u64>,
+    window_size: usize,
+    min_samples: usize,
+    // Between 0 and 100.
+    latency_percentile: f64,
+    margin_ms: u64,
+    min_timeout_ms: u64,
+    max_timeout_ms: u64,
+    // Base duration derived from the current latencies.
+    base_ms: u64,
+    // By how much the duration increases every round without commit, up to max_exponent times.
+    exponent_base: f64,
+    max_exponent: usize,
+}
+
+impl AdaptiveTimeInterval {
+    pub fn new(
+        initial_base: Duration,
+        config: &AdaptivePacemakerConfig,
+        exponent_base: f64,
+        max_exponent: usize,
+    ) -> Self {
+        assert!(config.window_size > 0, "window_size should be positive");
+        assert!(
+            config.min_samples <= config.window_size,
+            "min_samples should not exceed window_size"
+        );
+        assert!(
+            config.latency_percentile >= 0.0 && config.latency_percentile <= 100.0,
+            "latency_percentile should be between 0 and 100"
+        );
+        assert!(
+            config.min_timeout_ms <= config.max_timeout_ms,
+            "min_timeout_ms should not exceed max_timeout_ms"
+        );
+        assert!(
+            max_exponent < 32,
+            "max_exponent for PacemakerTimeInterval should be <32"
+        );
+        let mut interval = Self {
+            initial_base_ms: initial_base.as_millis() as u64,
+            latencies_ms: VecDeque::with_capacity(config.window_size),
+            window_size: config.window_size,
+            min_samples: config.min_samples,
+            latency_percentile: config.latency_percentile,
+            margin_ms: config.margin_ms,
+            min_timeout_ms: config.min_timeout_ms,
+            max_timeout_ms: config.max_timeout_ms,
+            base_ms: 0,
+            exponent_base,
+            max_exponent,
+        };
+        interval.update_base();
+        interval
+    }
+
+    fn bounded(&self, duration_ms: u64) -> u64 {
+        duration_ms
+            .max(self.min_timeout_ms)
+            .min(self.max_timeout_ms)
+    }
+
+    fn update_base(&mut self) {
+        let base_ms = if self.latencies_ms.len() < self.min_samples || self.latencies_ms.is_empty()
+        {
+            self.initial_base_ms
+        } else {
+            let mut sorted = self.latencies_ms.iter().cloned().collect::<Vec<_>>();
+            sorted.sort();
+            // Nearest-rank percentile.
+            let rank = (self.latency_percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.max(1) - 1] + self.margin_ms
+        };
+        self.base_ms = self.bounded(base_ms);
+        counters::ADAPTIVE_BASE_TIMEOUT_MS.set(self.base_ms as i64);
+    }
+}
+
+impl PacemakerTimeInterval for AdaptiveTimeInterval {
+    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+        let pow = round_index_after_committed_qc.min(self.max_exponent) as u32;
+        let base_multiplier = self.exponent_base.powf(f64::from(pow));
+        let duration_ms = ((self.base_ms as f64) * base_multiplier).ceil() as u64;
+        Duration::from_millis(self.bounded(duration_ms))
+    }
+
+    fn observe_qc_latency(&mut self, latency: Duration) {
+        if self.latencies_ms.len() == self.window_size {
+            self.latencies_ms.pop_front();
+        }
+        self.latencies_ms.push_back(latency.as_millis() as u64);
+        self.update_base();
+    }
+}
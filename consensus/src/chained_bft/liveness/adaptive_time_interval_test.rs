@@ -0,0 +1,69 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::liveness::{
+    adaptive_time_interval::AdaptiveTimeInterval, pacemaker::PacemakerTimeInterval,
+};
+use config::config::AdaptivePacemakerConfig;
+use std::time::Duration;
+
+fn make_interval(min_samples: usize) -> AdaptiveTimeInterval {
+    let config = AdaptivePacemakerConfig {
+        enabled: true,
+        window_size: 10,
+        min_samples,
+        latency_percentile: 90.0,
+        margin_ms: 100,
+        min_timeout_ms: 200,
+        max_timeout_ms: 5_000,
+    };
+    AdaptiveTimeInterval::new(Duration::from_millis(1_000), &config, 2.0, 3)
+}
+
+fn observe_all(interval: &mut AdaptiveTimeInterval, latencies_ms: &[u64]) {
+    for latency_ms in latencies_ms {
+        interval.observe_qc_latency(Duration::from_millis(*latency_ms));
+    }
+}
+
+#[test]
+fn test_initial_duration_until_min_samples() {
+    let mut interval = make_interval(3);
+    observe_all(&mut interval, &[10, 10]);
+    assert_eq!(1_000, interval.get_round_duration(0).as_millis());
+    assert_eq!(2_000, interval.get_round_duration(1).as_millis());
+    observe_all(&mut interval, &[10]);
+    // 10 + margin, raised to the min timeout.
+    assert_eq!(200, interval.get_round_duration(0).as_millis());
+}
+
+#[test]
+fn test_percentile_plus_margin() {
+    let mut interval = make_interval(1);
+    observe_all(
+        &mut interval,
+        &[100, 200, 300, 400, 500, 600, 700, 800, 900, 1_000],
+    );
+    // The 90th percentile of the 10 latencies is the 9th one.
+    assert_eq!(1_000, interval.get_round_duration(0).as_millis());
+    assert_eq!(2_000, interval.get_round_duration(1).as_millis());
+    assert_eq!(4_000, interval.get_round_duration(2).as_millis());
+    // Bounded by the max timeout.
+    assert_eq!(5_000, interval.get_round_duration(3).as_millis());
+    assert_eq!(5_000, interval.get_round_duration(1_000).as_millis());
+}
+
+#[test]
+fn test_window_follows_recent_latencies() {
+    let mut interval = make_interval(1);
+    observe_all(&mut interval, &[3_000; 10]);
+    assert_eq!(3_100, interval.get_round_duration(0).as_millis());
+    // The slow rounds leave the window once enough fast rounds are observed.
+    observe_all(&mut interval, &[300; 8]);
+    assert_eq!(3_100, interval.get_round_duration(0).as_millis());
+    observe_all(&mut interval, &[300]);
+    assert_eq!(400, interval.get_round_duration(0).as_millis());
+    // A single spike doesn't move the 90th percentile of 10 latencies.
+    observe_all(&mut interval, &[10_000]);
+    assert_eq!(400, interval.get_round_duration(0).as_millis());
+}
@@ -0,0 +1,103 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Payload, Round},
+    consensus_types::block::Block,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::RwLock};
+
+/// The participants of a committed block: its author and the voters of its QC (i.e., the
+/// validators that voted for its parent).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CommittedBlockInfo {
+    round: Round,
+    author: Option<Author>,
+    voters: Vec<Author>,
+}
+
+impl CommittedBlockInfo {
+    pub fn new(round: Round, author: Option<Author>, mut voters: Vec<Author>) -> Self {
+        voters.sort();
+        Self {
+            round,
+            author,
+            voters,
+        }
+    }
+
+    pub fn from_block<T: Payload>(block: &Block<T>) -> Self {
+        Self::new(
+            block.round(),
+            block.author(),
+            block
+                .quorum_cert()
+                .ledger_info()
+                .signatures()
+                .keys()
+                .cloned()
+                .collect(),
+        )
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    /// The author of the block followed by its voters.
+    pub fn participants(&self) -> impl Iterator<Item = &Author> {
+        self.author.iter().chain(self.voters.iter())
+    }
+}
+
+/// The latest committed blocks, ordered by round. They are recorded by the EventProcessor as
+/// the blocks get committed, persisted along with the other consensus data and recovered on
+/// restart, so that the history doesn't depend on the blocks kept by the local block tree.
+/// A replica that skips blocks through state synchronization doesn't record them.
+pub struct CommittedHistory {
+    blocks: RwLock<VecDeque<CommittedBlockInfo>>,
+    // Max number of committed blocks kept
+    capacity: usize,
+}
+
+impl CommittedHistory {
+    pub fn new(blocks: Vec<CommittedBlockInfo>, capacity: usize) -> Self {
+        let history = Self {
+            blocks: RwLock::new(VecDeque::new()),
+            capacity,
+        };
+        history.append(blocks);
+        history
+    }
+
+    /// Appends the newly committed blocks (ignoring the ones that are not higher than the last
+    /// recorded round) and returns the rounds of the blocks dropped to stay within capacity.
+    pub fn append(&self, committed: Vec<CommittedBlockInfo>) -> Vec<Round> {
+        let mut blocks = self.blocks.write().unwrap();
+        for block in committed {
+            if blocks.back().map_or(true, |last| last.round < block.round) {
+                blocks.push_back(block);
+            }
+        }
+        let num_pruned = blocks.len().saturating_sub(self.capacity);
+        blocks
+            .drain(..num_pruned)
+            .map(|block| block.round)
+            .collect()
+    }
+
+    /// Returns up to `size` of the highest committed blocks with a round lower than or equal to
+    /// `max_round`, highest first.
+    pub fn window(&self, max_round: Round, size: usize) -> Vec<CommittedBlockInfo> {
+        self.blocks
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .skip_while(|block| block.round > max_round)
+            .take(size)
+            .cloned()
+            .collect()
+    }
+}
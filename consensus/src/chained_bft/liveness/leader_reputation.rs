@@ -0,0 +1,123 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Payload, Round},
+    consensus_types::block::Block,
+    liveness::{
+        committed_history::{CommittedBlockInfo, CommittedHistory},
+        multi_proposer_election::hash,
+        proposer_election::ProposerElection,
+    },
+};
+use std::{collections::HashSet, sync::Arc};
+
+/// Committed blocks with a round higher than `round - EXCLUDE_ROUNDS` are not considered when
+/// choosing the proposer of `round`: validators are not expected to have committed them yet.
+pub const EXCLUDE_ROUNDS: Round = 4;
+/// Weight of the validators that authored or voted for the blocks of the window.
+pub const ACTIVE_WEIGHT: u64 = 100;
+/// Weight of the validators that did not participate in the blocks of the window.
+pub const INACTIVE_WEIGHT: u64 = 1;
+/// Default number of committed blocks looked at to find the active validators.
+pub const WINDOW_SIZE: usize = 10;
+/// Number of committed blocks to keep in the history for the default window: the blocks above
+/// `round - EXCLUDE_ROUNDS` are kept too.
+pub const HISTORY_SIZE: usize = WINDOW_SIZE + EXCLUDE_ROUNDS as usize;
+
+/// The leader reputation proposer election maps a round to an author by a weighted choice among
+/// the proposers, which prefers the ones that were active in a window of recently committed
+/// blocks (i.e., authored one of these blocks or voted for it), so that crashed validators only
+/// get a leader slot once in a while.
+///
+/// The window of `round` ends at the highest committed block with a round lower than or equal to
+/// `round - EXCLUDE_ROUNDS`, it is read from the committed history rather than from the block
+/// tree, whose root differs between the replicas. The choice is deterministic among the replicas
+/// that recorded the same committed blocks up to the end of the window. A replica that did not
+/// commit them yet, or skipped some of them through state synchronization (until `window_size`
+/// blocks are committed past the synchronization), might disagree about the proposer, which
+/// costs at most a timeout.
+pub struct LeaderReputation {
+    // Ordering of proposers to choose from (all honest replicas must agree on this)
+    proposers: Vec<Author>,
+    committed_history: Arc<CommittedHistory>,
+    // Number of committed blocks looked at to compute the active proposers
+    window_size: usize,
+}
+
+impl LeaderReputation {
+    pub fn new(
+        proposers: Vec<Author>,
+        committed_history: Arc<CommittedHistory>,
+        window_size: usize,
+    ) -> Self {
+        assert!(!proposers.is_empty());
+        Self {
+            proposers,
+            committed_history,
+            window_size,
+        }
+    }
+
+    /// Returns the authors and voters of the window of committed blocks preceding the given
+    /// round.
+    fn active_authors(&self, round: Round) -> HashSet<Author> {
+        self.committed_history
+            .window(round.saturating_sub(EXCLUDE_ROUNDS), self.window_size)
+            .iter()
+            .flat_map(CommittedBlockInfo::participants)
+            .cloned()
+            .collect()
+    }
+
+    fn get_proposer(&self, round: Round) -> Author {
+        let active = self.active_authors(round);
+        let weights: Vec<u64> = self
+            .proposers
+            .iter()
+            .map(|author| {
+                if active.contains(author) {
+                    ACTIVE_WEIGHT
+                } else {
+                    INACTIVE_WEIGHT
+                }
+            })
+            .collect();
+        let total_weight: u64 = weights.iter().sum();
+        let mut selection = hash(round) % total_weight;
+        for (author, weight) in self.proposers.iter().zip(weights) {
+            if selection < weight {
+                return *author;
+            }
+            selection -= weight;
+        }
+        unreachable!("The selection is lower than the total weight");
+    }
+}
+
+impl<T: Payload> ProposerElection<T> for LeaderReputation {
+    fn is_valid_proposer(&self, author: Author, round: Round) -> Option<Author> {
+        if self.get_proposer(round) == author {
+            Some(author)
+        } else {
+            None
+        }
+    }
+
+    fn get_valid_proposers(&self, round: Round) -> Vec<Author> {
+        vec![self.get_proposer(round)]
+    }
+
+    fn process_proposal(&mut self, proposal: Block<T>) -> Option<Block<T>> {
+        let round_author = self.get_proposer(proposal.round());
+        if Some(round_author) != proposal.author() {
+            None
+        } else {
+            Some(proposal)
+        }
+    }
+
+    fn take_backup_proposal(&mut self, _round: Round) -> Option<Block<T>> {
+        None
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Author, Round},
+    liveness::{
+        committed_history::{CommittedBlockInfo, CommittedHistory},
+        leader_reputation::{LeaderReputation, EXCLUDE_ROUNDS, HISTORY_SIZE, WINDOW_SIZE},
+        proposer_election::ProposerElection,
+    },
+    test_utils::TestPayload,
+};
+use std::sync::Arc;
+use types::crypto_proxies::ValidatorSigner;
+
+fn committed_blocks(
+    rounds: impl Iterator<Item = Round>,
+    author: Author,
+) -> Vec<CommittedBlockInfo> {
+    rounds
+        .map(|round| CommittedBlockInfo::new(round, Some(author), vec![author]))
+        .collect()
+}
+
+#[test]
+fn test_leader_reputation_is_deterministic() {
+    let active_author = ValidatorSigner::random(None).author();
+    let proposers = vec![
+        active_author,
+        ValidatorSigner::random(None).author(),
+        ValidatorSigner::random(None).author(),
+    ];
+    let pe: Box<dyn ProposerElection<TestPayload>> = Box::new(LeaderReputation::new(
+        proposers.clone(),
+        Arc::new(CommittedHistory::new(
+            committed_blocks(1..50, active_author),
+            HISTORY_SIZE,
+        )),
+        WINDOW_SIZE,
+    ));
+    // Another replica that recorded the blocks as they got committed.
+    let committed_history = Arc::new(CommittedHistory::new(vec![], HISTORY_SIZE));
+    for round in 1..50 {
+        committed_history.append(committed_blocks(round..=round, active_author));
+    }
+    let another_pe: Box<dyn ProposerElection<TestPayload>> = Box::new(LeaderReputation::new(
+        proposers.clone(),
+        committed_history,
+        WINDOW_SIZE,
+    ));
+
+    for round in 50..150 {
+        let valid_proposers = pe.get_valid_proposers(round);
+        assert_eq!(valid_proposers.len(), 1);
+        assert!(proposers.contains(&valid_proposers[0]));
+        assert_eq!(valid_proposers, another_pe.get_valid_proposers(round));
+        assert_eq!(
+            pe.is_valid_proposer(valid_proposers[0], round),
+            Some(valid_proposers[0])
+        );
+        for author in proposers.iter().filter(|a| **a != valid_proposers[0]) {
+            assert_eq!(pe.is_valid_proposer(*author, round), None);
+        }
+    }
+}
+
+#[test]
+fn test_leader_reputation_prefers_active_proposers() {
+    let active_author = ValidatorSigner::random(None).author();
+    let proposers = vec![
+        ValidatorSigner::random(None).author(),
+        active_author,
+        ValidatorSigner::random(None).author(),
+    ];
+    // All the committed blocks are authored and voted for by the same validator.
+    let last_round = WINDOW_SIZE as Round;
+    let pe = LeaderReputation::new(
+        proposers,
+        Arc::new(CommittedHistory::new(
+            committed_blocks(1..=last_round, active_author),
+            HISTORY_SIZE,
+        )),
+        WINDOW_SIZE,
+    );
+    let first_round = last_round + EXCLUDE_ROUNDS;
+    let num_rounds = 1000;
+    let active_rounds = (first_round..first_round + num_rounds)
+        .filter(|round| {
+            ProposerElection::<TestPayload>::get_valid_proposers(&pe, *round) == vec![active_author]
+        })
+        .count();
+    // The active author has a weight of 100 out of 102.
+    assert!(active_rounds > (num_rounds * 9 / 10) as usize);
+}
+
+#[test]
+fn test_committed_history_window() {
+    let author = ValidatorSigner::random(None).author();
+    let committed_history =
+        CommittedHistory::new(committed_blocks(vec![1, 2, 4, 7, 8].into_iter(), author), 4);
+    let rounds = |blocks: Vec<CommittedBlockInfo>| -> Vec<Round> {
+        blocks.iter().map(CommittedBlockInfo::round).collect()
+    };
+    // Only the highest blocks are kept.
+    assert_eq!(rounds(committed_history.window(8, 10)), vec![8, 7, 4, 2]);
+    // The window ends at the highest block not above the given round.
+    assert_eq!(rounds(committed_history.window(6, 2)), vec![4, 2]);
+    assert!(committed_history.window(1, 2).is_empty());
+
+    // Blocks that are not higher than the last recorded round are ignored.
+    assert_eq!(
+        committed_history.append(committed_blocks(vec![8, 9, 10].into_iter(), author)),
+        vec![2, 4]
+    );
+    assert_eq!(rounds(committed_history.window(10, 10)), vec![10, 9, 8, 7]);
+}
@@ -1,6 +1,9 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod adaptive_time_interval;
+pub(crate) mod committed_history;
+pub(crate) mod leader_reputation;
 pub(crate) mod multi_proposer_election;
 pub(crate) mod pacemaker;
 pub(crate) mod pacemaker_timeout_manager;
@@ -8,6 +11,10 @@ pub(crate) mod proposal_generator;
 pub(crate) mod proposer_election;
 pub(crate) mod rotating_proposer_election;
 
+#[cfg(test)]
+mod adaptive_time_interval_test;
+#[cfg(test)]
+mod leader_reputation_test;
 #[cfg(test)]
 mod multi_proposer_test;
 #[cfg(test)]
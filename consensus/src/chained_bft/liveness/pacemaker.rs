@@ -72,6 +72,10 @@ pub trait PacemakerTimeInterval: Send + Sync + 'static {
     /// to calculate the round duration of round 6 and the highest committed round is 3 (meaning
     /// the highest round to commit a block is round 5, then the round index is 0.
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration;
+
+    /// Called with the time it took to gather the QC of a round, i.e., from the start of the round
+    /// to the new round event it triggered. Ignored by default.
+    fn observe_qc_latency(&mut self, _latency: Duration) {}
 }
 
 /// Round durations increase exponentially
@@ -155,6 +159,8 @@ pub struct Pacemaker {
     current_round: Round,
     // Approximate deadline when current round ends
     current_round_deadline: Instant,
+    // When the current round started (according to the time service)
+    current_round_start: Duration,
     // Service for timer
     time_service: Arc<dyn TimeService>,
     // To send timeout events to other pacemakers
@@ -183,6 +189,7 @@ impl Pacemaker {
             highest_qc_round: 0,
             current_round: 0,
             current_round_deadline: Instant::now(),
+            current_round_start: time_service.get_current_timestamp(),
             time_service,
             timeout_sender,
             pacemaker_timeout_manager: PacemakerTimeoutManager::new(
@@ -269,7 +276,21 @@ impl Pacemaker {
             self.current_round,
             new_round
         );
+        let now = self.time_service.get_current_timestamp();
+        // The QC of the current round is ready: the previous rounds ended without QC, or were
+        // skipped (e.g., after syncing up), hence their latencies are not meaningful.
+        if best_reason == NewRoundReason::QCReady
+            && self.current_round > 0
+            && new_round == self.current_round + 1
+        {
+            let latency = now
+                .checked_sub(self.current_round_start)
+                .unwrap_or_default();
+            counters::QC_LATENCY_S.observe_duration(latency);
+            self.time_interval.observe_qc_latency(latency);
+        }
         self.current_round = new_round;
+        self.current_round_start = now;
         let timeout = self.setup_timeout();
         Some(NewRoundEvent {
             round: self.current_round,
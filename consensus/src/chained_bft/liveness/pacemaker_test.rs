@@ -19,7 +19,11 @@ use crate::{
 };
 use channel;
 use futures::{executor::block_on, StreamExt};
-use std::{sync::Arc, time::Duration, u64};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+    u64,
+};
 use types::crypto_proxies::ValidatorSigner;
 
 #[test]
@@ -87,6 +91,54 @@ fn test_basic_qc() {
     expect_qc(4, pm.process_certificates(3, None, None));
 }
 
+/// Fixed round duration, keeps the observed QC latencies.
+struct RecordingTimeInterval {
+    latencies: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl PacemakerTimeInterval for RecordingTimeInterval {
+    fn get_round_duration(&self, _round_index_after_committed_qc: usize) -> Duration {
+        Duration::from_secs(10)
+    }
+
+    fn observe_qc_latency(&mut self, latency: Duration) {
+        self.latencies.lock().unwrap().push(latency);
+    }
+}
+
+#[test]
+fn test_qc_latency() {
+    let latencies = Arc::new(Mutex::new(vec![]));
+    let time_interval = Box::new(RecordingTimeInterval {
+        latencies: Arc::clone(&latencies),
+    });
+    let time_service = SimulatedTimeService::new();
+    let (timeout_tx, _timeout_rx) = channel::new_test(1_024);
+    let mut pm = Pacemaker::new(
+        MockStorage::<TestPayload>::start_for_testing()
+            .0
+            .persistent_liveness_storage(),
+        time_interval,
+        Arc::new(time_service.clone()),
+        timeout_tx,
+        HighestTimeoutCertificates::default(),
+    );
+
+    // The first round doesn't have a start.
+    expect_qc(2, pm.process_certificates(1, None, None));
+    time_service.advance_to(Duration::from_millis(300));
+    expect_qc(3, pm.process_certificates(2, None, None));
+    // The skipped rounds are not observed.
+    time_service.advance_to(Duration::from_millis(500));
+    expect_qc(6, pm.process_certificates(5, None, None));
+    time_service.advance_to(Duration::from_millis(600));
+    expect_qc(7, pm.process_certificates(6, None, None));
+    assert_eq!(
+        *latencies.lock().unwrap(),
+        vec![Duration::from_millis(300), Duration::from_millis(100)]
+    );
+}
+
 fn make_pacemaker() -> (Pacemaker, channel::Receiver<Round>) {
     let time_interval = Box::new(ExponentialTimeInterval::fixed(Duration::from_millis(2)));
     let simulated_time = SimulatedTimeService::auto_advance_until(Duration::from_millis(4));
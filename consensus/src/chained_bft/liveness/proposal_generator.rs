@@ -1,7 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::{common::Round, consensus_types::block::Block};
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::block::{Block, BlockData},
+};
 
 use crate::{
     chained_bft::{block_storage::BlockReader, common::Payload},
@@ -59,6 +62,8 @@ pub struct ProposalGenerator<T> {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // Number of the transactions of a proposed block reserved for the privileged senders.
+    privileged_lane_size: u64,
     // Support increasing block timestamps
     enforce_increasing_timestamps: bool,
     // Last round that a proposal was generated
@@ -78,11 +83,20 @@ impl<T: Payload> ProposalGenerator<T> {
             txn_manager,
             time_service,
             max_block_size,
+            privileged_lane_size: 0,
             enforce_increasing_timestamps,
             last_round_generated: Mutex::new(0),
         }
     }
 
+    /// Reserves up to the given number of the transactions of each proposed block for the
+    /// transactions of the privileged senders (e.g. the validator set updates), so that they
+    /// can't be starved by the other transactions during congestion.
+    pub fn with_privileged_lane_size(mut self, privileged_lane_size: u64) -> Self {
+        self.privileged_lane_size = privileged_lane_size;
+        self
+    }
+
     /// Creates a NIL block proposal extending the highest certified block from the block store.
     pub fn generate_nil_block(&self, round: Round) -> Result<Block<T>, ProposalGenerationError> {
         let hqc_block = self.block_store.highest_certified_block();
@@ -101,8 +115,8 @@ impl<T: Payload> ProposalGenerator<T> {
         ))
     }
 
-    /// The function generates a new proposal block: the returned future is fulfilled when the
-    /// payload is delivered by the TxnManager implementation.  At most one proposal can be
+    /// The function generates a new proposal block, to be signed by SafetyRules: the returned
+    /// future is fulfilled when the payload is delivered by the TxnManager implementation.  At most one proposal can be
     /// generated per round (no proposal equivocation allowed).
     /// Errors returned by the TxnManager implementation are propagated to the caller.
     /// The logic for choosing the branch to extend is as follows:
@@ -116,7 +130,7 @@ impl<T: Payload> ProposalGenerator<T> {
         &self,
         round: Round,
         round_deadline: Instant,
-    ) -> Result<Block<T>, ProposalGenerationError> {
+    ) -> Result<BlockData<T>, ProposalGenerationError> {
         {
             let mut last_round_generated = self.last_round_generated.lock().unwrap();
             if *last_round_generated < round {
@@ -222,7 +236,11 @@ impl<T: Payload> ProposalGenerator<T> {
         let block_store = Arc::clone(&self.block_store);
         match self
             .txn_manager
-            .pull_txns(self.max_block_size, exclude_payload)
+            .pull_txns_with_privileged_lane(
+                self.max_block_size,
+                self.privileged_lane_size,
+                exclude_payload,
+            )
             .await
         {
             Ok(txns) => Ok(block_store.create_block(
@@ -76,15 +76,14 @@ fn test_proposal_generation_parent() {
     // Once a1 is certified, it should be the one to choose from
     let vote_msg_a1 = VoteMsg::new(
         a1.id(),
-        block_store.get_state_for_block(a1.id()).unwrap(),
         a1.round(),
         a1.quorum_cert().certified_parent_block_id(),
         a1.quorum_cert().certified_parent_block_round(),
         a1.quorum_cert().certified_grandparent_block_id(),
         a1.quorum_cert().certified_grandparent_block_round(),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
     );
     block_store.insert_vote_and_qc(vote_msg_a1, 1);
     let a1_child_res =
@@ -97,15 +96,14 @@ fn test_proposal_generation_parent() {
     // Once b1 is certified, it should be the one to choose from
     let vote_msg_b1 = VoteMsg::new(
         b1.id(),
-        block_store.get_state_for_block(b1.id()).unwrap(),
         b1.round(),
         b1.quorum_cert().certified_parent_block_id(),
         b1.quorum_cert().certified_parent_block_round(),
         b1.quorum_cert().certified_grandparent_block_id(),
         b1.quorum_cert().certified_grandparent_block_round(),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
     );
 
     block_store.insert_vote_and_qc(vote_msg_b1, 1);
@@ -132,15 +130,14 @@ fn test_old_proposal_generation() {
     let a1 = inserter.insert_block(genesis.as_ref(), 1);
     let vote_msg_a1 = VoteMsg::new(
         a1.id(),
-        block_store.get_state_for_block(a1.id()).unwrap(),
         a1.round(),
         a1.quorum_cert().certified_parent_block_id(),
         a1.quorum_cert().certified_parent_block_round(),
         a1.quorum_cert().certified_grandparent_block_id(),
         a1.quorum_cert().certified_grandparent_block_round(),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
     );
     block_store.insert_vote_and_qc(vote_msg_a1, 1);
 
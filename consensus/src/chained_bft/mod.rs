@@ -2,16 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod common;
-mod consensus_types;
-mod consensusdb;
-mod liveness;
-mod safety;
+pub(crate) mod consensus_types;
+pub(crate) mod consensusdb;
+pub(crate) mod liveness;
+pub(crate) mod safety;
 
+mod batching;
 mod block_storage;
 pub mod chained_bft_consensus_provider;
 pub use consensus_types::quorum_cert::QuorumCert;
 mod chained_bft_smr;
+mod equivocation_detector;
 mod event_processor;
+mod evidence_reporter;
 mod network;
 
 pub mod epoch_manager;
@@ -25,4 +28,6 @@ mod network_tests;
 #[cfg(test)]
 mod proto_test;
 #[cfg(test)]
+mod simulator;
+#[cfg(test)]
 pub mod test_utils;
@@ -3,10 +3,12 @@
 
 use crate::{
     chained_bft::{
+        batching::BatchStore,
         block_storage::BlockRetrievalFailure,
         common::{Author, Payload},
         consensus_types::{
-            block::Block, proposal_msg::ProposalMsg, sync_info::SyncInfo, timeout_msg::TimeoutMsg,
+            batch::Batch, block::Block, proposal_msg::ProposalMsg, sync_info::SyncInfo,
+            timeout_msg::TimeoutMsg,
         },
         epoch_manager::EpochManager,
         safety::vote_msg::VoteMsg,
@@ -23,7 +25,9 @@ use futures::{
 };
 use logger::prelude::*;
 use network::{
-    proto::{BlockRetrievalStatus, ConsensusMsg, RequestBlock, RespondBlock},
+    proto::{
+        BlockRetrievalStatus, ConsensusMsg, RequestBatch, RequestBlock, RespondBatch, RespondBlock,
+    },
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
 };
 use proto_conv::{FromProto, IntoProto};
@@ -33,7 +37,7 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio::runtime::TaskExecutor;
-use types::account_address::AccountAddress;
+use types::{account_address::AccountAddress, validator_set::ValidatorSet};
 
 /// The response sent back from EventProcessor for the BlockRetrievalRequest.
 #[derive(Debug)]
@@ -130,7 +134,13 @@ impl ConsensusNetworkImpl {
     }
 
     /// Establishes the initial connections with the peers and returns the receivers.
-    pub fn start<T: Payload>(&mut self, executor: &TaskExecutor) -> NetworkReceivers<T> {
+    /// The batches disseminated by the peers are added to the given batch store, which also
+    /// serves the batch retrieval requests.
+    pub fn start<T: Payload>(
+        &mut self,
+        executor: &TaskExecutor,
+        batch_store: Arc<BatchStore>,
+    ) -> NetworkReceivers<T> {
         let (proposal_tx, proposal_rx) = channel::new(1_024, &counters::PENDING_PROPOSAL);
         let (vote_tx, vote_rx) = channel::new(1_024, &counters::PENDING_VOTES);
         let (block_request_tx, block_request_rx) =
@@ -157,6 +167,7 @@ impl ConsensusNetworkImpl {
                 sync_info_tx,
                 all_events,
                 epoch_mgr: Arc::clone(&self.epoch_mgr),
+                batch_store,
             }
             .run()
             .boxed()
@@ -172,6 +183,17 @@ impl ConsensusNetworkImpl {
         }
     }
 
+    /// Returns the receiver of the messages this node sends to itself, for the tests that drive
+    /// the message delivery without starting the network task.
+    #[cfg(test)]
+    pub fn take_self_receiver(
+        &mut self,
+    ) -> channel::Receiver<Result<Event<ConsensusMsg>, failure::Error>> {
+        self.self_receiver
+            .take()
+            .expect("[consensus]: self receiver is already taken")
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is either fulfilled with BlockRetrievalResponse, or with a
     /// BlockRetrievalFailure.
@@ -217,6 +239,56 @@ impl ConsensusNetworkImpl {
         Ok(response)
     }
 
+    /// Tries to retrieve the batch with the given digest from the given peer.
+    pub async fn request_batch(
+        &mut self,
+        digest: HashValue,
+        from: Author,
+        timeout: Duration,
+    ) -> failure::Result<Batch> {
+        if from == self.author {
+            return Err(format_err!("Can not retrieve batch {} from itself", digest));
+        }
+        let mut req_msg = RequestBatch::new();
+        req_msg.set_digest(digest.into());
+        counters::BATCH_RETRIEVAL_COUNT.inc();
+        let pre_retrieval_instant = Instant::now();
+
+        let mut res_batch = self
+            .network_sender
+            .request_batch(from, req_msg, timeout)
+            .await?;
+        if !res_batch.has_batch() {
+            return Err(format_err!("Batch {} is unknown to {}", digest, from));
+        }
+        let batch = Batch::from_proto(res_batch.take_batch())?;
+        if batch.digest() != digest {
+            return Err(format_err!(
+                "Requested batch {}, received batch {}",
+                digest,
+                batch.digest()
+            ));
+        }
+        counters::BATCH_RETRIEVAL_DURATION_S.observe_duration(pre_retrieval_instant.elapsed());
+        Ok(batch)
+    }
+
+    /// Sends the given batch created by this validator to all the other participants, which add
+    /// it to their batch store. Like the other broadcasts, the future is fulfilled as soon as the
+    /// messages are put into the network channel.
+    pub async fn broadcast_batch(&mut self, batch: Batch) {
+        let mut msg = ConsensusMsg::new();
+        msg.set_batch(batch.into_proto());
+        for peer in self.epoch_mgr.validators().get_ordered_account_addresses() {
+            if self.author == peer {
+                continue;
+            }
+            if let Err(err) = self.network_sender.send_to(peer, msg.clone()).await {
+                error!("Error broadcasting batch to peer {:?}: {:?}", peer, err);
+            }
+        }
+    }
+
     /// Tries to send the given proposal (block and proposer metadata) to all the participants.
     /// A validator on the receiving end is going to be notified about a new proposal in the
     /// proposal queue.
@@ -301,6 +373,18 @@ impl ConsensusNetworkImpl {
             );
         }
     }
+
+    /// Notifies the network about the validator set that takes effect at the next epoch: the
+    /// network is going to accept and maintain connections to the new validators only.
+    pub async fn update_eligible_nodes(&self, validators: &ValidatorSet) {
+        let mut network_sender = self.network_sender.clone();
+        if let Err(e) = network_sender
+            .update_eligible_nodes(validators.payload().to_vec())
+            .await
+        {
+            error!("Failed to update the set of eligible nodes: {:?}", e);
+        }
+    }
 }
 
 struct NetworkTask<T, S> {
@@ -311,6 +395,7 @@ struct NetworkTask<T, S> {
     sync_info_tx: channel::Sender<(SyncInfo, AccountAddress)>,
     all_events: S,
     epoch_mgr: Arc<EpochManager>,
+    batch_store: Arc<BatchStore>,
 }
 
 impl<T, S> NetworkTask<T, S>
@@ -330,6 +415,8 @@ where
                         self.process_timeout_msg(&mut msg).await
                     } else if msg.has_sync_info() {
                         self.process_sync_info(&mut msg, peer_id).await
+                    } else if msg.has_batch() {
+                        self.process_batch(&mut msg, peer_id)
                     } else {
                         warn!("Unexpected msg from {}: {:?}", peer_id, msg);
                         continue;
@@ -341,6 +428,8 @@ where
                 Event::RpcRequest((peer_id, mut msg, callback)) => {
                     let r = if msg.has_request_block() {
                         self.process_request_block(&mut msg, callback).await
+                    } else if msg.has_request_batch() {
+                        self.process_request_batch(&mut msg, callback)
                     } else {
                         warn!("Unexpected RPC from {}: {:?}", peer_id, msg);
                         continue;
@@ -349,6 +438,16 @@ where
                         warn!("Failed to process RPC {:?}: {:?}", msg, e)
                     }
                 }
+                Event::StreamingRpcRequest((peer_id, msg, mut res_tx)) => {
+                    warn!("Unexpected streaming RPC from {}: {:?}", peer_id, msg);
+                    // Fail the stream rather than ending it, so the peer doesn't mistake it for
+                    // an empty response.
+                    let _ = res_tx
+                        .send(Err(RpcError::ApplicationError(format_err!(
+                            "Consensus does not serve streaming RPCs"
+                        ))))
+                        .await;
+                }
                 Event::NewPeer(peer_id) => {
                     debug!("Peer {} connected", peer_id);
                 }
@@ -427,6 +526,56 @@ where
         Ok(())
     }
 
+    fn process_batch(
+        &mut self,
+        msg: &mut ConsensusMsg,
+        peer: AccountAddress,
+    ) -> failure::Result<()> {
+        let batch = Batch::from_proto(msg.take_batch())?;
+        // Batches are not signed: a batch is accepted from its author only, which is
+        // authenticated by the network.
+        if batch.author() != peer
+            || self
+                .epoch_mgr
+                .validators()
+                .get_public_key(batch.author())
+                .is_none()
+        {
+            security_log(SecurityEvent::InvalidBatch)
+                .error("InvalidAuthor")
+                .data(&peer)
+                .data(batch.author())
+                .log();
+            return Err(format_err!("Batch {} is not sent by its author", batch));
+        }
+        debug!("Received {}", batch);
+        self.batch_store.insert(batch);
+        Ok(())
+    }
+
+    fn process_request_batch(
+        &mut self,
+        msg: &mut ConsensusMsg,
+        callback: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) -> failure::Result<()> {
+        let digest = HashValue::from_slice(msg.get_request_batch().get_digest())?;
+        debug!("Received request_batch RPC for {}", digest);
+        let mut response = RespondBatch::new();
+        if let Some(batch) = self.batch_store.get(digest) {
+            response.set_batch(Batch::clone(batch.as_ref()).into_proto());
+        }
+        let mut response_msg = ConsensusMsg::new();
+        response_msg.set_respond_batch(response);
+        let response_data = Bytes::from(
+            response_msg
+                .write_to_bytes()
+                .expect("fail to serialize proto"),
+        );
+        callback
+            .send(Ok(response_data))
+            .map_err(|_| format_err!("handling inbound rpc call timed out"))
+    }
+
     async fn process_request_block<'a>(
         &'a mut self,
         msg: &'a mut ConsensusMsg,
@@ -1,18 +1,17 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{
-    chained_bft::{
-        common::Author,
-        consensus_types::{
-            block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert, sync_info::SyncInfo,
-        },
-        epoch_manager::EpochManager,
-        network::{BlockRetrievalResponse, ConsensusNetworkImpl, NetworkReceivers},
-        safety::vote_msg::VoteMsg,
-        test_utils::{consensus_runtime, placeholder_ledger_info},
+use crate::chained_bft::{
+    batching::BatchStore,
+    common::Author,
+    consensus_types::{
+        batch::Batch, block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
     },
-    state_replication::ExecutedState,
+    epoch_manager::EpochManager,
+    network::{BlockRetrievalResponse, ConsensusNetworkImpl, NetworkReceivers},
+    safety::vote_msg::VoteMsg,
+    test_utils::{consensus_runtime, placeholder_ledger_info},
 };
 use channel;
 use crypto::{ed25519::*, HashValue};
@@ -349,12 +348,11 @@ fn test_network_api() {
             network_events,
             Arc::clone(&epoch_mgr),
         );
-        receivers.push(node.start(&runtime.executor()));
+        receivers.push(node.start(&runtime.executor(), Arc::new(BatchStore::new(1_000))));
         nodes.push(node);
     }
     let vote = VoteMsg::new(
         HashValue::random(),
-        ExecutedState::state_for_genesis(),
         1,
         HashValue::random(),
         0,
@@ -399,6 +397,7 @@ fn test_rpc() {
     let mut receivers: Vec<NetworkReceivers<u64>> = Vec::new();
     let mut playground = NetworkPlayground::new(runtime.executor());
     let mut nodes = Vec::new();
+    let mut batch_stores = Vec::new();
     let mut author_to_public_keys = HashMap::new();
     for i in 0..num_nodes {
         let random_validator_signer = ValidatorSigner::<Ed25519PrivateKey>::random([i as u8; 32]);
@@ -426,7 +425,9 @@ fn test_rpc() {
             Arc::clone(&epoch_mgr),
         );
         senders.push(network_sender);
-        receivers.push(node.start(&runtime.executor()));
+        let batch_store = Arc::new(BatchStore::new(1_000));
+        receivers.push(node.start(&runtime.executor(), Arc::clone(&batch_store)));
+        batch_stores.push(batch_store);
         nodes.push(node);
     }
     let receiver_1 = receivers.remove(1);
@@ -450,11 +451,23 @@ fn test_rpc() {
         .executor()
         .spawn(on_request_block.boxed().unit_error().compat());
     let peer = peers[1];
+    // verify request batch rpc, served by the batch store
+    let batch = Batch::new(peer, &[1usize, 2, 3]);
+    batch_stores[1].insert(batch.clone());
     block_on(async move {
         let response = nodes[0]
             .request_block(genesis.id(), 1, peer, Duration::from_secs(5))
             .await
             .unwrap();
         assert_eq!(response.blocks[0], *genesis);
+        let fetched = nodes[0]
+            .request_batch(batch.digest(), peer, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(fetched, batch);
+        assert!(nodes[0]
+            .request_batch(HashValue::random(), peer, Duration::from_secs(5))
+            .await
+            .is_err());
     });
 }
@@ -3,11 +3,14 @@
 
 use crate::{
     chained_bft::{
-        common::Payload,
-        consensus_types::{block::Block, quorum_cert::QuorumCert},
+        common::{Payload, Round},
+        consensus_types::{block::Block, evidence::Evidence, quorum_cert::QuorumCert},
         consensusdb::ConsensusDB,
-        liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
-        safety::safety_rules::ConsensusState,
+        liveness::{
+            committed_history::CommittedBlockInfo,
+            pacemaker_timeout_manager::HighestTimeoutCertificates,
+        },
+        safety::safety_rules::{ConsensusState, SignedConsensusState},
     },
     consensus_provider::create_storage_read_client,
 };
@@ -20,6 +23,7 @@ use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
+use types::{crypto_proxies::ValidatorSigner, validator_set::ValidatorSet};
 
 /// Persistent storage for liveness data
 pub trait PersistentLivenessStorage: Send + Sync {
@@ -51,6 +55,21 @@ pub trait PersistentStorage<T>: PersistentLivenessStorage + Send + Sync {
     /// Persist the consensus state.
     fn save_consensus_state(&self, state: ConsensusState) -> Result<()>;
 
+    /// Persist the evidence of a validator misbehavior, returns false if there is already evidence
+    /// of the same kind for its author and round, in which case nothing is written.
+    fn save_evidence(&self, evidence: Evidence<T>) -> Result<bool>;
+
+    /// Persist the current epoch and its validator set.
+    fn save_epoch_info(&self, epoch: usize, validators: &ValidatorSet) -> Result<()>;
+
+    /// Persist the info of newly committed blocks and delete the ones of the pruned rounds from
+    /// the committed history atomically.
+    fn save_committed_blocks(
+        &self,
+        committed_blocks: Vec<CommittedBlockInfo>,
+        pruned_rounds: Vec<Round>,
+    ) -> Result<()>;
+
     /// When the node restart, construct the instance and returned the data read from db.
     /// This could guarantee we only read once during start, and we would panic if the
     /// read fails.
@@ -76,6 +95,12 @@ pub struct RecoveryData<T> {
     // Liveness data
     highest_timeout_certificates: HighestTimeoutCertificates,
 
+    // The latest epoch and its validator set, None until the first reconfiguration
+    epoch_info: Option<(usize, ValidatorSet)>,
+
+    // The latest committed blocks, ordered by round
+    committed_history: Vec<CommittedBlockInfo>,
+
     // whether root is consistent with StateComputer, if not we need to do the state sync before
     // starting
     need_sync: bool,
@@ -120,10 +145,24 @@ impl<T: Payload> RecoveryData<T> {
             quorum_certs,
             blocks_to_prune,
             highest_timeout_certificates,
+            epoch_info: None,
+            committed_history: vec![],
             need_sync,
         })
     }
 
+    /// Sets the latest epoch and its validator set as recovered from the storage.
+    pub fn with_epoch_info(mut self, epoch_info: Option<(usize, ValidatorSet)>) -> Self {
+        self.epoch_info = epoch_info;
+        self
+    }
+
+    /// Sets the latest committed blocks as recovered from the storage.
+    pub fn with_committed_history(mut self, committed_history: Vec<CommittedBlockInfo>) -> Self {
+        self.committed_history = committed_history;
+        self
+    }
+
     pub fn state(&self) -> ConsensusState {
         self.state.clone()
     }
@@ -148,6 +187,14 @@ impl<T: Payload> RecoveryData<T> {
         &self.highest_timeout_certificates
     }
 
+    pub fn epoch_info(&self) -> Option<&(usize, ValidatorSet)> {
+        self.epoch_info.as_ref()
+    }
+
+    pub fn committed_history(&self) -> &[CommittedBlockInfo] {
+        &self.committed_history
+    }
+
     pub fn root_ledger_info(&self) -> QuorumCert {
         self.root.2.clone()
     }
@@ -266,11 +313,26 @@ impl<T: Payload> RecoveryData<T> {
 /// The proxy we use to persist data in libra db storage service via grpc.
 pub struct StorageWriteProxy {
     db: Arc<ConsensusDB>,
+    // Signs the persisted consensus state, see `SignedConsensusState`.
+    state_signer: Option<ValidatorSigner>,
 }
 
 impl StorageWriteProxy {
     pub fn new(db: Arc<ConsensusDB>) -> Self {
-        StorageWriteProxy { db }
+        StorageWriteProxy {
+            db,
+            state_signer: None,
+        }
+    }
+
+    /// Persists the consensus state as a SignedConsensusState, signed by `signer`.
+    pub fn with_state_signer(mut self, signer: ValidatorSigner) -> Self {
+        self.state_signer = Some(signer);
+        self
+    }
+
+    pub fn consensus_db(&self) -> Arc<ConsensusDB> {
+        Arc::clone(&self.db)
     }
 }
 
@@ -304,7 +366,39 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
     }
 
     fn save_consensus_state(&self, state: ConsensusState) -> Result<()> {
-        self.db.save_state(to_vec_named(&state)?)
+        match self.state_signer.as_ref() {
+            Some(signer) => self
+                .db
+                .save_state(to_vec_named(&SignedConsensusState::new(state, signer)?)?),
+            None => self.db.save_state(to_vec_named(&state)?),
+        }
+    }
+
+    fn save_evidence(&self, evidence: Evidence<T>) -> Result<bool> {
+        self.db.save_evidence(
+            evidence.round(),
+            evidence.evidence_kind(),
+            evidence.author(),
+            to_vec_named(&evidence)?,
+        )
+    }
+
+    fn save_epoch_info(&self, epoch: usize, validators: &ValidatorSet) -> Result<()> {
+        self.db.save_epoch_info(to_vec_named(&(epoch, validators))?)
+    }
+
+    fn save_committed_blocks(
+        &self,
+        committed_blocks: Vec<CommittedBlockInfo>,
+        pruned_rounds: Vec<Round>,
+    ) -> Result<()> {
+        self.db.save_committed_blocks(
+            committed_blocks
+                .iter()
+                .map(|block| Ok((block.round(), to_vec_named(block)?)))
+                .collect::<Result<_>>()?,
+            pruned_rounds,
+        )
     }
 
     fn start(config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
@@ -322,6 +416,23 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
             .map_or_else(HighestTimeoutCertificates::default, |s| {
                 from_slice(&s[..]).expect("unable to deserialize highest timeout certificates")
             });
+        let epoch_info: Option<(usize, ValidatorSet)> = db
+            .get_epoch_info()
+            .expect("unable to read epoch info")
+            .map(|s| from_slice(&s[..]).expect("unable to deserialize epoch info"));
+        if let Some((epoch, validators)) = epoch_info.as_ref() {
+            debug!(
+                "Recovered epoch {} with {} validators",
+                epoch,
+                validators.payload().len()
+            );
+        }
+        let committed_history: Vec<CommittedBlockInfo> = db
+            .get_committed_blocks()
+            .expect("unable to read committed blocks")
+            .iter()
+            .map(|s| from_slice(&s[..]).expect("unable to deserialize committed block"))
+            .collect();
         let mut blocks = initial_data.2;
         let mut quorum_certs: Vec<_> = initial_data.3;
         // bootstrap the empty store with genesis block and qc.
@@ -363,7 +474,9 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
             root_from_storage,
             highest_timeout_certificates,
         )
-        .unwrap_or_else(|e| panic!("Can not construct recovery data due to {}", e));
+        .unwrap_or_else(|e| panic!("Can not construct recovery data due to {}", e))
+        .with_epoch_info(epoch_info)
+        .with_committed_history(committed_history);
 
         <dyn PersistentStorage<T>>::prune_tree(proxy.as_ref(), initial_data.take_blocks_to_prune())
             .expect("unable to prune dangling blocks during restart");
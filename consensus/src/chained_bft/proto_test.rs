@@ -1,19 +1,17 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{
-    chained_bft::{
-        consensus_types::{
-            block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert, sync_info::SyncInfo,
-        },
-        safety::vote_msg::VoteMsg,
-        test_utils::placeholder_ledger_info,
+use crate::chained_bft::{
+    consensus_types::{
+        batch::Batch, block::Block, proposal_msg::ProposalMsg, quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
     },
-    state_replication::ExecutedState,
+    safety::vote_msg::VoteMsg,
+    test_utils::placeholder_ledger_info,
 };
 use crypto::HashValue;
 use proto_conv::test_helper::assert_protobuf_encode_decode;
-use types::validator_signer::ValidatorSigner;
+use types::{account_address::AccountAddress, validator_signer::ValidatorSigner};
 
 #[test]
 fn test_proto_convert_block() {
@@ -36,7 +34,6 @@ fn test_proto_convert_vote() {
     let signer = ValidatorSigner::random(None);
     let vote = VoteMsg::new(
         HashValue::random(),
-        ExecutedState::state_for_genesis(),
         1,
         HashValue::random(),
         0,
@@ -48,3 +45,9 @@ fn test_proto_convert_vote() {
     );
     assert_protobuf_encode_decode(&vote);
 }
+
+#[test]
+fn test_proto_convert_batch() {
+    let batch = Batch::new(AccountAddress::random(), &[1usize, 2, 3]);
+    assert_protobuf_encode_decode(&batch);
+}
@@ -0,0 +1,150 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::BlockStore,
+    common::{Payload, Round},
+    consensus_types::{
+        block::{Block, BlockData},
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::{PacemakerTimeout, TimeoutMsg},
+    },
+    persistent_storage::PersistentStorage,
+    safety::{
+        safety_rules::{ConsensusState, SafetyRules},
+        t_safety_rules::TSafetyRules,
+        vote_msg::VoteMsg,
+    },
+};
+use failure::prelude::*;
+use futures::{future, Future, FutureExt};
+use std::{pin::Pin, sync::Arc};
+use types::{crypto_proxies::ValidatorSigner, ledger_info::LedgerInfo};
+
+#[cfg(test)]
+#[path = "local_safety_rules_test.rs"]
+mod local_safety_rules_test;
+
+/// SafetyRules running in the same process as its caller. The consensus state is persisted via
+/// the given PersistentStorage before any proposal, vote or timeout is signed.
+pub struct LocalSafetyRules<T> {
+    safety_rules: SafetyRules,
+    signer: ValidatorSigner,
+    storage: Arc<dyn PersistentStorage<T>>,
+}
+
+impl<T: Payload> LocalSafetyRules<T> {
+    pub fn new(
+        consensus_state: ConsensusState,
+        signer: ValidatorSigner,
+        storage: Arc<dyn PersistentStorage<T>>,
+    ) -> Self {
+        Self {
+            safety_rules: SafetyRules::new(consensus_state),
+            signer,
+            storage,
+        }
+    }
+}
+
+impl<T: Payload> LocalSafetyRules<T> {
+    fn sign_proposal_inner(&mut self, block_data: BlockData<T>) -> Result<Block<T>> {
+        ensure!(
+            block_data.author() == self.signer.author(),
+            "Proposal {} is not authored by {}",
+            block_data,
+            self.signer.author().short_str()
+        );
+        // Never sign two different proposals for the same round, even after a restart.
+        if let Some(consensus_state) = self
+            .safety_rules
+            .proposal_rule(block_data.round(), block_data.id())?
+        {
+            self.storage.save_consensus_state(consensus_state)?;
+        }
+        Ok(block_data.sign(&self.signer))
+    }
+
+    fn construct_and_sign_vote_inner(
+        &mut self,
+        proposed_block: Arc<Block<T>>,
+        commit_ledger_info: LedgerInfo,
+    ) -> Result<VoteMsg> {
+        let vote_info = self.safety_rules.voting_rule(Arc::clone(&proposed_block))?;
+        self.storage
+            .save_consensus_state(vote_info.consensus_state().clone())?;
+
+        // Only sign a commit for the block the commit rule designates.
+        let ledger_info_placeholder = match vote_info.potential_commit_id() {
+            Some(id) if commit_ledger_info.consensus_block_id() == id => commit_ledger_info,
+            _ => BlockStore::<T>::zero_ledger_info_placeholder(),
+        };
+        Ok(VoteMsg::new(
+            vote_info.proposal_id(),
+            proposed_block.round(),
+            vote_info.parent_block_id(),
+            vote_info.parent_block_round(),
+            vote_info.grandparent_block_id(),
+            vote_info.grandparent_block_round(),
+            self.signer.author(),
+            ledger_info_placeholder,
+            &self.signer,
+        ))
+    }
+
+    fn sign_timeout_inner(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Result<TimeoutMsg> {
+        // Stop voting at this round, persist the consensus state to support restarting from
+        // a recent round (i.e. > the last vote round).
+        if let Some(consensus_state) = self.safety_rules.increase_last_vote_round(round) {
+            self.storage.save_consensus_state(consensus_state)?;
+        }
+        Ok(TimeoutMsg::new(
+            sync_info,
+            PacemakerTimeout::new(round, &self.signer, vote),
+            &self.signer,
+        ))
+    }
+}
+
+/// The requests are handled synchronously: the returned futures are ready.
+impl<T: Payload> TSafetyRules<T> for LocalSafetyRules<T> {
+    fn consensus_state(&self) -> Pin<Box<dyn Future<Output = Result<ConsensusState>> + Send>> {
+        future::ok(self.safety_rules.consensus_state()).boxed()
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.safety_rules.update(qc);
+        future::ok(()).boxed()
+    }
+
+    fn sign_proposal(
+        &mut self,
+        block_data: BlockData<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<Block<T>>> + Send>> {
+        future::ready(self.sign_proposal_inner(block_data)).boxed()
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        proposed_block: Arc<Block<T>>,
+        commit_ledger_info: LedgerInfo,
+    ) -> Pin<Box<dyn Future<Output = Result<VoteMsg>> + Send>> {
+        future::ready(self.construct_and_sign_vote_inner(proposed_block, commit_ledger_info))
+            .boxed()
+    }
+
+    fn sign_timeout(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Pin<Box<dyn Future<Output = Result<TimeoutMsg>> + Send>> {
+        future::ready(self.sign_timeout_inner(sync_info, round, vote)).boxed()
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    block_storage::{BlockReader, BlockStore},
+    consensus_types::{block::Block, sync_info::SyncInfo},
+    safety::{
+        local_safety_rules::LocalSafetyRules, safety_rules::ConsensusState,
+        t_safety_rules::TSafetyRules,
+    },
+    test_utils::{build_empty_tree, MockStorage, TestPayload, TreeInserter},
+};
+use futures::executor::block_on;
+use std::sync::Arc;
+use types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+
+fn make_safety_rules(
+    signer: ValidatorSigner,
+) -> (LocalSafetyRules<TestPayload>, Arc<MockStorage<TestPayload>>) {
+    let (storage, _) = MockStorage::start_for_testing();
+    let safety_rules = LocalSafetyRules::new(ConsensusState::default(), signer, storage.clone());
+    (safety_rules, storage)
+}
+
+#[test]
+fn test_vote_is_persisted_and_signed_once_per_round() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let (mut safety_rules, storage) = make_safety_rules(signer);
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(genesis.as_ref(), 1);
+    let b1 = inserter.insert_block(genesis.as_ref(), 1);
+
+    block_on(safety_rules.update(a1.quorum_cert())).unwrap();
+    let vote = block_on(safety_rules.construct_and_sign_vote(
+        a1.clone(),
+        BlockStore::<TestPayload>::zero_ledger_info_placeholder(),
+    ))
+    .unwrap();
+    assert_eq!(vote.proposed_block_id(), a1.id());
+    assert!(vote.verify(&validator).is_ok());
+    assert_eq!(
+        storage
+            .shared_storage
+            .state
+            .lock()
+            .unwrap()
+            .last_vote_round(),
+        1
+    );
+
+    // A different block at the same round must not be voted for.
+    assert!(block_on(safety_rules.construct_and_sign_vote(
+        b1.clone(),
+        BlockStore::<TestPayload>::zero_ledger_info_placeholder(),
+    ))
+    .is_err());
+}
+
+#[test]
+fn test_timeout_stops_voting_at_round() {
+    let block_tree = build_empty_tree();
+    let mut inserter = TreeInserter::new(block_tree.clone());
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let (mut safety_rules, storage) = make_safety_rules(signer);
+
+    let genesis = block_tree.root();
+    let a1 = inserter.insert_block(genesis.as_ref(), 1);
+    let sync_info = SyncInfo::new(
+        block_tree.highest_quorum_cert().as_ref().clone(),
+        block_tree.highest_ledger_info().as_ref().clone(),
+        None,
+    );
+
+    let timeout_msg = block_on(safety_rules.sign_timeout(sync_info, 1, None)).unwrap();
+    assert!(timeout_msg.verify(&validator).is_ok());
+    assert_eq!(
+        storage
+            .shared_storage
+            .state
+            .lock()
+            .unwrap()
+            .last_vote_round(),
+        1
+    );
+    assert_eq!(
+        block_on(safety_rules.consensus_state())
+            .unwrap()
+            .last_vote_round(),
+        1
+    );
+
+    assert!(block_on(safety_rules.construct_and_sign_vote(
+        a1.clone(),
+        BlockStore::<TestPayload>::zero_ledger_info_placeholder(),
+    ))
+    .is_err());
+}
+
+#[test]
+fn test_proposal_is_signed_for_own_author_only() {
+    let block_tree = build_empty_tree();
+    let signer = ValidatorSigner::random(None);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let (mut safety_rules, _) = make_safety_rules(signer.clone());
+
+    let genesis = block_tree.root();
+    let proposal_data = block_tree.create_block(genesis.clone(), vec![1], 1, 1);
+    let proposal = block_on(safety_rules.sign_proposal(proposal_data)).unwrap();
+    assert_eq!(proposal.author(), Some(signer.author()));
+    assert!(proposal.verify(&validator).is_ok());
+
+    // A proposal authored by another validator must not be signed.
+    let other_proposal_data = Block::make_block_data(
+        genesis.as_ref(),
+        vec![2],
+        2,
+        2,
+        genesis.quorum_cert().clone(),
+        ValidatorSigner::random([1; 32]).author(),
+    );
+    assert!(block_on(safety_rules.sign_proposal(other_proposal_data)).is_err());
+}
+
+#[test]
+fn test_proposal_is_signed_once_per_round() {
+    let block_tree = build_empty_tree();
+    let signer = ValidatorSigner::random(None);
+    let (mut safety_rules, storage) = make_safety_rules(signer);
+
+    let genesis = block_tree.root();
+    let proposal_data = block_tree.create_block(genesis.clone(), vec![1], 1, 1);
+    let proposal = block_on(safety_rules.sign_proposal(proposal_data.clone())).unwrap();
+    assert_eq!(
+        storage
+            .shared_storage
+            .state
+            .lock()
+            .unwrap()
+            .last_proposed_round(),
+        1
+    );
+
+    // The same proposal can be signed again, e.g. when the request is retried.
+    assert_eq!(
+        block_on(safety_rules.sign_proposal(proposal_data)).unwrap(),
+        proposal
+    );
+    // A different proposal at the same round must not be signed.
+    let other_proposal_data = block_tree.create_block(genesis.clone(), vec![2], 1, 2);
+    assert!(block_on(safety_rules.sign_proposal(other_proposal_data)).is_err());
+}
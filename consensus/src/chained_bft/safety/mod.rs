@@ -1,5 +1,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod local_safety_rules;
+pub(crate) mod proto;
+pub(crate) mod remote_safety_rules;
 pub(crate) mod safety_rules;
+pub(crate) mod safety_rules_server;
+pub(crate) mod t_safety_rules;
 pub(crate) mod vote_msg;
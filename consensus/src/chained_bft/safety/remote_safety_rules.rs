@@ -0,0 +1,143 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::{Payload, Round},
+    consensus_types::{
+        block::{Block, BlockData},
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::TimeoutMsg,
+    },
+    safety::{
+        proto::{
+            safety_rules::{
+                ConsensusStateRequest, ConstructAndSignVoteRequest, SignProposalRequest,
+                SignTimeoutRequest, UpdateRequest,
+            },
+            safety_rules_grpc::SafetyRulesClient,
+        },
+        safety_rules::ConsensusState,
+        t_safety_rules::TSafetyRules,
+        vote_msg::VoteMsg,
+    },
+};
+use failure::Result;
+use futures::{compat::Future01CompatExt, future, Future, FutureExt};
+use grpcio::{ChannelBuilder, ClientUnaryReceiver, EnvBuilder};
+use rmp_serde::{from_slice, to_vec_named};
+use std::{marker::PhantomData, pin::Pin, sync::Arc};
+use types::ledger_info::LedgerInfo;
+
+/// SafetyRules running in a separate process (see `SafetyRulesNode`), reached over gRPC.
+/// The consensus state is persisted by the remote process, which also holds the signing key.
+/// The requests are sent asynchronously: the event processing is not blocked waiting for the
+/// responses.
+pub struct RemoteSafetyRules<T> {
+    client: SafetyRulesClient,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Payload> RemoteSafetyRules<T> {
+    pub fn new(address: &str, port: u16) -> Self {
+        let env = Arc::new(EnvBuilder::new().name_prefix("grpc-safety-rules-").build());
+        let channel = ChannelBuilder::new(env).connect(&format!("{}:{}", address, port));
+        Self {
+            client: SafetyRulesClient::new(channel),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Waits for the response to a request and decodes it.
+    fn response<R, O>(
+        request: Result<grpcio::Result<ClientUnaryReceiver<R>>>,
+        decode: impl FnOnce(R) -> Result<O> + Send + 'static,
+    ) -> Pin<Box<dyn Future<Output = Result<O>> + Send>>
+    where
+        R: Send + 'static,
+        O: Send + 'static,
+    {
+        match request {
+            Ok(Ok(receiver)) => {
+                // convert from grpcio enum to failure::Error
+                async move {
+                    match receiver.compat().await {
+                        Ok(response) => decode(response),
+                        Err(e) => Err(e.into()),
+                    }
+                }
+                    .boxed()
+            }
+            Ok(Err(e)) => future::err(e.into()).boxed(),
+            Err(e) => future::err(e).boxed(),
+        }
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for RemoteSafetyRules<T> {
+    fn consensus_state(&self) -> Pin<Box<dyn Future<Output = Result<ConsensusState>> + Send>> {
+        Self::response(
+            Ok(self
+                .client
+                .consensus_state_async(&ConsensusStateRequest::new())),
+            |response| Ok(from_slice(response.get_consensus_state())?),
+        )
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        let request = to_vec_named(qc).map(|qc| {
+            let mut request = UpdateRequest::new();
+            request.set_quorum_cert(qc);
+            self.client.update_async(&request)
+        });
+        Self::response(request.map_err(Into::into), |_| Ok(()))
+    }
+
+    fn sign_proposal(
+        &mut self,
+        block_data: BlockData<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<Block<T>>> + Send>> {
+        let request = to_vec_named(&block_data).map(|block_data| {
+            let mut request = SignProposalRequest::new();
+            request.set_block_data(block_data);
+            self.client.sign_proposal_async(&request)
+        });
+        Self::response(request.map_err(Into::into), |response| {
+            Ok(from_slice(response.get_block())?)
+        })
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        proposed_block: Arc<Block<T>>,
+        commit_ledger_info: LedgerInfo,
+    ) -> Pin<Box<dyn Future<Output = Result<VoteMsg>> + Send>> {
+        let request = (|| -> Result<_> {
+            let mut request = ConstructAndSignVoteRequest::new();
+            request.set_proposed_block(to_vec_named(proposed_block.as_ref())?);
+            request.set_commit_ledger_info(to_vec_named(&commit_ledger_info)?);
+            Ok(self.client.construct_and_sign_vote_async(&request))
+        })();
+        Self::response(request, |response| Ok(from_slice(response.get_vote())?))
+    }
+
+    fn sign_timeout(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Pin<Box<dyn Future<Output = Result<TimeoutMsg>> + Send>> {
+        let request = (|| -> Result<_> {
+            let mut request = SignTimeoutRequest::new();
+            request.set_sync_info(to_vec_named(&sync_info)?);
+            request.set_round(round);
+            if let Some(vote) = vote {
+                request.set_vote(to_vec_named(&vote)?);
+            }
+            Ok(self.client.sign_timeout_async(&request))
+        })();
+        Self::response(request, |response| {
+            Ok(from_slice(response.get_timeout_msg())?)
+        })
+    }
+}
@@ -9,12 +9,21 @@ use crate::{
     counters,
 };
 
-use crypto::HashValue;
+use crypto::{
+    hash::{ConsensusStateHasher, CryptoHash, CryptoHasher},
+    HashValue,
+};
+use failure::format_err;
+use rmp_serde::to_vec_named;
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::{Display, Formatter},
     sync::Arc,
 };
+use types::{
+    account_address::AccountAddress,
+    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+};
 
 #[cfg(test)]
 #[path = "safety_rules_test.rs"]
@@ -97,6 +106,17 @@ pub enum ProposalReject {
         last_vote_round: Round,
         proposal_round: Round,
     },
+
+    /// A different block was already proposed at this round or a later one - return
+    /// last_proposed_round
+    #[fail(
+        display = "Proposal at round {:?} is not newer than the last proposed round {:?}",
+        proposal_round, last_proposed_round
+    )]
+    AlreadyProposed {
+        last_proposed_round: Round,
+        proposal_round: Round,
+    },
 }
 
 /// The state required to guarantee safety of the protocol.
@@ -116,6 +136,11 @@ pub struct ConsensusState {
     // parent. 2) The "max round" rule applies to the HEAD of the chain and not its TAIL (one
     // does not necessarily apply the other).
     preferred_block_round: Round,
+
+    // The round and the id of the last block proposed by this node: a node proposes at most one
+    // block per round.
+    #[serde(default)]
+    last_proposal: Option<(Round, HashValue)>,
 }
 
 impl Display for ConsensusState {
@@ -124,19 +149,33 @@ impl Display for ConsensusState {
             f,
             "ConsensusState: [\n\
              \tlast_vote_round = {},\n\
-             \tpreferred_block_round = {}\n\
+             \tpreferred_block_round = {},\n\
+             \tlast_proposed_round = {}\n\
              ]",
-            self.last_vote_round, self.preferred_block_round
+            self.last_vote_round,
+            self.preferred_block_round,
+            self.last_proposed_round()
         )
     }
 }
 
+impl CryptoHash for ConsensusState {
+    type Hasher = ConsensusStateHasher;
+
+    fn hash(&self) -> HashValue {
+        let mut state = Self::Hasher::default();
+        state.write(&to_vec_named(self).expect("Should serialize."));
+        state.finish()
+    }
+}
+
 impl ConsensusState {
     #[cfg(test)]
     pub fn new(last_vote_round: Round, preferred_block_round: Round) -> Self {
         Self {
             last_vote_round,
             preferred_block_round,
+            last_proposal: None,
         }
     }
 
@@ -150,6 +189,11 @@ impl ConsensusState {
         self.preferred_block_round
     }
 
+    /// Returns the round of the last block proposed by this node, 0 if none
+    pub fn last_proposed_round(&self) -> Round {
+        self.last_proposal.map_or(0, |(round, _)| round)
+    }
+
     /// Set the last vote round that ensures safety.  If the last vote round increases, return
     /// the new consensus state based with the updated last vote round.  Otherwise, return None.
     fn set_last_vote_round(&mut self, last_vote_round: Round) -> Option<ConsensusState> {
@@ -229,6 +273,35 @@ impl SafetyRules {
         self.state.set_last_vote_round(round)
     }
 
+    /// Checks that this node may propose the block with the given id at the given round: the
+    /// proposal must be newer than the last vote round, and the node proposes at most one block
+    /// per round, so that it can't equivocate. Returns the new consensus state, to be persisted
+    /// before the proposal is signed, or None in case the same block was already proposed (e.g.
+    /// the request is retried).
+    pub fn proposal_rule(
+        &mut self,
+        round: Round,
+        block_id: HashValue,
+    ) -> Result<Option<ConsensusState>, ProposalReject> {
+        if self.state.last_proposal == Some((round, block_id)) {
+            return Ok(None);
+        }
+        if round <= self.state.last_vote_round() {
+            return Err(ProposalReject::OldProposal {
+                proposal_round: round,
+                last_vote_round: self.state.last_vote_round(),
+            });
+        }
+        if self.state.last_proposal.is_some() && round <= self.state.last_proposed_round() {
+            return Err(ProposalReject::AlreadyProposed {
+                proposal_round: round,
+                last_proposed_round: self.state.last_proposed_round(),
+            });
+        }
+        self.state.last_proposal = Some((round, block_id));
+        Ok(Some(self.state.clone()))
+    }
+
     /// Clones the up-to-date state of consensus (for monitoring / debugging purposes)
     pub fn consensus_state(&self) -> ConsensusState {
         self.state.clone()
@@ -283,3 +356,33 @@ impl SafetyRules {
         }
     }
 }
+
+/// ConsensusState signed with the consensus key of the node it belongs to, as persisted by the
+/// standalone SafetyRules service: a state that was not written by the service (e.g. edited to
+/// lower the last vote round) is detected when the service recovers it. The signature doesn't
+/// cover the freshness of the state, i.e. it can't tell an older state written by the service
+/// from the latest one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedConsensusState {
+    state: ConsensusState,
+    signature: Signature,
+}
+
+impl SignedConsensusState {
+    pub fn new(state: ConsensusState, signer: &ValidatorSigner) -> failure::Result<Self> {
+        let signature = signer.sign_message(state.hash())?.into();
+        Ok(Self { state, signature })
+    }
+
+    /// Returns the state if it is signed by the consensus key of `author`.
+    pub fn verify(
+        self,
+        author: AccountAddress,
+        verifier: &ValidatorVerifier,
+    ) -> failure::Result<ConsensusState> {
+        self.signature
+            .verify(verifier, author, self.state.hash())
+            .map_err(|e| format_err!("Consensus state was tampered with: {:?}", e))?;
+        Ok(self.state)
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Payload,
+    consensus_types::{
+        block::{Block, BlockData},
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+    },
+    persistent_storage::PersistentStorage,
+    safety::{
+        local_safety_rules::LocalSafetyRules,
+        proto::{
+            safety_rules::{
+                ConsensusStateRequest, ConsensusStateResponse, ConstructAndSignVoteRequest,
+                ConstructAndSignVoteResponse, SignProposalRequest, SignProposalResponse,
+                SignTimeoutRequest, SignTimeoutResponse, UpdateRequest, UpdateResponse,
+            },
+            safety_rules_grpc,
+        },
+        t_safety_rules::TSafetyRules,
+        vote_msg::VoteMsg,
+    },
+};
+use failure::prelude::*;
+use futures::executor::block_on;
+use grpc_helpers::provide_grpc_response;
+use logger::prelude::*;
+use rmp_serde::{from_slice, to_vec_named};
+use std::sync::{Arc, Mutex};
+use types::crypto_proxies::ValidatorVerifier;
+
+/// The safety rules along with the validators of the current epoch, against which the
+/// certificates and the blocks sent by the caller are verified.
+struct SafetyRulesState<T> {
+    safety_rules: LocalSafetyRules<T>,
+    epoch: usize,
+    validators: ValidatorVerifier,
+}
+
+/// Exposes a LocalSafetyRules over gRPC, see proto/safety_rules.proto.
+/// The requests are processed one at a time: the safety rules must see the votes and timeouts in
+/// the order they are signed.
+/// The caller is not trusted: the quorum certificates and the proposed blocks are verified before
+/// the consensus state is updated, and the validator set changes when a verified quorum
+/// certificate commits the end of the current epoch.
+pub struct SafetyRulesServer<T> {
+    state: Arc<Mutex<SafetyRulesState<T>>>,
+    storage: Arc<dyn PersistentStorage<T>>,
+}
+
+impl<T> Clone for SafetyRulesServer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+            storage: Arc::clone(&self.storage),
+        }
+    }
+}
+
+impl<T: Payload> SafetyRulesServer<T> {
+    pub fn new(
+        safety_rules: LocalSafetyRules<T>,
+        epoch: usize,
+        validators: ValidatorVerifier,
+        storage: Arc<dyn PersistentStorage<T>>,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SafetyRulesState {
+                safety_rules,
+                epoch,
+                validators,
+            })),
+            storage,
+        }
+    }
+
+    fn consensus_state_inner(&self) -> Result<ConsensusStateResponse> {
+        let consensus_state = block_on(self.state.lock().unwrap().safety_rules.consensus_state())?;
+        let mut response = ConsensusStateResponse::new();
+        response.set_consensus_state(to_vec_named(&consensus_state)?);
+        Ok(response)
+    }
+
+    fn update_inner(&self, req: &UpdateRequest) -> Result<UpdateResponse> {
+        let qc: QuorumCert = from_slice(req.get_quorum_cert())?;
+        let mut state = self.state.lock().unwrap();
+        qc.verify(&state.validators)?;
+        block_on(state.safety_rules.update(&qc))?;
+
+        let ledger_info = qc.ledger_info().ledger_info();
+        if let Some(validators) = ledger_info.next_validator_set() {
+            if ledger_info.epoch_num() == state.epoch as u64 {
+                let epoch = state.epoch + 1;
+                self.storage.save_epoch_info(epoch, validators)?;
+                info!(
+                    "Starting epoch {} with {} validators",
+                    epoch,
+                    validators.payload().len()
+                );
+                state.epoch = epoch;
+                state.validators = ValidatorVerifier::from(validators);
+            }
+        }
+        Ok(UpdateResponse::new())
+    }
+
+    fn sign_proposal_inner(&self, req: &SignProposalRequest) -> Result<SignProposalResponse> {
+        let block_data: BlockData<T> = from_slice(req.get_block_data())?;
+        let mut state = self.state.lock().unwrap();
+        block_data.quorum_cert().verify(&state.validators)?;
+        let block = block_on(state.safety_rules.sign_proposal(block_data))?;
+        let mut response = SignProposalResponse::new();
+        response.set_block(to_vec_named(&block)?);
+        Ok(response)
+    }
+
+    fn construct_and_sign_vote_inner(
+        &self,
+        req: &ConstructAndSignVoteRequest,
+    ) -> Result<ConstructAndSignVoteResponse> {
+        let proposed_block: Block<T> = from_slice(req.get_proposed_block())?;
+        let commit_ledger_info = from_slice(req.get_commit_ledger_info())?;
+        let mut state = self.state.lock().unwrap();
+        proposed_block
+            .verify(&state.validators)
+            .map_err(|e| format_err!("Invalid proposed block: {:?}", e))?;
+        let vote = block_on(
+            state
+                .safety_rules
+                .construct_and_sign_vote(Arc::new(proposed_block), commit_ledger_info),
+        )?;
+        let mut response = ConstructAndSignVoteResponse::new();
+        response.set_vote(to_vec_named(&vote)?);
+        Ok(response)
+    }
+
+    fn sign_timeout_inner(&self, req: &SignTimeoutRequest) -> Result<SignTimeoutResponse> {
+        let sync_info: SyncInfo = from_slice(req.get_sync_info())?;
+        let vote: Option<VoteMsg> = if req.get_vote().is_empty() {
+            None
+        } else {
+            Some(from_slice(req.get_vote())?)
+        };
+        let mut state = self.state.lock().unwrap();
+        sync_info.verify(&state.validators)?;
+        if let Some(vote) = vote.as_ref() {
+            vote.verify(&state.validators)?;
+        }
+        let timeout_msg = block_on(state.safety_rules.sign_timeout(
+            sync_info,
+            req.get_round(),
+            vote,
+        ))?;
+        let mut response = SignTimeoutResponse::new();
+        response.set_timeout_msg(to_vec_named(&timeout_msg)?);
+        Ok(response)
+    }
+}
+
+impl<T: Payload> safety_rules_grpc::SafetyRules for SafetyRulesServer<T> {
+    fn consensus_state(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        _req: ConsensusStateRequest,
+        sink: ::grpcio::UnarySink<ConsensusStateResponse>,
+    ) {
+        provide_grpc_response(self.consensus_state_inner(), ctx, sink);
+    }
+
+    fn update(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: UpdateRequest,
+        sink: ::grpcio::UnarySink<UpdateResponse>,
+    ) {
+        provide_grpc_response(self.update_inner(&req), ctx, sink);
+    }
+
+    fn sign_proposal(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignProposalRequest,
+        sink: ::grpcio::UnarySink<SignProposalResponse>,
+    ) {
+        provide_grpc_response(self.sign_proposal_inner(&req), ctx, sink);
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: ConstructAndSignVoteRequest,
+        sink: ::grpcio::UnarySink<ConstructAndSignVoteResponse>,
+    ) {
+        provide_grpc_response(self.construct_and_sign_vote_inner(&req), ctx, sink);
+    }
+
+    fn sign_timeout(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignTimeoutRequest,
+        sink: ::grpcio::UnarySink<SignTimeoutResponse>,
+    ) {
+        provide_grpc_response(self.sign_timeout_inner(&req), ctx, sink);
+    }
+}
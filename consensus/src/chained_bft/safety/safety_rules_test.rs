@@ -5,7 +5,7 @@ use crate::chained_bft::{
     block_storage::BlockReader,
     common::Round,
     consensus_types::block::{block_test, Block},
-    safety::safety_rules::{ConsensusState, ProposalReject, SafetyRules},
+    safety::safety_rules::{ConsensusState, ProposalReject, SafetyRules, SignedConsensusState},
     test_utils::{build_empty_tree, build_empty_tree_with_custom_signing, TreeInserter},
 };
 use cached::{cached_key, SizedCache};
@@ -16,7 +16,7 @@ use std::{
     hash::{Hash, Hasher},
     sync::Arc,
 };
-use types::validator_signer::ValidatorSigner;
+use types::{crypto_proxies::ValidatorVerifier, validator_signer::ValidatorSigner};
 
 fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
@@ -405,3 +405,50 @@ fn test_commit_rule_consecutive_rounds() {
         Some(a2.id())
     );
 }
+
+#[test]
+fn test_proposal_rule() {
+    let mut safety_rules = SafetyRules::new(ConsensusState::new(1, 0));
+    let (a, b) = (HashValue::random(), HashValue::random());
+
+    assert_eq!(
+        safety_rules.proposal_rule(1, a),
+        Err(ProposalReject::OldProposal {
+            last_vote_round: 1,
+            proposal_round: 1,
+        })
+    );
+    let state = safety_rules.proposal_rule(2, a).unwrap().unwrap();
+    assert_eq!(state.last_proposed_round(), 2);
+    // Proposing the same block again is a no-op, proposing another one is rejected.
+    assert_eq!(safety_rules.proposal_rule(2, a), Ok(None));
+    assert_eq!(
+        safety_rules.proposal_rule(2, b),
+        Err(ProposalReject::AlreadyProposed {
+            last_proposed_round: 2,
+            proposal_round: 2,
+        })
+    );
+    assert!(safety_rules.proposal_rule(3, b).unwrap().is_some());
+}
+
+#[test]
+fn test_signed_consensus_state() {
+    let signer = ValidatorSigner::random(None);
+    let verifier = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let state = ConsensusState::new(5, 3);
+
+    let signed_state = SignedConsensusState::new(state.clone(), &signer).unwrap();
+    assert_eq!(
+        signed_state
+            .clone()
+            .verify(signer.author(), &verifier)
+            .unwrap(),
+        state
+    );
+
+    // e.g. rolled back to vote again at a past round
+    let mut tampered_state = signed_state;
+    tampered_state.state.last_vote_round = 1;
+    assert!(tampered_state.verify(signer.author(), &verifier).is_err());
+}
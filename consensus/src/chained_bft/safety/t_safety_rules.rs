@@ -0,0 +1,58 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    common::Round,
+    consensus_types::{
+        block::{Block, BlockData},
+        quorum_cert::QuorumCert,
+        sync_info::SyncInfo,
+        timeout_msg::TimeoutMsg,
+    },
+    safety::{safety_rules::ConsensusState, vote_msg::VoteMsg},
+};
+use failure::Result;
+use futures::Future;
+use std::{pin::Pin, sync::Arc};
+use types::ledger_info::LedgerInfo;
+
+/// Interface between the consensus process and SafetyRules.
+/// SafetyRules holds the consensus signing key and the state required to guarantee the safety of
+/// the protocol (the last vote round and the preferred block round): votes and timeouts are only
+/// signed after the voting rules are checked and the new state is persisted, so that even a
+/// compromised caller cannot make the validator vote twice at the same round.
+/// Proposals are signed by SafetyRules as well: the consensus process doesn't need the signing key.
+/// The requests return futures so that the event processing is not blocked while a remote
+/// SafetyRules handles them.
+pub trait TSafetyRules<T>: Send + Sync {
+    /// Returns the up-to-date consensus state (for monitoring / debugging purposes).
+    fn consensus_state(&self) -> Pin<Box<dyn Future<Output = Result<ConsensusState>> + Send>>;
+
+    /// Learn about a new quorum certificate: might update the preferred block round.
+    fn update(&mut self, qc: &QuorumCert) -> Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+    /// Signs a proposal authored by this validator.
+    fn sign_proposal(
+        &mut self,
+        block_data: BlockData<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<Block<T>>> + Send>>;
+
+    /// Checks the voting rules for the given proposal and returns the signed vote in case it
+    /// passes them. The vote only covers the ordering of the proposal: `commit_ledger_info` is
+    /// the ledger info placeholder of the grandparent of the proposal carrying its executed state,
+    /// which is committed in case the vote gathers a QC and the commit rule holds.
+    fn construct_and_sign_vote(
+        &mut self,
+        proposed_block: Arc<Block<T>>,
+        commit_ledger_info: LedgerInfo,
+    ) -> Pin<Box<dyn Future<Output = Result<VoteMsg>> + Send>>;
+
+    /// Stops voting at the given round and returns the signed timeout message for it, optionally
+    /// carrying the vote sent at this round.
+    fn sign_timeout(
+        &mut self,
+        sync_info: SyncInfo,
+        round: Round,
+        vote: Option<VoteMsg>,
+    ) -> Pin<Box<dyn Future<Output = Result<TimeoutMsg>> + Send>>;
+}
@@ -1,10 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{
-    chained_bft::common::{Author, Round},
-    state_replication::ExecutedState,
-};
+use crate::chained_bft::common::{Author, Round};
 use canonical_serialization::{CanonicalSerialize, CanonicalSerializer, SimpleSerializer};
 use crypto::{
     hash::{CryptoHash, CryptoHasher, VoteMsgHasher},
@@ -39,7 +36,6 @@ pub enum VoteMsgVerificationError {
 // computation of its hash.
 struct VoteMsgSerializer {
     proposed_block_id: HashValue,
-    executed_state: ExecutedState,
     round: Round,
     parent_block_id: HashValue,
     parent_block_round: Round,
@@ -51,7 +47,6 @@ impl CanonicalSerialize for VoteMsgSerializer {
     fn serialize(&self, serializer: &mut impl CanonicalSerializer) -> failure::Result<()> {
         serializer
             .encode_bytes(self.proposed_block_id.as_ref())?
-            .encode_struct(&self.executed_state)?
             .encode_u64(self.round)?
             .encode_bytes(self.parent_block_id.as_ref())?
             .encode_u64(self.parent_block_round)?
@@ -79,12 +74,12 @@ impl CryptoHash for VoteMsgSerializer {
 /// receiving a proposal.
 /// VoteMsg carries the `LedgerInfo` of a block that is going to be committed in case this vote
 /// is gathers QuorumCertificate (see the detailed explanation in the comments of `LedgerInfo`).
+/// The vote itself only covers the ordering of the proposed block: the state resulting from the
+/// execution of a block is signed in the `LedgerInfo` committing it.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct VoteMsg {
     /// The id of the proposed block.
     proposed_block_id: HashValue,
-    /// The id of the state generated by the StateExecutor after executing the proposed block.
-    executed_state: ExecutedState,
     /// The round of the block.
     round: Round,
     /// The id of the parent block of the proposal
@@ -124,7 +119,6 @@ impl Display for VoteMsg {
 impl VoteMsg {
     pub fn new(
         proposed_block_id: HashValue,
-        executed_state: ExecutedState,
         round: Round,
         parent_block_id: HashValue,
         parent_block_round: Round,
@@ -136,7 +130,6 @@ impl VoteMsg {
     ) -> Self {
         ledger_info_placeholder.set_consensus_data_hash(Self::vote_digest(
             proposed_block_id,
-            executed_state,
             round,
             parent_block_id,
             parent_block_round,
@@ -148,7 +141,6 @@ impl VoteMsg {
             .expect("Failed to sign LedgerInfo");
         Self {
             proposed_block_id,
-            executed_state,
             round,
             parent_block_id,
             parent_block_round,
@@ -165,11 +157,6 @@ impl VoteMsg {
         self.proposed_block_id
     }
 
-    /// Return the executed state of the proposed block
-    pub fn executed_state(&self) -> ExecutedState {
-        self.executed_state
-    }
-
     /// Return the round of the block
     pub fn round(&self) -> Round {
         self.round
@@ -225,7 +212,6 @@ impl VoteMsg {
     pub fn vote_hash(&self) -> HashValue {
         Self::vote_digest(
             self.proposed_block_id,
-            self.executed_state,
             self.round,
             self.parent_block_id,
             self.parent_block_round,
@@ -237,7 +223,6 @@ impl VoteMsg {
     /// Return a digest of the vote
     pub fn vote_digest(
         proposed_block_id: HashValue,
-        executed_state: ExecutedState,
         round: Round,
         parent_block_id: HashValue,
         parent_block_round: Round,
@@ -246,7 +231,6 @@ impl VoteMsg {
     ) -> HashValue {
         VoteMsgSerializer {
             proposed_block_id,
-            executed_state,
             round,
             parent_block_id,
             parent_block_round,
@@ -263,8 +247,6 @@ impl IntoProto for VoteMsg {
     fn into_proto(self) -> Self::ProtoType {
         let mut proto = Self::ProtoType::new();
         proto.set_proposed_block_id(self.proposed_block_id().into());
-        proto.set_executed_state_id(self.executed_state().state_id.into());
-        proto.set_version(self.executed_state().version);
         proto.set_round(self.round);
         proto.set_parent_block_id(self.parent_block_id.into());
         proto.set_parent_block_round(self.parent_block_round);
@@ -282,8 +264,6 @@ impl FromProto for VoteMsg {
 
     fn from_proto(mut object: Self::ProtoType) -> ProtoResult<Self> {
         let proposed_block_id = HashValue::from_slice(object.get_proposed_block_id())?;
-        let state_id = HashValue::from_slice(object.get_executed_state_id())?;
-        let version = object.get_version();
         let round = object.get_round();
         let parent_block_id = HashValue::from_slice(object.get_parent_block_id())?;
         let parent_block_round = object.get_parent_block_round();
@@ -294,7 +274,6 @@ impl FromProto for VoteMsg {
         let signature = Signature::try_from(object.get_signature())?;
         Ok(VoteMsg {
             proposed_block_id,
-            executed_state: ExecutedState { state_id, version },
             round,
             parent_block_id,
             parent_block_round,
@@ -0,0 +1,735 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A deterministic simulator of the chained BFT protocol.
+//!
+//! All the validators run in a single thread: their EventProcessors are driven one event at a
+//! time and the messages they send are intercepted and buffered instead of going through the
+//! network. At every step a seeded random scheduler picks the next message to deliver (or fires
+//! the pacemaker timeouts when no message is ready), so that a given seed always reproduces the
+//! same execution. The simulation time is provided by a shared `SimulatedTimeService`. (The only
+//! choice left to the validators is the peer a failed block retrieval is retried with, which does
+//! not change the retrieved blocks.)
+//!
+//! Faults are scripted by the tests: messages can be dropped at random or between given peers and
+//! delayed, validators can be crashed and restarted from their `MockStorage`, and a validator can
+//! be made Byzantine so that it equivocates its proposals. The safety invariants are checked after
+//! every step, the liveness is checked by the tests with `run_until`.
+
+use crate::{
+    chained_bft::{
+        block_storage::{BlockReader, BlockStore},
+        common::{Author, Height, Round},
+        consensus_types::{
+            block::Block, proposal_msg::ProposalMsg, sync_info::SyncInfo, timeout_msg::TimeoutMsg,
+        },
+        epoch_manager::EpochManager,
+        event_processor::EventProcessor,
+        liveness::{
+            pacemaker::{ExponentialTimeInterval, Pacemaker},
+            proposal_generator::ProposalGenerator,
+            rotating_proposer_election::RotatingProposer,
+        },
+        network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
+        persistent_storage::PersistentStorage,
+        safety::{local_safety_rules::LocalSafetyRules, vote_msg::VoteMsg},
+        test_utils::{
+            test_executor, MockStateComputer, MockStorage, MockTransactionManager, TestPayload,
+        },
+    },
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+};
+use bytes::Bytes;
+use channel;
+use crypto::HashValue;
+use failure::bail;
+use futures::{
+    channel::{mpsc, oneshot},
+    executor::block_on,
+    task::{noop_waker, Context, Poll},
+    Future, Stream, StreamExt,
+};
+use logger::prelude::*;
+use network::{
+    interface::NetworkRequest,
+    proto::{ConsensusMsg, RespondBlock},
+    protocols::rpc::OutboundRpcRequest,
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
+};
+use proto_conv::{FromProto, IntoProto};
+use protobuf::Message;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier};
+
+#[cfg(test)]
+#[path = "simulator_test.rs"]
+mod simulator_test;
+
+/// The simulated duration of a round. The pacemaker deadlines are computed with the actual clock,
+/// hence it also bounds the real time a round can take in the simulation.
+const ROUND_TIMEOUT: Duration = Duration::from_secs(10);
+/// The committed blocks must stay available in memory to check the committed chains.
+const MAX_PRUNED_BLOCKS_IN_MEM: usize = 10_000;
+const MAX_PENDING_EXECUTIONS: usize = 4;
+
+/// An event to be processed by the EventProcessor of a validator.
+enum NodeEvent {
+    Start,
+    Proposal(ProposalMsg<TestPayload>),
+    Vote(VoteMsg),
+    RemoteTimeout(TimeoutMsg),
+    SyncInfo(SyncInfo, Author),
+    LocalTimeout(Round),
+}
+
+impl NodeEvent {
+    async fn process(self, event_processor: &mut EventProcessor<TestPayload>) {
+        match self {
+            NodeEvent::Start => event_processor.start().await,
+            NodeEvent::Proposal(proposal) => event_processor.process_proposal_msg(proposal).await,
+            NodeEvent::Vote(vote) => event_processor.process_vote(vote).await,
+            NodeEvent::RemoteTimeout(timeout_msg) => {
+                event_processor
+                    .process_remote_timeout_msg(timeout_msg)
+                    .await
+            }
+            NodeEvent::SyncInfo(sync_info, peer) => {
+                event_processor.process_sync_info_msg(sync_info, peer).await
+            }
+            NodeEvent::LocalTimeout(round) => event_processor.process_local_timeout(round).await,
+        }
+    }
+}
+
+/// A message sent and not delivered yet.
+struct Envelope {
+    src: Author,
+    dst: Author,
+    msg: ConsensusMsg,
+    /// The simulation time from which the message can be delivered.
+    deliver_at: Duration,
+}
+
+/// The components of a running validator.
+struct SimulatedNode {
+    event_processor: EventProcessor<TestPayload>,
+    block_store: Arc<BlockStore<TestPayload>>,
+    network_reqs_rx: channel::Receiver<NetworkRequest>,
+    self_rx: channel::Receiver<Result<Event<ConsensusMsg>, failure::Error>>,
+    timeout_rx: channel::Receiver<Round>,
+    commit_rx: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    // The transaction manager fails to commit once this receiver is dropped.
+    _txn_commit_rx: mpsc::Receiver<usize>,
+}
+
+/// A validator of the simulation: its storage and its safety records survive the crashes.
+struct Validator {
+    signer: ValidatorSigner,
+    storage: Arc<MockStorage<TestPayload>>,
+    // None while the validator is crashed.
+    node: Option<SimulatedNode>,
+    // A Byzantine validator sends a conflicting proposal to some of the validators.
+    byzantine: bool,
+    // The height of the last block committed by the validator.
+    committed_height: Height,
+    // The highest round the validator voted at.
+    last_vote_round: Round,
+}
+
+/// Runs a set of validators in a single thread, with a seeded scheduler of the message deliveries
+/// and of the timeouts.
+pub struct ConsensusSimulator {
+    rng: StdRng,
+    time_service: SimulatedTimeService,
+    epoch_mgr: Arc<EpochManager>,
+    validators: Vec<Validator>,
+    in_flight: Vec<Envelope>,
+    drop_probability: f64,
+    max_delay: Duration,
+    blocked_links: HashSet<(Author, Author)>,
+    // The blocks committed by any validator, by height.
+    committed: BTreeMap<Height, HashValue>,
+    steps: usize,
+}
+
+impl ConsensusSimulator {
+    /// Starts `num_nodes` validators from genesis, the scheduler is seeded with `seed`.
+    pub fn new(num_nodes: usize, seed: u64) -> Self {
+        let signers: Vec<_> = (0..num_nodes)
+            .map(|i| ValidatorSigner::random([i as u8; 32]))
+            .collect();
+        let author_to_public_keys = signers
+            .iter()
+            .map(|signer| (signer.author(), signer.public_key()))
+            .collect();
+        let epoch_mgr = Arc::new(EpochManager::new(
+            0,
+            ValidatorVerifier::new(author_to_public_keys),
+        ));
+        let validators = signers
+            .into_iter()
+            .map(|signer| Validator {
+                signer,
+                storage: MockStorage::start_for_testing().0,
+                node: None,
+                byzantine: false,
+                committed_height: 0,
+                last_vote_round: 0,
+            })
+            .collect();
+        let mut simulator = Self {
+            rng: StdRng::seed_from_u64(seed),
+            time_service: SimulatedTimeService::new(),
+            epoch_mgr,
+            validators,
+            in_flight: vec![],
+            drop_probability: 0.0,
+            max_delay: Duration::from_millis(0),
+            blocked_links: HashSet::new(),
+            committed: BTreeMap::new(),
+            steps: 0,
+        };
+        for idx in 0..num_nodes {
+            simulator.start_node(idx);
+        }
+        simulator
+    }
+
+    /// The authors of the validators, in the order of the proposer rotation.
+    pub fn authors(&self) -> Vec<Author> {
+        self.validators
+            .iter()
+            .map(|validator| validator.signer.author())
+            .collect()
+    }
+
+    /// Every message between two different validators is lost with the given probability.
+    pub fn set_drop_probability(&mut self, drop_probability: f64) {
+        self.drop_probability = drop_probability;
+    }
+
+    /// Every message between two different validators is delayed by up to `max_delay`.
+    pub fn set_max_delay(&mut self, max_delay: Duration) {
+        self.max_delay = max_delay;
+    }
+
+    /// Drops all the messages and RPCs from `src` to `dst` (including the messages in flight) until
+    /// `restore_link` is called.
+    pub fn block_link(&mut self, src: Author, dst: Author) {
+        self.blocked_links.insert((src, dst));
+    }
+
+    /// Stops dropping the messages from `src` to `dst`.
+    pub fn restore_link(&mut self, src: Author, dst: Author) {
+        self.blocked_links.remove(&(src, dst));
+    }
+
+    /// Cuts all the links of the given validator in both directions.
+    pub fn isolate(&mut self, author: Author) {
+        for peer in self.authors() {
+            self.block_link(author, peer);
+            self.block_link(peer, author);
+        }
+    }
+
+    /// Restores all the links of the given validator.
+    pub fn reconnect(&mut self, author: Author) {
+        for peer in self.authors() {
+            self.restore_link(author, peer);
+            self.restore_link(peer, author);
+        }
+    }
+
+    /// Stops the given validator: the messages sent to it are lost, its storage is kept.
+    pub fn crash(&mut self, author: Author) {
+        let idx = self.index(author);
+        assert!(
+            self.validators[idx].node.take().is_some(),
+            "[simulator] {} is already crashed",
+            author.short_str()
+        );
+    }
+
+    /// Restarts a crashed validator from the data it persisted.
+    pub fn restart(&mut self, author: Author) {
+        let idx = self.index(author);
+        assert!(
+            self.validators[idx].node.is_none(),
+            "[simulator] {} is running",
+            author.short_str()
+        );
+        self.start_node(idx);
+    }
+
+    /// Makes the given validator send a conflicting proposal, in addition to its actual one, to
+    /// the validators at the odd positions.
+    pub fn make_byzantine(&mut self, author: Author) {
+        let idx = self.index(author);
+        self.validators[idx].byzantine = true;
+    }
+
+    /// The storage of the given validator.
+    pub fn storage(&self, author: Author) -> Arc<MockStorage<TestPayload>> {
+        Arc::clone(&self.validators[self.index(author)].storage)
+    }
+
+    /// The blocks committed by the validators, by height.
+    pub fn committed_blocks(&self) -> &BTreeMap<Height, HashValue> {
+        &self.committed
+    }
+
+    /// The highest height committed by any validator.
+    pub fn committed_height(&self) -> Height {
+        self.committed.keys().next_back().cloned().unwrap_or(0)
+    }
+
+    /// The height of the last block committed by the given validator.
+    pub fn node_committed_height(&self, author: Author) -> Height {
+        self.validators[self.index(author)].committed_height
+    }
+
+    /// The current simulation time.
+    pub fn now(&self) -> Duration {
+        self.time_service.get_current_timestamp()
+    }
+
+    /// The number of steps run so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Runs the steps until the predicate holds, for at most `max_steps` steps. Returns whether
+    /// the predicate holds.
+    pub fn run_until<F>(&mut self, predicate: F, max_steps: usize) -> bool
+    where
+        F: Fn(&Self) -> bool,
+    {
+        for _ in 0..max_steps {
+            if predicate(self) || !self.step() {
+                break;
+            }
+        }
+        predicate(self)
+    }
+
+    /// Fires the pacemaker timeouts that are due, or delivers one of the messages that are ready,
+    /// or moves the time forward to the next timeout or message, then checks the invariants.
+    /// Returns false if there is nothing left to run.
+    pub fn step(&mut self) -> bool {
+        let now = self.now();
+        let next_deadline = self.time_service.next_deadline();
+        match next_deadline {
+            Some(deadline) if deadline <= now => self.advance_time(now),
+            _ => {
+                let ready: Vec<_> = (0..self.in_flight.len())
+                    .filter(|i| self.in_flight[*i].deliver_at <= now)
+                    .collect();
+                if ready.is_empty() {
+                    let next_delivery = self.in_flight.iter().map(|e| e.deliver_at).min();
+                    match next_deadline.into_iter().chain(next_delivery).min() {
+                        Some(time) => self.advance_time(time),
+                        None => return false,
+                    }
+                } else {
+                    let envelope = self
+                        .in_flight
+                        .remove(ready[self.rng.gen_range(0, ready.len())]);
+                    self.deliver(envelope);
+                }
+            }
+        }
+        self.steps += 1;
+        self.check_invariants();
+        true
+    }
+
+    fn index(&self, author: Author) -> usize {
+        self.validators
+            .iter()
+            .position(|validator| validator.signer.author() == author)
+            .unwrap_or_else(|| panic!("[simulator] unknown validator {}", author.short_str()))
+    }
+
+    fn start_node(&mut self, idx: usize) {
+        let signer = self.validators[idx].signer.clone();
+        let author = signer.author();
+        let storage = Arc::clone(&self.validators[idx].storage);
+        let initial_data = storage
+            .get_recovery_data()
+            .expect("[simulator] failed to recover the persisted data");
+        let consensus_state = initial_data.state();
+        let highest_timeout_certificates = initial_data.highest_timeout_certificates().clone();
+        let time_service: Arc<dyn TimeService> = Arc::new(self.time_service.clone());
+
+        // The inbound network events are never used: the simulator calls the EventProcessor.
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(1_024);
+        let (_, consensus_rx) = channel::new_test(1);
+        let mut network = ConsensusNetworkImpl::new(
+            author,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&self.epoch_mgr),
+        );
+        let self_rx = network.take_self_receiver();
+
+        let (commit_cb_sender, commit_rx) = mpsc::unbounded::<LedgerInfoWithSignatures>();
+        let state_computer = Arc::new(MockStateComputer::new(commit_cb_sender));
+        let mut txn_manager = MockTransactionManager::new();
+        let txn_commit_rx = txn_manager.take_commit_receiver();
+        let txn_manager = Arc::new(txn_manager);
+        let block_store = Arc::new(block_on(BlockStore::new(
+            storage.clone(),
+            initial_data,
+            author,
+            state_computer.clone(),
+            true,
+            MAX_PRUNED_BLOCKS_IN_MEM,
+            MAX_PENDING_EXECUTIONS,
+            test_executor(),
+        )));
+
+        let proposal_generator = ProposalGenerator::new(
+            Arc::clone(&block_store),
+            txn_manager.clone(),
+            Arc::clone(&time_service),
+            1,
+            true,
+        );
+        let safety_rules = Box::new(LocalSafetyRules::new(
+            consensus_state,
+            signer,
+            storage.clone(),
+        ));
+        let (timeout_sender, timeout_rx) = channel::new_test(1_024);
+        let pacemaker = Pacemaker::new(
+            storage.persistent_liveness_storage(),
+            Box::new(ExponentialTimeInterval::fixed(ROUND_TIMEOUT)),
+            Arc::clone(&time_service),
+            timeout_sender,
+            highest_timeout_certificates,
+        );
+        let proposer_election = Box::new(RotatingProposer::new(self.authors(), 1));
+        let event_processor = EventProcessor::new(
+            author,
+            Arc::clone(&block_store),
+            pacemaker,
+            proposer_election,
+            proposal_generator,
+            safety_rules,
+            state_computer,
+            txn_manager,
+            network,
+            storage,
+            time_service,
+            true,
+            Arc::clone(&self.epoch_mgr),
+        );
+        self.validators[idx].node = Some(SimulatedNode {
+            event_processor,
+            block_store,
+            network_reqs_rx,
+            self_rx,
+            timeout_rx,
+            commit_rx,
+            _txn_commit_rx: txn_commit_rx,
+        });
+        self.run_node(idx, NodeEvent::Start);
+    }
+
+    /// Moves the time forward and processes the pacemaker timeouts that fired, validator by
+    /// validator.
+    fn advance_time(&mut self, time: Duration) {
+        self.time_service.advance_to(time);
+        for idx in 0..self.validators.len() {
+            let rounds = match self.validators[idx].node.as_mut() {
+                Some(node) => drain(&mut node.timeout_rx),
+                None => continue,
+            };
+            for round in rounds {
+                self.run_node(idx, NodeEvent::LocalTimeout(round));
+            }
+        }
+    }
+
+    fn deliver(&mut self, envelope: Envelope) {
+        let Envelope { src, dst, msg, .. } = envelope;
+        let idx = self.index(dst);
+        if self.validators[idx].node.is_none() || self.blocked_links.contains(&(src, dst)) {
+            return;
+        }
+        match self.decode(src, msg) {
+            Ok(event) => self.run_node(idx, event),
+            Err(e) => warn!(
+                "[simulator] {} rejects a message from {}: {:?}",
+                dst.short_str(),
+                src.short_str(),
+                e
+            ),
+        }
+    }
+
+    /// Deserializes and verifies a message the same way the network task of consensus does.
+    fn decode(&self, src: Author, mut msg: ConsensusMsg) -> failure::Result<NodeEvent> {
+        let validators = self.epoch_mgr.validators();
+        if msg.has_proposal() {
+            let proposal = ProposalMsg::<TestPayload>::from_proto(msg.take_proposal())?;
+            proposal.verify(validators.as_ref())?;
+            Ok(NodeEvent::Proposal(proposal))
+        } else if msg.has_vote() {
+            let vote = VoteMsg::from_proto(msg.take_vote())?;
+            vote.verify(validators.as_ref())?;
+            Ok(NodeEvent::Vote(vote))
+        } else if msg.has_timeout_msg() {
+            let timeout_msg = TimeoutMsg::from_proto(msg.take_timeout_msg())?;
+            timeout_msg.verify(validators.as_ref())?;
+            Ok(NodeEvent::RemoteTimeout(timeout_msg))
+        } else if msg.has_sync_info() {
+            let sync_info = SyncInfo::from_proto(msg.take_sync_info())?;
+            sync_info.verify(validators.as_ref())?;
+            Ok(NodeEvent::SyncInfo(sync_info, src))
+        } else {
+            bail!("Unexpected msg: {:?}", msg)
+        }
+    }
+
+    /// Processes the event at the given validator until completion, then queues the messages it
+    /// sent. The block retrievals it issues are served right away by the reachable peers.
+    fn run_node(&mut self, idx: usize, event: NodeEvent) {
+        let author = self.validators[idx].signer.author();
+        let blocked_links = &self.blocked_links;
+        let (before, rest) = self.validators.split_at_mut(idx);
+        let (validator, after) = rest
+            .split_first_mut()
+            .expect("[simulator] invalid validator index");
+        let node = match validator.node.as_mut() {
+            Some(node) => node,
+            None => return,
+        };
+        let peers: HashMap<Author, &SimulatedNode> = before
+            .iter()
+            .chain(after.iter())
+            .filter_map(|peer| Some((peer.signer.author(), peer.node.as_ref()?)))
+            .filter(|(peer, _)| {
+                !blocked_links.contains(&(author, *peer))
+                    && !blocked_links.contains(&(*peer, author))
+            })
+            .collect();
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut sent = vec![];
+        let SimulatedNode {
+            event_processor,
+            network_reqs_rx,
+            self_rx,
+            ..
+        } = node;
+        let mut processing = Box::pin(event.process(event_processor));
+        loop {
+            let done = processing.as_mut().poll(&mut cx).is_ready();
+            let mut progress = false;
+            while let Poll::Ready(Some(request)) = network_reqs_rx.poll_next_unpin(&mut cx) {
+                progress = true;
+                match request {
+                    NetworkRequest::SendMessage(dst, message) => {
+                        let msg = protobuf::parse_from_bytes(message.mdata.as_ref())
+                            .expect("[simulator] failed to parse a message");
+                        sent.push((dst, msg));
+                    }
+                    NetworkRequest::SendRpc(dst, request) => {
+                        serve_block_retrieval(peers.get(&dst).cloned(), request)
+                    }
+                    _ => (),
+                }
+            }
+            while let Poll::Ready(Some(event)) = self_rx.poll_next_unpin(&mut cx) {
+                progress = true;
+                if let Ok(Event::Message((_, msg))) = event {
+                    sent.push((author, msg));
+                }
+            }
+            if done {
+                break;
+            }
+            assert!(
+                progress,
+                "[simulator] {} is blocked on an event",
+                author.short_str()
+            );
+        }
+        drop(processing);
+        for (dst, msg) in sent {
+            self.send(author, dst, msg);
+        }
+    }
+
+    fn send(&mut self, src: Author, dst: Author, msg: ConsensusMsg) {
+        if src != dst && msg.has_proposal() && self.validators[self.index(src)].byzantine {
+            if self.index(dst) % 2 == 1 {
+                if let Some(conflicting) = self.conflicting_proposal(src, &msg) {
+                    self.enqueue(src, dst, conflicting);
+                }
+            }
+        }
+        self.enqueue(src, dst, msg);
+    }
+
+    /// A proposal for the same round and parent as the given one, with a different payload.
+    fn conflicting_proposal(&self, author: Author, msg: &ConsensusMsg) -> Option<ConsensusMsg> {
+        let ProposalMsg {
+            proposal,
+            sync_info,
+        } = ProposalMsg::<TestPayload>::from_proto(msg.get_proposal().clone()).ok()?;
+        let validator = &self.validators[self.index(author)];
+        let parent = validator
+            .node
+            .as_ref()?
+            .block_store
+            .get_block(proposal.parent_id())?;
+        let block = Block::make_block(
+            parent.as_ref(),
+            vec![std::usize::MAX],
+            proposal.round(),
+            proposal.timestamp_usecs(),
+            proposal.quorum_cert().clone(),
+            &validator.signer,
+        );
+        let mut conflicting = ConsensusMsg::new();
+        conflicting.set_proposal(
+            ProposalMsg {
+                proposal: block,
+                sync_info,
+            }
+            .into_proto(),
+        );
+        Some(conflicting)
+    }
+
+    /// Queues a message, unless it is dropped. The messages a validator sends to itself are
+    /// neither dropped nor delayed.
+    fn enqueue(&mut self, src: Author, dst: Author, msg: ConsensusMsg) {
+        let mut deliver_at = self.now();
+        if src != dst {
+            if self.blocked_links.contains(&(src, dst))
+                || (self.drop_probability > 0.0 && self.rng.gen_bool(self.drop_probability))
+            {
+                return;
+            }
+            let max_delay_ms = self.max_delay.as_millis() as u64;
+            if max_delay_ms > 0 {
+                deliver_at += Duration::from_millis(self.rng.gen_range(0, max_delay_ms + 1));
+            }
+        }
+        self.in_flight.push(Envelope {
+            src,
+            dst,
+            msg,
+            deliver_at,
+        });
+    }
+
+    /// Checks that no validator voted at a lower round than before (restarts included) and that
+    /// all the committed blocks form a single chain.
+    fn check_invariants(&mut self) {
+        for validator in self.validators.iter_mut() {
+            let author = validator.signer.author();
+            let node = match validator.node.as_mut() {
+                Some(node) => node,
+                None => continue,
+            };
+            let last_vote_round = node
+                .event_processor
+                .consensus_state()
+                .expect("[simulator] failed to get the consensus state")
+                .last_vote_round();
+            assert!(
+                last_vote_round >= validator.last_vote_round,
+                "[simulator] {} voted at round {} after voting at round {}",
+                author.short_str(),
+                last_vote_round,
+                validator.last_vote_round
+            );
+            validator.last_vote_round = last_vote_round;
+
+            for ledger_info in drain(&mut node.commit_rx) {
+                let committed_id = ledger_info.ledger_info().consensus_block_id();
+                // Walk back to the previous commit of the validator: the blocks that are not in
+                // the block store anymore (e.g. after a restart) are not checked.
+                let mut next = node.block_store.get_block(committed_id);
+                while let Some(block) = next {
+                    if block.height() < validator.committed_height {
+                        break;
+                    }
+                    let committed = *self.committed.entry(block.height()).or_insert(block.id());
+                    assert_eq!(
+                        committed,
+                        block.id(),
+                        "[simulator] safety violation: {} committed {} at height {}",
+                        author.short_str(),
+                        block,
+                        block.height()
+                    );
+                    next = node.block_store.get_block(block.parent_id());
+                }
+                if let Some(block) = node.block_store.get_block(committed_id) {
+                    validator.committed_height = validator.committed_height.max(block.height());
+                }
+            }
+        }
+    }
+}
+
+/// Answers a block retrieval RPC with the blocks of the given peer, or with a timeout if the peer
+/// is crashed or unreachable.
+fn serve_block_retrieval(peer: Option<&SimulatedNode>, request: OutboundRpcRequest) {
+    let response = match peer {
+        Some(peer) => {
+            let msg: ConsensusMsg = protobuf::parse_from_bytes(request.data.as_ref())
+                .expect("[simulator] failed to parse an RPC");
+            let request_block = msg.get_request_block();
+            let (response_sender, mut response_receiver) = oneshot::channel();
+            block_on(
+                peer.event_processor
+                    .process_block_retrieval(BlockRetrievalRequest {
+                        block_id: HashValue::from_slice(request_block.get_block_id())
+                            .expect("[simulator] invalid block id"),
+                        num_blocks: request_block.get_num_blocks(),
+                        response_sender,
+                    }),
+            );
+            let BlockRetrievalResponse { status, blocks } = response_receiver
+                .try_recv()
+                .ok()
+                .and_then(|response| response)
+                .expect("[simulator] no block retrieval response");
+            let mut response = RespondBlock::new();
+            response.set_status(status);
+            response.set_blocks(blocks.into_iter().map(IntoProto::into_proto).collect());
+            let mut response_msg = ConsensusMsg::new();
+            response_msg.set_respond_block(response);
+            Ok(Bytes::from(
+                response_msg
+                    .write_to_bytes()
+                    .expect("fail to serialize proto"),
+            ))
+        }
+        None => Err(RpcError::TimedOut),
+    };
+    let _ = request.res_tx.send(response);
+}
+
+/// Takes all the items that are ready in the stream.
+fn drain<S: Stream + Unpin>(stream: &mut S) -> Vec<S::Item> {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut items = vec![];
+    while let Poll::Ready(Some(item)) = stream.poll_next_unpin(&mut cx) {
+        items.push(item);
+    }
+    items
+}
@@ -0,0 +1,91 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::simulator::ConsensusSimulator;
+use std::time::Duration;
+
+fn lossy_simulator(seed: u64) -> ConsensusSimulator {
+    let mut simulator = ConsensusSimulator::new(4, seed);
+    simulator.set_drop_probability(0.1);
+    simulator.set_max_delay(Duration::from_millis(500));
+    simulator
+}
+
+#[test]
+fn test_simulation_is_deterministic() {
+    let mut first = lossy_simulator(7);
+    let mut second = lossy_simulator(7);
+    for _ in 0..2_000 {
+        first.step();
+        second.step();
+    }
+    assert!(first.committed_height() > 0);
+    assert_eq!(first.committed_blocks(), second.committed_blocks());
+    assert_eq!(first.now(), second.now());
+}
+
+#[test]
+fn test_liveness_with_message_loss_and_delays() {
+    let mut simulator = lossy_simulator(11);
+    simulator.set_drop_probability(0.2);
+    assert!(simulator.run_until(|s| s.committed_height() >= 10, 50_000));
+}
+
+#[test]
+fn test_crash_and_restart() {
+    let mut simulator = ConsensusSimulator::new(4, 3);
+    let crashed = simulator.authors()[2];
+    assert!(simulator.run_until(|s| s.committed_height() >= 3, 10_000));
+
+    // The other three validators form a quorum.
+    simulator.crash(crashed);
+    let height_at_crash = simulator.node_committed_height(crashed);
+    assert!(simulator.run_until(|s| s.committed_height() >= height_at_crash + 5, 20_000));
+
+    // The restarted validator recovers from its storage and catches up with the others.
+    simulator.restart(crashed);
+    let target = simulator.committed_height() + 3;
+    assert!(simulator.run_until(|s| s.node_committed_height(crashed) >= target, 20_000));
+}
+
+#[test]
+fn test_liveness_after_partition() {
+    let mut simulator = ConsensusSimulator::new(4, 5);
+    let authors = simulator.authors();
+    assert!(simulator.run_until(|s| s.committed_height() >= 2, 10_000));
+
+    // No quorum on either side of the partition.
+    simulator.isolate(authors[0]);
+    simulator.isolate(authors[1]);
+    simulator.run_until(|_| false, 2_000);
+    let height = simulator.committed_height();
+    simulator.run_until(|_| false, 2_000);
+    assert_eq!(simulator.committed_height(), height);
+
+    simulator.reconnect(authors[0]);
+    simulator.reconnect(authors[1]);
+    assert!(simulator.run_until(|s| s.committed_height() >= height + 5, 50_000));
+}
+
+#[test]
+fn test_byzantine_proposer() {
+    let mut simulator = ConsensusSimulator::new(4, 13);
+    let authors = simulator.authors();
+    simulator.make_byzantine(authors[0]);
+    // Safety is checked at every step.
+    assert!(simulator.run_until(|s| s.committed_height() >= 20, 50_000));
+
+    let evidence: usize = authors[1..]
+        .iter()
+        .map(|author| {
+            simulator
+                .storage(*author)
+                .shared_storage
+                .evidence
+                .lock()
+                .unwrap()
+                .len()
+        })
+        .sum();
+    assert!(evidence > 0);
+}
@@ -10,7 +10,7 @@ use crate::{
         persistent_storage::PersistentStorage,
     },
     counters,
-    state_replication::StateComputer,
+    state_replication::{CommitObserver, StateComputer},
 };
 use failure::{self, Fail};
 use logger::prelude::*;
@@ -31,6 +31,8 @@ pub struct SyncManager<T> {
     storage: Arc<dyn PersistentStorage<T>>,
     network: ConsensusNetworkImpl,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
+    // Notified about the blocks skipped by state synchronization.
+    commit_observer: Option<Arc<dyn CommitObserver<Payload = T>>>,
 }
 
 /// Keeps the necessary context for `SyncMgr` to bring the missing information.
@@ -73,9 +75,15 @@ where
             storage,
             network,
             state_computer,
+            commit_observer: None,
         }
     }
 
+    /// Notifies the given observer about the blocks committed by state synchronization.
+    pub fn set_commit_observer(&mut self, commit_observer: Arc<dyn CommitObserver<Payload = T>>) {
+        self.commit_observer = Some(commit_observer);
+    }
+
     /// Fetches dependencies for given sync_info.quorum_cert
     /// If gap is large, performs state sync using process_highest_ledger_info
     /// Inserts sync_info.quorum_cert into block store as the last step
@@ -230,6 +238,9 @@ where
             ),
         };
         counters::STATE_SYNC_DURATION_S.observe_duration(pre_sync_instance.elapsed());
+        if let Some(commit_observer) = self.commit_observer.as_ref() {
+            commit_observer.on_gap(highest_ledger_info.ledger_info());
+        }
         let root = (
             blocks.pop().expect("should have 3-chain"),
             quorum_certs.last().expect("should have 3-chain").clone(),
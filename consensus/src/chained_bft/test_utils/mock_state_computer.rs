@@ -9,17 +9,27 @@ use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
 use failure::Result;
 use futures::{channel::mpsc, future, Future, FutureExt};
 use logger::prelude::*;
-use std::pin::Pin;
+use std::{pin::Pin, sync::Mutex};
 use termion::color::*;
-use types::crypto_proxies::LedgerInfoWithSignatures;
+use types::{crypto_proxies::LedgerInfoWithSignatures, validator_set::ValidatorSet};
 
 pub struct MockStateComputer {
     commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>,
+    // Validator set carried by the next computed block, if any.
+    reconfiguration: Mutex<Option<ValidatorSet>>,
 }
 
 impl MockStateComputer {
     pub fn new(commit_callback: mpsc::UnboundedSender<LedgerInfoWithSignatures>) -> Self {
-        MockStateComputer { commit_callback }
+        MockStateComputer {
+            commit_callback,
+            reconfiguration: Mutex::new(None),
+        }
+    }
+
+    /// The next computed block carries the given validator set: committing it ends the epoch.
+    pub fn reconfigure_next_block(&self, validators: ValidatorSet) {
+        self.reconfiguration.lock().unwrap().replace(validators);
     }
 }
 
@@ -35,7 +45,7 @@ impl StateComputer for MockStateComputer {
             new_state_id: *ACCUMULATOR_PLACEHOLDER_HASH,
             compute_status: vec![],
             num_successful_txns: 0,
-            validators: None,
+            validators: self.reconfiguration.lock().unwrap().take(),
         })
         .boxed()
     }
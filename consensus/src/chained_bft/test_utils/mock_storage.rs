@@ -2,9 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::chained_bft::{
-    common::Payload,
-    consensus_types::{block::Block, quorum_cert::QuorumCert},
-    liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+    common::{Payload, Round},
+    consensus_types::{block::Block, evidence::Evidence, quorum_cert::QuorumCert},
+    liveness::{
+        committed_history::CommittedBlockInfo,
+        pacemaker_timeout_manager::HighestTimeoutCertificates,
+    },
     persistent_storage::{PersistentLivenessStorage, PersistentStorage, RecoveryData},
     safety::safety_rules::ConsensusState,
 };
@@ -12,15 +15,19 @@ use config::config::{NodeConfig, NodeConfigHelpers};
 use crypto::HashValue;
 use failure::Result;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{Arc, Mutex},
 };
+use types::validator_set::ValidatorSet;
 
 pub struct MockSharedStorage<T> {
     // Safety state
     pub block: Mutex<HashMap<HashValue, Block<T>>>,
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub state: Mutex<ConsensusState>,
+    pub evidence: Mutex<Vec<Evidence<T>>>,
+    pub epoch_info: Mutex<Option<(usize, ValidatorSet)>>,
+    pub committed_blocks: Mutex<BTreeMap<Round, CommittedBlockInfo>>,
 
     // Liveness state
     pub highest_timeout_certificates: Mutex<HighestTimeoutCertificates>,
@@ -71,6 +78,18 @@ impl<T: Payload> MockStorage<T> {
                 .unwrap()
                 .clone(),
         )
+        .map(|data| {
+            data.with_epoch_info(self.shared_storage.epoch_info.lock().unwrap().clone())
+                .with_committed_history(
+                    self.shared_storage
+                        .committed_blocks
+                        .lock()
+                        .unwrap()
+                        .values()
+                        .cloned()
+                        .collect(),
+                )
+        })
     }
 
     pub fn verify_consistency(&self) -> Result<()> {
@@ -141,11 +160,47 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
         Ok(())
     }
 
+    fn save_evidence(&self, evidence: Evidence<T>) -> Result<bool> {
+        let mut saved = self.shared_storage.evidence.lock().unwrap();
+        if saved.iter().any(|e| {
+            e.round() == evidence.round()
+                && e.evidence_kind() == evidence.evidence_kind()
+                && e.author() == evidence.author()
+        }) {
+            return Ok(false);
+        }
+        saved.push(evidence);
+        Ok(true)
+    }
+
+    fn save_epoch_info(&self, epoch: usize, validators: &ValidatorSet) -> Result<()> {
+        *self.shared_storage.epoch_info.lock().unwrap() = Some((epoch, validators.clone()));
+        Ok(())
+    }
+
+    fn save_committed_blocks(
+        &self,
+        committed_blocks: Vec<CommittedBlockInfo>,
+        pruned_rounds: Vec<Round>,
+    ) -> Result<()> {
+        let mut saved = self.shared_storage.committed_blocks.lock().unwrap();
+        for round in pruned_rounds {
+            saved.remove(&round);
+        }
+        for block in committed_blocks {
+            saved.insert(block.round(), block);
+        }
+        Ok(())
+    }
+
     fn start(_config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         let shared_storage = Arc::new(MockSharedStorage {
             block: Mutex::new(HashMap::new()),
             qc: Mutex::new(HashMap::new()),
             state: Mutex::new(ConsensusState::default()),
+            evidence: Mutex::new(vec![]),
+            epoch_info: Mutex::new(None),
+            committed_blocks: Mutex::new(BTreeMap::new()),
             highest_timeout_certificates: Mutex::new(HighestTimeoutCertificates::new(None, None)),
         });
         let storage = MockStorage {
@@ -198,6 +253,18 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         Ok(())
     }
 
+    fn save_evidence(&self, _: Evidence<T>) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn save_epoch_info(&self, _: usize, _: &ValidatorSet) -> Result<()> {
+        Ok(())
+    }
+
+    fn save_committed_blocks(&self, _: Vec<CommittedBlockInfo>, _: Vec<Round>) -> Result<()> {
+        Ok(())
+    }
+
     fn start(_: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         let genesis = Block::make_genesis_block();
         let genesis_qc = QuorumCert::certificate_for_genesis();
@@ -62,6 +62,16 @@ impl TxnManager for MockTransactionManager {
         future::ok(res).boxed()
     }
 
+    /// MockTransactions have no sender, hence no privileged ones.
+    fn pull_txns_with_privileged_lane(
+        &self,
+        max_size: u64,
+        _privileged_lane_size: u64,
+        exclude_txns: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
+        self.pull_txns(max_size, exclude_txns)
+    }
+
     fn commit_txns<'a>(
         &'a self,
         txns: &Self::Payload,
@@ -1,21 +1,21 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{
-    chained_bft::{
-        block_storage::BlockStore,
-        common::Round,
-        consensus_types::{block::Block, quorum_cert::QuorumCert},
-        safety::vote_msg::VoteMsg,
-    },
-    state_replication::ExecutedState,
+use crate::chained_bft::{
+    block_storage::BlockStore,
+    common::{Author, Round},
+    consensus_types::{block::Block, quorum_cert::QuorumCert},
+    safety::vote_msg::VoteMsg,
 };
 use crypto::{hash::CryptoHash, HashValue};
 use futures::{channel::mpsc, executor::block_on};
 use logger::{set_simple_logger, set_simple_logger_prefix};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 use termion::color::*;
-use tokio::runtime;
+use tokio::runtime::{self, TaskExecutor};
 use tools::output_capture::OutputCapture;
 use types::{
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorSigner},
@@ -32,41 +32,55 @@ pub use mock_txn_manager::MockTransactionManager;
 
 pub type TestPayload = Vec<usize>;
 
+/// Builds an empty tree proposing as the author of `ValidatorSigner::random(None)`.
 pub fn build_empty_tree() -> Arc<BlockStore<Vec<usize>>> {
-    let signer = ValidatorSigner::random(None);
-    build_empty_tree_with_custom_signing(signer)
+    build_empty_tree_with_custom_author(ValidatorSigner::random(None).author())
 }
 
-pub fn build_empty_tree_with_custom_signing(
-    my_signer: ValidatorSigner,
-) -> Arc<BlockStore<Vec<usize>>> {
+pub fn build_empty_tree_with_custom_author(author: Author) -> Arc<BlockStore<Vec<usize>>> {
     let (commit_cb_sender, _commit_cb_receiver) = mpsc::unbounded::<LedgerInfoWithSignatures>();
     let (storage, initial_data) = EmptyStorage::start_for_testing();
     Arc::new(block_on(BlockStore::new(
         storage,
         initial_data,
-        my_signer,
+        author,
         Arc::new(MockStateComputer::new(commit_cb_sender)),
         true,
         10, // max pruned blocks in mem
+        4,  // max pending executions
+        test_executor(),
     )))
 }
 
 pub struct TreeInserter {
     payload_val: usize,
     block_store: Arc<BlockStore<Vec<usize>>>,
+    signer: ValidatorSigner,
 }
 
 impl TreeInserter {
+    /// The blocks are signed by `ValidatorSigner::random(None)`.
     pub fn new(block_store: Arc<BlockStore<Vec<usize>>>) -> Self {
+        Self::new_with_signer(block_store, ValidatorSigner::random(None))
+    }
+
+    pub fn new_with_signer(
+        block_store: Arc<BlockStore<Vec<usize>>>,
+        signer: ValidatorSigner,
+    ) -> Self {
         Self {
             payload_val: 0,
             block_store,
+            signer,
         }
     }
 
+    pub fn signer(&self) -> &ValidatorSigner {
+        &self.signer
+    }
+
     /// This function is generating a placeholder QC for a block's parent that is signed by a single
-    /// signer kept by the block store. If more sophisticated QC required, please use
+    /// signer kept by the inserter. If more sophisticated QC required, please use
     /// `insert_block_with_qc`.
     pub fn insert_block(
         &mut self,
@@ -75,7 +89,7 @@ impl TreeInserter {
     ) -> Arc<Block<Vec<usize>>> {
         // Node must carry a QC to its parent
         let parent_qc = placeholder_certificate_for_block(
-            vec![self.block_store.signer()],
+            vec![&self.signer],
             parent.id(),
             parent.round(),
             parent.quorum_cert().certified_block_id(),
@@ -100,7 +114,7 @@ impl TreeInserter {
             round,
             parent.timestamp_usecs() + 1,
             parent_qc,
-            self.block_store.signer(),
+            &self.signer,
         )))
         .unwrap()
     }
@@ -161,11 +175,8 @@ pub fn placeholder_certificate_for_block(
     certified_grandparent_block_id: HashValue,
     certified_grandparent_block_round: u64,
 ) -> QuorumCert {
-    // Assuming executed state to be Genesis state.
-    let certified_block_state = ExecutedState::state_for_genesis();
     let consensus_data_hash = VoteMsg::vote_digest(
         certified_block_id,
-        certified_block_state,
         certified_block_round,
         certified_parent_block_id,
         certified_parent_block_round,
@@ -188,7 +199,6 @@ pub fn placeholder_certificate_for_block(
 
     QuorumCert::new(
         certified_block_id,
-        certified_block_state,
         certified_block_round,
         LedgerInfoWithSignatures::new(ledger_info_placeholder, signatures),
         certified_parent_block_id,
@@ -198,6 +208,16 @@ pub fn placeholder_certificate_for_block(
     )
 }
 
+lazy_static::lazy_static! {
+    // Runs the tasks spawned by the block stores of the tests, lives as long as the test binary.
+    static ref TEST_RUNTIME: Mutex<runtime::Runtime> = Mutex::new(consensus_runtime());
+}
+
+/// The executor of the tasks of the components that are not bound to a test's own runtime.
+pub fn test_executor() -> TaskExecutor {
+    TEST_RUNTIME.lock().unwrap().executor()
+}
+
 pub fn consensus_runtime() -> runtime::Runtime {
     set_simple_logger("consensus");
     let capture = OutputCapture::grab();
@@ -0,0 +1,77 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{counters, state_replication::CommitObserver};
+use channel::publisher::Publisher;
+use crypto::HashValue;
+use futures::{Stream, StreamExt};
+use std::{pin::Pin, sync::Mutex};
+use types::{
+    account_address::AccountAddress,
+    committed_block::{CommitNotification, CommittedBlock, CommittedBlockSource, SubscriberLagged},
+    crypto_proxies::LedgerInfoWithSignatures,
+    transaction::SignedTransaction,
+};
+
+#[cfg(test)]
+#[path = "committed_block_publisher_test.rs"]
+mod committed_block_publisher_test;
+
+/// Publishes the commit notifications to the subscribers, e.g. the clients of admission control
+/// following the committed blocks.
+#[derive(Default)]
+pub struct CommittedBlockPublisher {
+    subscribers: Mutex<Publisher<CommitNotification>>,
+}
+
+impl CommittedBlockPublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn publish(&self, notification: CommitNotification) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let num_lagged = subscribers.publish(&notification);
+        counters::COMMITTED_BLOCK_SUBSCRIBERS_DISCONNECTED_COUNT.inc_by(num_lagged as i64);
+        counters::NUM_COMMITTED_BLOCK_SUBSCRIBERS.set(subscribers.len() as i64);
+    }
+}
+
+impl CommitObserver for CommittedBlockPublisher {
+    type Payload = Vec<SignedTransaction>;
+
+    fn on_commit(
+        &self,
+        block_id: HashValue,
+        round: u64,
+        proposer: Option<AccountAddress>,
+        payload: &Self::Payload,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    ) {
+        if self.subscribers.lock().unwrap().is_empty() {
+            return;
+        }
+        self.publish(CommitNotification::Block(CommittedBlock {
+            block_id,
+            round,
+            proposer,
+            transactions: payload.clone(),
+            ledger_info_with_sigs: ledger_info_with_sigs.clone(),
+        }));
+    }
+
+    fn on_gap(&self, ledger_info_with_sigs: &LedgerInfoWithSignatures) {
+        self.publish(CommitNotification::Gap(ledger_info_with_sigs.clone()));
+    }
+}
+
+impl CommittedBlockSource for CommittedBlockPublisher {
+    fn subscribe(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = Result<CommitNotification, SubscriberLagged>> + Send>> {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let receiver = subscribers.subscribe();
+        counters::NUM_COMMITTED_BLOCK_SUBSCRIBERS.set(subscribers.len() as i64);
+        receiver.boxed()
+    }
+}
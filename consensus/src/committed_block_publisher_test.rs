@@ -0,0 +1,99 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::test_utils::placeholder_ledger_info,
+    committed_block_publisher::CommittedBlockPublisher, state_replication::CommitObserver,
+};
+use channel::publisher::SUBSCRIBER_CHANNEL_SIZE;
+use crypto::HashValue;
+use futures::{executor::block_on, StreamExt};
+use std::collections::HashMap;
+use types::{
+    account_address::AccountAddress,
+    committed_block::{CommitNotification, CommittedBlockSource, SubscriberLagged},
+    crypto_proxies::LedgerInfoWithSignatures,
+};
+
+fn commit(publisher: &CommittedBlockPublisher, round: u64) {
+    let ledger_info_with_sigs =
+        LedgerInfoWithSignatures::new(placeholder_ledger_info(), HashMap::new());
+    publisher.on_commit(
+        HashValue::random(),
+        round,
+        Some(AccountAddress::random()),
+        &vec![],
+        &ledger_info_with_sigs,
+    );
+}
+
+#[test]
+fn test_subscribers_receive_committed_blocks() {
+    let publisher = CommittedBlockPublisher::new();
+    // Nobody is notified about the blocks committed before subscribing.
+    commit(&publisher, 1);
+    let first = publisher.subscribe();
+    let second = publisher.subscribe();
+    commit(&publisher, 2);
+    commit(&publisher, 3);
+    for subscriber in vec![first, second] {
+        let rounds = block_on(subscriber.take(2).collect::<Vec<_>>())
+            .into_iter()
+            .map(|notification| match notification {
+                Ok(CommitNotification::Block(block)) => block.round,
+                _ => panic!("Unexpected notification: {:?}", notification),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rounds, vec![2, 3]);
+    }
+}
+
+#[test]
+fn test_dropped_subscriber_is_removed() {
+    let publisher = CommittedBlockPublisher::new();
+    let subscriber = publisher.subscribe();
+    drop(subscriber);
+    commit(&publisher, 1);
+    assert!(publisher.subscribers.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_lagging_subscriber_is_disconnected() {
+    let publisher = CommittedBlockPublisher::new();
+    let mut lagging = publisher.subscribe();
+    // The channel has one slot per sender on top of its buffer.
+    for round in 0..=SUBSCRIBER_CHANNEL_SIZE as u64 + 1 {
+        commit(&publisher, round);
+    }
+    assert!(publisher.subscribers.lock().unwrap().is_empty());
+    // The blocks sent before the disconnection are still delivered, then the stream ends with
+    // the error.
+    let notifications = block_on(lagging.by_ref().collect::<Vec<_>>());
+    assert_eq!(notifications.len(), SUBSCRIBER_CHANNEL_SIZE + 2);
+    assert!(notifications[..=SUBSCRIBER_CHANNEL_SIZE]
+        .iter()
+        .all(|notification| notification.is_ok()));
+    assert_eq!(
+        notifications.last(),
+        Some(&Err(SubscriberLagged(SUBSCRIBER_CHANNEL_SIZE)))
+    );
+    assert!(block_on(lagging.next()).is_none());
+}
+
+#[test]
+fn test_gap_is_published() {
+    let publisher = CommittedBlockPublisher::new();
+    let mut subscriber = publisher.subscribe();
+    let ledger_info_with_sigs =
+        LedgerInfoWithSignatures::new(placeholder_ledger_info(), HashMap::new());
+    publisher.on_gap(&ledger_info_with_sigs);
+    commit(&publisher, 1);
+    assert_eq!(
+        block_on(subscriber.next()),
+        Some(Ok(CommitNotification::Gap(ledger_info_with_sigs)))
+    );
+    match block_on(subscriber.next()) {
+        Some(Ok(CommitNotification::Block(block))) => assert_eq!(block.round, 1),
+        notification => panic!("Unexpected notification: {:?}", notification),
+    }
+}
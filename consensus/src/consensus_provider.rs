@@ -6,12 +6,14 @@ use failure::prelude::*;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 
 use crate::chained_bft::chained_bft_consensus_provider::ChainedBftProvider;
+use debug_interface::node_debug_service::ConsensusEvidenceProvider;
 use execution_proto::proto::execution_grpc::ExecutionClient;
 use grpcio::{ChannelBuilder, EnvBuilder};
 use mempool::proto::mempool_grpc::MempoolClient;
 use state_synchronizer::StateSyncClient;
 use std::sync::Arc;
 use storage_client::{StorageRead, StorageReadServiceClient};
+use types::committed_block::CommittedBlockSource;
 
 /// Public interface to a consensus protocol.
 pub trait ConsensusProvider {
@@ -24,6 +26,14 @@ pub trait ConsensusProvider {
 
     /// Stop the consensus operations. The function returns after graceful shutdown.
     fn stop(&mut self);
+
+    /// Returns the evidence of the validator misbehaviors detected by consensus, to be exposed
+    /// through the debug interface.
+    fn consensus_evidence(&self) -> Arc<dyn ConsensusEvidenceProvider>;
+
+    /// Returns the source of the committed blocks, to be streamed to the subscribers of admission
+    /// control.
+    fn committed_blocks(&self) -> Arc<dyn CommittedBlockSource>;
 }
 
 /// Helper function to create a ConsensusProvider based on configuration
@@ -0,0 +1,198 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline inspection of the data persisted in a ConsensusDB: the block tree with the quorum
+//! certificates, the safety state, the highest timeout certificates and the evidence of
+//! misbehaviors. The recovery that consensus runs at startup (see `RecoveryData`) is replayed on
+//! this data in order to show which root consensus would start from and which blocks it would
+//! prune.
+
+use crate::chained_bft::{
+    consensus_types::{block::Block, evidence::Evidence},
+    consensusdb::ConsensusDB,
+    liveness::pacemaker_timeout_manager::HighestTimeoutCertificates,
+    persistent_storage::RecoveryData,
+    safety::safety_rules::ConsensusState,
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use rmp_serde::from_slice;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+use types::transaction::SignedTransaction;
+
+#[cfg(test)]
+#[path = "consensusdb_inspector_test.rs"]
+mod consensusdb_inspector_test;
+
+type TxnPayload = Vec<SignedTransaction>;
+
+/// Reads a ConsensusDB without modifying it.
+pub struct ConsensusDBInspector {
+    db: ConsensusDB,
+}
+
+impl ConsensusDBInspector {
+    /// Opens the ConsensusDB found in `db_root_path` (i.e. the storage dir of the node config)
+    /// in read-only mode.
+    pub fn open<P: AsRef<Path>>(db_root_path: P) -> Result<Self> {
+        Ok(Self {
+            db: ConsensusDB::open_readonly(db_root_path)?,
+        })
+    }
+
+    /// Returns a human readable report of the persisted data and of the recovery decisions.
+    /// `root_from_storage` is the block id of the latest ledger info in storage (LI(S)); when it
+    /// is not given, storage is assumed to have committed the latest commit of consensus.
+    pub fn report(&self, root_from_storage: Option<HashValue>) -> Result<String> {
+        let (state, highest_timeout_certificates, blocks, quorum_certs) =
+            self.db.get_data::<TxnPayload>()?;
+        let consensus_state = match state {
+            Some(state) => from_slice(&state)?,
+            None => ConsensusState::default(),
+        };
+        let highest_timeout_certificates = match highest_timeout_certificates {
+            Some(certificates) => from_slice(&certificates)?,
+            None => HighestTimeoutCertificates::default(),
+        };
+
+        let mut out = String::new();
+        writeln!(out, "{}", consensus_state)?;
+        match highest_timeout_certificates.highest_timeout_certificate() {
+            Some(certificate) => writeln!(out, "Highest timeout certificate: {}", certificate)?,
+            None => writeln!(out, "Highest timeout certificate: none")?,
+        }
+
+        // Block tree, children sorted by round.
+        let certified: HashSet<_> = quorum_certs
+            .iter()
+            .map(|qc| qc.certified_block_id())
+            .collect();
+        let committed: HashSet<_> = quorum_certs
+            .iter()
+            .filter_map(|qc| qc.committed_block_id())
+            .collect();
+        let ids: HashSet<_> = blocks.iter().map(Block::id).collect();
+        let mut sorted_blocks: Vec<_> = blocks.iter().collect();
+        sorted_blocks.sort_by_key(|block| block.round());
+        let mut children: HashMap<HashValue, Vec<&Block<TxnPayload>>> = HashMap::new();
+        let mut tree_roots = vec![];
+        for block in sorted_blocks {
+            if ids.contains(&block.parent_id()) {
+                children.entry(block.parent_id()).or_default().push(block);
+            } else {
+                tree_roots.push(block);
+            }
+        }
+        writeln!(out, "Blocks ({}):", blocks.len())?;
+        let mut stack: Vec<_> = tree_roots.into_iter().rev().map(|b| (b, 0)).collect();
+        while let Some((block, depth)) = stack.pop() {
+            let mut status = vec![];
+            if certified.contains(&block.id()) {
+                status.push("certified");
+            }
+            if committed.contains(&block.id()) {
+                status.push("committed");
+            }
+            // The parents of the top level blocks are not in the DB.
+            let parent = if depth == 0 {
+                format!(", parent: {:x}", block.parent_id())
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                "{}{:x} round: {:02}{}, author: {}, txns: {} [{}]",
+                "  ".repeat(depth + 1),
+                block.id(),
+                block.round(),
+                parent,
+                block
+                    .author()
+                    .map_or_else(|| "none".to_string(), |author| author.short_str()),
+                block.get_payload().len(),
+                status.join(", ")
+            )?;
+            if let Some(block_children) = children.get(&block.id()) {
+                stack.extend(block_children.iter().rev().map(|b| (*b, depth + 1)));
+            }
+        }
+        let dangling_quorum_certs: Vec<_> = quorum_certs
+            .iter()
+            .filter(|qc| !ids.contains(&qc.certified_block_id()))
+            .collect();
+        writeln!(
+            out,
+            "Quorum certs ({}), for unknown blocks ({}):",
+            quorum_certs.len(),
+            dangling_quorum_certs.len()
+        )?;
+        for qc in dangling_quorum_certs {
+            writeln!(out, "  {}", qc)?;
+        }
+
+        let evidence = self.db.get_evidence()?;
+        writeln!(out, "Evidence ({}):", evidence.len())?;
+        for bytes in evidence {
+            let evidence: Evidence<TxnPayload> = from_slice(&bytes)?;
+            writeln!(out, "  {}", evidence)?;
+        }
+
+        if blocks.is_empty() && quorum_certs.is_empty() {
+            writeln!(out, "Recovery: empty ConsensusDB, starting from genesis")?;
+            return Ok(out);
+        }
+        let root_from_storage = match root_from_storage {
+            Some(id) => id,
+            // An unknown id makes the recovery pick the latest commit of consensus.
+            None => match RecoveryData::new(
+                consensus_state.clone(),
+                blocks.clone(),
+                quorum_certs.clone(),
+                HashValue::zero(),
+                highest_timeout_certificates.clone(),
+            ) {
+                Ok(recovery_data) => (recovery_data.take().0).0.id(),
+                Err(_) => HashValue::zero(),
+            },
+        };
+        writeln!(
+            out,
+            "Recovery with the storage root {:x}:",
+            root_from_storage
+        )?;
+        match RecoveryData::new(
+            consensus_state,
+            blocks,
+            quorum_certs,
+            root_from_storage,
+            highest_timeout_certificates,
+        ) {
+            Ok(mut recovery_data) => {
+                let blocks_to_prune = recovery_data.take_blocks_to_prune();
+                let need_sync = recovery_data.need_sync();
+                let ((root, root_quorum_cert, root_ledger_info), blocks, quorum_certs) =
+                    recovery_data.take();
+                writeln!(out, "  root: {}", root)?;
+                writeln!(out, "  root quorum cert: {}", root_quorum_cert)?;
+                writeln!(out, "  root ledger info: {}", root_ledger_info)?;
+                writeln!(out, "  need_sync: {}", need_sync)?;
+                writeln!(
+                    out,
+                    "  kept blocks: {}, kept quorum certs: {}",
+                    blocks.len(),
+                    quorum_certs.len()
+                )?;
+                writeln!(out, "  blocks to prune ({}):", blocks_to_prune.len())?;
+                for id in blocks_to_prune {
+                    writeln!(out, "    {:x}", id)?;
+                }
+            }
+            Err(e) => writeln!(out, "  recovery fails: {}", e)?,
+        }
+        Ok(out)
+    }
+}
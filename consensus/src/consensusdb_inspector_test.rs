@@ -0,0 +1,32 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::chained_bft::QuorumCert;
+use tempfile::tempdir;
+
+#[test]
+fn test_report_genesis() {
+    let tmp_dir = tempdir().unwrap();
+    assert!(ConsensusDBInspector::open(&tmp_dir).is_err());
+
+    let genesis = Block::<TxnPayload>::make_genesis_block();
+    {
+        let db = ConsensusDB::new(&tmp_dir);
+        db.save_blocks_and_quorum_certificates(
+            vec![genesis.clone()],
+            vec![QuorumCert::certificate_for_genesis()],
+        )
+        .unwrap();
+    }
+
+    let inspector = ConsensusDBInspector::open(&tmp_dir).unwrap();
+    let report = inspector.report(None).unwrap();
+    assert!(report.contains("Blocks (1):"));
+    assert!(report.contains(&format!("{:x} round: 00", genesis.id())));
+    assert!(report.contains("need_sync: false"));
+
+    // Storage not knowing about the consensus root requires a state synchronization.
+    let report = inspector.report(Some(HashValue::random())).unwrap();
+    assert!(report.contains("need_sync: true"));
+}
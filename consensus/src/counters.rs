@@ -41,6 +41,13 @@ pub static ref SUCCESS_TXNS_COUNT: IntCounter = OP_COUNTERS.counter("success_txn
 /// FAILED_TXNS_COUNT + SUCCESS_TXN_COUNT == COMMITTED_TXNS_COUNT
 pub static ref FAILED_TXNS_COUNT: IntCounter = OP_COUNTERS.counter("failed_txns_count");
 
+/// Number of the subscribers following the committed blocks.
+pub static ref NUM_COMMITTED_BLOCK_SUBSCRIBERS: IntGauge = OP_COUNTERS.gauge("num_committed_block_subscribers");
+
+/// Count of the subscribers disconnected because they lagged behind the committed blocks since
+/// last restart.
+pub static ref COMMITTED_BLOCK_SUBSCRIBERS_DISCONNECTED_COUNT: IntCounter = OP_COUNTERS.counter("committed_block_subscribers_disconnected_count");
+
 //////////////////////
 // PROPOSAL ELECTION
 //////////////////////
@@ -56,6 +63,17 @@ pub static ref VOTE_SECONDARY_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("
 /// Count the number of times a validator voted for a nil block since last restart.
 pub static ref VOTE_NIL_COUNT: IntCounter = OP_COUNTERS.counter("vote_nil_count");
 
+//////////////////////
+// MISBEHAVIOR COUNTERS
+//////////////////////
+/// Count of the double proposals (two blocks by the same author in the same round) detected
+/// since last restart.
+pub static ref DOUBLE_PROPOSAL_COUNT: IntCounter = OP_COUNTERS.counter("double_proposal_count");
+
+/// Count of the double votes (two votes by the same author for different blocks in the same
+/// round) detected since last restart.
+pub static ref DOUBLE_VOTE_COUNT: IntCounter = OP_COUNTERS.counter("double_vote_count");
+
 //////////////////////
 // PACEMAKER COUNTERS
 //////////////////////
@@ -74,6 +92,13 @@ pub static ref TIMEOUT_COUNT: IntCounter = OP_COUNTERS.counter("timeout_count");
 /// The timeout of the current round.
 pub static ref ROUND_TIMEOUT_MS: IntGauge = OP_COUNTERS.gauge("round_timeout_ms");
 
+/// Histogram of the time it took to gather the QC since the start of the round.
+pub static ref QC_LATENCY_S: DurationHistogram = OP_COUNTERS.duration_histogram("qc_latency_s");
+
+/// The round timeout derived from the recent QC latencies by the adaptive pacemaker, before the
+/// backoff of the rounds without commit.
+pub static ref ADAPTIVE_BASE_TIMEOUT_MS: IntGauge = OP_COUNTERS.gauge("adaptive_base_timeout_ms");
+
 ////////////////////////
 // SYNCMANAGER COUNTERS
 ////////////////////////
@@ -95,6 +120,21 @@ pub static ref SYNC_INFO_MSGS_SENT_COUNT: IntCounter = OP_COUNTERS.counter("sync
 /// Counts the number of times the sync info message has been received since last restart.
 pub static ref SYNC_INFO_MSGS_RECEIVED_COUNT: IntCounter = OP_COUNTERS.counter("sync_info_msg_received_count");
 
+//////////////////////
+// BATCHING COUNTERS
+//////////////////////
+/// Count of the transaction batches created and disseminated by this validator since last restart.
+pub static ref BATCHES_CREATED_COUNT: IntCounter = OP_COUNTERS.counter("batches_created_count");
+
+/// Count of the batches that had to be fetched from a peer since last restart.
+pub static ref BATCH_RETRIEVAL_COUNT: IntCounter = OP_COUNTERS.counter("batch_retrieval_count");
+
+/// Histogram of batch retrieval duration.
+pub static ref BATCH_RETRIEVAL_DURATION_S: DurationHistogram = OP_COUNTERS.duration_histogram("batch_retrieval_duration_s");
+
+/// Number of the batches kept in memory, waiting to be committed.
+pub static ref NUM_BATCHES_IN_STORE: IntGauge = OP_COUNTERS.gauge("num_batches_in_store");
+
 //////////////////////
 // RECONFIGURATION COUNTERS
 //////////////////////
@@ -113,6 +153,16 @@ pub static ref CURRENT_EPOCH_QUORUM_SIZE: IntGauge = OP_COUNTERS.gauge("current_
 /// In a "happy path" with no collisions and timeouts, should be equal to 3 or 4.
 pub static ref NUM_BLOCKS_IN_TREE: IntGauge = OP_COUNTERS.gauge("num_blocks_in_tree");
 
+/// Number of the proposed blocks executed speculatively, waiting to be inserted.
+pub static ref NUM_PENDING_EXECUTIONS: IntGauge = OP_COUNTERS.gauge("num_pending_executions");
+
+/// Count of the blocks whose execution started before their insertion since last restart.
+pub static ref SPECULATIVE_EXECUTION_COUNT: IntCounter = OP_COUNTERS.counter("speculative_execution_count");
+
+/// Count of the speculative executions that had to wait for the older ones to complete since
+/// last restart (i.e., the execution falls behind the proposals).
+pub static ref EXECUTION_BACK_PRESSURE_COUNT: IntCounter = OP_COUNTERS.counter("execution_back_pressure_count");
+
 //////////////////////
 // PERFORMANCE COUNTERS
 //////////////////////
@@ -23,6 +23,13 @@ mod util;
 /// use in the Libra Core blockchain.
 pub mod consensus_provider;
 
+/// Runs SafetyRules as a standalone service holding the consensus signing key.
+pub mod safety_rules_node;
+
+/// Inspects the data persisted in a ConsensusDB offline.
+pub mod consensusdb_inspector;
+
+mod committed_block_publisher;
 mod counters;
 
 mod state_computer;
@@ -0,0 +1,118 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A safety rules node runs SafetyRules in its own process, so that a compromised consensus
+//! process cannot make the validator equivocate: the node holds the consensus signing key along
+//! with the last vote round, the preferred block round and the last proposal, persisted in its
+//! own ConsensusDB. The persisted state is signed with the consensus key, and the node refuses to
+//! start if the state it recovers was not written by itself.
+//! It accepts connections on node_config.consensus.safety_rules().safety_rules_port; consensus
+//! talks to it when the safety rules backend is configured as "remote".
+//! The chained_bft/safety/proto/safety_rules.proto file shows the requests that the service
+//! accepts and the responses that it gives back.
+
+use crate::chained_bft::{
+    consensusdb::ConsensusDB,
+    persistent_storage::StorageWriteProxy,
+    safety::{
+        local_safety_rules::LocalSafetyRules,
+        proto::safety_rules_grpc,
+        safety_rules::{ConsensusState, SignedConsensusState},
+        safety_rules_server::SafetyRulesServer,
+    },
+};
+use config::config::NodeConfig;
+use failure::prelude::*;
+use grpc_helpers::spawn_service_thread;
+use logger::prelude::*;
+use rmp_serde::from_slice;
+use std::{convert::TryFrom, sync::Arc, thread};
+use types::{
+    account_address::AccountAddress,
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
+    transaction::SignedTransaction,
+    validator_set::ValidatorSet,
+};
+
+/// Safety rules node is run in a separate process and handles the consensus signing key.
+pub struct SafetyRulesNode {
+    node_config: NodeConfig,
+}
+
+impl SafetyRulesNode {
+    /// Instantiates the service with a config file.
+    pub fn new(node_config: NodeConfig) -> Self {
+        SafetyRulesNode { node_config }
+    }
+
+    /// Recovers the consensus state and starts the safety rules service.
+    pub fn run(mut self) -> Result<()> {
+        info!("Starting safety rules node");
+
+        let author = AccountAddress::try_from(self.node_config.network.peer_id.clone())?;
+        let private_key = self
+            .node_config
+            .network
+            .peer_keypairs
+            .take_consensus_private()
+            .ok_or_else(|| format_err!("Consensus private key absent or already read"))?;
+        let signer = ValidatorSigner::new(author, private_key);
+
+        let safety_rules_config = self.node_config.consensus.safety_rules().clone();
+        let db = Arc::new(ConsensusDB::new(safety_rules_config.dir.clone()));
+        let consensus_state = match db.get_state()? {
+            Some(state) => from_slice::<SignedConsensusState>(&state[..])?.verify(
+                signer.author(),
+                &ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+            )?,
+            None => ConsensusState::default(),
+        };
+        info!("Recovered consensus state: {}", consensus_state);
+        // The certificates are verified against the validators of the latest epoch the service
+        // knows about, the initial validators of the config otherwise.
+        let (epoch, validators) = match db.get_epoch_info()? {
+            Some(epoch_info) => {
+                let (epoch, validators): (usize, ValidatorSet) = from_slice(&epoch_info[..])?;
+                (epoch, ValidatorVerifier::from(&validators))
+            }
+            None => (
+                0,
+                ValidatorVerifier::new(
+                    self.node_config
+                        .network
+                        .trusted_peers
+                        .get_trusted_consensus_peers(),
+                ),
+            ),
+        };
+        info!(
+            "Verifying the certificates of epoch {} with {} validators",
+            epoch,
+            validators.len()
+        );
+        let storage = Arc::new(StorageWriteProxy::new(db).with_state_signer(signer.clone()));
+
+        let handle = SafetyRulesServer::<Vec<SignedTransaction>>::new(
+            LocalSafetyRules::new(consensus_state, signer, storage.clone()),
+            epoch,
+            validators,
+            storage,
+        );
+        let service = safety_rules_grpc::create_safety_rules(handle);
+        let _service_handle = spawn_service_thread(
+            service,
+            safety_rules_config.address.clone(),
+            safety_rules_config.safety_rules_port,
+            "safety_rules",
+        );
+
+        info!(
+            "Started safety rules node on port {}",
+            safety_rules_config.safety_rules_port
+        );
+
+        loop {
+            thread::park();
+        }
+    }
+}
@@ -5,11 +5,12 @@ use crate::chained_bft::QuorumCert;
 use canonical_serialization::{CanonicalSerialize, CanonicalSerializer};
 use crypto::{hash::ACCUMULATOR_PLACEHOLDER_HASH, HashValue};
 use failure::Result;
-use futures::Future;
+use futures::{future, Future, FutureExt};
 use serde::{Deserialize, Serialize};
-use std::{pin::Pin, sync::Arc};
+use std::{pin::Pin, sync::Arc, time::Instant};
 use types::{
-    crypto_proxies::LedgerInfoWithSignatures, transaction::Version, validator_set::ValidatorSet,
+    account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures,
+    transaction::Version, validator_set::ValidatorSet,
 };
 
 /// A structure that specifies the result of the execution.
@@ -31,7 +32,8 @@ pub struct StateComputeResult {
     pub num_successful_txns: u64,
     /// If set, these are the validator public keys that will be used to start the next epoch
     /// immediately after this state is committed
-    /// TODO [Reconfiguration] the validators are currently ignored, no reconfiguration yet.
+    /// Upon commit the network is notified about the new set of eligible peers and consensus
+    /// switches to the new epoch.
     pub validators: Option<ValidatorSet>,
 }
 
@@ -48,6 +50,16 @@ pub trait TxnManager: Send + Sync {
         exclude_txns: Vec<&Self::Payload>,
     ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>>;
 
+    /// Same as `pull_txns`, but up to `privileged_lane_size` of the transactions are reserved for
+    /// the privileged senders (e.g. the validator set updates), whatever the other transactions
+    /// pay.
+    fn pull_txns_with_privileged_lane(
+        &self,
+        max_size: u64,
+        privileged_lane_size: u64,
+        exclude_txns: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>>;
+
     /// Notifies TxnManager about the payload of the committed block including the state compute
     /// result, which includes the specifics of what transactions succeeded and failed.
     fn commit_txns<'a>(
@@ -57,6 +69,42 @@ pub trait TxnManager: Send + Sync {
         // Monotonic timestamp_usecs of committed blocks is used to GC expired transactions.
         timestamp_usecs: u64,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+    /// Makes sure the transactions a proposed payload refers to are available locally, fetching
+    /// them from the given peer (typically the proposer) before the deadline if needed. The future
+    /// is fulfilled with an error in case the transactions could not be retrieved or the payload
+    /// is not acceptable for a proposal.
+    /// Payloads carrying the transactions themselves are always available.
+    fn fetch_payload<'a>(
+        &'a self,
+        _payload: &Self::Payload,
+        _peer: AccountAddress,
+        _deadline: Instant,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        future::ok(()).boxed()
+    }
+}
+
+/// Observes the blocks committed by consensus (e.g., to stream them to external subscribers).
+pub trait CommitObserver: Send + Sync {
+    type Payload;
+
+    /// Notifies about a committed block once its state is persisted, for all the committed blocks
+    /// in the order of their commits. The LedgerInfo is the proof of the commit: it certifies
+    /// either the block or a descendant committed at the same time.
+    fn on_commit(
+        &self,
+        block_id: HashValue,
+        round: u64,
+        proposer: Option<AccountAddress>,
+        payload: &Self::Payload,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+    );
+
+    /// Notifies that the blocks committed up to the given LedgerInfo are not reported by
+    /// `on_commit`, e.g. because the state was synchronized from the peers instead of executing
+    /// them.
+    fn on_gap(&self, ledger_info_with_sigs: &LedgerInfoWithSignatures);
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -83,6 +83,15 @@ impl TxnManager for MempoolProxy {
         &self,
         max_size: u64,
         exclude_payloads: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
+        self.pull_txns_with_privileged_lane(max_size, 0, exclude_payloads)
+    }
+
+    fn pull_txns_with_privileged_lane(
+        &self,
+        max_size: u64,
+        privileged_lane_size: u64,
+        exclude_payloads: Vec<&Self::Payload>,
     ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
         let mut exclude_txns = vec![];
         for payload in exclude_payloads {
@@ -95,6 +104,7 @@ impl TxnManager for MempoolProxy {
         }
         let mut get_block_request = GetBlockRequest::new();
         get_block_request.set_max_block_size(max_size);
+        get_block_request.set_privileged_lane_size(privileged_lane_size);
         get_block_request.set_transactions(::protobuf::RepeatedField::from_vec(exclude_txns));
         match self.mempool.get_block_async(&get_block_request) {
             Ok(receiver) => async move {
@@ -134,6 +134,39 @@ impl SimulatedTimeService {
             futures::executor::block_on(t.run());
         }
     }
+
+    /// Returns the earliest deadline of the pending tasks, if any.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        let inner = self.inner.lock().unwrap();
+        inner.pending.iter().map(|(deadline, _)| *deadline).min()
+    }
+
+    /// Moves the current time forward to the given time (if it is later) and runs the pending
+    /// tasks that are due by then, in the order of their deadlines. The time limit is left
+    /// unchanged: the tasks scheduled afterwards wait for the next call.
+    pub fn advance_to(&self, time: Duration) {
+        let mut drain = vec![];
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if time > inner.now {
+                inner.now = std::cmp::min(time, inner.max);
+            }
+            let now = inner.now;
+            let mut i = 0;
+            while i != inner.pending.len() {
+                if inner.pending[i].0 <= now {
+                    drain.push(inner.pending.remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        // The sort is stable: the tasks with the same deadline run in the scheduling order.
+        drain.sort_by_key(|(deadline, _)| *deadline);
+        for (_, mut t) in drain {
+            futures::executor::block_on(t.run());
+        }
+    }
 }
 
 impl Clone for SimulatedTimeService {
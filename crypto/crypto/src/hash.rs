@@ -543,6 +543,20 @@ define_hasher! {
     (VoteMsgHasher, VOTE_MSG_HASHER, b"VoteMsg")
 }
 
+define_hasher! {
+    /// The hasher used to compute the digest of a consensus transaction Batch.
+    (BatchHasher, BATCH_HASHER, b"Batch")
+}
+
+define_hasher! {
+    /// The hasher used to compute the hash of a ConsensusState object, signed by SafetyRules.
+    (
+        ConsensusStateHasher,
+        CONSENSUS_STATE_HASHER,
+        b"ConsensusState"
+    )
+}
+
 define_hasher! {
     /// The hasher used to compute the hash of a ContractEvent object.
     (ContractEventHasher, CONTRACT_EVENT_HASHER, b"ContractEvent")
@@ -1,14 +1,20 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::network_state::NetworkStateReporter;
 use admission_control_proto::proto::admission_control_grpc::{
     create_admission_control, AdmissionControlClient,
 };
-use admission_control_service::admission_control_service::AdmissionControlService;
-use config::config::{NetworkConfig, NodeConfig, RoleType};
+use admission_control_service::{
+    admission_control_service::AdmissionControlService, submission_quota::SubmissionQuotas,
+};
+use config::config::{NetworkConfig, NetworkTransport, NodeConfig, RoleType};
 use consensus::consensus_provider::{make_consensus_provider, ConsensusProvider};
 use crypto::ed25519::*;
-use debug_interface::{node_debug_service::NodeDebugService, proto::node_debug_interface_grpc};
+use debug_interface::{
+    node_debug_service::{ConsensusEvidenceProvider, NodeDebugService},
+    proto::node_debug_interface_grpc,
+};
 use execution_proto::proto::execution_grpc;
 use execution_service::ExecutionService;
 use futures::future::{FutureExt, TryFutureExt};
@@ -19,6 +25,7 @@ use logger::prelude::*;
 use mempool::{proto::mempool_grpc::MempoolClient, MempoolRuntime};
 use metrics::metric_server;
 use network::{
+    network_state::NetworkState,
     validator_network::{
         network_builder::{NetworkBuilder, TransportType},
         LibraNetworkProvider, CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_RPC_PROTOCOL,
@@ -30,14 +37,14 @@ use state_synchronizer::StateSynchronizer;
 use std::{
     cmp::min,
     convert::{TryFrom, TryInto},
-    sync::Arc,
+    sync::{Arc, RwLock},
     thread,
     time::Instant,
 };
 use storage_client::{StorageRead, StorageReadServiceClient, StorageWriteServiceClient};
 use storage_service::start_storage_service;
 use tokio::runtime::{Builder, Runtime};
-use types::account_address::AccountAddress as PeerId;
+use types::{account_address::AccountAddress as PeerId, committed_block::CommittedBlockSource};
 use vm_validator::vm_validator::VMValidator;
 
 pub struct LibraHandle {
@@ -59,7 +66,19 @@ impl Drop for LibraHandle {
     }
 }
 
-fn setup_ac(config: &NodeConfig) -> (::grpcio::Server, AdmissionControlClient) {
+/// Full nodes only run mempool to forward the transactions submitted to them to their upstream
+/// peers.
+fn runs_mempool(config: &NodeConfig) -> bool {
+    match (&config.network.role).into() {
+        RoleType::Validator => true,
+        RoleType::FullNode => !config.mempool.upstream_peers.is_empty(),
+    }
+}
+
+fn setup_ac(
+    config: &NodeConfig,
+    committed_blocks: Option<Arc<dyn CommittedBlockSource>>,
+) -> (::grpcio::Server, AdmissionControlClient) {
     let env = Arc::new(
         EnvBuilder::new()
             .name_prefix("grpc-ac-")
@@ -69,15 +88,14 @@ fn setup_ac(config: &NodeConfig) -> (::grpcio::Server, AdmissionControlClient) {
     let port = config.admission_control.admission_control_service_port;
 
     // Create mempool client
-    let mempool_client = match (&config.network.role).into() {
-        RoleType::FullNode => None,
-        RoleType::Validator => {
-            let connection_str = format!("localhost:{}", config.mempool.mempool_service_port);
-            let env2 = Arc::new(EnvBuilder::new().name_prefix("grpc-ac-mem-").build());
-            Some(Arc::new(MempoolClient::new(
-                ChannelBuilder::new(env2).connect(&connection_str),
-            )))
-        }
+    let mempool_client = if runs_mempool(config) {
+        let connection_str = format!("localhost:{}", config.mempool.mempool_service_port);
+        let env2 = Arc::new(EnvBuilder::new().name_prefix("grpc-ac-mem-").build());
+        Some(Arc::new(MempoolClient::new(
+            ChannelBuilder::new(env2).connect(&connection_str),
+        )))
+    } else {
+        None
     };
 
     // Create storage read client
@@ -89,14 +107,19 @@ fn setup_ac(config: &NodeConfig) -> (::grpcio::Server, AdmissionControlClient) {
 
     let vm_validator = Arc::new(VMValidator::new(&config, Arc::clone(&storage_client)));
 
-    let handle = AdmissionControlService::new(
+    let mut handle = AdmissionControlService::new(
         mempool_client,
         storage_client,
         vm_validator,
         config
             .admission_control
             .need_to_check_mempool_before_validation,
-    );
+    )
+    .with_submission_quotas(SubmissionQuotas::from_config(&config.admission_control))
+    .with_max_submit_batch_size(config.admission_control.max_submit_batch_size);
+    if let Some(committed_blocks) = committed_blocks {
+        handle = handle.with_committed_blocks(committed_blocks);
+    }
     let service = create_admission_control(handle);
     let server = ServerBuilder::new(Arc::clone(&env))
         .register_service(service)
@@ -132,11 +155,19 @@ fn setup_executor(config: &NodeConfig) -> ::grpcio::Server {
         .expect("Unable to create grpc server")
 }
 
-fn setup_debug_interface(config: &NodeConfig) -> ::grpcio::Server {
+fn setup_debug_interface(
+    config: &NodeConfig,
+    network_state: Arc<RwLock<NetworkState>>,
+    consensus_evidence: Option<Arc<dyn ConsensusEvidenceProvider>>,
+) -> ::grpcio::Server {
     let env = Arc::new(EnvBuilder::new().name_prefix("grpc-debug-").build());
     // Start Debug interface
-    let debug_service =
-        node_debug_interface_grpc::create_node_debug_interface(NodeDebugService::new());
+    let mut debug_service =
+        NodeDebugService::with_network_state(Arc::new(NetworkStateReporter::new(network_state)));
+    if let Some(consensus_evidence) = consensus_evidence {
+        debug_service = debug_service.with_consensus_evidence(consensus_evidence);
+    }
+    let debug_service = node_debug_interface_grpc::create_node_debug_interface(debug_service);
     ::grpcio::ServerBuilder::new(env)
         .register_service(debug_service)
         .bind(
@@ -150,7 +181,11 @@ fn setup_debug_interface(config: &NodeConfig) -> ::grpcio::Server {
 pub fn setup_network(
     peer_id: PeerId,
     config: &mut NetworkConfig,
-) -> (Runtime, Box<dyn LibraNetworkProvider>) {
+) -> (
+    Runtime,
+    Box<dyn LibraNetworkProvider>,
+    Arc<RwLock<NetworkState>>,
+) {
     let runtime = Builder::new()
         .name_prefix("network-")
         .build()
@@ -184,12 +219,20 @@ pub fn setup_network(
     let listen_addr = config.listen_address.clone();
     let advertised_addr = config.advertised_address.clone();
     let mut network_builder = NetworkBuilder::new(runtime.executor(), peer_id, listen_addr, role);
-    if config.enable_encryption_and_authentication {
-        network_builder
-            .transport(TransportType::TcpNoise)
-            .identity_keys(config.peer_keypairs.get_network_identity_keypair());
-    } else {
-        network_builder.transport(TransportType::Tcp);
+    match config.get_transport() {
+        NetworkTransport::Tcp if config.enable_encryption_and_authentication => {
+            network_builder
+                .transport(TransportType::TcpNoise)
+                .identity_keys(config.peer_keypairs.get_network_identity_keypair());
+        }
+        NetworkTransport::Tcp => {
+            network_builder.transport(TransportType::Tcp);
+        }
+        NetworkTransport::Quic => {
+            network_builder
+                .transport(TransportType::Quic)
+                .identity_keys(config.peer_keypairs.get_network_identity_keypair());
+        }
     };
     let (_listen_addr, network_provider) = network_builder
         .advertised_address(advertised_addr)
@@ -205,7 +248,7 @@ pub fn setup_network(
         ])
         .rpc_protocols(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)])
         .build();
-    (runtime, network_provider)
+    (runtime, network_provider, network_builder.network_state())
 }
 
 pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClient, LibraHandle) {
@@ -225,14 +268,10 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
         instant.elapsed().as_millis()
     );
 
-    instant = Instant::now();
-    let (ac_server, ac_client) = setup_ac(&node_config);
-    let ac = ServerHandle::setup(ac_server);
-    debug!("AC started in {} ms", instant.elapsed().as_millis());
-
     instant = Instant::now();
     let peer_id = PeerId::try_from(node_config.network.peer_id.clone()).expect("Invalid PeerId");
-    let (runtime, mut network_provider) = setup_network(peer_id, &mut node_config.network);
+    let (runtime, mut network_provider, network_state) =
+        setup_network(peer_id, &mut node_config.network);
     debug!("Network started in {} ms", instant.elapsed().as_millis());
 
     let (state_sync_network_sender, state_sync_network_events) = network_provider
@@ -248,7 +287,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
 
     let mut mempool = None;
     let mut consensus = None;
-    if let RoleType::Validator = (&node_config.network.role).into() {
+    if runs_mempool(&node_config) {
         instant = Instant::now();
         let (mempool_network_sender, mempool_network_events) = network_provider
             .add_mempool(vec![ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL)]);
@@ -258,7 +297,9 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
             mempool_network_events,
         ));
         debug!("Mempool started in {} ms", instant.elapsed().as_millis());
+    }
 
+    if let RoleType::Validator = (&node_config.network.role).into() {
         instant = Instant::now();
         let (consensus_network_sender, consensus_network_events) =
             network_provider.add_consensus(vec![
@@ -278,12 +319,28 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> (AdmissionControlClien
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
     }
 
+    // AC is started after consensus, which publishes the committed blocks streamed by AC.
+    instant = Instant::now();
+    let committed_blocks = consensus
+        .as_ref()
+        .map(|consensus_provider| consensus_provider.committed_blocks());
+    let (ac_server, ac_client) = setup_ac(&node_config, committed_blocks);
+    let ac = ServerHandle::setup(ac_server);
+    debug!("AC started in {} ms", instant.elapsed().as_millis());
+
     // Start the network providers.
     runtime
         .executor()
         .spawn(network_provider.start().unit_error().compat());
 
-    let debug_if = ServerHandle::setup(setup_debug_interface(&node_config));
+    let consensus_evidence = consensus
+        .as_ref()
+        .map(|consensus_provider| consensus_provider.consensus_evidence());
+    let debug_if = ServerHandle::setup(setup_debug_interface(
+        &node_config,
+        network_state,
+        consensus_evidence,
+    ));
 
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
@@ -0,0 +1,86 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reports the state of the network stack through the debug interface.
+
+use config::config::RoleType;
+use debug_interface::{
+    node_debug_service::NetworkStateProvider,
+    proto::node_debug_interface::{ConnectedPeer, GetNetworkStateResponse, PeerDialState},
+};
+use network::network_state::NetworkState;
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Converts the network state shared by the network actors into `GetNetworkStateResponse`s.
+pub struct NetworkStateReporter {
+    network_state: Arc<RwLock<NetworkState>>,
+}
+
+impl NetworkStateReporter {
+    pub fn new(network_state: Arc<RwLock<NetworkState>>) -> Self {
+        Self { network_state }
+    }
+}
+
+impl NetworkStateProvider for NetworkStateReporter {
+    fn network_state(&self) -> GetNetworkStateResponse {
+        let state = self.network_state.read().unwrap();
+        let mut response = GetNetworkStateResponse::new();
+
+        for (peer_id, peer_state) in state.connected_peers.iter() {
+            let mut peer = ConnectedPeer::new();
+            peer.set_peer_id(peer_id.to_string());
+            peer.set_address(peer_state.address.to_string());
+            peer.set_origin(format!("{:?}", peer_state.origin).to_lowercase());
+            peer.set_role(
+                match peer_state.role {
+                    RoleType::Validator => "validator",
+                    RoleType::FullNode => "full_node",
+                }
+                .to_string(),
+            );
+            for (protocol, count) in peer_state.substreams_opened.iter() {
+                peer.mut_substreams_opened()
+                    .insert(String::from_utf8_lossy(protocol).to_string(), *count);
+            }
+            if let Some(ping_state) = state.pings.get(peer_id) {
+                if let Some(rtt) = ping_state.last_rtt {
+                    peer.set_has_last_ping_rtt(true);
+                    peer.set_last_ping_rtt_us(rtt.as_micros() as u64);
+                }
+                peer.set_ping_failures(ping_state.failures);
+            }
+            response.mut_connected_peers().push(peer);
+        }
+
+        let now = Instant::now();
+        for (peer_id, dial_state) in state.dials.iter() {
+            let mut dial = PeerDialState::new();
+            dial.set_peer_id(peer_id.to_string());
+            dial.set_address(dial_state.address.to_string());
+            dial.set_attempts(dial_state.attempts);
+            dial.set_backoff_ms(dial_state.backoff.as_millis() as u64);
+            if let Some(next_dial) = dial_state.next_dial {
+                dial.set_dial_queued(true);
+                let next_dial_in = if next_dial > now {
+                    next_dial - now
+                } else {
+                    Duration::default()
+                };
+                dial.set_next_dial_in_ms(next_dial_in.as_millis() as u64);
+            }
+            response.mut_dials().push(dial);
+        }
+
+        for (peer_id, epoch) in state.discovery_note_epochs.iter() {
+            response
+                .mut_discovery_note_epochs()
+                .insert(peer_id.to_string(), *epoch);
+        }
+
+        response
+    }
+}
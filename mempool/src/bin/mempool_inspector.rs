@@ -0,0 +1,122 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use grpcio::{ChannelBuilder, EnvBuilder};
+use mempool::proto::{
+    mempool::{GetMempoolContentRequest, MempoolIndex},
+    mempool_grpc::MempoolClient,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "mempool-inspector",
+    author = "Libra",
+    about = "Lists the pending transactions in the mempool of a running node"
+)]
+struct Args {
+    /// Address of the mempool service
+    #[structopt(short = "a", long = "address", default_value = "localhost")]
+    pub address: String,
+    /// Port of the mempool service
+    #[structopt(short = "p", long = "port", default_value = "6182")]
+    pub port: u16,
+    /// Only the transactions of this sender (hex)
+    #[structopt(short = "s", long = "sender")]
+    pub sender: Option<String>,
+    /// Only the transactions with at least this gas price
+    #[structopt(long = "min-gas-price", default_value = "0")]
+    pub min_gas_price: u64,
+    /// Only the transactions with at most this gas price
+    #[structopt(long = "max-gas-price")]
+    pub max_gas_price: Option<u64>,
+    /// Only the transactions in the priority queue, i.e. that can be included in the next block
+    #[structopt(long = "ready", conflicts_with = "parked")]
+    pub ready: bool,
+    /// Only the transactions in the parking lot
+    #[structopt(long = "parked")]
+    pub parked: bool,
+    /// Max number of transactions and accounts listed
+    #[structopt(short = "l", long = "limit", default_value = "100")]
+    pub limit: u64,
+}
+
+fn main() {
+    let args = Args::from_args();
+    let mut req = GetMempoolContentRequest::new();
+    if let Some(sender) = args.sender {
+        req.set_sender(hex::decode(&sender).expect("Invalid hex sender"));
+    }
+    req.set_min_gas_price(args.min_gas_price);
+    if let Some(max_gas_price) = args.max_gas_price {
+        req.set_max_gas_price(max_gas_price);
+    }
+    req.set_index(if args.ready {
+        MempoolIndex::PriorityQueue
+    } else if args.parked {
+        MempoolIndex::ParkingLot
+    } else {
+        MempoolIndex::AllIndexes
+    });
+    req.set_limit(args.limit);
+
+    let env = Arc::new(EnvBuilder::new().name_prefix("grpc-mem-insp-").build());
+    let channel = ChannelBuilder::new(env).connect(&format!("{}:{}", args.address, args.port));
+    let response = MempoolClient::new(channel)
+        .get_mempool_content(&req)
+        .unwrap_or_else(|e| panic!("Failed to query mempool content: {}", e));
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("init timestamp failure");
+    let remaining = |expiration_time_secs| {
+        Duration::from_secs(expiration_time_secs)
+            .checked_sub(now)
+            .map_or_else(|| "expired".to_string(), |d| format!("{}s", d.as_secs()))
+    };
+
+    println!(
+        "Transactions: {} ({} in priority queue, {} in parking lot)",
+        response.get_total_count(),
+        response.get_priority_queue_count(),
+        response.get_parking_lot_count(),
+    );
+    println!("Gas price histogram:");
+    for bucket in response.get_gas_price_histogram() {
+        println!(
+            "  [{}, {}]: {}",
+            bucket.get_min_gas_price(),
+            bucket.get_max_gas_price(),
+            bucket.get_count(),
+        );
+    }
+    println!("Accounts ({} listed):", response.get_accounts().len());
+    for account in response.get_accounts() {
+        println!(
+            "  {}: {:?}",
+            hex::encode(account.get_sender()),
+            account.get_sequence_numbers(),
+        );
+    }
+    println!(
+        "Transactions ({} listed):",
+        response.get_transactions().len()
+    );
+    for txn in response.get_transactions() {
+        println!(
+            "  {}:{} {:?} gas price {} max gas {} expires in {} (system: {}) hash {}",
+            hex::encode(txn.get_sender()),
+            txn.get_sequence_number(),
+            txn.get_state(),
+            txn.get_gas_unit_price(),
+            txn.get_max_gas_amount(),
+            remaining(txn.get_expiration_time()),
+            remaining(txn.get_system_expiration_time()),
+            hex::encode(txn.get_hash()),
+        );
+    }
+}
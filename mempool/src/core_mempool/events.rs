@@ -0,0 +1,162 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lifecycle events of the transactions in mempool, published to the subscribers, e.g. wallets
+//! following the status of their transactions.
+
+use crate::{
+    core_mempool::transaction::{MempoolAddTransactionStatus, RemovalReason},
+    proto::shared::transaction_event::{
+        TransactionEvent as ProtoTransactionEvent, TransactionEventType,
+    },
+    OP_COUNTERS,
+};
+use channel::publisher::{Publisher, SubscriberLagged, Subscription};
+use crypto::HashValue;
+use proto_conv::IntoProto;
+use std::collections::HashMap;
+use types::account_address::AccountAddress;
+
+/// What happened to a transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionEventKind {
+    // accepted into mempool
+    Added,
+    // moved from the parking lot to the priority queue, i.e. can now be included in a block
+    Promoted,
+    // pulled by consensus into a proposed block
+    IncludedInBlock,
+    // no longer in mempool
+    Removed(RemovalReason),
+    // refused on submission
+    Rejected(MempoolAddTransactionStatus),
+}
+
+/// Lifecycle event of a transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionEvent {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub hash: HashValue,
+    pub kind: TransactionEventKind,
+}
+
+pub(crate) type Event = std::result::Result<TransactionEvent, SubscriberLagged>;
+
+/// Publishes the lifecycle events of the transactions to the subscribers
+pub(crate) struct TransactionEventPublisher {
+    // subscribers following all the transactions
+    subscribers: Publisher<TransactionEvent>,
+    // subscribers following the transactions of a single sender, by sender
+    subscribers_by_sender: HashMap<AccountAddress, Publisher<TransactionEvent>>,
+    max_subscribers: usize,
+}
+
+impl TransactionEventPublisher {
+    pub(crate) fn new(max_subscribers: usize) -> Self {
+        Self {
+            subscribers: Publisher::new(),
+            subscribers_by_sender: HashMap::new(),
+            max_subscribers,
+        }
+    }
+
+    /// Returns the stream of the events from now on, of the given sender only if set, or None if
+    /// there are too many subscribers already.
+    /// The stream ends with an error in case the subscriber does not keep up with the events.
+    pub(crate) fn subscribe(
+        &mut self,
+        sender: Option<AccountAddress>,
+    ) -> Option<Subscription<TransactionEvent>> {
+        self.prune();
+        if self.len() >= self.max_subscribers {
+            OP_COUNTERS.inc("event_subscribers.refused");
+            return None;
+        }
+        let receiver = match sender {
+            Some(sender) => self
+                .subscribers_by_sender
+                .entry(sender)
+                .or_default()
+                .subscribe(),
+            None => self.subscribers.subscribe(),
+        };
+        OP_COUNTERS.set("event_subscribers", self.len());
+        Some(receiver)
+    }
+
+    /// Whether anyone follows the events of the given sender, so that the events that are costly
+    /// to build are only built when needed
+    pub(crate) fn is_followed(&self, sender: &AccountAddress) -> bool {
+        !self.subscribers.is_empty() || self.subscribers_by_sender.contains_key(sender)
+    }
+
+    pub(crate) fn publish(&mut self, event: TransactionEvent) {
+        if !self.is_followed(&event.sender) {
+            return;
+        }
+        let mut num_lagged = self.subscribers.publish(&event);
+        if let Some(subscribers) = self.subscribers_by_sender.get_mut(&event.sender) {
+            num_lagged += subscribers.publish(&event);
+            if subscribers.is_empty() {
+                self.subscribers_by_sender.remove(&event.sender);
+            }
+        }
+        OP_COUNTERS.inc_by("event_subscribers.disconnected", num_lagged);
+        OP_COUNTERS.set("event_subscribers", self.len());
+    }
+
+    /// Drops the subscribers that are gone, even if no event was published to them since
+    fn prune(&mut self) {
+        self.subscribers.prune();
+        self.subscribers_by_sender.retain(|_, subscribers| {
+            subscribers.prune();
+            !subscribers.is_empty()
+        });
+    }
+
+    fn len(&self) -> usize {
+        self.subscribers.len()
+            + self
+                .subscribers_by_sender
+                .values()
+                .map(Publisher::len)
+                .sum::<usize>()
+    }
+}
+
+//***********************************
+// Decoding/Encoding to Protobuffers
+//***********************************
+impl IntoProto for TransactionEvent {
+    type ProtoType = ProtoTransactionEvent;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut event = Self::ProtoType::new();
+        event.set_sender(self.sender.into_proto());
+        event.set_sequence_number(self.sequence_number);
+        event.set_hash(self.hash.to_vec());
+        let event_type = match self.kind {
+            TransactionEventKind::Added => TransactionEventType::Added,
+            TransactionEventKind::Promoted => TransactionEventType::Promoted,
+            TransactionEventKind::IncludedInBlock => TransactionEventType::IncludedInBlock,
+            TransactionEventKind::Removed(RemovalReason::Committed) => {
+                TransactionEventType::Committed
+            }
+            TransactionEventKind::Removed(RemovalReason::Rejected) => {
+                TransactionEventType::Discarded
+            }
+            TransactionEventKind::Removed(RemovalReason::Expired) => TransactionEventType::Expired,
+            TransactionEventKind::Removed(RemovalReason::Evicted) => TransactionEventType::Evicted,
+            TransactionEventKind::Removed(RemovalReason::Replaced) => {
+                TransactionEventType::Replaced
+            }
+            TransactionEventKind::Rejected(status) => {
+                event.set_message(format!("{:?}: {}", status.code, status.message));
+                TransactionEventType::Rejected
+            }
+        };
+        event.set_event_type(event_type);
+        event
+    }
+}
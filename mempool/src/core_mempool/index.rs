@@ -4,8 +4,8 @@
 /// This module provides various indexes used by Mempool
 use crate::core_mempool::transaction::{MempoolTransaction, TimelineState};
 use std::{
-    cmp::Ordering,
-    collections::{btree_set::Iter, BTreeMap, BTreeSet},
+    cmp::{Ordering, Reverse},
+    collections::{btree_set::Iter, BTreeMap, BTreeSet, HashSet},
     iter::Rev,
     ops::Bound,
     time::Duration,
@@ -20,27 +20,47 @@ pub type AccountTransactions = BTreeMap<u64, MempoolTransaction>;
 ///
 /// We don't store full content of transaction in index
 /// Instead we use `OrderedQueueKey` - logical reference to transaction in main store
+///
+/// The transactions of the privileged senders (e.g. the association) are also kept in a separate
+/// lane, so that a share of each block can be reserved for them
 pub struct PriorityIndex {
     data: BTreeSet<OrderedQueueKey>,
+    privileged_senders: HashSet<AccountAddress>,
+    privileged_lane: BTreeSet<OrderedQueueKey>,
 }
 
 pub type PriorityQueueIter<'a> = Rev<Iter<'a, OrderedQueueKey>>;
 
 impl PriorityIndex {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(privileged_senders: HashSet<AccountAddress>) -> Self {
         Self {
             data: BTreeSet::new(),
+            privileged_senders,
+            privileged_lane: BTreeSet::new(),
         }
     }
 
     /// add transaction to index
     pub(crate) fn insert(&mut self, txn: &MempoolTransaction) {
-        self.data.insert(self.make_key(&txn));
+        let key = self.make_key(&txn);
+        if self.is_privileged(&key.address) {
+            self.privileged_lane.insert(key.clone());
+        }
+        self.data.insert(key);
     }
 
     /// remove transaction from index
     pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
-        self.data.remove(&self.make_key(&txn));
+        let key = self.make_key(&txn);
+        if self.is_privileged(&key.address) {
+            self.privileged_lane.remove(&key);
+        }
+        self.data.remove(&key);
+    }
+
+    /// whether the transactions of the given sender go to the privileged lane
+    pub(crate) fn is_privileged(&self, sender: &AccountAddress) -> bool {
+        self.privileged_senders.contains(sender)
     }
 
     pub(crate) fn contains(&self, txn: &MempoolTransaction) -> bool {
@@ -60,6 +80,16 @@ impl PriorityIndex {
     pub(crate) fn iter(&self) -> PriorityQueueIter {
         self.data.iter().rev()
     }
+
+    /// returns iterator over the transactions of the privileged senders, in priority order
+    pub(crate) fn iter_privileged_lane(&self) -> PriorityQueueIter {
+        self.privileged_lane.iter().rev()
+    }
+
+    /// returns iterator over priority queue, starting from the lowest priority
+    pub(crate) fn iter_lowest_first(&self) -> Iter<OrderedQueueKey> {
+        self.data.iter()
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
@@ -233,7 +263,26 @@ impl TimelineIndex {
 /// (because their sequence number is too high)
 /// we keep separate index to be able to efficiently evict them when Mempool is full
 pub struct ParkingLotIndex {
-    data: BTreeSet<TxnPointer>,
+    data: BTreeSet<ParkedKey>,
+}
+
+/// Orders the parked transactions by gas price, and among the transactions with the same gas price
+/// the ones furthest from being ready first
+#[derive(Eq, PartialEq, PartialOrd, Ord, Debug)]
+struct ParkedKey {
+    gas_price: u64,
+    sequence_number: Reverse<u64>,
+    address: AccountAddress,
+}
+
+impl ParkedKey {
+    fn new(txn: &MempoolTransaction) -> Self {
+        Self {
+            gas_price: txn.get_gas_price(),
+            sequence_number: Reverse(txn.get_sequence_number()),
+            address: txn.get_sender(),
+        }
+    }
 }
 
 impl ParkingLotIndex {
@@ -245,17 +294,28 @@ impl ParkingLotIndex {
 
     /// add transaction to index
     pub(crate) fn insert(&mut self, txn: &MempoolTransaction) {
-        self.data.insert(TxnPointer::from(txn));
+        self.data.insert(ParkedKey::new(txn));
     }
 
-    /// remove transaction from index
-    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
-        self.data.remove(&TxnPointer::from(txn));
+    /// remove transaction from index, returns whether it was parked
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) -> bool {
+        self.data.remove(&ParkedKey::new(txn))
     }
 
-    /// returns random "non-ready" transaction (with highest sequence number for that account)
-    pub(crate) fn pop(&mut self) -> Option<TxnPointer> {
-        self.data.iter().rev().next().cloned()
+    pub(crate) fn contains(&self, txn: &MempoolTransaction) -> bool {
+        self.data.contains(&ParkedKey::new(txn))
+    }
+
+    /// returns the "non-ready" transaction with the lowest gas price among the ones whose sender
+    /// is evictable, the cheapest to evict
+    pub(crate) fn cheapest<F>(&self, is_evictable: F) -> Option<TxnPointer>
+    where
+        F: Fn(&AccountAddress) -> bool,
+    {
+        self.data
+            .iter()
+            .find(|key| is_evictable(&key.address))
+            .map(|key| (key.address, key.sequence_number.0))
     }
 
     pub(crate) fn size(&self) -> usize {
@@ -0,0 +1,251 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Read-only view over the transactions in mempool, for operators
+
+use crate::{
+    core_mempool::transaction::{MempoolTransaction, TransactionState},
+    proto::mempool::{
+        AccountQueue, GasPriceBucket as ProtoGasPriceBucket, GetMempoolContentRequest,
+        GetMempoolContentResponse, MempoolIndex, MempoolTransactionInfo, MempoolTransactionState,
+    },
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use proto_conv::{FromProto, IntoProto};
+use std::{collections::BTreeMap, convert::TryFrom, time::Duration};
+use types::account_address::AccountAddress;
+
+/// Number of transactions and accounts returned if the request doesn't set a limit
+pub const DEFAULT_CONTENT_LIMIT: usize = 1000;
+
+/// Index the inspected transactions are in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexFilter {
+    All,
+    // transactions that can be included in the next block
+    PriorityQueue,
+    // transactions waiting for the preceding sequence numbers of their account
+    ParkingLot,
+}
+
+/// Selects the transactions to inspect
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentFilter {
+    pub sender: Option<AccountAddress>,
+    pub min_gas_price: u64,
+    pub max_gas_price: Option<u64>,
+    pub index: IndexFilter,
+    // max number of transactions and of accounts listed
+    pub limit: usize,
+}
+
+impl ContentFilter {
+    pub(crate) fn matches(&self, txn: &MempoolTransaction, is_ready: bool) -> bool {
+        let gas_price = txn.get_gas_price();
+        let index_matches = match self.index {
+            IndexFilter::All => true,
+            IndexFilter::PriorityQueue => is_ready,
+            IndexFilter::ParkingLot => !is_ready,
+        };
+        index_matches
+            && gas_price >= self.min_gas_price
+            && self.max_gas_price.map_or(true, |max| gas_price <= max)
+    }
+}
+
+impl Default for ContentFilter {
+    fn default() -> Self {
+        Self {
+            sender: None,
+            min_gas_price: 0,
+            max_gas_price: None,
+            index: IndexFilter::All,
+            limit: DEFAULT_CONTENT_LIMIT,
+        }
+    }
+}
+
+/// Transaction listed by an inspection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionInfo {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub hash: HashValue,
+    pub gas_unit_price: u64,
+    pub max_gas_amount: u64,
+    pub state: TransactionState,
+    // client-specified expiration time
+    pub expiration_time: Duration,
+    // expiration time in the system TTLIndex
+    pub system_expiration_time: Duration,
+}
+
+/// Gas price range, bounds included, and the number of transactions within it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasPriceBucket {
+    pub min_gas_price: u64,
+    pub max_gas_price: u64,
+    pub count: usize,
+}
+
+/// Result of an inspection: the matching transactions up to the limit, and statistics over all
+/// of them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolContent {
+    pub transactions: Vec<TransactionInfo>,
+    // queued sequence numbers of each account, ordered by address
+    pub accounts: Vec<(AccountAddress, Vec<u64>)>,
+    pub total_count: usize,
+    pub priority_queue_count: usize,
+    pub parking_lot_count: usize,
+    // non-empty buckets by increasing gas price
+    pub gas_price_histogram: Vec<GasPriceBucket>,
+}
+
+impl MempoolContent {
+    /// Builds the content from the matching transactions, ordered by sender and sequence number
+    pub(crate) fn new(
+        matching: impl Iterator<Item = (TransactionInfo, bool)>,
+        limit: usize,
+    ) -> Self {
+        let mut content = Self::default();
+        // bucket i holds the gas prices with i significant bits
+        let mut buckets = BTreeMap::new();
+        for (info, is_ready) in matching {
+            content.total_count += 1;
+            if is_ready {
+                content.priority_queue_count += 1;
+            } else {
+                content.parking_lot_count += 1;
+            }
+            *buckets
+                .entry(64 - info.gas_unit_price.leading_zeros())
+                .or_insert(0) += 1;
+
+            let is_new_account = content
+                .accounts
+                .last()
+                .map_or(true, |(address, _)| *address != info.sender);
+            if is_new_account {
+                if content.accounts.len() < limit {
+                    content
+                        .accounts
+                        .push((info.sender, vec![info.sequence_number]));
+                }
+            } else if let Some((_, sequence_numbers)) = content.accounts.last_mut() {
+                sequence_numbers.push(info.sequence_number);
+            }
+            if content.transactions.len() < limit {
+                content.transactions.push(info);
+            }
+        }
+        content.gas_price_histogram = buckets
+            .into_iter()
+            .map(|(bits, count)| GasPriceBucket {
+                min_gas_price: if bits == 0 { 0 } else { 1 << (bits - 1) },
+                max_gas_price: if bits == 64 {
+                    u64::max_value()
+                } else {
+                    (1 << bits) - 1
+                },
+                count,
+            })
+            .collect();
+        content
+    }
+}
+
+//***********************************
+// Decoding/Encoding to Protobuffers
+//***********************************
+impl FromProto for ContentFilter {
+    type ProtoType = GetMempoolContentRequest;
+
+    fn from_proto(proto: Self::ProtoType) -> Result<Self> {
+        let sender = if proto.get_sender().is_empty() {
+            None
+        } else {
+            Some(AccountAddress::try_from(proto.get_sender())?)
+        };
+        let max_gas_price = match proto.get_max_gas_price() {
+            0 => None,
+            max => Some(max),
+        };
+        let index = match proto.get_index() {
+            MempoolIndex::AllIndexes => IndexFilter::All,
+            MempoolIndex::PriorityQueue => IndexFilter::PriorityQueue,
+            MempoolIndex::ParkingLot => IndexFilter::ParkingLot,
+        };
+        let limit = match proto.get_limit() {
+            0 => DEFAULT_CONTENT_LIMIT,
+            limit => limit as usize,
+        };
+        Ok(Self {
+            sender,
+            min_gas_price: proto.get_min_gas_price(),
+            max_gas_price,
+            index,
+            limit,
+        })
+    }
+}
+
+impl IntoProto for TransactionInfo {
+    type ProtoType = MempoolTransactionInfo;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut info = Self::ProtoType::new();
+        info.set_sender(self.sender.into_proto());
+        info.set_sequence_number(self.sequence_number);
+        info.set_hash(self.hash.to_vec());
+        info.set_gas_unit_price(self.gas_unit_price);
+        info.set_max_gas_amount(self.max_gas_amount);
+        info.set_state(match self.state {
+            TransactionState::Ready => MempoolTransactionState::Ready,
+            TransactionState::Parked => MempoolTransactionState::Parked,
+            TransactionState::Expiring => MempoolTransactionState::Expiring,
+            // removed transactions are not listed
+            TransactionState::Removed(_) => MempoolTransactionState::Unknown,
+        });
+        info.set_expiration_time(self.expiration_time.as_secs());
+        info.set_system_expiration_time(self.system_expiration_time.as_secs());
+        info
+    }
+}
+
+impl IntoProto for MempoolContent {
+    type ProtoType = GetMempoolContentResponse;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut response = Self::ProtoType::new();
+        response.set_transactions(self.transactions.into_proto());
+        response.set_accounts(
+            self.accounts
+                .into_iter()
+                .map(|(sender, sequence_numbers)| {
+                    let mut queue = AccountQueue::new();
+                    queue.set_sender(sender.into_proto());
+                    queue.set_sequence_numbers(sequence_numbers);
+                    queue
+                })
+                .collect(),
+        );
+        response.set_total_count(self.total_count as u64);
+        response.set_priority_queue_count(self.priority_queue_count as u64);
+        response.set_parking_lot_count(self.parking_lot_count as u64);
+        response.set_gas_price_histogram(
+            self.gas_price_histogram
+                .into_iter()
+                .map(|bucket| {
+                    let mut proto_bucket = ProtoGasPriceBucket::new();
+                    proto_bucket.set_min_gas_price(bucket.min_gas_price);
+                    proto_bucket.set_max_gas_price(bucket.max_gas_price);
+                    proto_bucket.set_count(bucket.count as u64);
+                    proto_bucket
+                })
+                .collect(),
+        );
+        response
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! On-disk journal of the transactions in mempool, used to recover them after a restart.
+//!
+//! The journal is an append-only log of records, each one prefixed with its length:
+//! - insertion: `[INSERT_TAG][is_local: u8][SignedTransaction proto bytes]`
+//! - removal: `[REMOVE_TAG][sender: 32 bytes][sequence_number: u64]`
+//!
+//! A later insertion of the same (sender, sequence_number) overrides the earlier one.
+//! The log grows with every removal, so it's periodically rewritten with only the transactions
+//! currently in mempool (see [`JournalSnapshot`]).
+
+use crate::{
+    core_mempool::{
+        index::TxnPointer,
+        transaction::{MempoolTransaction, TimelineState},
+    },
+    OP_COUNTERS,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use failure::prelude::*;
+use logger::prelude::*;
+use proto_conv::{FromProtoBytes, IntoProtoBytes};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, Cursor, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use types::{
+    account_address::{AccountAddress, ADDRESS_LENGTH},
+    transaction::{SignedTransaction, MAX_TRANSACTION_SIZE_IN_BYTES},
+};
+
+const INSERT_TAG: u8 = 0;
+const REMOVE_TAG: u8 = 1;
+// generous bound on the size of a record, i.e. of a transaction along with its signature, so that
+// a corrupted length never makes the replay allocate more
+const MAX_RECORD_SIZE: u64 = 2 * MAX_TRANSACTION_SIZE_IN_BYTES as u64;
+
+/// Transaction recovered from the journal
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournaledTransaction {
+    pub txn: SignedTransaction,
+    // whether the transaction was submitted to this node (rather than received from a peer),
+    // i.e. whether it should be broadcast
+    pub is_local: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum JournalRecord {
+    Insert(JournaledTransaction),
+    Remove(TxnPointer),
+}
+
+impl JournalRecord {
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        match self {
+            JournalRecord::Insert(journaled) => {
+                bytes.write_u8(INSERT_TAG)?;
+                bytes.write_u8(journaled.is_local as u8)?;
+                bytes.extend(journaled.txn.clone().into_proto_bytes()?);
+            }
+            JournalRecord::Remove((sender, sequence_number)) => {
+                bytes.write_u8(REMOVE_TAG)?;
+                bytes.extend_from_slice(sender.as_ref());
+                bytes.write_u64::<LittleEndian>(*sequence_number)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        match cursor.read_u8()? {
+            INSERT_TAG => {
+                let is_local = cursor.read_u8()? != 0;
+                let txn = SignedTransaction::from_proto_bytes(&bytes[2..])?;
+                Ok(JournalRecord::Insert(JournaledTransaction {
+                    txn,
+                    is_local,
+                }))
+            }
+            REMOVE_TAG => {
+                ensure!(
+                    bytes.len() == 1 + ADDRESS_LENGTH + 8,
+                    "invalid removal record length: {}",
+                    bytes.len()
+                );
+                let sender = AccountAddress::try_from(&bytes[1..=ADDRESS_LENGTH])?;
+                cursor.set_position(1 + ADDRESS_LENGTH as u64);
+                let sequence_number = cursor.read_u64::<LittleEndian>()?;
+                Ok(JournalRecord::Remove((sender, sequence_number)))
+            }
+            tag => bail!("unknown journal record tag: {}", tag),
+        }
+    }
+}
+
+/// Append-only log of the insertions into and removals from mempool
+pub struct TransactionJournal {
+    path: PathBuf,
+    file: File,
+    // length of the valid records in the file
+    len: u64,
+}
+
+impl TransactionJournal {
+    /// Opens the journal at the given path, creating it if needed.
+    /// Returns the journal along with the transactions it records as still in mempool, in their
+    /// insertion order.
+    pub fn open(path: &Path) -> Result<(Self, Vec<JournaledTransaction>)> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let (transactions, len) = match File::open(path) {
+            Ok(file) => Self::replay(file)?,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (vec![], 0),
+            Err(e) => return Err(e.into()),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        // drops the incomplete record at the end, otherwise the new records would follow it
+        if file.metadata()?.len() > len {
+            warn!("[mempool journal] truncating journal to {} bytes", len);
+            file.set_len(len)?;
+        }
+        let journal = Self {
+            path: path.to_path_buf(),
+            file,
+            len,
+        };
+        Ok((journal, transactions))
+    }
+
+    /// Returns the transactions recorded as still in mempool, along with the length of the valid
+    /// records in the file.
+    fn replay(file: File) -> Result<(Vec<JournaledTransaction>, u64)> {
+        let mut remaining = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        // insertion order, with the removed transactions left as None
+        let mut transactions: Vec<Option<JournaledTransaction>> = vec![];
+        let mut positions: HashMap<TxnPointer, usize> = HashMap::new();
+        let mut len = 0;
+        while remaining > 0 {
+            // the last record is incomplete if the node stopped in the middle of writing it
+            if remaining < 4 {
+                warn!("[mempool journal] truncated record length");
+                break;
+            }
+            let record_len = u64::from(reader.read_u32::<LittleEndian>()?);
+            if record_len > remaining - 4 {
+                warn!("[mempool journal] truncated record of {} bytes", record_len);
+                break;
+            }
+            if record_len > MAX_RECORD_SIZE {
+                // the following records can't be told apart anymore
+                OP_COUNTERS.inc("journal.corrupted_record");
+                error!("[mempool journal] invalid record length: {}", record_len);
+                break;
+            }
+            let mut bytes = vec![0; record_len as usize];
+            reader.read_exact(&mut bytes)?;
+            remaining -= 4 + record_len;
+            len += 4 + record_len;
+            match JournalRecord::decode(&bytes) {
+                Ok(JournalRecord::Insert(journaled)) => {
+                    let pointer = (journaled.txn.sender(), journaled.txn.sequence_number());
+                    if let Some(position) = positions.remove(&pointer) {
+                        transactions[position] = None;
+                    }
+                    positions.insert(pointer, transactions.len());
+                    transactions.push(Some(journaled));
+                }
+                Ok(JournalRecord::Remove(pointer)) => {
+                    if let Some(position) = positions.remove(&pointer) {
+                        transactions[position] = None;
+                    }
+                }
+                Err(e) => {
+                    OP_COUNTERS.inc("journal.corrupted_record");
+                    error!("[mempool journal] failed to decode record: {:?}", e);
+                }
+            }
+        }
+        Ok((transactions.into_iter().flatten().collect(), len))
+    }
+
+    /// Returns the number of bytes written
+    fn write_record(writer: &mut impl Write, record: &JournalRecord) -> Result<u64> {
+        let bytes = record.encode()?;
+        let mut buf = Vec::with_capacity(4 + bytes.len());
+        buf.write_u32::<LittleEndian>(bytes.len() as u32)?;
+        buf.extend(bytes);
+        // single write, so that a record is either fully written or truncated at the end
+        writer.write_all(&buf)?;
+        Ok(buf.len() as u64)
+    }
+
+    fn append(&mut self, record: &JournalRecord) {
+        match Self::write_record(&mut self.file, record) {
+            Ok(written) => self.len += written,
+            Err(e) => {
+                OP_COUNTERS.inc("journal.write_failed");
+                error!("[mempool journal] failed to append {:?}: {:?}", record, e);
+                // drops the partially written record, if any
+                if let Err(e) = self.file.set_len(self.len) {
+                    error!("[mempool journal] failed to truncate journal: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Records the insertion of the given transaction into mempool
+    pub fn record_insert(&mut self, txn: &MempoolTransaction) {
+        self.append(&JournalRecord::Insert(txn.into()));
+    }
+
+    /// Records the removal of the given transaction from mempool
+    pub fn record_remove(&mut self, txn: &MempoolTransaction) {
+        self.append(&JournalRecord::Remove((
+            txn.get_sender(),
+            txn.get_sequence_number(),
+        )));
+    }
+
+    /// Starts the compaction of the journal down to the given transactions, see
+    /// [`JournalSnapshot`].
+    pub fn snapshot<'a>(
+        &self,
+        transactions: impl Iterator<Item = &'a MempoolTransaction>,
+    ) -> JournalSnapshot {
+        JournalSnapshot {
+            records: transactions
+                .map(|txn| JournalRecord::Insert(txn.into()))
+                .collect(),
+            tmp_path: self.path.with_extension("tmp"),
+            offset: self.len,
+        }
+    }
+
+    /// Replaces the journal with the written snapshot, followed by the records appended to the
+    /// journal since the snapshot was taken.
+    /// The snapshot is renamed over the current journal, so the current journal stays intact if
+    /// the node stops in the middle of the compaction.
+    pub fn complete_compaction(&mut self, snapshot: JournalSnapshot) -> Result<()> {
+        let mut appended = vec![];
+        let mut current = File::open(&self.path)?;
+        current.seek(SeekFrom::Start(snapshot.offset))?;
+        current
+            .take(self.len - snapshot.offset)
+            .read_to_end(&mut appended)?;
+
+        let mut compacted = OpenOptions::new().append(true).open(&snapshot.tmp_path)?;
+        compacted.write_all(&appended)?;
+        compacted.sync_all()?;
+        fs::rename(&snapshot.tmp_path, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.len = self.file.metadata()?.len();
+        OP_COUNTERS.inc("journal.compaction");
+        Ok(())
+    }
+}
+
+/// Transactions in mempool at the start of a compaction of the journal.
+/// The snapshot is taken under the mempool lock, but written to disk without holding it: the
+/// records appended to the journal in the meantime are carried over when the compaction
+/// completes.
+pub struct JournalSnapshot {
+    records: Vec<JournalRecord>,
+    tmp_path: PathBuf,
+    // length of the journal when the snapshot was taken
+    offset: u64,
+}
+
+impl JournalSnapshot {
+    /// Writes the snapshot next to the journal and syncs it to disk
+    pub fn write(&self) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(&self.tmp_path)?);
+        for record in &self.records {
+            TransactionJournal::write_record(&mut writer, record)?;
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        Ok(())
+    }
+}
+
+impl From<&MempoolTransaction> for JournaledTransaction {
+    fn from(txn: &MempoolTransaction) -> Self {
+        Self {
+            txn: txn.txn.clone(),
+            is_local: txn.timeline_state != TimelineState::NonQualified,
+        }
+    }
+}
@@ -7,8 +7,14 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::{
     core_mempool::{
-        index::TxnPointer,
-        transaction::{MempoolAddTransactionStatus, MempoolTransaction, TimelineState},
+        events::{Event, TransactionEvent, TransactionEventKind},
+        index::{PriorityQueueIter, TxnPointer},
+        inspection::{ContentFilter, MempoolContent},
+        journal::{JournalSnapshot, JournaledTransaction, TransactionJournal},
+        transaction::{
+            AddTransactionInfo, MempoolAddTransactionStatus, MempoolTransaction, TimelineState,
+            TransactionStatus,
+        },
         transaction_store::TransactionStore,
     },
     proto::shared::mempool_status::MempoolAddTransactionStatusCode,
@@ -16,12 +22,16 @@ use crate::{
 };
 use chrono::Utc;
 use config::config::NodeConfig;
+use crypto::{hash::CryptoHash, HashValue};
+use failure::prelude::*;
+use futures_preview::channel::mpsc;
 use logger::prelude::*;
 use lru_cache::LruCache;
 use std::{
     cmp::{max, min},
     collections::HashSet,
     convert::TryFrom,
+    sync::Mutex,
 };
 use ttl_cache::TtlCache;
 use types::{account_address::AccountAddress, transaction::SignedTransaction};
@@ -37,17 +47,35 @@ pub struct Mempool {
     // by consensus
     pub(crate) metrics_cache: TtlCache<(AccountAddress, u64), i64>,
     pub system_transaction_timeout: Duration,
+    // max distance between the sequence number of a new transaction and its account's one
+    max_sequence_number_gap: u64,
+    // transactions recovered from the journal, not yet added back to mempool
+    journaled_transactions: Vec<JournaledTransaction>,
 }
 
 impl Mempool {
     pub(crate) fn new(config: &NodeConfig) -> Self {
+        let mut transactions = TransactionStore::new(&config.mempool);
+        let mut journaled_transactions = vec![];
+        if config.mempool.journal_enabled {
+            let (journal, recovered) = TransactionJournal::open(&config.mempool.journal_path)
+                .expect("[mempool] failed to open journal");
+            info!(
+                "[Mempool] Recovered {} transactions from journal",
+                recovered.len()
+            );
+            transactions.set_journal(journal);
+            journaled_transactions = recovered;
+        }
         Mempool {
-            transactions: TransactionStore::new(&config.mempool),
+            transactions,
             sequence_number_cache: LruCache::new(config.mempool.sequence_cache_capacity),
             metrics_cache: TtlCache::new(config.mempool.capacity),
             system_transaction_timeout: Duration::from_secs(
                 config.mempool.system_transaction_timeout_secs,
             ),
+            max_sequence_number_gap: config.mempool.max_sequence_number_gap,
+            journaled_transactions,
         }
     }
 
@@ -80,7 +108,7 @@ impl Mempool {
             .insert(sender.clone(), new_sequence_number);
 
         self.transactions
-            .commit_transaction(&sender, sequence_number);
+            .commit_transaction(&sender, sequence_number, is_rejected);
     }
 
     fn log_latency(&mut self, account: AccountAddress, sequence_number: u64, metric: &str) {
@@ -106,34 +134,88 @@ impl Mempool {
         balance: u64,
         timeline_state: TimelineState,
     ) -> MempoolAddTransactionStatus {
+        self.add_txn_with_info(txn, gas_amount, db_sequence_number, balance, timeline_state)
+            .0
+    }
+
+    /// Same as `add_txn`, but also returns the account's sequence number and the expiration of
+    /// the transaction, to report them to the client
+    pub(crate) fn add_txn_with_info(
+        &mut self,
+        txn: SignedTransaction,
+        gas_amount: u64,
+        db_sequence_number: u64,
+        balance: u64,
+        timeline_state: TimelineState,
+    ) -> (MempoolAddTransactionStatus, AddTransactionInfo) {
+        // the hash is only computed if anyone is notified of a rejection
+        let rejected_txn = if self.transactions.is_followed(&txn.sender()) {
+            Some((txn.sender(), txn.sequence_number(), txn.hash()))
+        } else {
+            None
+        };
+        let (status, info) =
+            self.try_add_txn(txn, gas_amount, db_sequence_number, balance, timeline_state);
+        if status.code != MempoolAddTransactionStatusCode::Valid {
+            if let Some((sender, sequence_number, hash)) = rejected_txn {
+                self.transactions.publish(TransactionEvent {
+                    sender,
+                    sequence_number,
+                    hash,
+                    kind: TransactionEventKind::Rejected(status.clone()),
+                });
+            }
+        }
+        (status, info)
+    }
+
+    fn try_add_txn(
+        &mut self,
+        txn: SignedTransaction,
+        gas_amount: u64,
+        db_sequence_number: u64,
+        balance: u64,
+        timeline_state: TimelineState,
+    ) -> (MempoolAddTransactionStatus, AddTransactionInfo) {
         debug!(
             "[Mempool] Adding transaction to mempool: {}:{}",
             &txn.sender(),
             db_sequence_number
         );
 
+        let cached_value = self.sequence_number_cache.get_mut(&txn.sender());
+        let sequence_number = match cached_value {
+            Some(value) => max(*value, db_sequence_number),
+            None => db_sequence_number,
+        };
+        self.sequence_number_cache
+            .insert(txn.sender(), sequence_number);
+
+        let expiration_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("init timestamp failure")
+            + self.system_transaction_timeout;
+        let info = AddTransactionInfo {
+            account_sequence_number: sequence_number,
+            sequence_number_gap: txn.sequence_number().saturating_sub(sequence_number),
+            expiration_time: min(expiration_time, txn.expiration_time()),
+        };
+
         let required_balance = self.get_required_balance(&txn, gas_amount);
         if balance < required_balance {
-            return MempoolAddTransactionStatus::new(
+            let status = MempoolAddTransactionStatus::new(
                 MempoolAddTransactionStatusCode::InsufficientBalance,
                 format!(
                     "balance: {}, required_balance: {}, gas_amount: {}",
                     balance, required_balance, gas_amount
                 ),
             );
+            return (status, info);
         }
 
-        let cached_value = self.sequence_number_cache.get_mut(&txn.sender());
-        let sequence_number = match cached_value {
-            Some(value) => max(*value, db_sequence_number),
-            None => db_sequence_number,
-        };
-        self.sequence_number_cache
-            .insert(txn.sender(), sequence_number);
-
         // don't accept old transactions (e.g. seq is less than account's current seq_number)
         if txn.sequence_number() < sequence_number {
-            return MempoolAddTransactionStatus::new(
+            let status = MempoolAddTransactionStatus::new(
                 MempoolAddTransactionStatusCode::InvalidSeqNumber,
                 format!(
                     "transaction sequence number is {}, current sequence number is  {}",
@@ -141,12 +223,24 @@ impl Mempool {
                     sequence_number,
                 ),
             );
+            return (status, info);
+        }
+
+        // don't park transactions that are unlikely to ever become ready
+        if info.sequence_number_gap > self.max_sequence_number_gap {
+            OP_COUNTERS.inc("insert.sequence_number_gap_too_large");
+            let status = MempoolAddTransactionStatus::new(
+                MempoolAddTransactionStatusCode::SequenceNumberGapTooLarge,
+                format!(
+                    "transaction sequence number is {}, current sequence number is {}, max gap: {}",
+                    txn.sequence_number(),
+                    sequence_number,
+                    self.max_sequence_number_gap,
+                ),
+            );
+            return (status, info);
         }
 
-        let expiration_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("init timestamp failure")
-            + self.system_transaction_timeout;
         if timeline_state != TimelineState::NonQualified {
             self.metrics_cache.insert(
                 (txn.sender(), txn.sequence_number()),
@@ -159,7 +253,7 @@ impl Mempool {
 
         let status = self.transactions.insert(txn_info, sequence_number);
         OP_COUNTERS.inc(&format!("insert.{:?}", status));
-        status
+        (status, info)
     }
 
     /// Fetches next block of transactions for consensus
@@ -169,6 +263,18 @@ impl Mempool {
     pub(crate) fn get_block(
         &mut self,
         batch_size: u64,
+        seen: HashSet<TxnPointer>,
+    ) -> Vec<SignedTransaction> {
+        self.get_block_with_privileged_lane(batch_size, 0, seen)
+    }
+
+    /// Same as `get_block`, but up to `privileged_lane_size` transactions of the block are
+    /// reserved for the transactions of the privileged senders, so that they can't be crowded out
+    /// by the other transactions whatever their gas price
+    pub(crate) fn get_block_with_privileged_lane(
+        &mut self,
+        batch_size: u64,
+        privileged_lane_size: u64,
         mut seen: HashSet<TxnPointer>,
     ) -> Vec<SignedTransaction> {
         let mut result = vec![];
@@ -180,13 +286,70 @@ impl Mempool {
         // `skipped` DS and rechecked once it's ancestor becomes available
         let mut skipped = HashSet::new();
 
+        // the privileged lane first, then the whole queue (the privileged transactions that
+        // didn't fit in the lane compete with the others)
+        Self::collect_ready_transactions(
+            self.transactions.iter_privileged_lane(),
+            &mut self.sequence_number_cache,
+            min(privileged_lane_size, batch_size),
+            &mut seen,
+            &mut skipped,
+            &mut result,
+        );
+        let privileged_count = result.len();
+        Self::collect_ready_transactions(
+            self.transactions.iter_queue(),
+            &mut self.sequence_number_cache,
+            batch_size,
+            &mut seen,
+            &mut skipped,
+            &mut result,
+        );
+        OP_COUNTERS.inc_by("get_block.privileged_lane", privileged_count);
+
+        // convert transaction pointers to real values
+        let block: Vec<_> = result
+            .into_iter()
+            .filter_map(|(address, seq)| self.transactions.get(&address, seq))
+            .collect();
+        for transaction in &block {
+            self.log_latency(
+                transaction.sender(),
+                transaction.sequence_number(),
+                "txn_pre_consensus_s",
+            );
+            if self.transactions.is_followed(&transaction.sender()) {
+                self.transactions.publish(TransactionEvent {
+                    sender: transaction.sender(),
+                    sequence_number: transaction.sequence_number(),
+                    hash: transaction.hash(),
+                    kind: TransactionEventKind::IncludedInBlock,
+                });
+            }
+        }
+        block
+    }
+
+    /// Appends the transactions of the queue that can be executed in order to `result`, until it
+    /// holds `batch_size` transactions
+    fn collect_ready_transactions(
+        queue: PriorityQueueIter,
+        sequence_number_cache: &mut LruCache<AccountAddress, u64>,
+        batch_size: u64,
+        seen: &mut HashSet<TxnPointer>,
+        skipped: &mut HashSet<TxnPointer>,
+        result: &mut Vec<TxnPointer>,
+    ) {
+        if result.len() as u64 >= batch_size {
+            return;
+        }
         // iterate over the queue of transactions based on gas price
-        'main: for txn in self.transactions.iter_queue() {
+        'main: for txn in queue {
             if seen.contains(&TxnPointer::from(txn)) {
                 continue;
             }
             let mut seq = txn.sequence_number;
-            let account_sequence_number = self.sequence_number_cache.get_mut(&txn.address);
+            let account_sequence_number = sequence_number_cache.get_mut(&txn.address);
             let seen_previous = seq > 0 && seen.contains(&(txn.address, seq - 1));
             // include transaction if it's "next" for given account or
             // we've already sent its ancestor to Consensus
@@ -213,19 +376,6 @@ impl Mempool {
                 skipped.insert(TxnPointer::from(txn));
             }
         }
-        // convert transaction pointers to real values
-        let block: Vec<_> = result
-            .into_iter()
-            .filter_map(|(address, seq)| self.transactions.get(&address, seq))
-            .collect();
-        for transaction in &block {
-            self.log_latency(
-                transaction.sender(),
-                transaction.sequence_number(),
-                "txn_pre_consensus_s",
-            );
-        }
-        block
     }
 
     /// TTL based garbage collection. Remove all transactions that got expired
@@ -248,6 +398,82 @@ impl Mempool {
         self.transactions.read_timeline(timeline_id, count)
     }
 
+    /// Looks up the transaction of the given account with the given sequence number, including
+    /// the recently removed ones.
+    pub(crate) fn get_transaction_status(
+        &mut self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<TransactionStatus> {
+        self.transactions.get_status(sender, sequence_number)
+    }
+
+    /// Looks up the transaction with the given hash, including the recently removed ones.
+    pub(crate) fn get_transaction_status_by_hash(
+        &mut self,
+        hash: &HashValue,
+    ) -> Option<TransactionStatus> {
+        self.transactions.get_status_by_hash(hash)
+    }
+
+    /// Lists the transactions matching the given filter, along with statistics over them
+    pub(crate) fn get_content(&self, filter: &ContentFilter) -> MempoolContent {
+        self.transactions.get_content(filter)
+    }
+
+    /// Lists the accounts with transactions in Mempool, along with their lowest sequence number
+    pub(crate) fn get_accounts(&self) -> Vec<(AccountAddress, u64)> {
+        self.transactions.get_accounts()
+    }
+
+    /// Takes the transactions recovered from the journal on startup. They should be validated
+    /// against the latest state before being added back to mempool.
+    pub(crate) fn take_journaled_transactions(&mut self) -> Vec<JournaledTransaction> {
+        std::mem::replace(&mut self.journaled_transactions, vec![])
+    }
+
+    /// Rewrites the journal with only the transactions currently in mempool. The lock is held to
+    /// snapshot the transactions and to swap the journals, but not while the snapshot is written.
+    pub(crate) fn compact_journal(mempool: &Mutex<Self>) {
+        let snapshot = mempool
+            .lock()
+            .expect("[mempool] failed to acquire mempool lock")
+            .journal_snapshot();
+        let result = match snapshot {
+            Some(snapshot) => snapshot.write().and_then(|()| {
+                mempool
+                    .lock()
+                    .expect("[mempool] failed to acquire mempool lock")
+                    .complete_journal_compaction(snapshot)
+            }),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            OP_COUNTERS.inc("journal.compaction_failed");
+            error!("[Mempool] Failed to compact journal: {:?}", e);
+        }
+    }
+
+    /// Snapshots the transactions currently in mempool to compact the journal, if enabled
+    pub(crate) fn journal_snapshot(&self) -> Option<JournalSnapshot> {
+        self.transactions.journal_snapshot()
+    }
+
+    /// Replaces the journal with the given written snapshot
+    pub(crate) fn complete_journal_compaction(&mut self, snapshot: JournalSnapshot) -> Result<()> {
+        self.transactions.complete_journal_compaction(snapshot)
+    }
+
+    /// Streams the lifecycle events of the transactions from now on, of the given sender only if
+    /// set, or None if there are too many subscribers already. The stream ends with an error in
+    /// case the subscriber does not keep up with the events.
+    pub(crate) fn subscribe(
+        &mut self,
+        sender: Option<AccountAddress>,
+    ) -> Option<mpsc::Receiver<Event>> {
+        self.transactions.subscribe(sender)
+    }
+
     /// Check the health of core mempool.
     pub(crate) fn health_check(&self) -> bool {
         self.transactions.health_check()
@@ -1,15 +1,23 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod events;
 mod index;
+mod inspection;
+mod journal;
 mod mempool;
 mod transaction;
 mod transaction_store;
 
 pub use self::{
+    events::TransactionEvent,
     index::TxnPointer,
+    inspection::ContentFilter,
     mempool::Mempool as CoreMempool,
-    transaction::{MempoolAddTransactionStatus, TimelineState},
+    transaction::{
+        AddTransactionInfo, MempoolAddTransactionStatus, RemovalReason, TimelineState,
+        TransactionState, TransactionStatus,
+    },
 };
 
 #[cfg(test)]
@@ -1,7 +1,11 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::proto::shared::mempool_status::MempoolAddTransactionStatusCode;
+use crate::proto::{
+    mempool::{GetTransactionStatusResponse, MempoolTransactionState},
+    shared::mempool_status::MempoolAddTransactionStatusCode,
+};
+use crypto::{hash::CryptoHash, HashValue};
 use failure::prelude::*;
 use proto_conv::{FromProto, IntoProto};
 use std::time::Duration;
@@ -10,6 +14,7 @@ use types::{account_address::AccountAddress, transaction::SignedTransaction};
 #[derive(Clone)]
 pub struct MempoolTransaction {
     pub txn: SignedTransaction,
+    pub hash: HashValue,
     // system expiration time of transaction. It should be removed from mempool by that time
     pub expiration_time: Duration,
     pub gas_amount: u64,
@@ -24,6 +29,7 @@ impl MempoolTransaction {
         timeline_state: TimelineState,
     ) -> Self {
         Self {
+            hash: txn.hash(),
             txn,
             gas_amount,
             expiration_time,
@@ -54,6 +60,44 @@ pub enum TimelineState {
     NonQualified,
 }
 
+/// Why a transaction was removed from mempool
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RemovalReason {
+    Committed,
+    // consensus reported the transaction as discarded by the VM
+    Rejected,
+    // client-specified or system expiration time is reached
+    Expired,
+    // removed from the parking lot to make room for new transactions
+    Evicted,
+    // replaced by the same transaction with a higher gas price
+    Replaced,
+}
+
+/// State of a transaction known to mempool
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransactionState {
+    // can be included in the next block
+    Ready,
+    // waits in ParkingLotIndex for the preceding sequence numbers of its account
+    Parked,
+    // expired, will be removed at the next garbage collection
+    Expiring,
+    // recently removed from mempool
+    Removed(RemovalReason),
+}
+
+/// Status of a transaction looked up in mempool
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TransactionStatus {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub hash: HashValue,
+    pub state: TransactionState,
+    // client-specified expiration time of the transactions in mempool
+    pub expiration_time: Option<Duration>,
+}
+
 /// Status of transaction insertion operation
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct MempoolAddTransactionStatus {
@@ -70,6 +114,20 @@ impl MempoolAddTransactionStatus {
     }
 }
 
+/// Account state and expiration of a submitted transaction, reported to the client
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AddTransactionInfo {
+    /// Sequence number of the account's next transaction, i.e. its number of committed
+    /// transactions as known to mempool
+    pub account_sequence_number: u64,
+    /// Distance between the transaction's sequence number and the account's, 0 if the
+    /// transaction is the account's next one
+    pub sequence_number_gap: u64,
+    /// Time (since the epoch) the transaction is removed from mempool if not committed: the
+    /// earliest of its client-specified and system expiration times
+    pub expiration_time: Duration,
+}
+
 //***********************************
 // Decoding/Encoding to Protobuffers
 //***********************************
@@ -94,3 +152,43 @@ impl FromProto for MempoolAddTransactionStatus {
         ))
     }
 }
+
+impl IntoProto for TransactionStatus {
+    type ProtoType = GetTransactionStatusResponse;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut response = Self::ProtoType::new();
+        let (state, message) = match self.state {
+            TransactionState::Ready => (MempoolTransactionState::Ready, ""),
+            TransactionState::Parked => (MempoolTransactionState::Parked, ""),
+            TransactionState::Expiring => (MempoolTransactionState::Expiring, ""),
+            TransactionState::Removed(RemovalReason::Committed) => {
+                (MempoolTransactionState::Committed, "")
+            }
+            TransactionState::Removed(RemovalReason::Rejected) => (
+                MempoolTransactionState::Discarded,
+                "Rejected during execution",
+            ),
+            TransactionState::Removed(RemovalReason::Expired) => {
+                (MempoolTransactionState::Discarded, "Expired")
+            }
+            TransactionState::Removed(RemovalReason::Evicted) => (
+                MempoolTransactionState::Discarded,
+                "Evicted as mempool is full",
+            ),
+            TransactionState::Removed(RemovalReason::Replaced) => (
+                MempoolTransactionState::Discarded,
+                "Replaced by a transaction with a higher gas price",
+            ),
+        };
+        response.set_state(state);
+        response.set_message(message.to_string());
+        response.set_sender(self.sender.into_proto());
+        response.set_sequence_number(self.sequence_number);
+        response.set_hash(self.hash.to_vec());
+        if let Some(expiration_time) = self.expiration_time {
+            response.set_expiration_time(expiration_time.as_secs());
+        }
+        response
+    }
+}
@@ -3,18 +3,28 @@
 
 use crate::{
     core_mempool::{
+        events::{Event, TransactionEvent, TransactionEventKind, TransactionEventPublisher},
         index::{
             AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
-            TimelineIndex,
+            TimelineIndex, TxnPointer,
+        },
+        inspection::{ContentFilter, MempoolContent, TransactionInfo},
+        journal::{JournalSnapshot, TransactionJournal},
+        transaction::{
+            MempoolAddTransactionStatus, MempoolTransaction, RemovalReason, TimelineState,
+            TransactionState, TransactionStatus,
         },
-        transaction::{MempoolAddTransactionStatus, MempoolTransaction, TimelineState},
     },
     proto::shared::mempool_status::MempoolAddTransactionStatusCode,
     OP_COUNTERS,
 };
 use config::config::MempoolConfig;
+use crypto::HashValue;
 use failure::prelude::*;
+use futures_preview::channel::mpsc;
+use lru_cache::LruCache;
 use std::{
+    cmp::max,
     collections::HashMap,
     ops::Bound,
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -37,10 +47,23 @@ pub struct TransactionStore {
     timeline_index: TimelineIndex,
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
     parking_lot_index: ParkingLotIndex,
+    // transactions by hash
+    hash_index: HashMap<HashValue, TxnPointer>,
+
+    // recently removed transactions, kept to report their status
+    removed_txns: LruCache<TxnPointer, (HashValue, RemovalReason)>,
+    removed_hashes: LruCache<HashValue, TxnPointer>,
+
+    // on-disk journal of the insertions and removals, if persistence is enabled
+    journal: Option<TransactionJournal>,
+
+    // lifecycle events of the transactions
+    event_publisher: TransactionEventPublisher,
 
     // configuration
     capacity: usize,
     capacity_per_user: usize,
+    replace_by_fee_min_bump_percent: u64,
 }
 
 impl TransactionStore {
@@ -54,13 +77,22 @@ impl TransactionStore {
             expiration_time_index: TTLIndex::new(Box::new(|t: &MempoolTransaction| {
                 t.txn.expiration_time()
             })),
-            priority_index: PriorityIndex::new(),
+            priority_index: PriorityIndex::new(config.privileged_senders.iter().cloned().collect()),
             timeline_index: TimelineIndex::new(),
             parking_lot_index: ParkingLotIndex::new(),
+            hash_index: HashMap::new(),
+
+            removed_txns: LruCache::new(config.removed_txns_cache_capacity),
+            removed_hashes: LruCache::new(config.removed_txns_cache_capacity),
+
+            journal: None,
+
+            event_publisher: TransactionEventPublisher::new(config.max_event_subscribers),
 
             // configuration
             capacity: config.capacity,
             capacity_per_user: config.capacity_per_user,
+            replace_by_fee_min_bump_percent: config.replace_by_fee_min_bump_percent,
         }
     }
 
@@ -80,102 +112,321 @@ impl TransactionStore {
 
     /// insert transaction into TransactionStore
     /// performs validation checks and updates indexes
+    /// Room is made for the transaction (by evicting another one) only once all the other checks
+    /// passed, so that nothing is evicted for a transaction that is rejected anyway
     pub(crate) fn insert(
         &mut self,
         txn: MempoolTransaction,
         current_sequence_number: u64,
     ) -> MempoolAddTransactionStatus {
-        if self.handle_gas_price_update(&txn).is_err() {
-            return MempoolAddTransactionStatus::new(
-                MempoolAddTransactionStatusCode::InvalidUpdate,
-                format!("Failed to update gas price to {}", txn.get_gas_price()),
-            );
-        }
-
-        if self.check_if_full() {
-            return MempoolAddTransactionStatus::new(
-                MempoolAddTransactionStatusCode::MempoolIsFull,
-                format!(
-                    "mempool size: {}, capacity: {}",
-                    self.system_ttl_index.size(),
-                    self.capacity,
-                ),
-            );
-        }
+        let is_update = match self.check_gas_price_update(&txn) {
+            Ok(is_update) => is_update,
+            Err(_) => {
+                return MempoolAddTransactionStatus::new(
+                    MempoolAddTransactionStatusCode::InvalidUpdate,
+                    format!("Failed to update gas price to {}", txn.get_gas_price()),
+                );
+            }
+        };
 
         let address = txn.get_sender();
         let sequence_number = txn.get_sequence_number();
 
-        self.transactions
-            .entry(address)
-            .or_insert_with(AccountTransactions::new);
-
-        if let Some(txns) = self.transactions.get_mut(&address) {
+        if is_update {
+            // the new version takes the place of the current one
+            self.replace(&address, sequence_number);
+        } else {
             // capacity check
-            if txns.len() >= self.capacity_per_user {
+            let num_txns = self.transactions.get(&address).map_or(0, |txns| txns.len());
+            if num_txns >= self.capacity_per_user {
                 return MempoolAddTransactionStatus::new(
                     MempoolAddTransactionStatusCode::TooManyTransactions,
                     format!(
                         "txns length: {} capacity per user: {}",
-                        txns.len(),
-                        self.capacity_per_user,
+                        num_txns, self.capacity_per_user,
                     ),
                 );
             }
 
-            // insert into storage and other indexes
-            self.system_ttl_index.insert(&txn);
-            self.expiration_time_index.insert(&txn);
-            txns.insert(sequence_number, txn);
-            OP_COUNTERS.set("txn.system_ttl_index", self.system_ttl_index.size());
+            if self.check_if_full(&txn, current_sequence_number) {
+                return MempoolAddTransactionStatus::new(
+                    MempoolAddTransactionStatusCode::MempoolIsFull,
+                    format!(
+                        "mempool size: {}, capacity: {}",
+                        self.system_ttl_index.size(),
+                        self.capacity,
+                    ),
+                );
+            }
+        }
+
+        // insert into storage and other indexes
+        self.system_ttl_index.insert(&txn);
+        self.expiration_time_index.insert(&txn);
+        self.hash_index.insert(txn.hash, (address, sequence_number));
+        if let Some(journal) = &mut self.journal {
+            journal.record_insert(&txn);
         }
+        self.event_publisher.publish(TransactionEvent {
+            sender: address,
+            sequence_number,
+            hash: txn.hash,
+            kind: TransactionEventKind::Added,
+        });
+        self.transactions
+            .entry(address)
+            .or_insert_with(AccountTransactions::new)
+            .insert(sequence_number, txn);
+        OP_COUNTERS.set("txn.system_ttl_index", self.system_ttl_index.size());
         self.process_ready_transactions(&address, current_sequence_number);
         MempoolAddTransactionStatus::new(MempoolAddTransactionStatusCode::Valid, "".to_string())
     }
 
+    /// Returns the status of the given transaction, either in mempool or recently removed from it
+    pub(crate) fn get_status(
+        &mut self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<TransactionStatus> {
+        if let Some(txn) = self
+            .transactions
+            .get(address)
+            .and_then(|txns| txns.get(&sequence_number))
+        {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("init timestamp failure");
+            return Some(TransactionStatus {
+                sender: *address,
+                sequence_number,
+                hash: txn.hash,
+                state: self.state(txn, now),
+                expiration_time: Some(txn.txn.expiration_time()),
+            });
+        }
+        self.get_removed_status(address, sequence_number)
+    }
+
+    /// Returns the status of the transaction with the given hash, either in mempool or recently
+    /// removed from it
+    pub(crate) fn get_status_by_hash(&mut self, hash: &HashValue) -> Option<TransactionStatus> {
+        if let Some((address, sequence_number)) = self.hash_index.get(hash).cloned() {
+            return self.get_status(&address, sequence_number);
+        }
+        let (address, sequence_number) = *self.removed_hashes.get_mut(hash)?;
+        // the sequence number might have been reused by another removed transaction since
+        self.get_removed_status(&address, sequence_number)
+            .filter(|status| status.hash == *hash)
+    }
+
+    fn state(&self, txn: &MempoolTransaction, now: Duration) -> TransactionState {
+        if txn.expiration_time <= now || txn.txn.expiration_time() <= now {
+            TransactionState::Expiring
+        } else if self.priority_index.contains(txn) {
+            TransactionState::Ready
+        } else {
+            TransactionState::Parked
+        }
+    }
+
+    /// Lists the transactions matching the given filter, ordered by sender and sequence number,
+    /// along with statistics over them
+    pub(crate) fn get_content(&self, filter: &ContentFilter) -> MempoolContent {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("init timestamp failure");
+        let mut addresses: Vec<_> = match filter.sender {
+            Some(sender) => vec![sender],
+            None => self.transactions.keys().cloned().collect(),
+        };
+        addresses.sort();
+        let matching = addresses
+            .into_iter()
+            .filter_map(move |address| self.transactions.get(&address))
+            .flat_map(|txns| txns.values())
+            .filter_map(|txn| {
+                let is_ready = self.priority_index.contains(txn);
+                if !filter.matches(txn, is_ready) {
+                    return None;
+                }
+                let info = TransactionInfo {
+                    sender: txn.get_sender(),
+                    sequence_number: txn.get_sequence_number(),
+                    hash: txn.hash,
+                    gas_unit_price: txn.get_gas_price(),
+                    max_gas_amount: txn.txn.max_gas_amount(),
+                    state: self.state(txn, now),
+                    expiration_time: txn.txn.expiration_time(),
+                    system_expiration_time: txn.expiration_time,
+                };
+                Some((info, is_ready))
+            });
+        MempoolContent::new(matching, filter.limit)
+    }
+
+    /// Lists the accounts with transactions in the store, along with their lowest sequence number
+    pub(crate) fn get_accounts(&self) -> Vec<(AccountAddress, u64)> {
+        self.transactions
+            .iter()
+            .filter_map(|(address, txns)| {
+                txns.keys()
+                    .next()
+                    .map(|sequence_number| (*address, *sequence_number))
+            })
+            .collect()
+    }
+
+    fn get_removed_status(
+        &mut self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<TransactionStatus> {
+        let (hash, reason) = *self.removed_txns.get_mut(&(*address, sequence_number))?;
+        Some(TransactionStatus {
+            sender: *address,
+            sequence_number,
+            hash,
+            state: TransactionState::Removed(reason),
+            expiration_time: None,
+        })
+    }
+
     /// Check if mempool can handle new insertion requests
     pub(crate) fn health_check(&self) -> bool {
         self.system_ttl_index.size() < self.capacity || self.parking_lot_index.size() > 0
     }
 
     /// checks if Mempool is full
-    /// If it's full, tries to free some space by evicting transactions from ParkingLot
-    fn check_if_full(&mut self) -> bool {
-        if self.system_ttl_index.size() >= self.capacity {
+    /// If it's full, tries to free some space for the given transaction: first by evicting the
+    /// cheapest transaction from ParkingLot, then by evicting ready transactions that pay a lower
+    /// gas price than the given one. The privileged senders' transactions are never evicted, and
+    /// they evict the cheapest ready transaction whatever its gas price. A transaction is only
+    /// evicted if that makes room for the given one, so this must be the last check before the
+    /// insertion
+    fn check_if_full(&mut self, txn: &MempoolTransaction, current_sequence_number: u64) -> bool {
+        if self.system_ttl_index.size() == self.capacity {
+            let priority_index = &self.priority_index;
             // try to free some space in Mempool from ParkingLot
-            if let Some((address, sequence_number)) = self.parking_lot_index.pop() {
-                if let Some(txns) = self.transactions.get_mut(&address) {
-                    if let Some(txn) = txns.remove(&sequence_number) {
-                        self.index_remove(&txn);
-                    }
+            if let Some((address, sequence_number)) = self
+                .parking_lot_index
+                .cheapest(|address| !priority_index.is_privileged(address))
+            {
+                self.evict(&address, sequence_number);
+                OP_COUNTERS.inc("evict.parking_lot");
+            } else if self.priority_index.is_privileged(&txn.get_sender()) {
+                // privileged senders are always admitted, whatever their gas price
+                if let Some((address, sequence_number)) = self.find_cheapest_ready(txn, None) {
+                    self.evict(&address, sequence_number);
+                    OP_COUNTERS.inc("evict.privileged");
+                }
+            } else if self.is_ready(txn, current_sequence_number) {
+                if let Some((address, sequence_number)) =
+                    self.find_cheapest_ready(txn, Some(txn.get_gas_price()))
+                {
+                    self.evict(&address, sequence_number);
+                    OP_COUNTERS.inc("evict.gas_price");
                 }
             }
         }
         self.system_ttl_index.size() >= self.capacity
     }
 
+    /// checks whether the given transaction would be ready once inserted, i.e. whether it's next
+    /// for its account or follows a ready transaction
+    fn is_ready(&self, txn: &MempoolTransaction, current_sequence_number: u64) -> bool {
+        let sequence_number = txn.get_sequence_number();
+        if sequence_number == current_sequence_number {
+            return true;
+        }
+        sequence_number > current_sequence_number
+            && self
+                .transactions
+                .get(&txn.get_sender())
+                .and_then(|txns| txns.get(&(sequence_number - 1)))
+                .map_or(false, |previous| self.priority_index.contains(previous))
+    }
+
+    /// finds the ready transaction of another sender with the lowest gas price, lower than
+    /// `max_gas_price` if given.
+    /// Only the last transaction of other accounts are considered, so that the eviction
+    /// doesn't leave a gap in their sequence numbers. The privileged senders' transactions are
+    /// never evicted
+    fn find_cheapest_ready(
+        &self,
+        txn: &MempoolTransaction,
+        max_gas_price: Option<u64>,
+    ) -> Option<TxnPointer> {
+        let sender = txn.get_sender();
+        self.priority_index
+            .iter_lowest_first()
+            .take_while(|key| max_gas_price.map_or(true, |max| key.gas_price < max))
+            .find(|key| {
+                key.address != sender
+                    && !self.priority_index.is_privileged(&key.address)
+                    && self
+                        .transactions
+                        .get(&key.address)
+                        .and_then(|txns| txns.keys().next_back())
+                        == Some(&key.sequence_number)
+            })
+            .map(TxnPointer::from)
+    }
+
+    fn evict(&mut self, address: &AccountAddress, sequence_number: u64) {
+        if let Some(txns) = self.transactions.get_mut(address) {
+            if let Some(txn) = txns.remove(&sequence_number) {
+                self.index_remove(&txn);
+                self.record_removal(&txn, RemovalReason::Evicted);
+            }
+        }
+    }
+
     /// check if transaction is already present in Mempool
     /// e.g. given request is update
-    /// we allow increase in gas price to speed up process
-    fn handle_gas_price_update(&mut self, txn: &MempoolTransaction) -> Result<()> {
-        if let Some(txns) = self.transactions.get_mut(&txn.get_sender()) {
-            if let Some(current_version) = txns.get_mut(&txn.get_sequence_number()) {
-                if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
-                    && current_version.txn.payload() == txn.txn.payload()
-                    && current_version.txn.expiration_time() == txn.txn.expiration_time()
-                    && current_version.get_gas_price() < txn.get_gas_price()
-                {
-                    if let Some(txn) = txns.remove(&txn.get_sequence_number()) {
-                        self.index_remove(&txn);
-                    }
-                } else {
-                    return Err(format_err!("Invalid gas price update. txn gas price: {}, current_version gas price: {}",
-                            txn.get_gas_price(),
-                            current_version.get_gas_price()));
-                }
-            }
+    /// we allow increase in gas price to speed up process, by at least
+    /// `replace_by_fee_min_bump_percent`
+    /// Returns whether the transaction is a valid update of the one in Mempool
+    fn check_gas_price_update(&self, txn: &MempoolTransaction) -> Result<bool> {
+        let current_version = match self
+            .transactions
+            .get(&txn.get_sender())
+            .and_then(|txns| txns.get(&txn.get_sequence_number()))
+        {
+            Some(current_version) => current_version,
+            None => return Ok(false),
+        };
+        let current_gas_price = current_version.get_gas_price();
+        let min_gas_price = current_gas_price.saturating_add(max(
+            1,
+            current_gas_price
+                .saturating_mul(self.replace_by_fee_min_bump_percent)
+                .saturating_add(99)
+                / 100,
+        ));
+        ensure!(
+            current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
+                && current_version.txn.payload() == txn.txn.payload()
+                && current_version.txn.expiration_time() == txn.txn.expiration_time()
+                && txn.get_gas_price() >= min_gas_price,
+            "Invalid gas price update. txn gas price: {}, current_version gas price: {}, min gas price: {}",
+            txn.get_gas_price(),
+            current_gas_price,
+            min_gas_price
+        );
+        Ok(true)
+    }
+
+    /// removes the current version of an updated transaction
+    fn replace(&mut self, address: &AccountAddress, sequence_number: u64) {
+        if let Some(txn) = self
+            .transactions
+            .get_mut(address)
+            .and_then(|txns| txns.remove(&sequence_number))
+        {
+            self.index_remove(&txn);
+            self.record_removal(&txn, RemovalReason::Replaced);
+            OP_COUNTERS.inc("replace_by_fee");
         }
-        Ok(())
     }
 
     /// fixes following invariants:
@@ -191,6 +442,14 @@ impl TransactionStore {
         if let Some(txns) = self.transactions.get_mut(&address) {
             let mut sequence_number = current_sequence_number;
             while let Some(txn) = txns.get_mut(&sequence_number) {
+                if self.parking_lot_index.remove(txn) {
+                    self.event_publisher.publish(TransactionEvent {
+                        sender: *address,
+                        sequence_number,
+                        hash: txn.hash,
+                        kind: TransactionEventKind::Promoted,
+                    });
+                }
                 self.priority_index.insert(txn);
 
                 if txn.timeline_state == TimelineState::NotReady {
@@ -212,7 +471,14 @@ impl TransactionStore {
     /// handles transaction commit
     /// it includes deletion of all transactions with sequence number <= `sequence_number`
     /// and potential promotion of sequential txns to PriorityIndex/TimelineIndex
-    pub(crate) fn commit_transaction(&mut self, account: &AccountAddress, sequence_number: u64) {
+    /// `is_rejected` tells whether the transaction was discarded during execution rather than
+    /// committed
+    pub(crate) fn commit_transaction(
+        &mut self,
+        account: &AccountAddress,
+        sequence_number: u64,
+        is_rejected: bool,
+    ) {
         if let Some(txns) = self.transactions.get_mut(&account) {
             // remove all previous seq number transactions for this account
             // This can happen if transactions are sent to multiple nodes and one of
@@ -225,6 +491,13 @@ impl TransactionStore {
 
             for transaction in txns_for_removal.values() {
                 self.index_remove(transaction);
+                let reason = if is_rejected && transaction.get_sequence_number() == sequence_number
+                {
+                    RemovalReason::Rejected
+                } else {
+                    RemovalReason::Committed
+                };
+                self.record_removal(transaction, reason);
             }
         }
         self.process_ready_transactions(account, sequence_number + 1);
@@ -237,9 +510,66 @@ impl TransactionStore {
         self.priority_index.remove(&txn);
         self.timeline_index.remove(&txn);
         self.parking_lot_index.remove(&txn);
+        self.hash_index.remove(&txn.hash);
         OP_COUNTERS.set("txn.system_ttl_index", self.system_ttl_index.size());
     }
 
+    /// remembers why the transaction was removed, to report its status later on, journals the
+    /// removal and notifies the subscribers
+    fn record_removal(&mut self, txn: &MempoolTransaction, reason: RemovalReason) {
+        let pointer = (txn.get_sender(), txn.get_sequence_number());
+        self.removed_hashes.insert(txn.hash, pointer);
+        self.removed_txns.insert(pointer, (txn.hash, reason));
+        if let Some(journal) = &mut self.journal {
+            journal.record_remove(txn);
+        }
+        self.event_publisher.publish(TransactionEvent {
+            sender: pointer.0,
+            sequence_number: pointer.1,
+            hash: txn.hash,
+            kind: TransactionEventKind::Removed(reason),
+        });
+    }
+
+    /// Streams the lifecycle events of the transactions from now on, of the given sender only if
+    /// set. None if there are too many subscribers already
+    pub(crate) fn subscribe(
+        &mut self,
+        sender: Option<AccountAddress>,
+    ) -> Option<mpsc::Receiver<Event>> {
+        self.event_publisher.subscribe(sender)
+    }
+
+    /// Whether anyone follows the events of the transactions of the given sender
+    pub(crate) fn is_followed(&self, sender: &AccountAddress) -> bool {
+        self.event_publisher.is_followed(sender)
+    }
+
+    /// Notifies the subscribers of an event that happened outside of the store
+    pub(crate) fn publish(&mut self, event: TransactionEvent) {
+        self.event_publisher.publish(event);
+    }
+
+    /// Journals all the following insertions and removals
+    pub(crate) fn set_journal(&mut self, journal: TransactionJournal) {
+        self.journal = Some(journal);
+    }
+
+    /// Snapshots the transactions currently in mempool to compact the journal, if any
+    pub(crate) fn journal_snapshot(&self) -> Option<JournalSnapshot> {
+        self.journal.as_ref().map(|journal| {
+            journal.snapshot(self.transactions.values().flat_map(|txns| txns.values()))
+        })
+    }
+
+    /// Replaces the journal with the given written snapshot
+    pub(crate) fn complete_journal_compaction(&mut self, snapshot: JournalSnapshot) -> Result<()> {
+        match &mut self.journal {
+            Some(journal) => journal.complete_compaction(snapshot),
+            None => Ok(()),
+        }
+    }
+
     /// returns gas amount required to process all transactions for given account
     pub(crate) fn get_required_balance(&mut self, address: &AccountAddress) -> u64 {
         match self.transactions.get_mut(&address) {
@@ -307,6 +637,7 @@ impl TransactionStore {
                     let status = if is_active { "active" } else { "parked" };
                     OP_COUNTERS.inc(&format!("{}.{}", index_name, status));
                     self.index_remove(&txn);
+                    self.record_removal(&txn, RemovalReason::Expired);
                 }
             }
         }
@@ -316,4 +647,8 @@ impl TransactionStore {
     pub(crate) fn iter_queue(&self) -> PriorityQueueIter {
         self.priority_index.iter()
     }
+
+    pub(crate) fn iter_privileged_lane(&self) -> PriorityQueueIter {
+        self.priority_index.iter_privileged_lane()
+    }
 }
@@ -3,16 +3,27 @@
 
 use crate::{
     core_mempool::{
+        events::{Event, TransactionEvent, TransactionEventKind, TransactionEventPublisher},
+        inspection::{ContentFilter, IndexFilter},
         unit_tests::common::{
             add_signed_txn, add_txn, add_txns_to_mempool, exist_in_metrics_cache, setup_mempool,
             TestTransaction,
         },
-        CoreMempool, TimelineState,
+        CoreMempool, RemovalReason, TimelineState, TransactionState,
     },
     proto::shared::mempool_status::MempoolAddTransactionStatusCode,
 };
+use channel::publisher::{SubscriberLagged, SUBSCRIBER_CHANNEL_SIZE};
 use config::config::NodeConfigHelpers;
-use std::{collections::HashSet, time::Duration};
+use crypto::{hash::CryptoHash, HashValue};
+use futures_preview::channel::mpsc;
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::Write,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use types::transaction::SignedTransaction;
 
 #[test]
@@ -115,6 +126,36 @@ fn test_update_invalid_transaction_in_mempool() {
     assert_eq!(next_tnx[0].gas_unit_price(), 1);
 }
 
+#[test]
+fn test_get_accounts() {
+    let (mut pool, _) = setup_mempool();
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(0, 2, 1),
+            TestTransaction::new(1, 0, 1),
+        ],
+    );
+    let mut accounts = pool.get_accounts();
+    accounts.sort();
+    assert_eq!(
+        accounts,
+        vec![
+            (TestTransaction::get_address(0), 1),
+            (TestTransaction::get_address(1), 0),
+        ]
+    );
+
+    // the lowest sequence number moves forward as the transactions are committed
+    pool.remove_transaction(&TestTransaction::get_address(0), 1, false);
+    pool.remove_transaction(&TestTransaction::get_address(1), 0, false);
+    assert_eq!(
+        pool.get_accounts(),
+        vec![(TestTransaction::get_address(0), 2)]
+    );
+}
+
 #[test]
 fn test_remove_transaction() {
     let (mut pool, mut consensus) = setup_mempool();
@@ -330,6 +371,332 @@ fn test_parking_lot_eviction() {
     assert!(add_txn(&mut pool, TestTransaction::new(0, 2, 1)).is_err());
 }
 
+#[test]
+fn test_parking_lot_evicts_cheapest() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.capacity = 3;
+    let mut pool = CoreMempool::new(&config);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(1, 5, 3),
+            TestTransaction::new(2, 5, 2),
+        ],
+    );
+    let status = |pool: &mut CoreMempool, account| {
+        pool.get_transaction_status(&TestTransaction::get_address(account), 5)
+            .map(|status| status.state)
+    };
+
+    add_txn(&mut pool, TestTransaction::new(3, 0, 1)).unwrap();
+    assert_eq!(
+        status(&mut pool, 2),
+        Some(TransactionState::Removed(RemovalReason::Evicted))
+    );
+    assert_eq!(status(&mut pool, 1), Some(TransactionState::Parked));
+}
+
+#[test]
+fn test_promoted_transactions_are_not_evicted_as_parked() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.capacity = 2;
+    let mut pool = CoreMempool::new(&config);
+    // the second transaction is parked until the first one arrives
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(0, 1, 1), TestTransaction::new(0, 0, 1)],
+    );
+
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 0, 1)).is_err());
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 1)
+            .map(|status| status.state),
+        Some(TransactionState::Ready)
+    );
+}
+
+#[test]
+fn test_no_eviction_for_rejected_transaction() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.capacity = 2;
+    config.mempool.capacity_per_user = 1;
+    let mut pool = CoreMempool::new(&config);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(0, 0, 1), TestTransaction::new(1, 5, 1)],
+    );
+
+    // the sender already has too many transactions: the parked one stays
+    assert!(add_txn(&mut pool, TestTransaction::new(0, 1, 10)).is_err());
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(1), 5)
+            .map(|status| status.state),
+        Some(TransactionState::Parked)
+    );
+}
+
+#[test]
+fn test_gas_price_eviction() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.capacity = 3;
+    let mut pool = CoreMempool::new(&config);
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(1, 0, 3),
+        ],
+    );
+
+    // the last transaction of the cheapest account makes room for a better paying one
+    let txn = TestTransaction::new(1, 1, 5).make_signed_transaction();
+    add_signed_txn(&mut pool, txn.clone()).unwrap();
+    assert_eq!(
+        pool.get_transaction_status(&TestTransaction::get_address(0), 1)
+            .map(|status| status.state),
+        Some(TransactionState::Removed(RemovalReason::Evicted))
+    );
+    let mut block = pool.get_block(3, HashSet::new());
+    block.sort_by_key(|t| (t.sender(), t.sequence_number()));
+    let mut expected = vec![txns[0].clone(), txns[2].clone(), txn];
+    expected.sort_by_key(|t| (t.sender(), t.sequence_number()));
+    assert_eq!(block, expected);
+
+    // transactions of the same account are not evicted, even if cheaper
+    assert!(add_txn(&mut pool, TestTransaction::new(0, 1, 2)).is_err());
+    // nor in favor of a transaction that is not ready
+    assert!(add_txn(&mut pool, TestTransaction::new(0, 5, 10)).is_err());
+    // nor in favor of a transaction that doesn't pay more
+    let mut pool = CoreMempool::new(&config);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 2),
+            TestTransaction::new(0, 1, 2),
+            TestTransaction::new(0, 2, 2),
+        ],
+    );
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 0, 2)).is_err());
+}
+
+#[test]
+fn test_replace_by_fee_min_bump() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.replace_by_fee_min_bump_percent = 50;
+    let mut pool = CoreMempool::new(&config);
+    add_txn(&mut pool, TestTransaction::new(0, 0, 10)).unwrap();
+
+    let status = pool.add_txn(
+        TestTransaction::new(0, 0, 14).make_signed_transaction(),
+        0,
+        0,
+        1000,
+        TimelineState::NotReady,
+    );
+    assert_eq!(status.code, MempoolAddTransactionStatusCode::InvalidUpdate);
+
+    let update = TestTransaction::new(0, 0, 15).make_signed_transaction();
+    add_signed_txn(&mut pool, update.clone()).unwrap();
+    assert_eq!(pool.get_block(1, HashSet::new()), vec![update]);
+}
+
+#[test]
+fn test_sequence_number_gap() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.max_sequence_number_gap = 2;
+    let mut pool = CoreMempool::new(&config);
+
+    let (status, info) = pool.add_txn_with_info(
+        TestTransaction::new(0, 5, 1).make_signed_transaction(),
+        0,
+        3,
+        1000,
+        TimelineState::NotReady,
+    );
+    assert_eq!(status.code, MempoolAddTransactionStatusCode::Valid);
+    assert_eq!(info.account_sequence_number, 3);
+    assert_eq!(info.sequence_number_gap, 2);
+
+    // too far ahead of the account's sequence number
+    let (status, info) = pool.add_txn_with_info(
+        TestTransaction::new(0, 6, 1).make_signed_transaction(),
+        0,
+        3,
+        1000,
+        TimelineState::NotReady,
+    );
+    assert_eq!(
+        status.code,
+        MempoolAddTransactionStatusCode::SequenceNumberGapTooLarge
+    );
+    assert_eq!(info.sequence_number_gap, 3);
+    assert!(pool
+        .get_transaction_status(&TestTransaction::get_address(0), 6)
+        .is_none());
+
+    // no gap for old transactions
+    let (status, info) = pool.add_txn_with_info(
+        TestTransaction::new(0, 1, 1).make_signed_transaction(),
+        0,
+        3,
+        1000,
+        TimelineState::NotReady,
+    );
+    assert_eq!(
+        status.code,
+        MempoolAddTransactionStatusCode::InvalidSeqNumber
+    );
+    assert_eq!(info.sequence_number_gap, 0);
+}
+
+#[test]
+fn test_reported_expiration_time() {
+    let mut pool = setup_mempool().0;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+    // the system timeout applies to transactions expiring later
+    let (_, info) = pool.add_txn_with_info(
+        TestTransaction::new(0, 0, 1).make_signed_transaction(),
+        0,
+        0,
+        1000,
+        TimelineState::NotReady,
+    );
+    assert!(info.expiration_time >= now + pool.system_transaction_timeout);
+    assert!(info.expiration_time < now + pool.system_transaction_timeout + Duration::from_secs(60));
+
+    let expiration_time = now + Duration::from_secs(10);
+    let (_, info) = pool.add_txn_with_info(
+        TestTransaction::new(1, 0, 1).make_signed_transaction_with_expiration_time(expiration_time),
+        0,
+        0,
+        1000,
+        TimelineState::NotReady,
+    );
+    assert_eq!(info.expiration_time, expiration_time);
+}
+
+#[test]
+fn test_privileged_lane() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.privileged_senders = vec![TestTransaction::get_address(1)];
+    let mut pool = CoreMempool::new(&config);
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 10),
+            TestTransaction::new(0, 1, 10),
+            TestTransaction::new(1, 0, 1),
+            TestTransaction::new(1, 1, 1),
+        ],
+    );
+
+    // ordered by gas price only without a reserved lane
+    assert_eq!(
+        pool.get_block(2, HashSet::new()),
+        vec![txns[0].clone(), txns[1].clone()]
+    );
+    assert_eq!(
+        pool.get_block_with_privileged_lane(2, 1, HashSet::new()),
+        vec![txns[2].clone(), txns[0].clone()]
+    );
+    // the privileged transactions that don't fit in the lane compete with the others
+    assert_eq!(
+        pool.get_block_with_privileged_lane(3, 1, HashSet::new()),
+        vec![txns[2].clone(), txns[0].clone(), txns[1].clone()]
+    );
+    assert_eq!(
+        pool.get_block_with_privileged_lane(4, 5, HashSet::new()),
+        vec![
+            txns[2].clone(),
+            txns[3].clone(),
+            txns[0].clone(),
+            txns[1].clone()
+        ]
+    );
+}
+
+#[test]
+fn test_privileged_sender_admitted_to_full_mempool() {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.capacity = 3;
+    config.mempool.privileged_senders = vec![TestTransaction::get_address(2)];
+    let mut pool = CoreMempool::new(&config);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 5),
+            TestTransaction::new(1, 0, 3),
+            TestTransaction::new(2, 5, 1),
+        ],
+    );
+    let status = |pool: &mut CoreMempool, account, sequence_number| {
+        pool.get_transaction_status(&TestTransaction::get_address(account), sequence_number)
+            .map(|status| status.state)
+    };
+
+    // the privileged transaction is admitted despite its low gas price: the parked privileged
+    // transaction is kept and the cheapest other transaction is evicted instead
+    add_txn(&mut pool, TestTransaction::new(2, 0, 1)).unwrap();
+    assert_eq!(status(&mut pool, 2, 0), Some(TransactionState::Ready));
+    assert_eq!(status(&mut pool, 2, 5), Some(TransactionState::Parked));
+    assert_eq!(
+        status(&mut pool, 1, 0),
+        Some(TransactionState::Removed(RemovalReason::Evicted))
+    );
+    assert_eq!(status(&mut pool, 0, 0), Some(TransactionState::Ready));
+}
+
+#[test]
+fn test_mempool_content() {
+    let mut pool = setup_mempool().0;
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 2),
+            TestTransaction::new(0, 3, 7),
+            TestTransaction::new(1, 0, 4),
+        ],
+    );
+    let address = TestTransaction::get_address(0);
+
+    let content = pool.get_content(&ContentFilter::default());
+    assert_eq!(content.total_count, 4);
+    assert_eq!(content.priority_queue_count, 3);
+    assert_eq!(content.parking_lot_count, 1);
+    let histogram: Vec<_> = content
+        .gas_price_histogram
+        .iter()
+        .map(|b| (b.min_gas_price, b.max_gas_price, b.count))
+        .collect();
+    assert_eq!(histogram, vec![(1, 1, 1), (2, 3, 1), (4, 7, 2)]);
+    assert!(content.accounts.contains(&(address, vec![0, 1, 3])));
+
+    // filter by sender and index
+    let content = pool.get_content(&ContentFilter {
+        sender: Some(address),
+        index: IndexFilter::ParkingLot,
+        ..ContentFilter::default()
+    });
+    assert_eq!(content.total_count, 1);
+    assert_eq!(content.transactions[0].sequence_number, 3);
+    assert_eq!(content.transactions[0].state, TransactionState::Parked);
+
+    // filter by gas price, with a limit on the listed transactions
+    let content = pool.get_content(&ContentFilter {
+        min_gas_price: 2,
+        max_gas_price: Some(4),
+        limit: 1,
+        ..ContentFilter::default()
+    });
+    assert_eq!(content.total_count, 2);
+    assert_eq!(content.transactions.len(), 1);
+    assert_eq!(content.accounts.len(), 1);
+}
+
 #[test]
 fn test_gc_ready_transaction() {
     let mut pool = setup_mempool().0;
@@ -361,3 +728,317 @@ fn test_gc_ready_transaction() {
     assert_eq!(timeline.len(), 1);
     assert_eq!(timeline[0].sequence_number(), 0);
 }
+
+#[test]
+fn test_transaction_status() {
+    let mut pool = setup_mempool().0;
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(1, 0, 1),
+            TestTransaction::new(1, 1, 1),
+            TestTransaction::new(1, 3, 1),
+        ],
+    );
+    let address = TestTransaction::get_address(1);
+    let state = |pool: &mut CoreMempool, sequence_number| {
+        pool.get_transaction_status(&address, sequence_number)
+            .map(|status| status.state)
+    };
+    assert_eq!(state(&mut pool, 0), Some(TransactionState::Ready));
+    assert_eq!(state(&mut pool, 3), Some(TransactionState::Parked));
+    assert_eq!(state(&mut pool, 2), None);
+
+    // lookup by hash
+    let status = pool
+        .get_transaction_status_by_hash(&txns[1].hash())
+        .unwrap();
+    assert_eq!(status.sender, address);
+    assert_eq!(status.sequence_number, 1);
+    assert_eq!(status.state, TransactionState::Ready);
+
+    // the removed transactions are remembered
+    pool.remove_transaction(&address, 0, false);
+    pool.remove_transaction(&address, 1, true);
+    assert_eq!(
+        state(&mut pool, 0),
+        Some(TransactionState::Removed(RemovalReason::Committed))
+    );
+    assert_eq!(
+        pool.get_transaction_status_by_hash(&txns[1].hash())
+            .map(|status| status.state),
+        Some(TransactionState::Removed(RemovalReason::Rejected))
+    );
+}
+
+#[test]
+fn test_expired_transaction_status() {
+    let mut pool = setup_mempool().0;
+    let txn = TestTransaction::new(1, 0, 1)
+        .make_signed_transaction_with_expiration_time(Duration::from_secs(0));
+    pool.add_txn(txn.clone(), 0, 0, 100, TimelineState::NotReady);
+    assert_eq!(
+        pool.get_transaction_status_by_hash(&txn.hash())
+            .map(|status| status.state),
+        Some(TransactionState::Expiring)
+    );
+
+    pool.gc_by_expiration_time(Duration::from_secs(1));
+    assert_eq!(
+        pool.get_transaction_status(&txn.sender(), 0)
+            .map(|status| status.state),
+        Some(TransactionState::Removed(RemovalReason::Expired))
+    );
+}
+
+#[test]
+fn test_journal_recovery() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.journal_enabled = true;
+    config.mempool.journal_path = dir.path().join("journal");
+
+    let mut pool = CoreMempool::new(&config);
+    assert!(pool.take_journaled_transactions().is_empty());
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(0, 1, 1),
+            TestTransaction::new(1, 0, 1),
+        ],
+    );
+    // the update and the commit are journaled as well
+    let update = TestTransaction::new(1, 0, 5).make_signed_transaction();
+    add_signed_txn(&mut pool, update.clone()).unwrap();
+    pool.remove_transaction(&TestTransaction::get_address(0), 0, false);
+    drop(pool);
+
+    let journaled = |config| {
+        CoreMempool::new(config)
+            .take_journaled_transactions()
+            .into_iter()
+            .map(|t| t.txn)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(journaled(&config), vec![txns[1].clone(), update.clone()]);
+
+    // compaction keeps the transactions in mempool only
+    let mut pool = CoreMempool::new(&config);
+    for journaled_txn in pool.take_journaled_transactions() {
+        assert!(journaled_txn.is_local);
+        add_signed_txn(&mut pool, journaled_txn.txn).unwrap();
+    }
+    pool.remove_transaction(&TestTransaction::get_address(1), 0, false);
+    let pool = Mutex::new(pool);
+    CoreMempool::compact_journal(&pool);
+    drop(pool);
+    assert_eq!(journaled(&config), vec![txns[1].clone()]);
+}
+
+#[test]
+fn test_journal_compaction_keeps_concurrent_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.journal_enabled = true;
+    config.mempool.journal_path = dir.path().join("journal");
+
+    let mut pool = CoreMempool::new(&config);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(0, 0, 1), TestTransaction::new(1, 0, 1)],
+    );
+    pool.remove_transaction(&TestTransaction::get_address(0), 0, false);
+
+    // the mempool changes while the snapshot is written: the insertion and the removal are
+    // carried over to the compacted journal
+    let snapshot = pool.journal_snapshot().unwrap();
+    let added = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(2, 0, 1)]);
+    pool.remove_transaction(&TestTransaction::get_address(1), 0, false);
+    snapshot.write().unwrap();
+    pool.complete_journal_compaction(snapshot).unwrap();
+    drop(pool);
+
+    let journaled = CoreMempool::new(&config)
+        .take_journaled_transactions()
+        .into_iter()
+        .map(|t| t.txn)
+        .collect::<Vec<_>>();
+    assert_eq!(journaled, vec![added[0].clone()]);
+}
+
+#[test]
+fn test_journal_truncated_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.journal_enabled = true;
+    config.mempool.journal_path = dir.path().join("journal");
+
+    let mut pool = CoreMempool::new(&config);
+    let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(0, 0, 1)]);
+    drop(pool);
+
+    // a record cut in the middle, as if the node stopped while writing it
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&config.mempool.journal_path)
+        .unwrap();
+    file.write_all(&[100, 0, 0, 0, 0, 1]).unwrap();
+
+    let mut pool = CoreMempool::new(&config);
+    let journaled = pool.take_journaled_transactions();
+    assert_eq!(journaled.len(), 1);
+    assert_eq!(journaled[0].txn, txns[0]);
+
+    // the truncated record is dropped, so the following records can be read back
+    let added = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 0, 1)]);
+    drop(pool);
+    let journaled = CoreMempool::new(&config).take_journaled_transactions();
+    assert_eq!(journaled.len(), 2);
+    assert_eq!(journaled[1].txn, added[0]);
+}
+
+#[test]
+fn test_journal_invalid_record_length() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.journal_enabled = true;
+    config.mempool.journal_path = dir.path().join("journal");
+
+    let mut pool = CoreMempool::new(&config);
+    let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(0, 0, 1)]);
+    drop(pool);
+    let valid_len = std::fs::metadata(&config.mempool.journal_path)
+        .unwrap()
+        .len();
+
+    // a corrupted length, larger than any record, followed by as many bytes
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&config.mempool.journal_path)
+        .unwrap();
+    let len: u32 = 1 << 20;
+    file.write_all(&len.to_le_bytes()).unwrap();
+    file.write_all(&vec![0; len as usize]).unwrap();
+    drop(file);
+
+    let journaled = CoreMempool::new(&config).take_journaled_transactions();
+    assert_eq!(journaled.len(), 1);
+    assert_eq!(journaled[0].txn, txns[0]);
+    assert_eq!(
+        std::fs::metadata(&config.mempool.journal_path)
+            .unwrap()
+            .len(),
+        valid_len
+    );
+}
+
+fn take_events(events: &mut mpsc::Receiver<Event>) -> Vec<(u64, TransactionEventKind)> {
+    let mut result = vec![];
+    while let Ok(Some(Ok(event))) = events.try_next() {
+        result.push((event.sequence_number, event.kind));
+    }
+    result
+}
+
+#[test]
+fn test_transaction_events() {
+    let (mut pool, mut consensus) = setup_mempool();
+    let mut all_events = pool.subscribe(None).unwrap();
+    let mut account_events = pool
+        .subscribe(Some(TestTransaction::get_address(1)))
+        .unwrap();
+
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 1, 1)).unwrap();
+    assert_eq!(
+        take_events(&mut all_events),
+        vec![
+            (0, TransactionEventKind::Added),
+            (2, TransactionEventKind::Added),
+            (1, TransactionEventKind::Added),
+            (2, TransactionEventKind::Promoted),
+        ]
+    );
+
+    // same gas price
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 0, 1)).is_ok());
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 0, 1)).is_err());
+    let events = take_events(&mut account_events);
+    assert_eq!(events[0], (0, TransactionEventKind::Added));
+    match &events[1] {
+        (0, TransactionEventKind::Rejected(status)) => {
+            assert_eq!(status.code, MempoolAddTransactionStatusCode::InvalidUpdate)
+        }
+        event => panic!("unexpected event {:?}", event),
+    }
+    assert_eq!(events.len(), 2);
+
+    let block = consensus.get_block(&mut pool, 1);
+    pool.remove_transaction(&block[0].sender(), block[0].sequence_number(), false);
+    let events = take_events(&mut all_events);
+    assert_eq!(
+        &events[events.len() - 2..],
+        &[
+            (0, TransactionEventKind::IncludedInBlock),
+            (0, TransactionEventKind::Removed(RemovalReason::Committed)),
+        ]
+    );
+}
+
+#[test]
+fn test_lagging_event_subscriber() {
+    let mut publisher = TransactionEventPublisher::new(1);
+    let mut events = publisher.subscribe(None).unwrap();
+    // the channel has one slot per sender on top of its buffer
+    for sequence_number in 0..=SUBSCRIBER_CHANNEL_SIZE as u64 + 1 {
+        publisher.publish(TransactionEvent {
+            sender: TestTransaction::get_address(0),
+            sequence_number,
+            hash: HashValue::zero(),
+            kind: TransactionEventKind::Added,
+        });
+    }
+    for _ in 0..=SUBSCRIBER_CHANNEL_SIZE {
+        assert!(events.try_next().unwrap().unwrap().is_ok());
+    }
+    // disconnected, with an error despite the full channel
+    assert_eq!(
+        events.try_next().unwrap(),
+        Some(Err(SubscriberLagged(SUBSCRIBER_CHANNEL_SIZE)))
+    );
+    assert!(events.try_next().unwrap().is_none());
+}
+
+#[test]
+fn test_event_subscribers() {
+    let mut publisher = TransactionEventPublisher::new(2);
+    let mut all_events = publisher.subscribe(None).unwrap();
+    let mut account_events = publisher
+        .subscribe(Some(TestTransaction::get_address(1)))
+        .unwrap();
+    // too many subscribers
+    assert!(publisher.subscribe(None).is_none());
+
+    for address in 0..2 {
+        publisher.publish(TransactionEvent {
+            sender: TestTransaction::get_address(address),
+            sequence_number: address as u64,
+            hash: HashValue::zero(),
+            kind: TransactionEventKind::Added,
+        });
+    }
+    assert_eq!(take_events(&mut all_events).len(), 2);
+    assert_eq!(
+        take_events(&mut account_events),
+        vec![(1, TransactionEventKind::Added)]
+    );
+
+    // the subscribers that are gone make room for new ones, even without events published
+    drop(all_events);
+    let _events = publisher
+        .subscribe(Some(TestTransaction::get_address(1)))
+        .unwrap();
+    assert!(!publisher.is_followed(&TestTransaction::get_address(0)));
+}
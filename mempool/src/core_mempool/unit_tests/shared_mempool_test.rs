@@ -18,6 +18,7 @@ use futures_preview::{
 use network::{
     interface::{NetworkNotification, NetworkRequest},
     proto::MempoolSyncMsg,
+    protocols::direct_send::Message,
     validator_network::{MempoolNetworkEvents, MempoolNetworkSender},
 };
 use proto_conv::FromProto;
@@ -145,6 +146,42 @@ impl SharedMempoolNetwork {
         }
     }
 
+    /// emulates timer tick of given node and returns the next message it sends
+    fn next_message(&mut self, peer: &PeerId) -> (PeerId, MempoolSyncMsg, Message) {
+        self.timers
+            .get(peer)
+            .unwrap()
+            .unbounded_send(SyncEvent)
+            .unwrap();
+        self.take_message(peer)
+    }
+
+    /// returns the next message sent by given node
+    fn take_message(&mut self, peer: &PeerId) -> (PeerId, MempoolSyncMsg, Message) {
+        let network_reqs_rx = self.network_reqs_rxs.get_mut(peer).unwrap();
+        match block_on(network_reqs_rx.next()).unwrap() {
+            NetworkRequest::SendMessage(peer_id, msg) => {
+                let sync_msg = ::protobuf::parse_from_bytes(msg.mdata.as_ref()).unwrap();
+                (peer_id, sync_msg, msg)
+            }
+            _ => panic!("peer {:?} didn't send a message", peer),
+        }
+    }
+
+    /// delivers message sent by given node to its recipient, and waits for the recipient to
+    /// process it
+    fn deliver(
+        &mut self,
+        sender: &PeerId,
+        recipient: &PeerId,
+        msg: Message,
+        event: SharedMempoolNotification,
+    ) {
+        let network_notifs_tx = self.network_notifs_txs.get_mut(recipient).unwrap();
+        block_on(network_notifs_tx.send(NetworkNotification::RecvMessage(*sender, msg))).unwrap();
+        self.wait_for_event(recipient, event);
+    }
+
     fn exist_in_metrics_cache(&self, peer_id: &PeerId, txn: &TestTransaction) -> bool {
         let mempool = self.mempools.get(peer_id).unwrap().lock().unwrap();
         mempool
@@ -346,3 +383,114 @@ fn test_broadcast_updated_transaction() {
     assert_eq!(txn.sequence_number(), 0);
     assert_eq!(txn.gas_unit_price(), 5);
 }
+
+fn upstream_config(upstream_peers: &[PeerId]) -> NodeConfig {
+    let mut config = NodeConfigHelpers::get_single_node_test_config(true);
+    config.mempool.upstream_peers = upstream_peers
+        .iter()
+        .map(|peer_id| format!("{:x}", peer_id))
+        .collect();
+    config
+}
+
+#[test]
+fn test_upstream_ack() {
+    let (peer_a, peer_b, peer_c) = (PeerId::random(), PeerId::random(), PeerId::random());
+    let mut smp = SharedMempoolNetwork::bootstrap_with_config(
+        vec![peer_a, peer_b, peer_c],
+        upstream_config(&[peer_b, peer_c]),
+    );
+    smp.add_txns(
+        &peer_a,
+        vec![TestTransaction::new(1, 0, 1), TestTransaction::new(1, 1, 1)],
+    );
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(peer_c));
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(peer_b));
+
+    // only the preferred upstream peer receives the transactions
+    let (recipient, sync_msg, msg) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+    assert_ne!(sync_msg.get_batch_id(), 0);
+    smp.deliver(
+        &peer_a,
+        &peer_b,
+        msg,
+        SharedMempoolNotification::NewTransactions,
+    );
+
+    // B acknowledges the batch
+    let (recipient, ack_msg, msg) = smp.take_message(&peer_b);
+    assert_eq!(recipient, peer_a);
+    assert_eq!(ack_msg.get_ack().get_batch_id(), sync_msg.get_batch_id());
+    smp.deliver(&peer_b, &peer_a, msg, SharedMempoolNotification::Ack);
+
+    // so A moves on to the next transaction
+    let (recipient, sync_msg, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+    let txn = SignedTransaction::from_proto(sync_msg.get_transactions()[0].clone()).unwrap();
+    assert_eq!(txn.sequence_number(), 1);
+}
+
+#[test]
+fn test_upstream_retry_and_failover() {
+    let (peer_a, peer_b, peer_c) = (PeerId::random(), PeerId::random(), PeerId::random());
+    let mut config = upstream_config(&[peer_b, peer_c]);
+    config.mempool.upstream_ack_timeout_ms = 0;
+    config.mempool.upstream_max_retries = 2;
+    let mut smp = SharedMempoolNetwork::bootstrap_with_config(vec![peer_a, peer_b, peer_c], config);
+    smp.add_txns(&peer_a, vec![TestTransaction::new(1, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(peer_b));
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(peer_c));
+
+    // the unacknowledged batch is sent again
+    let (recipient, first_msg, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+    let (recipient, sync_msg, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+    assert_eq!(sync_msg.get_transactions(), first_msg.get_transactions());
+
+    // B is failed over after the second unacknowledged batch
+    smp.timers
+        .get(&peer_a)
+        .unwrap()
+        .unbounded_send(SyncEvent)
+        .unwrap();
+    smp.wait_for_event(&peer_a, SharedMempoolNotification::Sync);
+    let (recipient, sync_msg, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_c);
+    assert_eq!(sync_msg.get_transactions(), first_msg.get_transactions());
+
+    // C is failed over when it disconnects, even if B remains unresponsive
+    smp.send_event(&peer_a, NetworkNotification::LostPeer(peer_c));
+    let (recipient, _, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+}
+
+#[test]
+fn test_upstream_failover_backoff() {
+    let (peer_a, peer_b, peer_c) = (PeerId::random(), PeerId::random(), PeerId::random());
+    let mut config = upstream_config(&[peer_b, peer_c]);
+    config.mempool.upstream_ack_timeout_ms = 0;
+    config.mempool.upstream_max_retries = 1;
+    config.mempool.upstream_failover_backoff_ms = 0;
+    let mut smp = SharedMempoolNetwork::bootstrap_with_config(vec![peer_a, peer_b, peer_c], config);
+    smp.add_txns(&peer_a, vec![TestTransaction::new(1, 0, 1)]);
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(peer_b));
+    smp.send_event(&peer_a, NetworkNotification::NewPeer(peer_c));
+
+    let (recipient, first_msg, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+
+    // B is failed over after the unacknowledged batch
+    smp.timers
+        .get(&peer_a)
+        .unwrap()
+        .unbounded_send(SyncEvent)
+        .unwrap();
+    smp.wait_for_event(&peer_a, SharedMempoolNotification::Sync);
+
+    // but it is tried again once the backoff elapsed, rather than C
+    let (recipient, sync_msg, _) = smp.next_message(&peer_a);
+    assert_eq!(recipient, peer_b);
+    assert_eq!(sync_msg.get_transactions(), first_msg.get_transactions());
+}
@@ -2,12 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TxnPointer},
+    core_mempool::{
+        AddTransactionInfo, ContentFilter, CoreMempool, MempoolAddTransactionStatus, TimelineState,
+        TxnPointer,
+    },
     proto::mempool_grpc::Mempool,
     OP_COUNTERS,
 };
+use crypto::HashValue;
 use futures::Future;
-use grpc_helpers::{create_grpc_invalid_arg_status, default_reply_error_logger};
+use futures_preview::StreamExt;
+use grpc_helpers::{
+    create_grpc_invalid_arg_status, default_reply_error_logger, provide_grpc_stream,
+};
+use grpcio::{RpcStatus, RpcStatusCode};
 use logger::prelude::*;
 use metrics::counters::SVC_COUNTERS;
 use proto_conv::{FromProto, IntoProto};
@@ -55,23 +63,82 @@ impl Mempool for MempoolService {
                     .core_mempool
                     .lock()
                     .expect("[add txn] acquire mempool lock")
-                    .add_txn(
+                    .add_txn_with_info(
                         transaction,
                         req.max_gas_cost,
                         req.latest_sequence_number,
                         req.account_balance,
                         TimelineState::NotReady,
                     );
-
-                let mut response =
-                    crate::proto::mempool::AddTransactionWithValidationResponse::new();
-                response.set_status(insertion_result.into_proto());
+                let response = add_transaction_response(insertion_result);
                 ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
             }
         }
         SVC_COUNTERS.resp(&ctx, success);
     }
 
+    fn add_transactions_with_validation(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        mut req: crate::proto::mempool::AddTransactionsWithValidationRequest,
+        sink: ::grpcio::UnarySink<crate::proto::mempool::AddTransactionsWithValidationResponse>,
+    ) {
+        trace!("[GRPC] Mempool::add_transactions_with_validation");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let transactions = req
+            .take_transactions()
+            .into_iter()
+            .map(|mut txn_req| {
+                SignedTransaction::from_proto(txn_req.take_signed_txn()).map(|transaction| {
+                    (
+                        transaction,
+                        txn_req.max_gas_cost,
+                        txn_req.latest_sequence_number,
+                        txn_req.account_balance,
+                    )
+                })
+            })
+            .collect::<failure::Result<Vec<_>>>();
+        match transactions {
+            Err(e) => {
+                ctx.spawn(
+                    sink.fail(create_grpc_invalid_arg_status(
+                        "add_transactions_with_validation",
+                        e,
+                    ))
+                    .map_err(default_reply_error_logger),
+                );
+                SVC_COUNTERS.resp(&ctx, false);
+            }
+            Ok(transactions) => {
+                OP_COUNTERS.inc_by("add_txns.batch_size", transactions.len());
+                let mut response =
+                    crate::proto::mempool::AddTransactionsWithValidationResponse::new();
+                {
+                    let mut mempool = self
+                        .core_mempool
+                        .lock()
+                        .expect("[add txns] acquire mempool lock");
+                    for (transaction, max_gas_cost, latest_sequence_number, balance) in transactions
+                    {
+                        let insertion_result = mempool.add_txn_with_info(
+                            transaction,
+                            max_gas_cost,
+                            latest_sequence_number,
+                            balance,
+                            TimelineState::NotReady,
+                        );
+                        response
+                            .mut_responses()
+                            .push(add_transaction_response(insertion_result));
+                    }
+                }
+                ctx.spawn(sink.success(response).map_err(default_reply_error_logger));
+                SVC_COUNTERS.resp(&ctx, true);
+            }
+        }
+    }
+
     fn get_block(
         &mut self,
         ctx: ::grpcio::RpcContext<'_>,
@@ -95,7 +162,11 @@ impl Mempool for MempoolService {
             .core_mempool
             .lock()
             .expect("[get_block] acquire mempool lock")
-            .get_block(block_size, exclude_transactions);
+            .get_block_with_privileged_lane(
+                block_size,
+                req.get_privileged_lane_size(),
+                exclude_transactions,
+            );
 
         let transactions = txns.drain(..).map(SignedTransaction::into_proto).collect();
 
@@ -154,4 +225,150 @@ impl Mempool for MempoolService {
         response.set_is_healthy(pool.health_check());
         ctx.spawn(sink.success(response).map_err(default_reply_error_logger));
     }
+
+    fn get_transaction_status(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: crate::proto::mempool::GetTransactionStatusRequest,
+        sink: ::grpcio::UnarySink<crate::proto::mempool::GetTransactionStatusResponse>,
+    ) {
+        trace!("[GRPC] Mempool::get_transaction_status");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let status = if req.get_hash().is_empty() {
+            AccountAddress::try_from(req.get_sender()).map(|sender| {
+                self.core_mempool
+                    .lock()
+                    .expect("[get_transaction_status] acquire mempool lock")
+                    .get_transaction_status(&sender, req.get_sequence_number())
+            })
+        } else {
+            HashValue::from_slice(req.get_hash()).map(|hash| {
+                self.core_mempool
+                    .lock()
+                    .expect("[get_transaction_status] acquire mempool lock")
+                    .get_transaction_status_by_hash(&hash)
+            })
+        };
+        match status {
+            Ok(status) => {
+                let response = status.map_or_else(
+                    crate::proto::mempool::GetTransactionStatusResponse::new,
+                    IntoProto::into_proto,
+                );
+                ctx.spawn(sink.success(response).map_err(default_reply_error_logger));
+                SVC_COUNTERS.resp(&ctx, true);
+            }
+            Err(e) => {
+                ctx.spawn(
+                    sink.fail(create_grpc_invalid_arg_status("get_transaction_status", e))
+                        .map_err(default_reply_error_logger),
+                );
+                SVC_COUNTERS.resp(&ctx, false);
+            }
+        }
+    }
+
+    fn get_mempool_content(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: crate::proto::mempool::GetMempoolContentRequest,
+        sink: ::grpcio::UnarySink<crate::proto::mempool::GetMempoolContentResponse>,
+    ) {
+        trace!("[GRPC] Mempool::get_mempool_content");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        match ContentFilter::from_proto(req) {
+            Ok(filter) => {
+                let content = self
+                    .core_mempool
+                    .lock()
+                    .expect("[get_mempool_content] acquire mempool lock")
+                    .get_content(&filter);
+                ctx.spawn(
+                    sink.success(content.into_proto())
+                        .map_err(default_reply_error_logger),
+                );
+                SVC_COUNTERS.resp(&ctx, true);
+            }
+            Err(e) => {
+                ctx.spawn(
+                    sink.fail(create_grpc_invalid_arg_status("get_mempool_content", e))
+                        .map_err(default_reply_error_logger),
+                );
+                SVC_COUNTERS.resp(&ctx, false);
+            }
+        }
+    }
+
+    fn subscribe_to_transaction_events(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: crate::proto::mempool::SubscribeToTransactionEventsRequest,
+        sink: ::grpcio::ServerStreamingSink<
+            crate::proto::shared::transaction_event::TransactionEvent,
+        >,
+    ) {
+        trace!("[GRPC] Mempool::subscribe_to_transaction_events");
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let sender = if req.get_sender().is_empty() {
+            Ok(None)
+        } else {
+            AccountAddress::try_from(req.get_sender()).map(Some)
+        };
+        match sender {
+            Ok(sender) => {
+                let events = self
+                    .core_mempool
+                    .lock()
+                    .expect("[subscribe_to_transaction_events] acquire mempool lock")
+                    .subscribe(sender);
+                match events {
+                    Some(events) => {
+                        OP_COUNTERS.inc("subscribe_to_transaction_events.subscribed");
+                        let events = events.map(|event| match event {
+                            Ok(event) => Ok(event.into_proto()),
+                            Err(lagged) => {
+                                OP_COUNTERS.inc("subscribe_to_transaction_events.lagged");
+                                Err(RpcStatus::new(
+                                    RpcStatusCode::ResourceExhausted,
+                                    Some(lagged.to_string()),
+                                ))
+                            }
+                        });
+                        provide_grpc_stream(&ctx, sink, events);
+                        SVC_COUNTERS.resp(&ctx, true);
+                    }
+                    None => {
+                        OP_COUNTERS.inc("subscribe_to_transaction_events.refused");
+                        let status = RpcStatus::new(
+                            RpcStatusCode::ResourceExhausted,
+                            Some("Too many subscribers".to_string()),
+                        );
+                        ctx.spawn(sink.fail(status).map_err(default_reply_error_logger));
+                        SVC_COUNTERS.resp(&ctx, false);
+                    }
+                }
+            }
+            Err(e) => {
+                ctx.spawn(
+                    sink.fail(create_grpc_invalid_arg_status(
+                        "subscribe_to_transaction_events",
+                        e,
+                    ))
+                    .map_err(default_reply_error_logger),
+                );
+                SVC_COUNTERS.resp(&ctx, false);
+            }
+        }
+    }
+}
+
+fn add_transaction_response(
+    (status, info): (MempoolAddTransactionStatus, AddTransactionInfo),
+) -> crate::proto::mempool::AddTransactionWithValidationResponse {
+    let mut response = crate::proto::mempool::AddTransactionWithValidationResponse::new();
+    response.set_status(status.into_proto());
+    response.set_account_sequence_number(info.account_sequence_number);
+    response.set_sequence_number_gap(info.sequence_number_gap);
+    response.set_expiration_time(info.expiration_time.as_secs());
+    response
 }
@@ -16,16 +16,17 @@ use futures_preview::{
 };
 use logger::prelude::*;
 use network::{
-    proto::MempoolSyncMsg,
+    proto::{MempoolSyncAck, MempoolSyncMsg},
     validator_network::{Event, MempoolNetworkEvents, MempoolNetworkSender},
 };
 use proto_conv::{FromProto, IntoProto};
 use std::{
     collections::HashMap,
+    convert::TryFrom,
     ops::Deref,
     pin::Pin,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use storage_client::StorageRead;
 use tokio::{
@@ -38,10 +39,26 @@ use vm_validator::vm_validator::{get_account_state, TransactionValidation};
 /// state of last sync with peer
 /// `timeline_id` is position in log of ready transactions
 /// `is_alive` - is connection healthy
+/// `pending_batch` - batch sent to an upstream peer and not acknowledged yet
+/// `failed_attempts` - consecutive batches the upstream peer didn't acknowledge in time
+/// `failovers` - consecutive times the upstream peer was failed over
+/// `retry_at` - when a failed over upstream peer is tried again
 #[derive(Clone)]
 struct PeerSyncState {
     timeline_id: u64,
     is_alive: bool,
+    pending_batch: Option<PendingBatch>,
+    failed_attempts: usize,
+    failovers: u32,
+    retry_at: Option<Instant>,
+}
+
+/// Batch forwarded to an upstream peer. Its id is the position in the timeline the peer reaches
+/// once it acknowledges it.
+#[derive(Clone, Debug)]
+struct PendingBatch {
+    batch_id: u64,
+    deadline: Instant,
 }
 
 type PeerInfo = HashMap<PeerId, PeerSyncState>;
@@ -57,6 +74,7 @@ pub enum SharedMempoolNotification {
     Sync,
     PeerStateChange,
     NewTransactions,
+    Ack,
 }
 
 /// Struct that owns all dependencies required by shared mempool routines
@@ -70,6 +88,9 @@ where
     storage_read_client: Arc<dyn StorageRead>,
     validator: Arc<V>,
     peer_info: Arc<Mutex<PeerInfo>>,
+    // peers the local transactions are forwarded to, by order of preference. If empty, they are
+    // broadcast to all the peers
+    upstream_peers: Arc<Vec<PeerId>>,
     subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
 }
 
@@ -87,6 +108,7 @@ where
             storage_read_client: Arc::clone(&self.storage_read_client),
             validator: Arc::clone(&self.validator),
             peer_info: self.peer_info.clone(),
+            upstream_peers: Arc::clone(&self.upstream_peers),
             subscribers: self.subscribers.clone(),
         }
     }
@@ -116,11 +138,21 @@ fn new_peer(peer_info: &Mutex<PeerInfo>, peer_id: PeerId) {
         .lock()
         .expect("[shared mempool] failed to acquire peer_info lock")
         .entry(peer_id)
+        .and_modify(|state| {
+            state.is_alive = true;
+            state.pending_batch = None;
+            state.failed_attempts = 0;
+            state.failovers = 0;
+            state.retry_at = None;
+        })
         .or_insert(PeerSyncState {
             timeline_id: 0,
             is_alive: true,
-        })
-        .is_alive = true;
+            pending_batch: None,
+            failed_attempts: 0,
+            failovers: 0,
+            retry_at: None,
+        });
 }
 
 /// lost peer handler. Marks connection as dead
@@ -200,11 +232,137 @@ async fn sync_with_peers<'a>(
     }
 }
 
+/// sync routine used instead of `sync_with_peers` when upstream peers are configured
+/// forwards ready to go transactions to the first responsive upstream peer only, one batch at a
+/// time: the batch is sent again if the peer doesn't acknowledge it in time, and the peer is
+/// failed over after `upstream_max_retries` consecutive unacknowledged batches. A failed over peer
+/// is tried again after a backoff that doubles with each consecutive failover, up to
+/// `upstream_failover_max_backoff_ms`
+async fn sync_with_upstream_peers<'a>(
+    peer_info: &'a Mutex<PeerInfo>,
+    mempool: &'a Mutex<CoreMempool>,
+    network_sender: &'a mut MempoolNetworkSender,
+    upstream_peers: &'a [PeerId],
+    config: &'a MempoolConfig,
+) {
+    let now = Instant::now();
+    // Prepare the batch while holding the peer_info lock, but release it before sending.
+    let batch = {
+        let mut peer_info = peer_info
+            .lock()
+            .expect("[shared mempool] failed to acquire peer_info lock");
+        let is_alive = |peer_id: &PeerId| peer_info.get(peer_id).map_or(false, |s| s.is_alive);
+        let is_responsive = |peer_id: &PeerId| {
+            peer_info.get(peer_id).map_or(false, |s| {
+                s.is_alive && s.retry_at.map_or(true, |retry_at| now >= retry_at)
+            })
+        };
+        // keep trying the preferred peer if all of them failed
+        let target = upstream_peers
+            .iter()
+            .cloned()
+            .find(is_responsive)
+            .or_else(|| upstream_peers.iter().cloned().find(is_alive));
+        let target = match target {
+            Some(peer_id) => peer_id,
+            None => {
+                OP_COUNTERS.inc("smp.upstream.unavailable");
+                return;
+            }
+        };
+        let state = peer_info
+            .get_mut(&target)
+            .expect("[shared mempool] upstream peer state missing");
+
+        if let Some(pending_batch) = &state.pending_batch {
+            if now < pending_batch.deadline {
+                // waiting for the ack
+                return;
+            }
+            OP_COUNTERS.inc("smp.upstream.ack_timeout");
+            state.pending_batch = None;
+            state.failed_attempts += 1;
+            if state.failed_attempts >= config.upstream_max_retries {
+                state.failed_attempts = 0;
+                state.failovers += 1;
+                let backoff = config
+                    .upstream_failover_backoff_ms
+                    .saturating_mul(2u64.saturating_pow(state.failovers - 1))
+                    .min(config.upstream_failover_max_backoff_ms);
+                state.retry_at = Some(now + Duration::from_millis(backoff));
+                warn!(
+                    "[shared mempool] upstream peer {} didn't acknowledge {} batches, failing over \
+                     for {} ms",
+                    target, config.upstream_max_retries, backoff
+                );
+                return;
+            }
+        }
+
+        let (transactions, new_timeline_id) = mempool
+            .lock()
+            .expect("[shared mempool] failed to acquire mempool lock")
+            .read_timeline(state.timeline_id, config.shared_mempool_batch_size);
+        if transactions.is_empty() {
+            return;
+        }
+        state.pending_batch = Some(PendingBatch {
+            batch_id: new_timeline_id,
+            deadline: now + Duration::from_millis(config.upstream_ack_timeout_ms),
+        });
+        (target, transactions, new_timeline_id)
+    };
+
+    let (peer_id, transactions, batch_id) = batch;
+    OP_COUNTERS.inc_by("smp.sync_with_upstream_peers", transactions.len());
+    let mut msg = MempoolSyncMsg::new();
+    msg.set_peer_id(peer_id.into());
+    msg.set_transactions(
+        transactions
+            .into_iter()
+            .map(IntoProto::into_proto)
+            .collect(),
+    );
+    msg.set_batch_id(batch_id);
+    // Since this is a direct-send, this will only error if the network
+    // module has unexpectedly crashed or shutdown.
+    network_sender
+        .send_to(peer_id, msg)
+        .await
+        .expect("[shared mempool] failed to direct-send mempool sync message");
+}
+
+/// ack handler. Moves the upstream peer forward in the timeline if it acknowledged the pending
+/// batch
+fn handle_ack(peer_info: &Mutex<PeerInfo>, peer_id: PeerId, batch_id: u64) {
+    let mut peer_info = peer_info
+        .lock()
+        .expect("[shared mempool] failed to acquire peer_info lock");
+    if let Some(state) = peer_info.get_mut(&peer_id) {
+        let is_pending = state
+            .pending_batch
+            .as_ref()
+            .map_or(false, |pending_batch| pending_batch.batch_id == batch_id);
+        if is_pending {
+            OP_COUNTERS.inc("smp.upstream.ack");
+            state.timeline_id = batch_id;
+            state.pending_batch = None;
+            state.failed_attempts = 0;
+            state.failovers = 0;
+            state.retry_at = None;
+        } else {
+            OP_COUNTERS.inc("smp.upstream.stale_ack");
+        }
+    }
+}
+
 /// used to validate incoming transactions and add them to local Mempool
+/// acknowledges the batch to the sender if it asked for it
 async fn process_incoming_transactions<V>(
     smp: SharedMempool<V>,
     peer_id: PeerId,
     transactions: Vec<SignedTransaction>,
+    batch_id: u64,
 ) where
     V: TransactionValidation,
 {
@@ -265,6 +423,21 @@ async fn process_incoming_transactions<V>(
         }
     }
     notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
+
+    if batch_id != 0 {
+        let mut ack = MempoolSyncAck::new();
+        ack.set_batch_id(batch_id);
+        let mut msg = MempoolSyncMsg::new();
+        msg.set_peer_id(peer_id.into());
+        msg.set_ack(ack);
+        let mut network_sender = smp.network_sender.clone();
+        if let Err(e) = network_sender.send_to(peer_id, msg).await {
+            error!(
+                "[shared mempool] failed to acknowledge batch to {}: {:?}",
+                peer_id, e
+            );
+        }
+    }
 }
 
 /// This task handles [`SyncEvent`], which is periodically emitted for us to
@@ -276,14 +449,32 @@ where
     let peer_info = smp.peer_info;
     let mempool = smp.mempool;
     let mut network_sender = smp.network_sender;
-    let batch_size = smp.config.shared_mempool_batch_size;
+    let config = smp.config;
+    let upstream_peers = smp.upstream_peers;
     let subscribers = smp.subscribers;
 
     while let Some(sync_event) = interval.next().await {
         trace!("SyncEvent: {:?}", sync_event);
         match sync_event {
             Ok(_) => {
-                sync_with_peers(&peer_info, &mempool, &mut network_sender, batch_size).await;
+                if upstream_peers.is_empty() {
+                    sync_with_peers(
+                        &peer_info,
+                        &mempool,
+                        &mut network_sender,
+                        config.shared_mempool_batch_size,
+                    )
+                    .await;
+                } else {
+                    sync_with_upstream_peers(
+                        &peer_info,
+                        &mempool,
+                        &mut network_sender,
+                        &upstream_peers,
+                        &config,
+                    )
+                    .await;
+                }
                 notify_subscribers(SharedMempoolNotification::Sync, &subscribers);
             }
             Err(e) => {
@@ -328,6 +519,11 @@ async fn inbound_network_task<V>(
                 }
                 Event::Message((peer_id, mut msg)) => {
                     OP_COUNTERS.inc("smp.event.message");
+                    if msg.has_ack() {
+                        handle_ack(&peer_info, peer_id, msg.get_ack().get_batch_id());
+                        notify_subscribers(SharedMempoolNotification::Ack, &subscribers);
+                        continue;
+                    }
                     let transactions: Vec<_> = msg
                         .take_transactions()
                         .into_iter()
@@ -351,6 +547,7 @@ async fn inbound_network_task<V>(
                             smp.clone(),
                             peer_id,
                             transactions,
+                            msg.get_batch_id(),
                         ))
                         .await;
                 }
@@ -393,11 +590,169 @@ async fn gc_task(mempool: Arc<Mutex<CoreMempool>>, gc_interval_ms: u64) {
     crit!("SharedMempool gc_task terminated");
 }
 
+/// removes the transactions committed to the ledger: a node that doesn't run consensus, e.g. a
+/// full node, is never notified about the commits, so the sequence numbers of the accounts with
+/// transactions in Mempool are checked against storage instead
+async fn remove_committed_transactions(
+    mempool: &Mutex<CoreMempool>,
+    storage_read_client: &Arc<dyn StorageRead>,
+) {
+    let accounts = mempool
+        .lock()
+        .expect("[shared mempool] failed to acquire mempool lock")
+        .get_accounts();
+    if accounts.is_empty() {
+        return;
+    }
+
+    let account_states = join_all(
+        accounts
+            .iter()
+            .map(|(address, _)| get_account_state(storage_read_client.clone(), *address)),
+    )
+    .await;
+
+    let mut mempool = mempool
+        .lock()
+        .expect("[shared mempool] failed to acquire mempool lock");
+    for ((address, lowest_sequence_number), account_state) in
+        accounts.into_iter().zip(account_states)
+    {
+        match account_state {
+            Ok((sequence_number, _)) if sequence_number > lowest_sequence_number => {
+                OP_COUNTERS.inc("smp.committed_txns_check.removed");
+                mempool.remove_transaction(&address, sequence_number - 1, false);
+            }
+            Ok(_) => (),
+            Err(e) => {
+                OP_COUNTERS.inc("smp.committed_txns_check.storage_error");
+                debug!(
+                    "[shared mempool] failed to get the state of account {}: {:?}",
+                    address, e
+                );
+            }
+        }
+    }
+}
+
+/// Periodically removes the transactions committed to the ledger
+async fn committed_transactions_task(
+    mempool: Arc<Mutex<CoreMempool>>,
+    storage_read_client: Arc<dyn StorageRead>,
+    check_interval_ms: u64,
+) {
+    let mut interval = Interval::new_interval(Duration::from_millis(check_interval_ms)).compat();
+    while let Some(res) = interval.next().await {
+        match res {
+            Ok(_) => remove_committed_transactions(&mempool, &storage_read_client).await,
+            Err(e) => {
+                error!(
+                    "Error in committed_transactions_task timer interval: {:?}",
+                    e
+                );
+                break;
+            }
+        }
+    }
+
+    crit!("SharedMempool committed_transactions_task terminated");
+}
+
+/// validates the transactions recovered from the journal against the latest state and adds the
+/// valid ones back to local Mempool
+async fn recover_journaled_transactions<V>(smp: &SharedMempool<V>)
+where
+    V: TransactionValidation,
+{
+    let journaled = smp
+        .mempool
+        .lock()
+        .expect("[shared mempool] failed to acquire mempool lock")
+        .take_journaled_transactions();
+    if journaled.is_empty() {
+        return;
+    }
+
+    let account_states = join_all(
+        journaled
+            .iter()
+            .map(|t| get_account_state(smp.storage_read_client.clone(), t.txn.sender())),
+    )
+    .await;
+    let validations = join_all(
+        journaled
+            .iter()
+            .map(|t| smp.validator.validate_transaction(t.txn.clone()).compat()),
+    )
+    .await;
+
+    {
+        let mut mempool = smp
+            .mempool
+            .lock()
+            .expect("[shared mempool] failed to acquire mempool lock");
+
+        for (idx, journaled_txn) in journaled.into_iter().enumerate() {
+            match (&account_states[idx], &validations[idx]) {
+                (Ok((sequence_number, balance)), Ok(None))
+                    if journaled_txn.txn.sequence_number() >= *sequence_number =>
+                {
+                    // local transactions are broadcast again, as peers might have lost them too
+                    let timeline_state = if journaled_txn.is_local {
+                        TimelineState::NotReady
+                    } else {
+                        TimelineState::NonQualified
+                    };
+                    let gas_cost = journaled_txn.txn.max_gas_amount();
+                    let insertion_result = mempool.add_txn(
+                        journaled_txn.txn,
+                        gas_cost,
+                        *sequence_number,
+                        *balance,
+                        timeline_state,
+                    );
+                    OP_COUNTERS.inc(&format!("smp.journal.status.{:?}", insertion_result.code));
+                }
+                _ => OP_COUNTERS.inc("smp.journal.status.invalid"),
+            }
+        }
+    }
+    // drops the transactions that were not recovered
+    CoreMempool::compact_journal(&smp.mempool);
+    notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
+}
+
+/// Recovers the journaled transactions, then periodically compacts the journal
+async fn journal_task<V>(smp: SharedMempool<V>, compaction_interval_ms: u64)
+where
+    V: TransactionValidation,
+{
+    recover_journaled_transactions(&smp).await;
+
+    let mut interval =
+        Interval::new_interval(Duration::from_millis(compaction_interval_ms)).compat();
+    while let Some(res) = interval.next().await {
+        match res {
+            Ok(_) => CoreMempool::compact_journal(&smp.mempool),
+            Err(e) => {
+                error!("Error in journal_task timer interval: {:?}", e);
+                break;
+            }
+        }
+    }
+
+    crit!("SharedMempool journal_task terminated");
+}
+
 /// bootstrap of SharedMempool
 /// creates separate Tokio Runtime that runs following routines:
 ///   - outbound_sync_task (task that periodically broadcasts transactions to peers)
 ///   - inbound_network_task (task that handles inbound mempool messages and network events)
 ///   - gc_task (task that performs GC of all expired transactions by SystemTTL)
+///   - journal_task (task that recovers the journaled transactions and compacts the journal), if
+///     the journal is enabled
+///   - committed_transactions_task (task that removes the transactions committed to the ledger), if
+///     upstream peers are configured, i.e. the node doesn't learn about the commits from consensus
 pub(crate) fn start_shared_mempool<V>(
     config: &NodeConfig,
     mempool: Arc<Mutex<CoreMempool>>,
@@ -418,14 +773,23 @@ where
     let executor = runtime.executor();
 
     let peer_info = Arc::new(Mutex::new(PeerInfo::new()));
+    let upstream_peers = config
+        .mempool
+        .upstream_peers
+        .iter()
+        .map(|peer_id| {
+            PeerId::try_from(peer_id.clone()).expect("[shared mempool] invalid upstream peer id")
+        })
+        .collect();
 
     let smp = SharedMempool {
         mempool: mempool.clone(),
         config: config.mempool.clone(),
         network_sender,
-        storage_read_client,
+        storage_read_client: Arc::clone(&storage_read_client),
         validator,
         peer_info,
+        upstream_peers: Arc::new(upstream_peers),
         subscribers,
     };
 
@@ -439,6 +803,28 @@ where
             .compat(),
     );
 
+    if config.mempool.journal_enabled {
+        executor.spawn(
+            journal_task(smp.clone(), config.mempool.journal_compaction_interval_ms)
+                .boxed()
+                .unit_error()
+                .compat(),
+        );
+    }
+
+    if !smp.upstream_peers.is_empty() {
+        executor.spawn(
+            committed_transactions_task(
+                mempool.clone(),
+                storage_read_client,
+                config.mempool.committed_txns_check_interval_ms,
+            )
+            .boxed()
+            .unit_error()
+            .compat(),
+        );
+    }
+
     executor.spawn(
         inbound_network_task(smp, executor.clone(), network_events)
             .boxed()
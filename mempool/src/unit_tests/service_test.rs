@@ -80,6 +80,35 @@ fn test_add_transaction() {
     );
 }
 
+#[test]
+fn test_add_transactions() {
+    let (server, client) = setup_mempool();
+    let _handle = ServerHandle::setup(server);
+    let mut req = AddTransactionsWithValidationRequest::new();
+    req.mut_transactions()
+        .push(create_add_transaction_request(0));
+    let mut poor_txn_req = create_add_transaction_request(0);
+    poor_txn_req.set_account_balance(0);
+    req.mut_transactions().push(poor_txn_req);
+    req.mut_transactions()
+        .push(create_add_transaction_request(0));
+
+    let response = client.add_transactions_with_validation(&req).unwrap();
+    let codes: Vec<_> = response
+        .get_responses()
+        .iter()
+        .map(|txn_response| txn_response.get_status().get_code())
+        .collect();
+    assert_eq!(
+        codes,
+        vec![
+            MempoolAddTransactionStatusCode::Valid,
+            MempoolAddTransactionStatusCode::InsufficientBalance,
+            MempoolAddTransactionStatusCode::Valid,
+        ]
+    );
+}
+
 #[test]
 fn test_get_block() {
     let (server, client) = setup_mempool();
@@ -156,3 +185,32 @@ fn test_gc_by_expiration_time() {
     let response = client.get_block(&GetBlockRequest::new()).unwrap();
     assert_eq!(response.get_block().get_transactions().len(), 1);
 }
+
+#[test]
+fn test_get_mempool_content() {
+    let (server, client) = setup_mempool();
+    let _handle = ServerHandle::setup(server);
+
+    let add_req = create_add_transaction_request(u64::max_value());
+    client.add_transaction_with_validation(&add_req).unwrap();
+    client
+        .add_transaction_with_validation(&create_add_transaction_request(u64::max_value()))
+        .unwrap();
+
+    let signed_txn = SignedTransaction::from_proto(add_req.get_signed_txn().clone()).unwrap();
+    let mut req = GetMempoolContentRequest::new();
+    req.set_sender(signed_txn.sender().as_ref().to_vec());
+    let response = client.get_mempool_content(&req).unwrap();
+    assert_eq!(response.get_total_count(), 1);
+    assert_eq!(response.get_priority_queue_count(), 1);
+    assert_eq!(response.get_transactions().len(), 1);
+    assert_eq!(
+        response.get_transactions()[0].get_state(),
+        MempoolTransactionState::Ready
+    );
+    assert_eq!(response.get_accounts()[0].get_sequence_numbers(), &[0]);
+
+    // invalid sender
+    req.set_sender(vec![1, 2, 3]);
+    assert!(client.get_mempool_content(&req).is_err());
+}
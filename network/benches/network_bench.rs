@@ -191,15 +191,24 @@ fn compose_proposal(msg_len: usize) -> ConsensusMsg {
 }
 
 fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
+    rpc_bench_over_transport(b, msg_len, TransportType::TcpNoise, "/ip4/127.0.0.1/tcp/0");
+}
+
+// QUIC multiplexes substreams natively, so a lost packet does not block the RPCs on the other
+// substreams of the connection.
+fn quic_rpc_bench(b: &mut Bencher, msg_len: &usize) {
+    rpc_bench_over_transport(b, msg_len, TransportType::Quic, "/ip4/127.0.0.1/udp/0/quic");
+}
+
+fn rpc_bench_over_transport(
+    b: &mut Bencher,
+    msg_len: &usize,
+    transport: TransportType,
+    addr: &str,
+) {
     let mut runtime = Runtime::new().unwrap();
-    let (dialer_peer_id, dialer_addr) = (
-        PeerId::random(),
-        Multiaddr::from_str("/ip4/127.0.0.1/tcp/0").unwrap(),
-    );
-    let (listener_peer_id, listener_addr) = (
-        PeerId::random(),
-        Multiaddr::from_str("/ip4/127.0.0.1/tcp/0").unwrap(),
-    );
+    let (dialer_peer_id, dialer_addr) = (PeerId::random(), Multiaddr::from_str(addr).unwrap());
+    let (listener_peer_id, listener_addr) = (PeerId::random(), Multiaddr::from_str(addr).unwrap());
 
     // Setup keys for dialer.
     let mut rng = StdRng::from_seed(TEST_SEED);
@@ -241,7 +250,7 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
         listener_addr,
         RoleType::Validator,
     )
-    .transport(TransportType::TcpNoise)
+    .transport(transport)
     .trusted_peers(trusted_peers.clone())
     .identity_keys((listener_identity_private_key, listener_identity_public_key))
     .signing_keys((listener_signing_private_key, listener_signing_public_key))
@@ -261,7 +270,7 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
         dialer_addr,
         RoleType::Validator,
     )
-    .transport(TransportType::TcpNoise)
+    .transport(transport)
     .trusted_peers(trusted_peers.clone())
     .identity_keys((dialer_identity_private_key, dialer_identity_public_key))
     .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
@@ -360,6 +369,7 @@ fn network_crate_benchmark(c: &mut Criterion) {
         "network_crate_benchmark",
         ParameterizedBenchmark::new("direct_send", direct_send_bench, msg_lens)
             .with_function("rpc", rpc_bench)
+            .with_function("rpc_quic", quic_rpc_bench)
             .sample_size(10)
             .plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic))
             .throughput(|msg_len| Throughput::Bytes((*msg_len as u32) * NUM_MSGS)),
@@ -6,13 +6,15 @@
 //! The main component of this module is the [`StreamMultiplexer`] trait, which
 //! provides an interface for multiplexing multiple [`AsyncRead`]/[`AsyncWrite`] substreams over a
 //! single underlying [`AsyncRead`]/[`AsyncWrite`] stream. [`Yamux`], an implementation of this
-//! trait over [`TcpStream`], is also provided.
+//! trait over [`TcpStream`], is also provided, as well as [`QuicConnection`] which relies on the
+//! native stream multiplexing of QUIC.
 //!
 //! [`StreamMultiplexer`]: crate::multiplexing::StreamMultiplexer
 //! [`AsyncRead`]: futures::io::AsyncRead
 //! [`AsyncWrite`]: futures::io::AsyncWrite
 //! [`TcpStream`]: tokio::net::tcp::TcpStream
 //! [`Yamux`]: crate::multiplexing::yamux::Yamux
+//! [`QuicConnection`]: crate::multiplexing::quic::QuicConnection
 
 use futures::{
     future::Future,
@@ -21,6 +23,7 @@ use futures::{
 };
 use std::{fmt::Debug, io};
 
+pub mod quic;
 pub mod yamux;
 
 /// A StreamMultiplexer is responsible for multiplexing multiple [`AsyncRead`]/[`AsyncWrite`]
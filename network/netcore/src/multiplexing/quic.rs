@@ -0,0 +1,194 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implementation of [`StreamMultiplexer`] over a [`quinn`] QUIC connection
+//!
+//! Unlike [`Yamux`], which multiplexes substreams over a single ordered byte stream, QUIC
+//! multiplexes streams natively: a lost packet only stalls the streams whose data it carried
+//! instead of every substream on the connection.
+//!
+//! [`StreamMultiplexer`]: crate::multiplexing::StreamMultiplexer
+//! [`Yamux`]: crate::multiplexing::yamux::Yamux
+//! [`quinn`]: https://github.com/djc/quinn
+
+use crate::multiplexing::StreamMultiplexer;
+use futures::{
+    compat::{Compat01As03, Future01CompatExt},
+    future::{self, BoxFuture, FutureExt},
+    io::{AsyncRead, AsyncWrite},
+    ready,
+    stream::Stream,
+};
+use std::{
+    fmt, io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// The network keys the remote peer authenticated with during the handshake, both raw.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PeerKeys {
+    /// Ed25519 key the peer's certificate is issued for, which signed the handshake.
+    pub signing_public_key: Vec<u8>,
+    /// X25519 static key carried in the peer's certificate.
+    pub static_public_key: Vec<u8>,
+}
+
+/// A QUIC connection, every substream opened over it is a bidirectional QUIC stream.
+pub struct QuicConnection {
+    connection: quinn::Connection,
+    peer_keys: PeerKeys,
+    /// Streams opened by the remote side. They are shared between all the `Listener`s returned
+    /// by `listen_for_inbound`, the same way all yamux `Listener`s share the connection.
+    streams: Arc<Mutex<Compat01As03<quinn::IncomingStreams>>>,
+    /// Handle to the endpoint the connection was established on, kept alive for as long as the
+    /// connection is.
+    _endpoint: quinn::Endpoint,
+}
+
+impl QuicConnection {
+    pub fn new(
+        endpoint: quinn::Endpoint,
+        connection: quinn::Connection,
+        streams: quinn::IncomingStreams,
+        peer_keys: PeerKeys,
+    ) -> Self {
+        Self {
+            connection,
+            peer_keys,
+            streams: Arc::new(Mutex::new(Compat01As03::new(streams))),
+            _endpoint: endpoint,
+        }
+    }
+
+    /// The keys the remote peer authenticated with, accepted by the transport's
+    /// `PeerKeyVerifier`.
+    pub fn peer_keys(&self) -> &PeerKeys {
+        &self.peer_keys
+    }
+}
+
+impl fmt::Debug for QuicConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicConnection")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+impl StreamMultiplexer for QuicConnection {
+    type Substream = QuicSubstream;
+    type Listener = Listener;
+    type Outbound = BoxFuture<'static, io::Result<Self::Substream>>;
+    type Close = future::Ready<io::Result<()>>;
+
+    fn listen_for_inbound(&self) -> Self::Listener {
+        Listener {
+            streams: self.streams.clone(),
+        }
+    }
+
+    fn open_outbound(&self) -> Self::Outbound {
+        self.connection
+            .open_bi()
+            .compat()
+            .map(|result| {
+                result
+                    .map(|(send, recv)| QuicSubstream::new(send, recv))
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            })
+            .boxed()
+    }
+
+    fn close(&self) -> Self::Close {
+        // Closing a QUIC connection is immediate: pending reads and writes on its streams fail
+        // and the remote side is notified by the connection driver.
+        self.connection.close(0u32.into(), b"");
+        future::ready(Ok(()))
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct Listener {
+    streams: Arc<Mutex<Compat01As03<quinn::IncomingStreams>>>,
+}
+
+impl fmt::Debug for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Listener").finish()
+    }
+}
+
+impl Stream for Listener {
+    type Item = io::Result<QuicSubstream>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut streams = self.streams.lock().unwrap();
+        loop {
+            match ready!(Pin::new(&mut *streams).poll_next(context)) {
+                Some(Ok(quinn::NewStream::Bi(send, recv))) => {
+                    return Poll::Ready(Some(Ok(QuicSubstream::new(send, recv))));
+                }
+                // Substreams are always opened as bidirectional streams, so unidirectional
+                // streams opened by the remote side are dropped.
+                Some(Ok(quinn::NewStream::Uni(_))) => continue,
+                Some(Err(e)) => {
+                    return Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::Other, e))));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// A bidirectional QUIC stream.
+///
+/// Closing the substream finishes its send side; the remote side then reads EOF.
+pub struct QuicSubstream {
+    send: Compat01As03<quinn::SendStream>,
+    recv: Compat01As03<quinn::RecvStream>,
+}
+
+impl QuicSubstream {
+    fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
+        Self {
+            send: Compat01As03::new(send),
+            recv: Compat01As03::new(recv),
+        }
+    }
+}
+
+impl fmt::Debug for QuicSubstream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicSubstream").finish()
+    }
+}
+
+impl AsyncRead for QuicSubstream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSubstream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_close(context)
+    }
+}
@@ -0,0 +1,696 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! Connections are established over UDP with [`quinn`] and are multiplexed natively, so the
+//! [`Output`] of this transport is already a [`StreamMultiplexer`].
+//!
+//! All the connections of a transport, inbound and outbound, share a single endpoint (i.e. UDP
+//! socket) per address family: the endpoint bound by [`listen_on`] is reused to dial, and dialing
+//! before listening binds an endpoint on an ephemeral port that later dials reuse.
+//!
+//! Peers authenticate mutually with their network keys: the certificate each side presents is
+//! issued for its Ed25519 signing key, which signs the TLS handshake, and carries its X25519
+//! static key in its subject alternative names. Both the dialer and the listener hand the other
+//! side's keys to the [`PeerKeyVerifier`] the transport is configured with, e.g. to check them
+//! against the trusted peers, and abort the handshake if they are rejected. The accepted keys
+//! are then available from [`QuicConnection::peer_keys`], to identify the peer.
+//!
+//! [`quinn`]: https://github.com/djc/quinn
+//! [`Output`]: crate::transport::Transport::Output
+//! [`StreamMultiplexer`]: crate::multiplexing::StreamMultiplexer
+//! [`listen_on`]: crate::transport::Transport::listen_on
+//! [`QuicConnection::peer_keys`]: crate::multiplexing::quic::QuicConnection::peer_keys
+use crate::{
+    multiplexing::quic::{PeerKeys, QuicConnection},
+    transport::Transport,
+};
+use futures::{compat::Compat01As03, future::Future, ready, stream::Stream};
+use futures_01::Future as Future01;
+use parity_multiaddr::{Multiaddr, Protocol};
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::runtime::TaskExecutor;
+
+/// Server name the listener's certificate is issued for. It is not verified by the dialer, which
+/// authenticates the listener by its keys instead.
+const SERVER_NAME: &str = "libra";
+
+/// Suffix of the subject alternative name carrying the X25519 static key of the certificate
+/// owner. The hex encoded key is split in two labels, as a label is at most 63 characters long.
+const STATIC_KEY_NAME_SUFFIX: &str = ".static-key.libra";
+
+/// DER encoding of the contents of an Ed25519 SubjectPublicKeyInfo, up to the key itself.
+const ED25519_SPKI_PREFIX: &[u8] = &[0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+/// DER encoding of the subjectAltName extension's object identifier (2.5.29.17).
+const SUBJECT_ALT_NAME_OID: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// Decides whether a peer presenting the given Ed25519 signing key and X25519 static key (in
+/// this order, both raw) is allowed to connect.
+pub type PeerKeyVerifier = Arc<dyn Fn(&[u8], &[u8]) -> bool + Send + Sync>;
+
+/// Transport to build QUIC connections
+#[derive(Clone)]
+pub struct QuicTransport {
+    /// Executor the endpoint and connection drivers are spawned on.
+    executor: TaskExecutor,
+    server_config: quinn::ServerConfig,
+    client_config: quinn::ClientConfig,
+    endpoints: Arc<Mutex<Endpoints>>,
+}
+
+/// The endpoints shared by all the connections of a transport, at most one per address family.
+#[derive(Default)]
+struct Endpoints {
+    v4: Option<quinn::Endpoint>,
+    v6: Option<quinn::Endpoint>,
+}
+
+impl Endpoints {
+    fn for_addr(&mut self, addr: &SocketAddr) -> &mut Option<quinn::Endpoint> {
+        match addr {
+            SocketAddr::V4(_) => &mut self.v4,
+            SocketAddr::V6(_) => &mut self.v6,
+        }
+    }
+}
+
+impl QuicTransport {
+    /// Creates a transport authenticating as the owner of `signing_key_pkcs8`, a PKCS#8 encoded
+    /// Ed25519 key pair, and `static_public_key`. Peers, both listeners and dialers, are accepted
+    /// by `verify_peer`.
+    pub fn new(
+        executor: TaskExecutor,
+        signing_key_pkcs8: &[u8],
+        static_public_key: &[u8],
+        verify_peer: PeerKeyVerifier,
+    ) -> io::Result<Self> {
+        let mut params = rcgen::CertificateParams::new(vec![
+            SERVER_NAME.to_string(),
+            static_key_to_name(static_public_key),
+        ]);
+        params.alg = &rcgen::PKCS_ED25519;
+        params.key_pair = Some(rcgen::KeyPair::from_der(signing_key_pkcs8).map_err(invalid_data)?);
+        let cert = rcgen::Certificate::from_params(params).map_err(invalid_data)?;
+        let cert_der = cert.serialize_der().map_err(invalid_data)?;
+        let cert_chain =
+            quinn::CertificateChain::from_certs(vec![
+                quinn::Certificate::from_der(&cert_der).map_err(invalid_data)?
+            ]);
+        let private_key = quinn::PrivateKey::from_der(signing_key_pkcs8).map_err(invalid_data)?;
+        let verification = Arc::new(PeerKeyVerification(verify_peer));
+
+        let mut server_config = quinn::ServerConfigBuilder::default();
+        server_config
+            .certificate(cert_chain, private_key)
+            .map_err(invalid_data)?;
+        let mut server_config = server_config.build();
+        // Dialers must present a certificate too, checked the same way as the listeners'.
+        Arc::get_mut(&mut server_config.crypto)
+            .expect("Server crypto config is not shared yet")
+            .set_client_certificate_verifier(verification.clone());
+
+        let mut client_config = quinn::ClientConfigBuilder::default().build();
+        let client_crypto = Arc::get_mut(&mut client_config.crypto)
+            .expect("Client crypto config is not shared yet");
+        client_crypto
+            .dangerous()
+            .set_certificate_verifier(verification);
+        client_crypto.set_single_client_cert(
+            vec![rustls::Certificate(cert_der)],
+            rustls::PrivateKey(signing_key_pkcs8.to_vec()),
+        );
+
+        Ok(Self {
+            executor,
+            server_config,
+            client_config,
+            endpoints: Arc::new(Mutex::new(Endpoints::default())),
+        })
+    }
+
+    /// Binds a new endpoint to `addr`, which accepts connections if `listen` is set, and spawns
+    /// its driver.
+    fn bind(
+        &self,
+        addr: &SocketAddr,
+        listen: bool,
+    ) -> io::Result<(quinn::Endpoint, quinn::Incoming)> {
+        let mut builder = quinn::Endpoint::builder();
+        builder.default_client_config(self.client_config.clone());
+        if listen {
+            builder.listen(self.server_config.clone());
+        }
+        let (driver, endpoint, incoming) = builder
+            .bind(addr)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.executor.spawn(driver.map_err(|_| ()));
+        Ok((endpoint, incoming))
+    }
+}
+
+impl fmt::Debug for QuicTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicTransport").finish()
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicConnection;
+    type Error = ::std::io::Error;
+    type Listener = QuicListenerStream;
+    type Inbound = QuicConnecting;
+    type Outbound = QuicConnecting;
+
+    fn listen_on(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let (endpoint, incoming) = self.bind(&socket_addr, true)?;
+        let local_addr = socketaddr_to_multiaddr(endpoint.local_addr()?);
+        // Dial from the listening endpoint from now on, so that peers see a single address.
+        *self.endpoints.lock().unwrap().for_addr(&socket_addr) = Some(endpoint.clone());
+        Ok((
+            QuicListenerStream {
+                inner: Compat01As03::new(incoming),
+                endpoint,
+                executor: self.executor.clone(),
+            },
+            local_addr,
+        ))
+    }
+
+    fn dial(&self, addr: Multiaddr) -> Result<Self::Outbound, Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let endpoint = {
+            let mut endpoints = self.endpoints.lock().unwrap();
+            let slot = endpoints.for_addr(&socket_addr);
+            if slot.is_none() {
+                let bind_addr = match socket_addr {
+                    SocketAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+                    SocketAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+                };
+                let (endpoint, _incoming) = self.bind(&bind_addr, false)?;
+                *slot = Some(endpoint);
+            }
+            slot.as_ref().expect("Endpoint was just bound").clone()
+        };
+        let connecting = endpoint
+            .connect(&socket_addr, SERVER_NAME)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        Ok(QuicConnecting::new(
+            connecting,
+            endpoint,
+            self.executor.clone(),
+        ))
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicListenerStream {
+    inner: Compat01As03<quinn::Incoming>,
+    endpoint: quinn::Endpoint,
+    executor: TaskExecutor,
+}
+
+impl fmt::Debug for QuicListenerStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicListenerStream").finish()
+    }
+}
+
+impl Stream for QuicListenerStream {
+    type Item = io::Result<(QuicConnecting, Multiaddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.inner).poll_next(context) {
+            Poll::Ready(Some(Ok(connecting))) => {
+                let dialer_addr = socketaddr_to_multiaddr(connecting.remote_address());
+                Poll::Ready(Some(Ok((
+                    QuicConnecting::new(connecting, self.endpoint.clone(), self.executor.clone()),
+                    dialer_addr,
+                ))))
+            }
+            // `quinn::Incoming` never fails, it ends once the endpoint is closed.
+            Poll::Ready(Some(Err(()))) | Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A pending QUIC connection, either inbound or outbound.
+#[must_use = "futures do nothing unless polled"]
+pub struct QuicConnecting {
+    inner: Compat01As03<quinn::Connecting>,
+    endpoint: quinn::Endpoint,
+    executor: TaskExecutor,
+}
+
+impl QuicConnecting {
+    fn new(
+        connecting: quinn::Connecting,
+        endpoint: quinn::Endpoint,
+        executor: TaskExecutor,
+    ) -> Self {
+        Self {
+            inner: Compat01As03::new(connecting),
+            endpoint,
+            executor,
+        }
+    }
+}
+
+impl fmt::Debug for QuicConnecting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicConnecting").finish()
+    }
+}
+
+impl Future for QuicConnecting {
+    type Output = io::Result<QuicConnection>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let (driver, connection, streams) = ready!(Pin::new(&mut self.inner).poll(context))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.executor.spawn(driver.map_err(|_| ()));
+        // The handshake only completes once the peer's certificate has been verified.
+        let peer_keys = connection
+            .peer_der_certificates()
+            .and_then(|certs| certs.into_iter().next())
+            .and_then(|cert| {
+                cert_keys(cert.as_der()).map(|(signing_public_key, static_public_key)| PeerKeys {
+                    signing_public_key: signing_public_key.to_vec(),
+                    static_public_key,
+                })
+            })
+            .ok_or_else(|| invalid_data("Peer did not authenticate with its network keys"))?;
+        Poll::Ready(Ok(QuicConnection::new(
+            self.endpoint.clone(),
+            connection,
+            streams,
+            peer_keys,
+        )))
+    }
+}
+
+/// Accepts the peers, listeners and dialers, whose certificate keys are accepted by the
+/// transport's `PeerKeyVerifier`, see the module documentation. The handshake itself proves the
+/// peer owns the signing key its certificate is issued for.
+struct PeerKeyVerification(PeerKeyVerifier);
+
+impl PeerKeyVerification {
+    fn verify(&self, presented_certs: &[rustls::Certificate]) -> Result<(), rustls::TLSError> {
+        let cert = presented_certs
+            .first()
+            .ok_or(rustls::TLSError::NoCertificatesPresented)?;
+        let (signing_public_key, static_public_key) =
+            cert_keys(&cert.0).ok_or(rustls::TLSError::WebPKIError(webpki::Error::BadDER))?;
+        if (self.0)(signing_public_key, &static_public_key) {
+            Ok(())
+        } else {
+            Err(rustls::TLSError::General("Not a trusted peer".to_string()))
+        }
+    }
+}
+
+impl rustls::ServerCertVerifier for PeerKeyVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        self.verify(presented_certs)
+            .map(|()| rustls::ServerCertVerified::assertion())
+    }
+}
+
+impl rustls::ClientCertVerifier for PeerKeyVerification {
+    // Client authentication is offered and mandatory by default.
+
+    fn client_auth_root_subjects(&self) -> rustls::DistinguishedNames {
+        // The certificates are self-signed, there is no certificate authority to hint at.
+        rustls::DistinguishedNames::new()
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[rustls::Certificate],
+    ) -> Result<rustls::ClientCertVerified, rustls::TLSError> {
+        self.verify(presented_certs)
+            .map(|()| rustls::ClientCertVerified::assertion())
+    }
+}
+
+fn invalid_data<E: fmt::Debug>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e))
+}
+
+fn static_key_to_name(static_public_key: &[u8]) -> String {
+    let hex: String = static_public_key
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let (first, second) = hex.split_at(hex.len() / 2);
+    format!("{}.{}{}", first, second, STATIC_KEY_NAME_SUFFIX)
+}
+
+fn name_to_static_key(name: &[u8]) -> Option<Vec<u8>> {
+    let name = std::str::from_utf8(name).ok()?;
+    if !name.ends_with(STATIC_KEY_NAME_SUFFIX) {
+        return None;
+    }
+    let hex = name[..name.len() - STATIC_KEY_NAME_SUFFIX.len()].replacen('.', "", 1);
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Splits the next DER element off `input`, returning its tag, its contents and the rest of the
+/// input.
+fn der_next(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&len_byte, mut input) = input.split_first()?;
+    let len = if len_byte < 0x80 {
+        len_byte as usize
+    } else {
+        let num_bytes = (len_byte & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 || input.len() < num_bytes {
+            return None;
+        }
+        let len = input[..num_bytes]
+            .iter()
+            .fold(0, |len, byte| (len << 8) | *byte as usize);
+        input = &input[num_bytes..];
+        len
+    };
+    if input.len() < len {
+        return None;
+    }
+    Some((tag, &input[..len], &input[len..]))
+}
+
+/// Extracts the Ed25519 key a DER encoded certificate is issued for and the X25519 static key
+/// carried in its subject alternative names.
+fn cert_keys(cert: &[u8]) -> Option<(&[u8], Vec<u8>)> {
+    let (_, cert, _) = der_next(cert)?;
+    let (_, tbs_cert, _) = der_next(cert)?;
+    // The version is optional, the serial number follows it.
+    let (tag, _, mut rest) = der_next(tbs_cert)?;
+    if tag == 0xa0 {
+        rest = der_next(rest)?.2;
+    }
+    // Skip the signature algorithm, issuer, validity and subject.
+    for _ in 0..4 {
+        rest = der_next(rest)?.2;
+    }
+    let (_, spki, mut rest) = der_next(rest)?;
+    if spki.len() != ED25519_SPKI_PREFIX.len() + 32 || !spki.starts_with(ED25519_SPKI_PREFIX) {
+        return None;
+    }
+    let signing_public_key = &spki[ED25519_SPKI_PREFIX.len()..];
+
+    // Look for the subject alternative names in the extensions, i.e. the [3] element.
+    while !rest.is_empty() {
+        let (tag, contents, next) = der_next(rest)?;
+        rest = next;
+        if tag != 0xa3 {
+            continue;
+        }
+        let (_, mut extensions, _) = der_next(contents)?;
+        while !extensions.is_empty() {
+            let (_, extension, next) = der_next(extensions)?;
+            extensions = next;
+            let (_, oid, mut extension) = der_next(extension)?;
+            if oid != SUBJECT_ALT_NAME_OID {
+                continue;
+            }
+            // Skip the optional critical flag, the names are the last element.
+            let mut value = None;
+            while !extension.is_empty() {
+                let (_, contents, next) = der_next(extension)?;
+                value = Some(contents);
+                extension = next;
+            }
+            let (_, mut names, _) = der_next(value?)?;
+            while !names.is_empty() {
+                let (tag, name, next) = der_next(names)?;
+                names = next;
+                // dNSName
+                if tag == 0x82 {
+                    if let Some(static_public_key) = name_to_static_key(name) {
+                        return Some((signing_public_key, static_public_key));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn socketaddr_to_multiaddr(socketaddr: SocketAddr) -> Multiaddr {
+    let ipaddr: Multiaddr = socketaddr.ip().into();
+    ipaddr
+        .with(Protocol::Udp(socketaddr.port()))
+        .with(Protocol::Quic)
+}
+
+fn multiaddr_to_socketaddr(addr: &Multiaddr) -> ::std::io::Result<SocketAddr> {
+    let invalid_multiaddr = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid Multiaddr '{:?}'", addr),
+        )
+    };
+    let mut iter = addr.iter();
+    let proto1 = iter.next().ok_or_else(invalid_multiaddr)?;
+    let proto2 = iter.next().ok_or_else(invalid_multiaddr)?;
+    let proto3 = iter.next().ok_or_else(invalid_multiaddr)?;
+
+    if iter.next().is_some() {
+        return Err(invalid_multiaddr());
+    }
+
+    match (proto1, proto2, proto3) {
+        (Protocol::Ip4(ip), Protocol::Udp(port), Protocol::Quic) => {
+            Ok(SocketAddr::new(ip.into(), port))
+        }
+        (Protocol::Ip6(ip), Protocol::Udp(port), Protocol::Quic) => {
+            Ok(SocketAddr::new(ip.into(), port))
+        }
+        _ => Err(invalid_multiaddr()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        multiplexing::{quic::PeerKeys, StreamMultiplexer},
+        transport::{
+            quic::{cert_keys, PeerKeyVerifier, QuicTransport},
+            ConnectionOrigin, Transport, TransportExt,
+        },
+    };
+    use futures::{
+        executor::block_on,
+        future::{join, FutureExt},
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+    };
+    use std::{io, sync::Arc};
+    use tokio::runtime::Runtime;
+
+    /// Key pairs of a test peer: the PKCS#8 encoded signing key pair, the raw signing public key
+    /// and the static public key.
+    fn peer_keys(static_public_key: u8) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let key_pair = rcgen::KeyPair::generate(&rcgen::PKCS_ED25519).unwrap();
+        (
+            key_pair.serialize_der(),
+            key_pair.public_key_raw().to_vec(),
+            vec![static_public_key; 32],
+        )
+    }
+
+    fn trust(signing_public_key: Vec<u8>, static_public_key: Vec<u8>) -> PeerKeyVerifier {
+        Arc::new(move |signing: &[u8], static_key: &[u8]| {
+            signing == &signing_public_key[..] && static_key == &static_public_key[..]
+        })
+    }
+
+    #[test]
+    fn listen_and_dial_concurrent_substreams() -> Result<(), ::std::io::Error> {
+        let runtime = Runtime::new().unwrap();
+        let (signing_key_pkcs8, signing_public_key, static_public_key) = peer_keys(1);
+        // Connect the transport to itself, so it only trusts its own keys.
+        let verify_peer = trust(signing_public_key.clone(), static_public_key.clone());
+        let own_keys = PeerKeys {
+            signing_public_key,
+            static_public_key: static_public_key.clone(),
+        };
+        let t = QuicTransport::new(
+            runtime.executor(),
+            &signing_key_pkcs8,
+            &static_public_key,
+            verify_peer,
+        )?
+        .and_then(move |muxer, origin| {
+            async move {
+                assert_eq!(muxer.peer_keys(), &own_keys);
+                match origin {
+                    ConnectionOrigin::Inbound => {
+                        let mut listener = muxer.listen_for_inbound();
+                        // Open a substream for each message before answering any of them.
+                        let mut substreams = Vec::new();
+                        for _ in 0..2 {
+                            let substream = listener.next().await.ok_or_else(|| {
+                                io::Error::new(io::ErrorKind::Other, "no substream")
+                            })??;
+                            substreams.push(substream);
+                        }
+                        for mut substream in substreams {
+                            let mut buf = [0; 5];
+                            substream.read_exact(&mut buf).await?;
+                            substream.write_all(&buf).await?;
+                            substream.close().await?;
+                        }
+                    }
+                    ConnectionOrigin::Outbound => {
+                        let mut earth = muxer.open_outbound().await?;
+                        let mut water = muxer.open_outbound().await?;
+                        earth.write_all(b"Earth").await?;
+                        water.write_all(b"Water").await?;
+                        let mut buf = Vec::new();
+                        earth.read_to_end(&mut buf).await?;
+                        assert_eq!(buf, b"Earth");
+                        buf.clear();
+                        water.read_to_end(&mut buf).await?;
+                        assert_eq!(buf, b"Water");
+                    }
+                }
+                Ok(muxer)
+            }
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+
+        let dial = t.dial(addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = block_on(join(dial, listener));
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn untrusted_listener() -> Result<(), ::std::io::Error> {
+        let runtime = Runtime::new().unwrap();
+        let (listener_pkcs8, listener_signing, listener_static) = peer_keys(1);
+        let (dialer_pkcs8, _, dialer_static) = peer_keys(2);
+        let listener = QuicTransport::new(
+            runtime.executor(),
+            &listener_pkcs8,
+            &listener_static,
+            trust(vec![], vec![]),
+        )?;
+        let (mut incoming, addr) =
+            listener.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+
+        // The listener's signing key is trusted, but with a different static key.
+        let dialer = QuicTransport::new(
+            runtime.executor(),
+            &dialer_pkcs8,
+            &dialer_static,
+            trust(listener_signing, dialer_static.clone()),
+        )?;
+        let result = block_on(join(dialer.dial(addr)?, async move {
+            let _ = incoming.next().await;
+        }))
+        .0;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn untrusted_dialer() -> Result<(), ::std::io::Error> {
+        let runtime = Runtime::new().unwrap();
+        let (listener_pkcs8, listener_signing, listener_static) = peer_keys(1);
+        let (dialer_pkcs8, _, dialer_static) = peer_keys(2);
+        // The listener only trusts itself, so it must reject the dialer.
+        let listener = QuicTransport::new(
+            runtime.executor(),
+            &listener_pkcs8,
+            &listener_static,
+            trust(listener_signing.clone(), listener_static.clone()),
+        )?;
+        let (mut incoming, addr) =
+            listener.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+
+        let dialer = QuicTransport::new(
+            runtime.executor(),
+            &dialer_pkcs8,
+            &dialer_static,
+            trust(listener_signing, listener_static),
+        )?;
+        let result = block_on(join(dialer.dial(addr)?, async move {
+            let (connecting, _addr) = incoming.next().await.unwrap().unwrap();
+            connecting.await
+        }))
+        .1;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn cert_keys_roundtrip() {
+        let (signing_key_pkcs8, signing_public_key, static_public_key) = peer_keys(7);
+        let mut params = rcgen::CertificateParams::new(vec![
+            "libra".to_string(),
+            super::static_key_to_name(&static_public_key),
+        ]);
+        params.alg = &rcgen::PKCS_ED25519;
+        params.key_pair = Some(rcgen::KeyPair::from_der(&signing_key_pkcs8).unwrap());
+        let cert = rcgen::Certificate::from_params(params)
+            .unwrap()
+            .serialize_der()
+            .unwrap();
+
+        let (signing, static_key) = cert_keys(&cert).unwrap();
+        assert_eq!(signing, &signing_public_key[..]);
+        assert_eq!(static_key, static_public_key);
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() -> Result<(), ::std::io::Error> {
+        let runtime = Runtime::new().unwrap();
+        let (signing_key_pkcs8, _, static_public_key) = peer_keys(1);
+        let t = QuicTransport::new(
+            runtime.executor(),
+            &signing_key_pkcs8,
+            &static_public_key,
+            trust(vec![], vec![]),
+        )?;
+
+        let result = t.listen_on("/memory/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.listen_on("/ip4/127.0.0.1/udp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial("/ip4/127.0.0.1/tcp/22".parse().unwrap());
+        assert!(result.is_err());
+        Ok(())
+    }
+}
@@ -17,7 +17,7 @@ use netcore::{
     transport::ConnectionOrigin,
 };
 use snow::{self, params::NoiseParams, Keypair};
-use std::io;
+use std::{io, sync::RwLock};
 
 mod socket;
 
@@ -29,8 +29,11 @@ const NOISE_IX_PARAMETER: &str = "Noise_IX_25519_AESGCM_SHA256";
 
 /// The Noise protocol configuration to be used to perform a protocol upgrade on an underlying
 /// socket.
+///
+/// The static keypair can be rotated at runtime with [`NoiseConfig::rotate_keypair`]: handshakes
+/// started afterwards use the new keypair, while already established sessions are unaffected.
 pub struct NoiseConfig {
-    keypair: Keypair,
+    keypair: RwLock<Keypair>,
     parameters: NoiseParams,
 }
 
@@ -38,12 +41,8 @@ impl NoiseConfig {
     /// Create a new NoiseConfig with the provided keypair
     pub fn new(keypair: (X25519StaticPrivateKey, X25519StaticPublicKey)) -> Self {
         let parameters: NoiseParams = NOISE_IX_PARAMETER.parse().expect("Invalid protocol name");
-        let keypair = Keypair {
-            private: keypair.0.to_bytes().to_vec(),
-            public: keypair.1.to_bytes().to_vec(),
-        };
         Self {
-            keypair,
+            keypair: RwLock::new(to_noise_keypair(keypair)),
             parameters,
         }
     }
@@ -55,11 +54,21 @@ impl NoiseConfig {
             .generate_keypair()
             .expect("Noise failed to generate a random static keypair");
         Self {
-            keypair,
+            keypair: RwLock::new(keypair),
             parameters,
         }
     }
 
+    /// Replace the static keypair used for subsequent handshakes.
+    pub fn rotate_keypair(&self, keypair: (X25519StaticPrivateKey, X25519StaticPublicKey)) {
+        *self.keypair.write().unwrap() = to_noise_keypair(keypair);
+    }
+
+    /// The static public key currently presented to remote peers.
+    pub fn public_key(&self) -> Vec<u8> {
+        self.keypair.read().unwrap().public.clone()
+    }
+
     /// Perform a protocol upgrade on an underlying connection. In addition perform the noise IX
     /// handshake to establish a noise session and exchange static public keys. Upon success,
     /// returns the static public key of the remote as well as a NoiseSocket.
@@ -88,8 +97,9 @@ impl NoiseConfig {
         // Note: We need to scope the Builder struct so that the compiler doesn't over eagerly
         // capture it into the Async State-machine.
         let session = {
-            let builder = snow::Builder::new(self.parameters.clone())
-                .local_private_key(&self.keypair.private);
+            let private_key = self.keypair.read().unwrap().private.clone();
+            let builder =
+                snow::Builder::new(self.parameters.clone()).local_private_key(&private_key);
             match origin {
                 ConnectionOrigin::Inbound => builder.build_responder(),
                 ConnectionOrigin::Outbound => builder.build_initiator(),
@@ -107,3 +117,46 @@ impl NoiseConfig {
         Ok((remote_static_key, socket))
     }
 }
+
+fn to_noise_keypair(keypair: (X25519StaticPrivateKey, X25519StaticPublicKey)) -> Keypair {
+    Keypair {
+        private: keypair.0.to_bytes().to_vec(),
+        public: keypair.1.to_bytes().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crypto::{test_utils::TEST_SEED, x25519};
+    use futures::{executor::block_on, future::join};
+    use memsocket::MemorySocket;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn handshake(dialer: &NoiseConfig, listener: &NoiseConfig) -> (Vec<u8>, Vec<u8>) {
+        let (dialer_socket, listener_socket) = MemorySocket::new_pair();
+        let (dialer_result, listener_result) = block_on(join(
+            dialer.upgrade_connection(dialer_socket, ConnectionOrigin::Outbound),
+            listener.upgrade_connection(listener_socket, ConnectionOrigin::Inbound),
+        ));
+        (dialer_result.unwrap().0, listener_result.unwrap().0)
+    }
+
+    #[test]
+    fn rotated_keypair_is_used_for_new_handshakes() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let dialer = NoiseConfig::new(x25519::compat::generate_keypair(&mut rng));
+        let listener = NoiseConfig::new(x25519::compat::generate_keypair(&mut rng));
+
+        let (_, remote_key) = handshake(&dialer, &listener);
+        assert_eq!(remote_key, dialer.public_key());
+
+        let new_keypair = x25519::compat::generate_keypair(&mut rng);
+        let new_public_key = new_keypair.1.clone();
+        dialer.rotate_keypair(new_keypair);
+        assert_eq!(dialer.public_key(), new_public_key.to_bytes().to_vec());
+
+        let (_, remote_key) = handshake(&dialer, &listener);
+        assert_eq!(remote_key, new_public_key.to_bytes().to_vec());
+    }
+}
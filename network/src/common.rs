@@ -2,8 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::ProtocolId;
-use crypto::{ed25519::*, x25519::X25519StaticPublicKey};
-use std::fmt;
+use crypto::{ed25519::*, x25519::X25519StaticPublicKey, ValidKey};
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+use types::{validator_public_keys::ValidatorPublicKeys, PeerId};
 
 /// A Negotiated substream encapsulates a protocol and a substream for which that protocol has been
 /// negotiated.
@@ -32,3 +37,113 @@ pub struct NetworkPublicKeys {
     /// This key establishes a node's identity in the p2p network.
     pub identity_public_key: X25519StaticPublicKey,
 }
+
+impl From<&ValidatorPublicKeys> for NetworkPublicKeys {
+    fn from(keys: &ValidatorPublicKeys) -> Self {
+        Self {
+            signing_public_key: keys.network_signing_public_key().clone(),
+            identity_public_key: keys.network_identity_public_key().clone(),
+        }
+    }
+}
+
+/// Identity keys that peers have rotated to, as advertised through discovery. For a peer with an
+/// entry, the rotated key supersedes the identity key from the trusted peer set. The replaced key
+/// keeps being accepted until the end of an overlap window, so that the peer can still connect
+/// with it while its new key propagates through the network.
+/// An entry only lasts until the trusted peer set changes the key of the peer: the trusted set
+/// then either caught up with the rotation or superseded it.
+#[derive(Debug, Default)]
+pub struct IdentityKeyRotations {
+    rotations: HashMap<PeerId, IdentityKeyRotation>,
+}
+
+#[derive(Debug)]
+struct IdentityKeyRotation {
+    current: X25519StaticPublicKey,
+    previous: Option<(X25519StaticPublicKey, Instant)>,
+    // The key of the peer in the trusted peer set when it rotated.
+    trusted_key: X25519StaticPublicKey,
+}
+
+impl IdentityKeyRotations {
+    /// Records that `peer_id`, whose key in the trusted peer set is `trusted_key`, now uses
+    /// `new_key`, accepting `old_key` until `overlap` elapses.
+    pub fn rotate(
+        &mut self,
+        peer_id: PeerId,
+        trusted_key: X25519StaticPublicKey,
+        old_key: X25519StaticPublicKey,
+        new_key: X25519StaticPublicKey,
+        overlap: Duration,
+    ) {
+        if new_key == trusted_key {
+            // The peer is back to its trusted key.
+            self.rotations.remove(&peer_id);
+            return;
+        }
+        let previous = Some((old_key, Instant::now() + overlap));
+        self.rotations.insert(
+            peer_id,
+            IdentityKeyRotation {
+                current: new_key,
+                previous,
+                trusted_key,
+            },
+        );
+    }
+
+    /// Drops the rotations the given trusted peer set supersedes: the ones of the peers that are
+    /// no longer trusted, or whose trusted key changed since they rotated (e.g. the on-chain
+    /// validator set caught up with the rotation in a new epoch).
+    pub fn update_trusted_peers(&mut self, trusted_peers: &HashMap<PeerId, NetworkPublicKeys>) {
+        self.rotations.retain(|peer_id, rotation| {
+            trusted_peers.get(peer_id).map_or(false, |keys| {
+                keys.identity_public_key == rotation.trusted_key
+            })
+        });
+    }
+
+    /// Returns the identity key `peer_id` has rotated to, if any.
+    pub fn current_key(&self, peer_id: &PeerId) -> Option<&X25519StaticPublicKey> {
+        self.rotations
+            .get(peer_id)
+            .map(|rotation| &rotation.current)
+    }
+
+    /// Returns the identity key `peer_id` currently uses, given its key in the trusted peer set.
+    pub fn effective_key<'a>(
+        &'a self,
+        peer_id: &PeerId,
+        trusted_key: &'a X25519StaticPublicKey,
+    ) -> &'a X25519StaticPublicKey {
+        match self.rotations.get(peer_id) {
+            Some(rotation) if rotation.trusted_key == *trusted_key => &rotation.current,
+            _ => trusted_key,
+        }
+    }
+
+    /// Returns whether `key` is a valid identity key for `peer_id`, given the identity key of
+    /// `peer_id` in the trusted peer set.
+    pub fn is_valid_key(
+        &self,
+        peer_id: &PeerId,
+        trusted_key: &X25519StaticPublicKey,
+        key: &[u8],
+    ) -> bool {
+        match self.rotations.get(peer_id) {
+            // No rotation since the trusted key was set.
+            Some(rotation) if rotation.trusted_key != *trusted_key => trusted_key.to_bytes() == key,
+            None => trusted_key.to_bytes() == key,
+            Some(rotation) => {
+                rotation.current.to_bytes() == key
+                    || rotation
+                        .previous
+                        .as_ref()
+                        .map_or(false, |(previous_key, deadline)| {
+                            Instant::now() < *deadline && previous_key.to_bytes() == key
+                        })
+            }
+        }
+    }
+}
@@ -10,7 +10,9 @@
 //! eligible nodes, and the Discovery actor infroms it about updates to addresses of eligible
 //! nodes.
 use crate::{
-    common::NetworkPublicKeys,
+    common::{IdentityKeyRotations, NetworkPublicKeys},
+    counters,
+    network_state::{DialState, NetworkState},
     peer_manager::{PeerManagerError, PeerManagerNotification, PeerManagerRequestSender},
 };
 use channel;
@@ -39,6 +41,9 @@ mod test;
 pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
     /// Nodes which are eligible to join the network.
     eligible: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    /// Identity key rotations learnt through discovery, superseded by the updates of the eligible
+    /// nodes.
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
     /// PeerId and address of remote peers to which this peer is connected.
     connected: HashMap<PeerId, Multiaddr>,
     /// Addresses of peers received from Discovery module.
@@ -63,6 +68,8 @@ pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
     /// A local counter incremented on receiving an incoming message. Printing this in debugging
     /// allows for easy debugging.
     event_id: u32,
+    /// Shared state of the network stack, in which we track the dial backoff state of peers.
+    network_state: Arc<RwLock<NetworkState>>,
 }
 
 /// Requests received by the [`ConnectivityManager`] manager actor from upstream modules.
@@ -92,15 +99,18 @@ where
     /// Creates a new instance of the [`ConnectivityManager`] actor.
     pub fn new(
         eligible: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+        key_rotations: Arc<RwLock<IdentityKeyRotations>>,
         ticker: TTicker,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         requests_rx: channel::Receiver<ConnectivityRequest>,
         backoff_strategy: TBackoff,
         max_delay_ms: u64,
+        network_state: Arc<RwLock<NetworkState>>,
     ) -> Self {
         Self {
             eligible,
+            key_rotations,
             connected: HashMap::new(),
             peer_addresses: HashMap::new(),
             ticker,
@@ -112,6 +122,7 @@ where
             event_id: 0,
             backoff_strategy,
             max_delay_ms,
+            network_state,
         }
     }
 
@@ -142,6 +153,7 @@ where
                 peer_id = pending_dials.select_next_some() => {
                     trace!("Event Id: {}, type: Dial complete, peer: {}", self.event_id, peer_id.short_str());
                     self.dial_queue.remove(&peer_id);
+                    self.dial_completed(peer_id);
                 },
                 complete => {
                     crit!("Connectivity manager actor terminated");
@@ -180,8 +192,10 @@ where
             .filter(|peer_id| !eligible.contains_key(peer_id))
             .cloned()
             .collect();
+        let mut network_state = self.network_state.write().unwrap();
         for p in stale_dials.into_iter() {
             self.dial_queue.remove(&p);
+            network_state.dials.remove(&p);
         }
     }
 
@@ -223,17 +237,16 @@ where
             let addr = addrs[0].clone();
             let (cancel_tx, cancel_rx) = oneshot::channel();
             let now = Instant::now();
-            let delay = timer::Delay::new(
-                now.checked_add(min(
-                    max_delay,
-                    self.dial_backoffs
-                        .entry(peer_id)
-                        .or_insert_with(|| default_backoff_strategy.clone())
-                        .next()
-                        .unwrap_or(max_delay),
-                ))
-                .unwrap_or_else(Instant::now),
+            let backoff = min(
+                max_delay,
+                self.dial_backoffs
+                    .entry(peer_id)
+                    .or_insert_with(|| default_backoff_strategy.clone())
+                    .next()
+                    .unwrap_or(max_delay),
             );
+            let delay = timer::Delay::new(now.checked_add(backoff).unwrap_or_else(Instant::now));
+            self.record_dial(peer_id, addr.clone(), backoff, delay.deadline());
             // Create future which completes by either dialing after calculated delay or on
             // cancellation.
             let f = async move {
@@ -265,6 +278,37 @@ where
         }
     }
 
+    // Records a dial queued to fire at `next_dial` in the shared network state.
+    fn record_dial(
+        &self,
+        peer_id: PeerId,
+        address: Multiaddr,
+        backoff: Duration,
+        next_dial: Instant,
+    ) {
+        let mut network_state = self.network_state.write().unwrap();
+        let attempts = network_state
+            .dials
+            .get(&peer_id)
+            .map_or(0, |dial_state| dial_state.attempts);
+        network_state.dials.insert(
+            peer_id,
+            DialState {
+                address,
+                attempts: attempts + 1,
+                backoff,
+                next_dial: Some(next_dial),
+            },
+        );
+    }
+
+    // Records in the shared network state that no dial is queued for `peer_id` anymore.
+    fn dial_completed(&self, peer_id: PeerId) {
+        if let Some(dial_state) = self.network_state.write().unwrap().dials.get_mut(&peer_id) {
+            dial_state.next_dial = None;
+        }
+    }
+
     // Note: We do not check that the connections to older incarnations of a node are broken, and
     // instead rely on the node moving to a new epoch to break connections made from older
     // incarnations.
@@ -287,7 +331,7 @@ where
                 self.peer_addresses.insert(peer_id, addrs);
             }
             ConnectivityRequest::UpdateEligibleNodes(nodes) => {
-                *self.eligible.write().unwrap() = nodes;
+                self.update_eligible_nodes(nodes);
             }
             ConnectivityRequest::GetDialQueueSize(sender) => {
                 sender.send(self.dial_queue.len()).unwrap();
@@ -295,6 +339,38 @@ where
         }
     }
 
+    // Replaces the set of eligible nodes. Since the set is shared with the transport and the
+    // Discovery actor, newly eligible peers can connect to us and have their discovery notes
+    // accepted immediately. The identity key rotations the new set catches up with or supersedes
+    // are dropped. Queued dials to peers that are no longer eligible are cancelled right
+    // away, while existing connections to them are closed on the next connectivity check.
+    // The key rotations are locked before the eligible set, in the same order as the transport
+    // does when it authenticates peers, so that both are updated at once without deadlocking.
+    fn update_eligible_nodes(&mut self, nodes: HashMap<PeerId, NetworkPublicKeys>) {
+        let mut key_rotations = self.key_rotations.write().unwrap();
+        let mut eligible = self.eligible.write().unwrap();
+        for peer_id in nodes.keys().filter(|p| !eligible.contains_key(p)) {
+            info!("Peer {} is now eligible", peer_id.short_str());
+        }
+        let removed: Vec<_> = eligible
+            .keys()
+            .filter(|peer_id| !nodes.contains_key(peer_id))
+            .cloned()
+            .collect();
+        let mut network_state = self.network_state.write().unwrap();
+        for peer_id in removed.iter() {
+            info!("Peer {} is no longer eligible", peer_id.short_str());
+            // Dropping the sender cancels the pending dial.
+            self.dial_queue.remove(peer_id);
+            self.dial_backoffs.remove(peer_id);
+            network_state.dials.remove(peer_id);
+        }
+        counters::ELIGIBLE_PEERS.set(nodes.len() as i64);
+        counters::ELIGIBLE_PEERS_UPDATES.inc();
+        key_rotations.update_trusted_peers(&nodes);
+        *eligible = nodes;
+    }
+
     fn handle_peer_mgr_notification(&mut self, notif: PeerManagerNotification<TSubstream>) {
         match notif {
             PeerManagerNotification::NewPeer(peer_id, addr) => {
@@ -303,6 +379,7 @@ where
                 self.dial_backoffs.remove(&peer_id);
                 // Cancel possible queued dial to this peer.
                 self.dial_queue.remove(&peer_id);
+                self.network_state.write().unwrap().dials.remove(&peer_id);
             }
             PeerManagerNotification::LostPeer(peer_id, addr) => {
                 match self.connected.get(&peer_id) {
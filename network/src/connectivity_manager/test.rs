@@ -20,6 +20,23 @@ fn setup_conn_mgr(
     channel::Sender<PeerManagerNotification<MemorySocket>>,
     channel::Sender<ConnectivityRequest>,
     channel::Sender<()>,
+) {
+    setup_conn_mgr_with_state(
+        rt,
+        seed_peer_id,
+        Arc::new(RwLock::new(NetworkState::default())),
+    )
+}
+
+fn setup_conn_mgr_with_state(
+    rt: &mut Runtime,
+    seed_peer_id: PeerId,
+    network_state: Arc<RwLock<NetworkState>>,
+) -> (
+    channel::Receiver<PeerManagerRequest<MemorySocket>>,
+    channel::Sender<PeerManagerNotification<MemorySocket>>,
+    channel::Sender<ConnectivityRequest>,
+    channel::Sender<()>,
 ) {
     let (peer_mgr_reqs_tx, peer_mgr_reqs_rx): (
         channel::Sender<PeerManagerRequest<MemorySocket>>,
@@ -44,12 +61,14 @@ fn setup_conn_mgr(
                 .into_iter()
                 .collect(),
             )),
+            Arc::new(RwLock::new(IdentityKeyRotations::default())),
             ticker_rx,
             PeerManagerRequestSender::new(peer_mgr_reqs_tx),
             peer_mgr_notifs_rx,
             conn_mgr_reqs_rx,
             FixedInterval::from_millis(100),
             300, /* ms */
+            network_state,
         )
     };
     rt.spawn(conn_mgr.start().boxed().unit_error().compat());
@@ -485,6 +504,60 @@ fn retry_on_failure() {
     rt.block_on(events_f.boxed().unit_error().compat()).unwrap();
 }
 
+// Tests that connectivity manager reports the dial state of peers it fails to connect to.
+#[test]
+fn dial_state() {
+    ::logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    let seed_peer_id = PeerId::random();
+    info!("Seed peer_id is {}", seed_peer_id.short_str());
+    let network_state = Arc::new(RwLock::new(NetworkState::default()));
+    let (mut peer_mgr_reqs_rx, mut peer_mgr_notifs_tx, mut conn_mgr_reqs_tx, mut ticker_tx) =
+        setup_conn_mgr_with_state(&mut rt, seed_peer_id, network_state.clone());
+
+    let events_f = async move {
+        let seed_address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+
+        // Send address of seed peer.
+        info!("Sending address of seed peer");
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(
+                seed_peer_id,
+                vec![seed_address.clone()],
+            ))
+            .await
+            .unwrap();
+
+        for attempts in 1..=2 {
+            // Trigger connectivity check.
+            info!("Sending tick to trigger connectivity check");
+            ticker_tx.send(()).await.unwrap();
+
+            // Peer manager receives a request to connect to the seed peer, which fails.
+            info!("Waiting to receive dial request");
+            expect_dial_request(
+                &mut peer_mgr_reqs_rx,
+                &mut peer_mgr_notifs_tx,
+                &mut conn_mgr_reqs_tx,
+                seed_peer_id,
+                seed_address.clone(),
+                Err(PeerManagerError::IoError(io::Error::from(
+                    io::ErrorKind::ConnectionRefused,
+                ))),
+            )
+            .await;
+
+            // The failed dial is reported, with no dial queued.
+            let state = network_state.read().unwrap();
+            let dial_state = state.dials.get(&seed_peer_id).unwrap();
+            assert_eq!(dial_state.address, seed_address);
+            assert_eq!(dial_state.attempts, attempts);
+            assert!(dial_state.next_dial.is_none());
+        }
+    };
+    rt.block_on(events_f.boxed().unit_error().compat()).unwrap();
+}
+
 #[test]
 // Tests that if we dial an already connected peer or disconnect from an already disconnected
 // peer, connectivity manager does not send any additional dial or disconnect requests.
@@ -663,3 +736,61 @@ fn backoff_on_failure() {
     };
     rt.block_on(events_f.boxed().unit_error().compat()).unwrap();
 }
+
+// Tests that a queued dial to a peer is cancelled as soon as the peer is no longer eligible.
+#[test]
+fn cancel_dial_on_eligible_nodes_update() {
+    ::logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    let seed_peer_id = PeerId::random();
+    info!("Seed peer_id is {}", seed_peer_id.short_str());
+    let (_peer_mgr_reqs_rx, _peer_mgr_notifs_tx, mut conn_mgr_reqs_tx, mut ticker_tx) =
+        setup_conn_mgr(&mut rt, seed_peer_id);
+
+    let events_f = async move {
+        let seed_address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+
+        // Send address of seed peer.
+        info!("Sending address of seed peer");
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(
+                seed_peer_id,
+                vec![seed_address.clone()],
+            ))
+            .await
+            .unwrap();
+
+        // Trigger connectivity check.
+        info!("Sending tick to trigger connectivity check");
+        ticker_tx.send(()).await.unwrap();
+
+        // Wait for the dial to the seed peer to be queued.
+        info!("Waiting for dial to be queued");
+        loop {
+            let (queue_size_tx, queue_size_rx) = oneshot::channel();
+            conn_mgr_reqs_tx
+                .send(ConnectivityRequest::GetDialQueueSize(queue_size_tx))
+                .await
+                .unwrap();
+            if queue_size_rx.await.unwrap() == 1 {
+                break;
+            }
+        }
+
+        // Send request to make seed peer ineligible.
+        info!("Sending request to make seed peer ineligible");
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateEligibleNodes(HashMap::new()))
+            .await
+            .unwrap();
+
+        // The queued dial should be cancelled without waiting for another connectivity check.
+        let (queue_size_tx, queue_size_rx) = oneshot::channel();
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::GetDialQueueSize(queue_size_tx))
+            .await
+            .unwrap();
+        assert_eq!(queue_size_rx.await.unwrap(), 0);
+    };
+    rt.block_on(events_f.boxed().unit_error().compat()).unwrap();
+}
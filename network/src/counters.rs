@@ -12,6 +12,12 @@ lazy_static::lazy_static! {
     /// Counter of currently connected peers
     pub static ref CONNECTED_PEERS: IntGauge = OP_COUNTERS.gauge("connected_peers");
 
+    /// Counter of peers currently eligible to join the network
+    pub static ref ELIGIBLE_PEERS: IntGauge = OP_COUNTERS.gauge("eligible_peers");
+
+    /// Counter of updates to the set of eligible peers
+    pub static ref ELIGIBLE_PEERS_UPDATES: IntCounter = OP_COUNTERS.counter("eligible_peers_updates");
+
     /// Counter of rpc requests sent
     pub static ref RPC_REQUESTS_SENT: IntCounter = OP_COUNTERS.counter("rpc_requests_sent");
 
@@ -85,6 +91,9 @@ lazy_static::lazy_static! {
     /// Counter of pending requests in Connectivity Manager
     pub static ref PENDING_CONNECTIVITY_MANAGER_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_connectivity_manager_requests");
 
+    /// Counter of pending requests in Discovery
+    pub static ref PENDING_DISCOVERY_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_discovery_requests");
+
     /// Counter of pending requests in RPC
     pub static ref PENDING_RPC_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_rpc_requests");
 
@@ -18,7 +18,11 @@ use crate::{
     peer_manager::PeerManagerNotification,
     protocols::{
         direct_send::{DirectSendNotification, DirectSendRequest, Message},
-        rpc::{InboundRpcRequest, OutboundRpcRequest, RpcNotification, RpcRequest},
+        discovery::DiscoveryRequest,
+        rpc::{
+            InboundRpcRequest, InboundStreamingRpcRequest, OutboundRpcRequest,
+            OutboundStreamingRpcRequest, RpcNotification, RpcRequest,
+        },
     },
     validator_network::{
         ConsensusNetworkEvents, ConsensusNetworkSender, MempoolNetworkEvents, MempoolNetworkSender,
@@ -27,6 +31,7 @@ use crate::{
     ProtocolId,
 };
 use channel;
+use crypto::x25519::{X25519StaticPrivateKey, X25519StaticPublicKey};
 use futures::{future::BoxFuture, FutureExt, SinkExt, StreamExt};
 use logger::prelude::*;
 use std::{collections::HashMap, fmt::Debug, time::Duration};
@@ -41,10 +46,14 @@ pub const STATE_SYNCHRONIZER_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minut
 pub enum NetworkRequest {
     /// Send an RPC request to a remote peer.
     SendRpc(PeerId, OutboundRpcRequest),
+    /// Send a streaming RPC request to a remote peer.
+    SendStreamingRpc(PeerId, OutboundStreamingRpcRequest),
     /// Fire-and-forget style message send to a remote peer.
     SendMessage(PeerId, Message),
     /// Update set of nodes eligible to join the network.
     UpdateEligibleNodes(HashMap<PeerId, NetworkPublicKeys>),
+    /// Rotate the identity keypair used to authenticate this node in Noise handshakes.
+    RotateIdentityKey(X25519StaticPrivateKey, X25519StaticPublicKey),
 }
 
 /// Notifications that [`NetworkProvider`] sends to consumers of its API. The
@@ -58,6 +67,8 @@ pub enum NetworkNotification {
     LostPeer(PeerId),
     /// A new RPC request has been received from a remote peer.
     RecvRpc(PeerId, InboundRpcRequest),
+    /// A new streaming RPC request has been received from a remote peer.
+    RecvStreamingRpc(PeerId, InboundStreamingRpcRequest),
     /// A new message has been received from a remote peer.
     RecvMessage(PeerId, Message),
 }
@@ -94,6 +105,8 @@ pub struct NetworkProvider<TSubstream> {
     ds_notifs_rx: channel::Receiver<DirectSendNotification>,
     /// Channel over which we send requests to the ConnectivityManager actor.
     conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    /// Channel over which we send requests to the Discovery actor.
+    discovery_reqs_tx: channel::Sender<DiscoveryRequest>,
     /// Channel to receive requests from other actors.
     requests_rx: channel::Receiver<NetworkRequest>,
     /// Channel over which other actors send requests to network.
@@ -175,6 +188,7 @@ where
             let rpc_reqs_tx = self.rpc_reqs_tx.clone();
             let ds_reqs_tx = self.ds_reqs_tx.clone();
             let conn_mgr_reqs_tx = self.conn_mgr_reqs_tx.clone();
+            let discovery_reqs_tx = self.discovery_reqs_tx.clone();
             let mut reqs = self
                 .requests_rx
                 .map(move |req| {
@@ -183,6 +197,7 @@ where
                         rpc_reqs_tx.clone(),
                         ds_reqs_tx.clone(),
                         conn_mgr_reqs_tx.clone(),
+                        discovery_reqs_tx.clone(),
                     )
                     .boxed()
                 })
@@ -238,6 +253,7 @@ where
         ds_reqs_tx: channel::Sender<DirectSendRequest>,
         ds_notifs_rx: channel::Receiver<DirectSendNotification>,
         conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        discovery_reqs_tx: channel::Sender<DiscoveryRequest>,
         requests_rx: channel::Receiver<NetworkRequest>,
         requests_tx: channel::Sender<NetworkRequest>,
         max_concurrent_reqs: u32,
@@ -252,6 +268,7 @@ where
             ds_reqs_tx,
             ds_notifs_rx,
             conn_mgr_reqs_tx,
+            discovery_reqs_tx,
             requests_rx,
             requests_tx,
             max_concurrent_reqs,
@@ -265,6 +282,7 @@ where
         mut rpc_reqs_tx: channel::Sender<RpcRequest>,
         mut ds_reqs_tx: channel::Sender<DirectSendRequest>,
         mut conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        mut discovery_reqs_tx: channel::Sender<DiscoveryRequest>,
     ) {
         trace!("NetworkRequest::{:?}", req);
         match req {
@@ -274,6 +292,12 @@ where
                     .await
                     .unwrap();
             }
+            NetworkRequest::SendStreamingRpc(peer_id, req) => {
+                rpc_reqs_tx
+                    .send(RpcRequest::SendStreamingRpc(peer_id, req))
+                    .await
+                    .unwrap();
+            }
             NetworkRequest::SendMessage(peer_id, msg) => {
                 counters::DIRECT_SEND_MESSAGES_SENT.inc();
                 counters::DIRECT_SEND_BYTES_SENT.inc_by(msg.mdata.len() as i64);
@@ -288,6 +312,12 @@ where
                     .await
                     .unwrap();
             }
+            NetworkRequest::RotateIdentityKey(private_key, public_key) => {
+                discovery_reqs_tx
+                    .send(DiscoveryRequest::RotateIdentityKey(private_key, public_key))
+                    .await
+                    .unwrap();
+            }
         }
     }
 
@@ -334,6 +364,15 @@ where
                     unreachable!();
                 }
             }
+            RpcNotification::RecvStreamingRpc(peer_id, req) => {
+                if let Some(ch) = upstream_handlers.get_mut(&req.protocol) {
+                    ch.send(NetworkNotification::RecvStreamingRpc(peer_id, req))
+                        .await
+                        .unwrap();
+                } else {
+                    unreachable!();
+                }
+            }
         }
     }
 
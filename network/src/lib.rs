@@ -14,6 +14,7 @@ pub use common::NetworkPublicKeys;
 pub use interface::NetworkProvider;
 
 pub mod interface;
+pub mod network_state;
 pub mod proto;
 pub mod protocols;
 pub mod validator_network;
@@ -0,0 +1,79 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A snapshot of the state of the network stack, meant for debugging connectivity issues.
+//!
+//! The snapshot is shared between the network actors behind an `Arc<RwLock<_>>`. Every actor
+//! only updates the part of the state it owns:
+//!  * PeerManager tracks connected peers and counts the substreams negotiated with them.
+//!  * HealthChecker tracks the outcome of the latest pings.
+//!  * ConnectivityManager tracks the dial backoff state of peers we are not connected to.
+//!  * Discovery tracks the epochs of the latest notes it has seen for each peer.
+use crate::ProtocolId;
+use config::config::RoleType;
+use netcore::transport::ConnectionOrigin;
+use parity_multiaddr::Multiaddr;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use types::PeerId;
+
+/// State of the network stack, as reported by the network actors.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkState {
+    /// Peers we are currently connected to.
+    pub connected_peers: HashMap<PeerId, ConnectedPeerState>,
+    /// Outcome of the latest pings to connected peers.
+    pub pings: HashMap<PeerId, PingState>,
+    /// Dial backoff state of eligible peers we have been trying to connect to.
+    pub dials: HashMap<PeerId, DialState>,
+    /// Epoch of the latest discovery note seen for each peer, including self.
+    pub discovery_note_epochs: HashMap<PeerId, u64>,
+}
+
+/// State of a connection with a peer.
+#[derive(Clone, Debug)]
+pub struct ConnectedPeerState {
+    /// Address of the peer.
+    pub address: Multiaddr,
+    /// Whether the peer dialed us or we dialed the peer.
+    pub origin: ConnectionOrigin,
+    /// Role the peer advertised during the identity exchange.
+    pub role: RoleType,
+    /// Total number of substreams negotiated with the peer since the connection was established,
+    /// by protocol. The count is cumulative: substreams are handed over to the protocol actors,
+    /// which close them without reporting back, so it doesn't tell how many are still open.
+    pub substreams_opened: HashMap<ProtocolId, u64>,
+}
+
+/// Outcome of the pings sent to a peer by the HealthChecker.
+#[derive(Clone, Debug, Default)]
+pub struct PingState {
+    /// Round trip time of the latest successful ping.
+    pub last_rtt: Option<Duration>,
+    /// Number of pings that failed since the latest successful ping.
+    pub failures: u64,
+}
+
+/// Dial state of a peer we should be connected to.
+#[derive(Clone, Debug)]
+pub struct DialState {
+    /// Address the peer is dialed at.
+    pub address: Multiaddr,
+    /// Number of dials scheduled since we were last connected to the peer.
+    pub attempts: u64,
+    /// Backoff delay of the latest dial.
+    pub backoff: Duration,
+    /// Time at which the queued dial fires, if a dial is queued.
+    pub next_dial: Option<Instant>,
+}
+
+impl NetworkState {
+    /// Counts a substream negotiated for `protocol` with a connected peer.
+    pub(crate) fn record_substream(&mut self, peer_id: PeerId, protocol: &ProtocolId) {
+        if let Some(peer) = self.connected_peers.get_mut(&peer_id) {
+            *peer.substreams_opened.entry(protocol.clone()).or_insert(0) += 1;
+        }
+    }
+}
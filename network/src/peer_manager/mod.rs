@@ -12,7 +12,13 @@
 //!  * An actor responsible for dialing and listening for new connections.
 //!  * An actor per Peer which owns the underlying connection and is responsible for listening for
 //!  and opening substreams as well as negotiating particular protocols on those substreams.
-use crate::{common::NegotiatedSubstream, counters, protocols::identity::Identity, ProtocolId};
+use crate::{
+    common::NegotiatedSubstream,
+    counters,
+    network_state::{ConnectedPeerState, NetworkState},
+    protocols::identity::Identity,
+    ProtocolId,
+};
 use channel;
 use futures::{
     channel::oneshot,
@@ -27,7 +33,11 @@ use netcore::{
     transport::{ConnectionOrigin, Transport},
 };
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, marker::PhantomData};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
 use tokio::runtime::TaskExecutor;
 use types::PeerId;
 
@@ -167,6 +177,8 @@ where
     internal_event_tx: channel::Sender<InternalEvent<TMuxer>>,
     /// A map of outstanding disconnect requests
     outstanding_disconnect_requests: HashMap<PeerId, oneshot::Sender<Result<(), PeerManagerError>>>,
+    /// Shared state of the network stack, in which we track connected peers and their substreams.
+    network_state: Arc<RwLock<NetworkState>>,
     /// Pin the transport type corresponding to this PeerManager instance
     phantom_transport: PhantomData<TTransport>,
 }
@@ -188,6 +200,7 @@ where
             channel::Sender<PeerManagerNotification<TMuxer::Substream>>,
         >,
         peer_event_handlers: Vec<channel::Sender<PeerManagerNotification<TMuxer::Substream>>>,
+        network_state: Arc<RwLock<NetworkState>>,
     ) -> Self {
         let (internal_event_tx, internal_event_rx) =
             channel::new(1024, &counters::PENDING_PEER_MANAGER_INTERNAL_EVENTS);
@@ -213,6 +226,7 @@ where
             internal_event_tx,
             internal_event_rx,
             outstanding_disconnect_requests: HashMap::new(),
+            network_state,
             phantom_transport: PhantomData,
         }
     }
@@ -257,6 +271,10 @@ where
                     .protocol_handlers
                     .get_mut(&substream.protocol)
                     .expect("Received substream for unknown protocol");
+                self.network_state
+                    .write()
+                    .unwrap()
+                    .record_substream(peer_id, &substream.protocol);
                 let event = PeerManagerNotification::NewInboundSubstream(peer_id, substream);
                 ch.send(event).await.unwrap();
             }
@@ -275,6 +293,11 @@ where
                     return;
                 }
                 info!("Disconnected from peer: {}", peer_id.short_str());
+                self.network_state
+                    .write()
+                    .unwrap()
+                    .connected_peers
+                    .remove(&peer_id);
                 if let Some(oneshot_tx) = self.outstanding_disconnect_requests.remove(&peer_id) {
                     if oneshot_tx.send(Ok(())).is_err() {
                         error!("oneshot channel receiver dropped");
@@ -431,6 +454,7 @@ where
             &counters::OP_COUNTERS
                 .peer_gauge(&counters::PENDING_PEER_REQUESTS, &peer_id.short_str()),
         );
+        let role = identity.role();
         let peer = Peer::new(
             identity,
             connection,
@@ -438,6 +462,7 @@ where
             self.protocol_handlers.keys().cloned().collect(),
             self.internal_event_tx.clone(),
             peer_req_rx,
+            self.network_state.clone(),
         );
         let peer_handle = PeerHandle::new(peer_id, address.clone(), origin, peer_req_tx);
         info!(
@@ -446,6 +471,15 @@ where
             peer_id.short_str()
         );
         self.active_peers.insert(peer_id, peer_handle);
+        self.network_state.write().unwrap().connected_peers.insert(
+            peer_id,
+            ConnectedPeerState {
+                address: address.clone(),
+                origin,
+                role,
+                substreams_opened: HashMap::new(),
+            },
+        );
         self.executor
             .spawn(peer.start().boxed().unit_error().compat());
 
@@ -800,6 +834,7 @@ where
     requests_rx: channel::Receiver<PeerRequest<TMuxer::Substream>>,
     origin: ConnectionOrigin,
     shutdown: bool,
+    network_state: Arc<RwLock<NetworkState>>,
 }
 
 impl<TMuxer> Peer<TMuxer>
@@ -815,6 +850,7 @@ where
         own_supported_protocols: Vec<ProtocolId>,
         internal_event_tx: channel::Sender<InternalEvent<TMuxer>>,
         requests_rx: channel::Receiver<PeerRequest<TMuxer::Substream>>,
+        network_state: Arc<RwLock<NetworkState>>,
     ) -> Self {
         Self {
             identity,
@@ -824,6 +860,7 @@ where
             internal_event_tx,
             requests_rx,
             shutdown: false,
+            network_state,
         }
     }
 
@@ -930,6 +967,7 @@ where
             protocol,
             optimistic_negotiation,
             channel,
+            self.network_state.clone(),
         );
 
         negotiate.boxed()
@@ -941,6 +979,7 @@ where
         protocol: ProtocolId,
         optimistic_negotiation: bool,
         channel: oneshot::Sender<Result<TMuxer::Substream, PeerManagerError>>,
+        network_state: Arc<RwLock<NetworkState>>,
     ) {
         let response = match outbound_fut.await {
             Ok(substream) => {
@@ -965,11 +1004,17 @@ where
         .map_err(Into::into);
 
         match response {
-            Ok(_) => debug!(
-                "Successfully negotiated outbound substream '{:?}' with Peer {}",
-                protocol,
-                peer_id.short_str()
-            ),
+            Ok(_) => {
+                debug!(
+                    "Successfully negotiated outbound substream '{:?}' with Peer {}",
+                    protocol,
+                    peer_id.short_str()
+                );
+                network_state
+                    .write()
+                    .unwrap()
+                    .record_substream(peer_id, &protocol);
+            }
             Err(ref e) => debug!(
                 "Unable to negotiated outbound substream '{:?}' with Peer {}: {}",
                 protocol,
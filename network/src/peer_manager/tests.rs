@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    network_state::NetworkState,
     peer_manager::{
         DisconnectReason, InternalEvent, Peer, PeerHandle, PeerManager, PeerManagerNotification,
         PeerManagerRequest,
@@ -29,7 +30,12 @@ use netcore::{
     transport::{boxed::BoxedTransport, memory::MemoryTransport, ConnectionOrigin, TransportExt},
 };
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, io, time::Duration};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 use tokio::{runtime::TaskExecutor, timer::Timeout};
 use types::PeerId;
 
@@ -93,6 +99,7 @@ fn build_test_peer(
         vec![ProtocolId::from_static(HELLO_PROTOCOL)],
         internal_event_tx,
         peer_req_rx,
+        Arc::new(RwLock::new(NetworkState::default())),
     );
     let peer_handle = PeerHandle::new(peer_id, Multiaddr::empty(), origin, peer_req_tx);
 
@@ -310,6 +317,7 @@ fn build_test_peer_manager(
         peer_manager_request_rx,
         protocol_handlers,
         Vec::new(),
+        Arc::new(RwLock::new(NetworkState::default())),
     );
 
     (peer_manager, peer_manager_request_tx, hello_rx)
@@ -720,3 +728,52 @@ fn peer_manager_simultaneous_dial_disconnect_event() {
         .block_on(test.boxed().unit_error().compat())
         .unwrap();
 }
+
+#[test]
+fn peer_manager_tracks_network_state() {
+    let mut runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (mut peer_manager, _request_tx, mut hello_rx) =
+        build_test_peer_manager(runtime.executor(), ids[1]);
+    let network_state = peer_manager.network_state.clone();
+
+    let test = async move {
+        let (outbound, inbound) = build_test_connection();
+        peer_manager
+            .add_peer(
+                build_test_identity(ids[0]),
+                Multiaddr::empty(),
+                ConnectionOrigin::Inbound,
+                inbound,
+            )
+            .await;
+        {
+            let state = network_state.read().unwrap();
+            let peer = state.connected_peers.get(&ids[0]).unwrap();
+            assert_eq!(peer.origin, ConnectionOrigin::Inbound);
+            assert_eq!(peer.role, RoleType::Validator);
+            assert!(peer.substreams_opened.is_empty());
+        }
+
+        // An inbound substream is counted against its protocol.
+        open_hello_substream(&outbound).await.unwrap();
+        let event = peer_manager.internal_event_rx.next().await.unwrap();
+        join(peer_manager.handle_internal_event(event), hello_rx.next()).await;
+        assert_eq!(
+            network_state.read().unwrap().connected_peers[&ids[0]].substreams_opened
+                [&ProtocolId::from_static(HELLO_PROTOCOL)],
+            1
+        );
+
+        // The peer is dropped from the state once the connection is lost.
+        outbound.close().await.unwrap();
+        let event = peer_manager.internal_event_rx.next().await.unwrap();
+        peer_manager.handle_internal_event(event).await;
+        assert!(network_state.read().unwrap().connected_peers.is_empty());
+    };
+
+    runtime
+        .block_on(test.boxed().unit_error().compat())
+        .unwrap();
+}
@@ -13,10 +13,11 @@ use types::proto::{ledger_info, transaction};
 
 pub use self::{
     consensus::{
-        Block, BlockRetrievalStatus, ConsensusMsg, PacemakerTimeout, PacemakerTimeoutCertificate,
-        Proposal, QuorumCert, RequestBlock, RespondBlock, SyncInfo, TimeoutMsg, Vote,
+        Batch, Block, BlockRetrievalStatus, ConsensusMsg, PacemakerTimeout,
+        PacemakerTimeoutCertificate, Proposal, QuorumCert, RequestBatch, RequestBlock,
+        RespondBatch, RespondBlock, SyncInfo, TimeoutMsg, Vote,
     },
-    mempool::MempoolSyncMsg,
+    mempool::{MempoolSyncAck, MempoolSyncMsg},
     network::{
         DiscoveryMsg, FullNodePayload, IdentityMsg, IdentityMsg_Role, Note, PeerInfo, Ping, Pong,
         SignedFullNodePayload, SignedPeerInfo,
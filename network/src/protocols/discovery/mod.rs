@@ -22,6 +22,16 @@
 //!
 //! TODO: We need to handle to case of peers who may no longer be a part of the network.
 //!
+//! ## Identity key rotation
+//!
+//! Each note also carries the x25519 identity public key the peer uses in Noise handshakes. Since
+//! notes are signed with the peer's network signing key, a peer can rotate its identity key
+//! without an on-chain update: on a [`DiscoveryRequest::RotateIdentityKey`], the actor advertises
+//! the new key in a fresh note right away and switches its Noise keypair halfway through the
+//! rotation overlap window, then drops its connections so that they are re-established with the
+//! new key. Peers learning about a new key accept both the old and the new key until the overlap
+//! window elapses.
+//!
 //! ## Future work
 //!
 //! - Currently, we do not try to detect/punish nodes which are just lurking (without contributing
@@ -33,9 +43,10 @@
 //!
 //! [`ConnectivityManager`]: ../../connectivity_manager
 use crate::{
-    common::NegotiatedSubstream,
+    common::{IdentityKeyRotations, NegotiatedSubstream},
     connectivity_manager::ConnectivityRequest,
     error::{NetworkError, NetworkErrorKind},
+    network_state::NetworkState,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
     proto::{DiscoveryMsg, FullNodePayload, Note, PeerInfo, SignedFullNodePayload, SignedPeerInfo},
     utils, NetworkPublicKeys, ProtocolId,
@@ -45,7 +56,8 @@ use channel;
 use crypto::{
     ed25519::*,
     hash::{CryptoHasher, DiscoveryMsgHasher},
-    HashValue,
+    x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
+    HashValue, ValidKey,
 };
 use failure::{format_err, Fail};
 use futures::{
@@ -56,6 +68,7 @@ use futures::{
     stream::{FusedStream, FuturesUnordered, Stream, StreamExt},
 };
 use logger::prelude::*;
+use noise::NoiseConfig;
 use parity_multiaddr::Multiaddr;
 use protobuf::{self, Message};
 use rand::{rngs::SmallRng, FromEntropy, Rng};
@@ -65,9 +78,9 @@ use std::{
     fmt::Debug,
     pin::Pin,
     sync::{Arc, RwLock},
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
-use tokio::{codec::Framed, prelude::FutureExt as _};
+use tokio::{codec::Framed, prelude::FutureExt as _, timer::Delay};
 use types::{
     validator_signer::ValidatorSigner as Signer,
     validator_verifier::ValidatorVerifier as SignatureValidator, PeerId,
@@ -79,10 +92,28 @@ mod test;
 
 pub const DISCOVERY_PROTOCOL_NAME: &[u8] = b"/libra/discovery/0.1.0";
 
+/// Requests [`Discovery`] receives from the [`NetworkProvider`](crate::interface::NetworkProvider).
+#[derive(Debug)]
+pub enum DiscoveryRequest {
+    /// Advertise a new identity keypair and switch to it once peers had time to learn about it.
+    RotateIdentityKey(X25519StaticPrivateKey, X25519StaticPublicKey),
+}
+
 /// The actor running the discovery protocol.
 pub struct Discovery<TTicker, TSubstream> {
     /// Note for self.
     self_note: Note,
+    /// Addresses advertised in the note for self.
+    self_addrs: Vec<Multiaddr>,
+    /// Signer for the note for self.
+    signer: Signer<Ed25519PrivateKey>,
+    /// Noise configuration whose static keypair is the identity key advertised for self. `None` if
+    /// the transport does not use Noise.
+    noise_config: Option<Arc<NoiseConfig>>,
+    /// Identity keys rotated by other peers, shared with the transport.
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
+    /// Duration for which both the old and the new identity key of a peer are accepted.
+    key_rotation_overlap: Duration,
     /// Validator for verifying signatures on messages.
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
     /// Current state, maintaining the most recent Note for each peer, alongside parsed PeerInfo.
@@ -100,8 +131,12 @@ pub struct Discovery<TTicker, TSubstream> {
     peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
     /// Channel to send requests to ConnectivityManager.
     conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    /// Channel to receive requests from NetworkProvider.
+    requests_rx: channel::Receiver<DiscoveryRequest>,
     /// Message timeout duration.
     msg_timeout: Duration,
+    /// Shared state of the network stack, in which we track the epochs of known notes.
+    network_state: Arc<RwLock<NetworkState>>,
     /// Random-number generator.
     rng: SmallRng,
 }
@@ -121,25 +156,39 @@ where
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        requests_rx: channel::Receiver<DiscoveryRequest>,
+        noise_config: Option<Arc<NoiseConfig>>,
+        key_rotations: Arc<RwLock<IdentityKeyRotations>>,
+        key_rotation_overlap: Duration,
         msg_timeout: Duration,
+        network_state: Arc<RwLock<NetworkState>>,
     ) -> Self {
-        // TODO(philiphayes): wire through config
-        let dns_seed_addr = b"example.com";
-
-        let self_peer_info = create_peer_info(self_addrs);
-        let self_full_node_payload = create_full_node_payload(dns_seed_addr);
-        let self_note = create_note(
+        let identity_public_key = noise_config
+            .as_ref()
+            .map(|noise_config| noise_config.public_key())
+            .unwrap_or_default();
+        let (self_peer_info, self_note) = create_self_note(
             &signer,
             self_peer_id,
-            self_peer_info.clone(),
-            self_full_node_payload.clone(),
+            self_addrs.clone(),
+            identity_public_key,
         );
+        network_state
+            .write()
+            .unwrap()
+            .discovery_note_epochs
+            .insert(self_peer_id, self_peer_info.get_epoch());
 
         let known_peers = vec![(self_peer_id, (self_peer_info, self_note.clone()))]
             .into_iter()
             .collect();
         Self {
             self_note,
+            self_addrs,
+            signer,
+            noise_config,
+            key_rotations,
+            key_rotation_overlap,
             seed_peers,
             trusted_peers,
             known_peers,
@@ -148,7 +197,9 @@ where
             peer_mgr_reqs_tx,
             peer_mgr_notifs_rx,
             conn_mgr_reqs_tx,
+            requests_rx,
             msg_timeout,
+            network_state,
             rng: SmallRng::from_entropy(),
         }
     }
@@ -185,11 +236,14 @@ where
     // - an incoming substream from a peer wishing to send its state
     // - an internal task once it has processed incoming messages from a peer, and wishes for
     // discovery actor to update its state.
+    // - the NetworkProvider, requesting a rotation of our identity key, and a timer firing once
+    // the new key should be put to use.
     pub async fn start(mut self) {
         // Bootstrap by connecting to seed peers.
         self.connect_to_seed_peers().await;
         let mut unprocessed_inbound = FuturesUnordered::new();
         let mut unprocessed_outbound = FuturesUnordered::new();
+        let mut pending_key_rotations = FuturesUnordered::new();
         loop {
             futures::select! {
                 _ = self.ticker.select_next_some() => {
@@ -210,6 +264,12 @@ where
                     }
                 },
                 _ = unprocessed_outbound.select_next_some() => {}
+                req = self.requests_rx.select_next_some() => {
+                    self.handle_request(req, &mut pending_key_rotations);
+                }
+                keypair = pending_key_rotations.select_next_some() => {
+                    self.switch_identity_key(keypair).await;
+                }
                 complete => {
                     crit!("Discovery actor terminated");
                     break;
@@ -297,6 +357,128 @@ where
         }
     }
 
+    fn handle_request<'a>(
+        &'a mut self,
+        req: DiscoveryRequest,
+        pending_key_rotations: &'a mut FuturesUnordered<
+            Pin<Box<dyn Future<Output = (X25519StaticPrivateKey, X25519StaticPublicKey)> + Send>>,
+        >,
+    ) {
+        trace!("DiscoveryRequest::{:?}", req);
+        match req {
+            DiscoveryRequest::RotateIdentityKey(private_key, public_key) => {
+                if self.noise_config.is_none() {
+                    warn!("Ignoring identity key rotation: transport does not use Noise");
+                    return;
+                }
+                info!("Advertising new identity key {:?}", public_key);
+                self.update_self_note(public_key.to_bytes().to_vec());
+                // Peers learn about the new key through gossip, so we keep using the old key for
+                // the first half of the overlap window. Connections made with the new key during
+                // the second half can still be authenticated by peers which have missed it.
+                let delay = Delay::new(Instant::now() + self.key_rotation_overlap / 2);
+                let fut = async move {
+                    if let Err(err) = delay.compat().await {
+                        error!("Identity key rotation timer failed: {:?}", err);
+                    }
+                    (private_key, public_key)
+                };
+                pending_key_rotations.push(fut.boxed());
+            }
+        }
+    }
+
+    // Switches the Noise keypair to the rotated identity key, and disconnects from all peers so
+    // that the connections are re-established (by the ConnectivityManager) with the new key.
+    async fn switch_identity_key(
+        &mut self,
+        keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    ) {
+        if keypair.1.to_bytes() != self.identity_public_key() {
+            // A newer rotation superseded this one.
+            debug!("Skipping superseded identity key rotation");
+            return;
+        }
+        info!("Switching to new identity key {:?}", keypair.1);
+        if let Some(noise_config) = &self.noise_config {
+            noise_config.rotate_keypair(keypair);
+        }
+        let peers: Vec<_> = self.connected_peers.keys().cloned().collect();
+        for peer_id in peers {
+            if let Err(err) = self.peer_mgr_reqs_tx.disconnect_peer(peer_id).await {
+                warn!(
+                    "Failed to disconnect from peer {} after identity key rotation: {:?}",
+                    peer_id.short_str(),
+                    err
+                );
+            }
+        }
+    }
+
+    // Re-creates the note for self, advertising the given identity key.
+    fn update_self_note(&mut self, identity_public_key: Vec<u8>) {
+        let self_peer_id =
+            PeerId::try_from(self.self_note.get_peer_id()).expect("PeerId parsing failed");
+        let (self_peer_info, self_note) = create_self_note(
+            &self.signer,
+            self_peer_id,
+            self.self_addrs.clone(),
+            identity_public_key,
+        );
+        self.self_note = self_note.clone();
+        self.record_note_epoch(self_peer_id, &self_peer_info);
+        self.known_peers
+            .insert(self_peer_id, (self_peer_info, self_note));
+    }
+
+    // Records the epoch of the latest note for `peer_id` in the shared network state.
+    fn record_note_epoch(&self, peer_id: PeerId, peer_info: &PeerInfo) {
+        self.network_state
+            .write()
+            .unwrap()
+            .discovery_note_epochs
+            .insert(peer_id, peer_info.get_epoch());
+    }
+
+    // The identity key advertised in the note for self.
+    fn identity_public_key(&self) -> Vec<u8> {
+        let peer_info: PeerInfo =
+            protobuf::parse_from_bytes(self.self_note.get_signed_peer_info().get_peer_info())
+                .expect("PeerInfo parsing fails");
+        peer_info.get_identity_public_key().to_vec()
+    }
+
+    // Records an identity key rotation if `peer_info` advertises a key other than the one
+    // currently accepted for `peer_id`.
+    // Assumption: `peer_info` has already been verified to be signed by `peer_id`.
+    fn update_identity_key(&self, peer_id: PeerId, peer_info: &PeerInfo) {
+        if peer_info.get_identity_public_key().is_empty() {
+            return;
+        }
+        let new_key = X25519StaticPublicKey::try_from(peer_info.get_identity_public_key())
+            .expect("Identity public key parsing fails");
+        let trusted_key = match self.trusted_peers.read().unwrap().get(&peer_id) {
+            Some(keys) => keys.identity_public_key.clone(),
+            None => return,
+        };
+        let mut key_rotations = self.key_rotations.write().unwrap();
+        let current_key = key_rotations.effective_key(&peer_id, &trusted_key).clone();
+        if current_key != new_key {
+            info!(
+                "Peer {} rotated its identity key to {:?}",
+                peer_id.short_str(),
+                new_key
+            );
+            key_rotations.rotate(
+                peer_id,
+                trusted_key,
+                current_key,
+                new_key,
+                self.key_rotation_overlap,
+            );
+        }
+    }
+
     // Chooses a random connected neighbour.
     fn choose_random_neighbor(&mut self) -> Option<PeerId> {
         if !self.connected_peers.is_empty() {
@@ -355,6 +537,8 @@ where
                     // We can never receive a note with a higher epoch number on us than what we
                     // ourselves have broadcasted.
                     assert_ne!(peer_id, self_peer_id);
+                    self.update_identity_key(peer_id, &peer_info);
+                    self.record_note_epoch(peer_id, &peer_info);
                     // Update internal state of the peer with new Note.
                     self.known_peers.insert(peer_id, (peer_info.clone(), note));
                     self.conn_mgr_reqs_tx
@@ -377,8 +561,30 @@ where
     }
 }
 
-// Creates a PeerInfo combining the given addresses with the current unix timestamp as epoch.
-fn create_peer_info(addrs: Vec<Multiaddr>) -> PeerInfo {
+// Creates the PeerInfo and the Note advertised for self.
+fn create_self_note(
+    signer: &Signer<Ed25519PrivateKey>,
+    self_peer_id: PeerId,
+    self_addrs: Vec<Multiaddr>,
+    identity_public_key: Vec<u8>,
+) -> (PeerInfo, Note) {
+    // TODO(philiphayes): wire through config
+    let dns_seed_addr = b"example.com";
+
+    let self_peer_info = create_peer_info(self_addrs, identity_public_key);
+    let self_full_node_payload = create_full_node_payload(dns_seed_addr);
+    let self_note = create_note(
+        signer,
+        self_peer_id,
+        self_peer_info.clone(),
+        self_full_node_payload,
+    );
+    (self_peer_info, self_note)
+}
+
+// Creates a PeerInfo combining the given addresses and identity key with the current unix
+// timestamp as epoch.
+fn create_peer_info(addrs: Vec<Multiaddr>, identity_public_key: Vec<u8>) -> PeerInfo {
     let mut peer_info = PeerInfo::new();
     // TODO: Currently, SystemTime::now() in Rust is not guaranteed to use a monotonic clock.
     // At the moment, it's unclear how to do this in a platform-agnostic way. For Linux, we
@@ -389,6 +595,7 @@ fn create_peer_info(addrs: Vec<Multiaddr>) -> PeerInfo {
         .as_millis() as u64;
     peer_info.set_epoch(time_since_epoch);
     peer_info.set_addrs(addrs.into_iter().map(|addr| addr.as_ref().into()).collect());
+    peer_info.set_identity_public_key(identity_public_key.into());
     peer_info
 }
 
@@ -484,7 +691,8 @@ where
 // 1. We should be able to correctly parse the peer id in each note.
 // 2. The signature of the serialized peer info should be valid for the given peer_id.
 // 3. The address(es) in the PeerInfo should be correctly parsable as Multiaddrs.
-// 4. The signature of the serialized full node payload should be valid for the given peer_id.
+// 4. The identity public key in the PeerInfo, if any, should be a valid x25519 public key.
+// 5. The signature of the serialized full node payload should be valid for the given peer_id.
 fn is_valid(
     note: &Note,
     trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
@@ -511,6 +719,11 @@ fn is_valid(
     for addr in peer_info.get_addrs() {
         let _: Multiaddr = Multiaddr::try_from(addr.clone())?;
     }
+    if !peer_info.get_identity_public_key().is_empty() {
+        let _: X25519StaticPublicKey =
+            X25519StaticPublicKey::try_from(peer_info.get_identity_public_key())
+                .map_err(|err| err.context(NetworkErrorKind::ParsingError))?;
+    }
 
     // validate FullNodePayload (optional)
     // TODO(philiphayes): actually use the FullNodePayload
@@ -37,16 +37,20 @@ fn setup_discovery(
     seed_peer_info: PeerInfo,
     signer: Signer<Ed25519PrivateKey>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    noise_config: Option<Arc<NoiseConfig>>,
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
 ) -> (
     channel::Receiver<PeerManagerRequest<MemorySocket>>,
     channel::Receiver<ConnectivityRequest>,
     channel::Sender<PeerManagerNotification<MemorySocket>>,
     channel::Sender<()>,
+    channel::Sender<DiscoveryRequest>,
 ) {
     let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = channel::new_test(0);
     let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new_test(1);
     let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) = channel::new_test(0);
     let (ticker_tx, ticker_rx) = channel::new_test(0);
+    let (discovery_reqs_tx, discovery_reqs_rx) = channel::new_test(0);
     let discovery = {
         Discovery::new(
             peer_id,
@@ -58,7 +62,12 @@ fn setup_discovery(
             PeerManagerRequestSender::new(peer_mgr_reqs_tx),
             peer_mgr_notifs_rx,
             conn_mgr_reqs_tx,
+            discovery_reqs_rx,
+            noise_config,
+            key_rotations,
+            Duration::from_millis(0),
             Duration::from_secs(180),
+            Arc::new(RwLock::new(NetworkState::default())),
         )
     };
     rt.spawn(discovery.start().boxed().unit_error().compat());
@@ -67,9 +76,16 @@ fn setup_discovery(
         conn_mgr_reqs_rx,
         peer_mgr_notifs_tx,
         ticker_tx,
+        discovery_reqs_tx,
     )
 }
 
+fn get_identity_public_key(note: &Note) -> Vec<u8> {
+    let signed_peer_info = note.get_signed_peer_info();
+    let peer_info: PeerInfo = protobuf::parse_from_bytes(signed_peer_info.get_peer_info()).unwrap();
+    peer_info.get_identity_public_key().to_vec()
+}
+
 fn get_addrs(note: &Note) -> Vec<Multiaddr> {
     let signed_peer_info = note.get_signed_peer_info();
     let peer_info: PeerInfo = protobuf::parse_from_bytes(signed_peer_info.get_peer_info()).unwrap();
@@ -132,7 +148,7 @@ fn inbound() {
             .collect(),
     ));
     // Setup discovery.
-    let (_, mut conn_mgr_reqs_rx, mut peer_mgr_notifs_tx, _) = setup_discovery(
+    let (_, mut conn_mgr_reqs_rx, mut peer_mgr_notifs_tx, _, _) = setup_discovery(
         &mut rt,
         peer_id,
         address.clone(),
@@ -140,6 +156,8 @@ fn inbound() {
         seed_peer_info.clone(),
         self_signer,
         trusted_peers.clone(),
+        None,
+        Arc::new(RwLock::new(IdentityKeyRotations::default())),
     );
 
     // Fake connectivity manager and dialer.
@@ -273,7 +291,7 @@ fn outbound() {
             .collect(),
     ));
     // Setup discovery.
-    let (mut peer_mgr_reqs_rx, _conn_mgr_req_rx, mut peer_mgr_notifs_tx, mut ticker_tx) =
+    let (mut peer_mgr_reqs_rx, _conn_mgr_req_rx, mut peer_mgr_notifs_tx, mut ticker_tx, _) =
         setup_discovery(
             &mut rt,
             peer_id,
@@ -282,6 +300,8 @@ fn outbound() {
             seed_peer_info.clone(),
             self_signer,
             trusted_peers.clone(),
+            None,
+            Arc::new(RwLock::new(IdentityKeyRotations::default())),
         );
 
     // Fake connectivity manager and dialer.
@@ -323,3 +343,233 @@ fn outbound() {
     rt.block_on(f_peer_mgr.boxed().unit_error().compat())
         .unwrap();
 }
+
+async fn expect_outbound_msg(
+    peer_mgr_reqs_rx: &mut channel::Receiver<PeerManagerRequest<MemorySocket>>,
+    peer_id: PeerId,
+) -> DiscoveryMsg {
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    match peer_mgr_reqs_rx.next().await.unwrap() {
+        PeerManagerRequest::OpenSubstream(peer, protocol, ch) => {
+            assert_eq!(peer, peer_id);
+            assert_eq!(protocol, DISCOVERY_PROTOCOL_NAME);
+            ch.send(Ok(dialer_substream)).unwrap();
+        }
+        _ => {
+            panic!("unexpected request to peer manager");
+        }
+    }
+    recv_msg(listener_substream).await.unwrap()
+}
+
+#[test]
+// Test that a new identity key advertised by a trusted peer is accepted alongside its old key.
+fn inbound_identity_key_rotation() {
+    ::logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    // Setup self.
+    let peer_id = PeerId::random();
+    let address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    // Setup seed.
+    let mut seed_peer_info = gen_peer_info();
+    let seed_peer_id = PeerId::random();
+    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let old_identity_key = seed_pub_keys.identity_public_key.clone();
+    let trusted_peers = Arc::new(RwLock::new(
+        vec![(seed_peer_id, seed_pub_keys), (peer_id, self_pub_keys)]
+            .into_iter()
+            .collect(),
+    ));
+    let key_rotations = Arc::new(RwLock::new(IdentityKeyRotations::default()));
+    // Setup discovery.
+    let (_, mut conn_mgr_reqs_rx, mut peer_mgr_notifs_tx, _, _) = setup_discovery(
+        &mut rt,
+        peer_id,
+        address,
+        seed_peer_id,
+        seed_peer_info.clone(),
+        self_signer,
+        trusted_peers,
+        None,
+        key_rotations.clone(),
+    );
+
+    let f_peer_mgr = async move {
+        let seed_peer_address = Multiaddr::try_from(seed_peer_info.get_addrs()[0].clone()).unwrap();
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            seed_peer_id,
+            seed_peer_address.clone(),
+        )
+        .await;
+
+        // The seed peer advertises a new identity key in a newer note.
+        let (_, new_identity_key) =
+            x25519::compat::generate_keypair(&mut StdRng::from_seed([1u8; 32]));
+        seed_peer_info.set_epoch(2);
+        seed_peer_info.set_identity_public_key(new_identity_key.to_bytes().into());
+        let mut msg = DiscoveryMsg::new();
+        msg.mut_notes().push(create_note(
+            &seed_signer,
+            seed_peer_id,
+            seed_peer_info,
+            gen_full_node_payload(),
+        ));
+
+        let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+        peer_mgr_notifs_tx
+            .send(PeerManagerNotification::NewInboundSubstream(
+                seed_peer_id,
+                NegotiatedSubstream {
+                    protocol: ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
+                    substream: listener_substream,
+                },
+            ))
+            .await
+            .unwrap();
+        let mut dialer_substream =
+            Framed::new(dialer_substream.compat(), UviBytes::<Bytes>::default()).sink_compat();
+        dialer_substream
+            .send(msg.write_to_bytes().unwrap().into())
+            .await
+            .unwrap();
+        expect_address_update(&mut conn_mgr_reqs_rx, seed_peer_id, seed_peer_address).await;
+
+        // Both keys are accepted during the overlap window, which is empty in this test.
+        let key_rotations = key_rotations.read().unwrap();
+        assert_eq!(
+            Some(&new_identity_key),
+            key_rotations.current_key(&seed_peer_id)
+        );
+        assert!(key_rotations.is_valid_key(
+            &seed_peer_id,
+            &old_identity_key,
+            &new_identity_key.to_bytes()
+        ));
+        assert!(!key_rotations.is_valid_key(
+            &seed_peer_id,
+            &old_identity_key,
+            &old_identity_key.to_bytes()
+        ));
+    };
+    rt.block_on(f_peer_mgr.boxed().unit_error().compat())
+        .unwrap();
+}
+
+#[test]
+// Test that the identity key rotations are dropped once the trusted peer set changes the key.
+fn key_rotation_superseded_by_trusted_peers() {
+    let peer_id = PeerId::random();
+    let (mut keys, _) = generate_network_pub_keys_and_signer(peer_id);
+    let trusted_key = keys.identity_public_key.clone();
+    let (_, new_key) = x25519::compat::generate_keypair(&mut StdRng::from_seed([1u8; 32]));
+    let mut key_rotations = IdentityKeyRotations::default();
+    key_rotations.rotate(
+        peer_id,
+        trusted_key.clone(),
+        trusted_key.clone(),
+        new_key.clone(),
+        Duration::from_secs(0),
+    );
+
+    // The trusted peer set didn't catch up yet.
+    let mut trusted_peers: HashMap<_, _> = vec![(peer_id, keys.clone())].into_iter().collect();
+    key_rotations.update_trusted_peers(&trusted_peers);
+    assert_eq!(Some(&new_key), key_rotations.current_key(&peer_id));
+
+    // A later rotation on-chain supersedes the one learnt through discovery.
+    let (_, later_key) = x25519::compat::generate_keypair(&mut StdRng::from_seed([2u8; 32]));
+    assert!(key_rotations.is_valid_key(&peer_id, &later_key, &later_key.to_bytes()));
+    assert!(!key_rotations.is_valid_key(&peer_id, &later_key, &new_key.to_bytes()));
+    keys.identity_public_key = later_key;
+    trusted_peers.insert(peer_id, keys);
+    key_rotations.update_trusted_peers(&trusted_peers);
+    assert_eq!(None, key_rotations.current_key(&peer_id));
+}
+
+#[test]
+// Test that rotating the identity key advertises the new key, switches the Noise keypair and
+// drops existing connections.
+fn rotate_identity_key() {
+    ::logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    // Setup self.
+    let peer_id = PeerId::random();
+    let address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+    let (self_pub_keys, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    let mut rng = StdRng::from_seed([1u8; 32]);
+    let noise_config = Arc::new(NoiseConfig::new(x25519::compat::generate_keypair(&mut rng)));
+    let (new_private_key, new_public_key) = x25519::compat::generate_keypair(&mut rng);
+    // Setup seed.
+    let seed_peer_id = PeerId::random();
+    let seed_peer_info = gen_peer_info();
+    let (seed_pub_keys, _) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let trusted_peers = Arc::new(RwLock::new(
+        vec![(seed_peer_id, seed_pub_keys), (peer_id, self_pub_keys)]
+            .into_iter()
+            .collect(),
+    ));
+    // Setup discovery.
+    let (mut peer_mgr_reqs_rx, _, mut peer_mgr_notifs_tx, mut ticker_tx, mut discovery_reqs_tx) =
+        setup_discovery(
+            &mut rt,
+            peer_id,
+            address,
+            seed_peer_id,
+            seed_peer_info.clone(),
+            self_signer,
+            trusted_peers,
+            Some(noise_config.clone()),
+            Arc::new(RwLock::new(IdentityKeyRotations::default())),
+        );
+
+    let f_peer_mgr = async move {
+        let seed_peer_address = Multiaddr::try_from(seed_peer_info.get_addrs()[0].clone()).unwrap();
+        peer_mgr_notifs_tx
+            .send(PeerManagerNotification::NewPeer(
+                seed_peer_id,
+                seed_peer_address,
+            ))
+            .await
+            .unwrap();
+
+        // The note for self advertises the current identity key.
+        ticker_tx.send(()).await.unwrap();
+        let msg = expect_outbound_msg(&mut peer_mgr_reqs_rx, seed_peer_id).await;
+        assert_eq!(
+            noise_config.public_key(),
+            get_identity_public_key(&msg.get_notes()[0])
+        );
+
+        discovery_reqs_tx
+            .send(DiscoveryRequest::RotateIdentityKey(
+                new_private_key,
+                new_public_key.clone(),
+            ))
+            .await
+            .unwrap();
+
+        // Connections are dropped to be re-established with the new key.
+        match peer_mgr_reqs_rx.next().await.unwrap() {
+            PeerManagerRequest::DisconnectPeer(peer, ch) => {
+                assert_eq!(peer, seed_peer_id);
+                ch.send(Ok(())).unwrap();
+            }
+            _ => {
+                panic!("unexpected request to peer manager");
+            }
+        }
+        assert_eq!(new_public_key.to_bytes(), noise_config.public_key());
+
+        // The note for self now advertises the new identity key.
+        ticker_tx.send(()).await.unwrap();
+        let msg = expect_outbound_msg(&mut peer_mgr_reqs_rx, seed_peer_id).await;
+        assert_eq!(
+            new_public_key.to_bytes(),
+            get_identity_public_key(&msg.get_notes()[0])
+        );
+    };
+    rt.block_on(f_peer_mgr.boxed().unit_error().compat())
+        .unwrap();
+}
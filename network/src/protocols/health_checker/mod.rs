@@ -19,6 +19,7 @@
 //! - Ping a peer only in periods of no application-level communication with the peer
 use crate::{
     error::NetworkError,
+    network_state::{NetworkState, PingState},
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
     proto::{Ping, Pong},
     utils::read_proto,
@@ -36,7 +37,12 @@ use futures::{
 use logger::prelude::*;
 use protobuf::{self, Message};
 use rand::{rngs::SmallRng, seq::SliceRandom, FromEntropy};
-use std::{collections::HashMap, fmt::Debug, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 use tokio::{codec::Framed, prelude::FutureExt as _};
 use types::PeerId;
 use unsigned_varint::codec::UviBytes;
@@ -69,6 +75,8 @@ pub struct HealthChecker<TTicker, TSubstream> {
     ping_failures_tolerated: u64,
     /// Counter incremented in each round of health checks
     round: u64,
+    /// Shared state of the network stack, in which we track the outcome of pings.
+    network_state: Arc<RwLock<NetworkState>>,
 }
 
 impl<TTicker, TSubstream> HealthChecker<TTicker, TSubstream>
@@ -83,6 +91,7 @@ where
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         ping_timeout: Duration,
         ping_failures_tolerated: u64,
+        network_state: Arc<RwLock<NetworkState>>,
     ) -> Self {
         HealthChecker {
             ticker,
@@ -93,6 +102,7 @@ where
             ping_timeout,
             ping_failures_tolerated,
             round: 0,
+            network_state,
         }
     }
 
@@ -105,9 +115,15 @@ where
                     match notif {
                         PeerManagerNotification::NewPeer(peer_id, _) => {
                             self.connected.insert(peer_id, (self.round, 0));
+                            self.network_state
+                                .write()
+                                .unwrap()
+                                .pings
+                                .insert(peer_id, PingState::default());
                         }
                         PeerManagerNotification::LostPeer(peer_id, _) => {
                             self.connected.remove(&peer_id);
+                            self.network_state.write().unwrap().pings.remove(&peer_id);
                         }
                         PeerManagerNotification::NewInboundSubstream(peer_id, substream) => {
                             assert_eq!(substream.protocol, PING_PROTOCOL_NAME);
@@ -150,21 +166,30 @@ where
         &mut self,
         peer_id: PeerId,
         round: u64,
-        ping_result: Result<(), NetworkError>,
+        ping_result: Result<Duration, NetworkError>,
     ) {
         debug!("Got result for ping round: {}", round);
         match ping_result {
-            Ok(_) => {
-                debug!("Ping successful for peer: {}", peer_id.short_str());
+            Ok(rtt) => {
+                debug!(
+                    "Ping successful for peer: {} in {:?}",
+                    peer_id.short_str(),
+                    rtt
+                );
                 // Update last successful ping to current round.
-                self.connected
-                    .entry(peer_id)
-                    .and_modify(|(ref mut r, ref mut count)| {
-                        if round > *r {
-                            *r = round;
-                            *count = 0;
-                        }
-                    });
+                if let Some((ref mut r, ref mut count)) = self.connected.get_mut(&peer_id) {
+                    if round > *r {
+                        *r = round;
+                        *count = 0;
+                        self.network_state.write().unwrap().pings.insert(
+                            peer_id,
+                            PingState {
+                                last_rtt: Some(rtt),
+                                failures: 0,
+                            },
+                        );
+                    }
+                }
             }
             Err(err) => {
                 warn!(
@@ -187,6 +212,11 @@ where
                         // The HealthChecker only performs the disconnect. It relies on
                         // ConnectivityManager or the remote peer to re-establish the connection.
                         *failures += 1;
+                        if let Some(ping_state) =
+                            self.network_state.write().unwrap().pings.get_mut(&peer_id)
+                        {
+                            ping_state.failures = *failures;
+                        }
                         if *failures > self.ping_failures_tolerated {
                             info!("Disonnecting from peer: {}", peer_id.short_str());
                             if let Err(err) = self.peer_mgr_reqs_tx.disconnect_peer(peer_id).await {
@@ -208,8 +238,9 @@ where
         round: u64,
         mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         ping_timeout: Duration,
-    ) -> (PeerId, u64, Result<(), NetworkError>) {
+    ) -> (PeerId, u64, Result<Duration, NetworkError>) {
         let ping_result = async move {
+            let start = Instant::now();
             // Request a new substream to peer.
             debug!(
                 "Opening a new substream with peer: {} for Ping",
@@ -232,8 +263,8 @@ where
             // Read Pong.
             debug!("Waiting for Pong from peer: {}", peer_id.short_str());
             let _: Pong = read_proto(&mut substream).await?;
-            // Return success.
-            Ok(())
+            // Return the round trip time.
+            Ok(start.elapsed())
         };
         (
             peer_id,
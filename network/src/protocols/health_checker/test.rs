@@ -14,6 +14,7 @@ const PING_TIMEOUT: Duration = Duration::from_millis(500);
 fn setup_permissive_health_checker(
     rt: &mut Runtime,
     ping_failures_tolerated: u64,
+    network_state: Arc<RwLock<NetworkState>>,
 ) -> (
     channel::Receiver<PeerManagerRequest<MemorySocket>>,
     channel::Sender<PeerManagerNotification<MemorySocket>>,
@@ -28,6 +29,7 @@ fn setup_permissive_health_checker(
         peer_mgr_notifs_rx,
         PING_TIMEOUT,
         ping_failures_tolerated,
+        network_state,
     );
     rt.spawn(health_checker.start().boxed().unit_error().compat());
     (peer_mgr_reqs_rx, peer_mgr_notifs_tx, ticker_tx)
@@ -49,6 +51,7 @@ fn setup_default_health_checker(
         peer_mgr_notifs_rx,
         PING_TIMEOUT,
         0,
+        Arc::new(RwLock::new(NetworkState::default())),
     );
     rt.spawn(health_checker.start().boxed().unit_error().compat());
     (peer_mgr_reqs_rx, peer_mgr_notifs_tx, ticker_tx)
@@ -206,7 +209,11 @@ fn outbound_failure_permissive() {
     let mut rt = Runtime::new().unwrap();
     let ping_failures_tolerated = 10;
     let (mut peer_mgr_reqs_rx, mut peer_mgr_notifs_tx, mut ticker_tx) =
-        setup_permissive_health_checker(&mut rt, ping_failures_tolerated);
+        setup_permissive_health_checker(
+            &mut rt,
+            ping_failures_tolerated,
+            Arc::new(RwLock::new(NetworkState::default())),
+        );
 
     let events_f = async move {
         // Trigger ping to a peer. This should do nothing.
@@ -246,7 +253,11 @@ fn ping_success_resets_fail_counter() {
     let failures_triggered = 10;
     let ping_failures_tolerated = 2 * 10;
     let (mut peer_mgr_reqs_rx, mut peer_mgr_notifs_tx, mut ticker_tx) =
-        setup_permissive_health_checker(&mut rt, ping_failures_tolerated);
+        setup_permissive_health_checker(
+            &mut rt,
+            ping_failures_tolerated,
+            Arc::new(RwLock::new(NetworkState::default())),
+        );
 
     let events_f = async move {
         // Trigger ping to a peer. This should do nothing.
@@ -374,3 +385,43 @@ fn ping_timeout() {
     };
     rt.block_on(events_f.boxed().unit_error().compat()).unwrap();
 }
+
+#[test]
+fn ping_state() {
+    ::logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    let ping_failures_tolerated = 1;
+    let network_state = Arc::new(RwLock::new(NetworkState::default()));
+    let (mut peer_mgr_reqs_rx, mut peer_mgr_notifs_tx, mut ticker_tx) =
+        setup_permissive_health_checker(&mut rt, ping_failures_tolerated, network_state.clone());
+
+    let events_f = async move {
+        // Notify HealthChecker of new connected node.
+        let peer_id = PeerId::random();
+        let peer_address = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+        peer_mgr_notifs_tx
+            .send(PeerManagerNotification::NewPeer(
+                peer_id,
+                peer_address.clone(),
+            ))
+            .await
+            .unwrap();
+        // Trigger a successful ping.
+        ticker_tx.send(()).await.unwrap();
+        let listener_substream = expect_open_substream(peer_id, &mut peer_mgr_reqs_rx).await;
+        expect_ping_send_ok(listener_substream).await;
+        // Trigger failing pings until the health checker disconnects from the peer.
+        for _ in 0..=ping_failures_tolerated {
+            ticker_tx.send(()).await.unwrap();
+            let listener_substream = expect_open_substream(peer_id, &mut peer_mgr_reqs_rx).await;
+            expect_ping_send_notok(listener_substream).await;
+        }
+        expect_disconnect(peer_id, &mut peer_mgr_reqs_rx).await;
+        // The round trip time of the successful ping is kept along with the failure count.
+        let state = network_state.read().unwrap();
+        let ping_state = state.pings.get(&peer_id).unwrap();
+        assert!(ping_state.last_rtt.is_some());
+        assert_eq!(ping_state.failures, ping_failures_tolerated + 1);
+    };
+    rt.block_on(events_f.boxed().unit_error().compat()).unwrap();
+}
@@ -31,6 +31,15 @@ pub enum RpcError {
     #[fail(display = "Received unexpected rpc request message; expected remote to half-close.")]
     UnexpectedRpcRequest,
 
+    #[fail(display = "Received invalid streaming rpc credit frame")]
+    InvalidCreditFrame,
+
+    #[fail(display = "Streaming rpc ended without an end-of-stream frame")]
+    TruncatedStream,
+
+    #[fail(display = "Remote aborted the streaming rpc: {}", _0)]
+    RemoteStreamError(String),
+
     #[fail(display = "Application layer unexpectedly dropped response channel")]
     UnexpectedResponseChannelCancel,
 
@@ -51,6 +51,47 @@
 //! 6. Sends the serialized response message to the dialer.
 //! 7. Half-closes their output side to complete the substream close.
 //!
+//! ## Streaming rpc
+//!
+//! Rpc methods registered as streaming rpc protocols reply with a sequence of
+//! response frames instead of a single response message. The dialer controls
+//! the pace of the stream by granting the listener credits, where each credit
+//! allows the listener to send one more response frame. A credit frame is a
+//! 4 byte big-endian unsigned integer of the number of credits being granted.
+//!
+//! Each frame the listener sends starts with a 1 byte tag: a data frame
+//! carries a response, while the last frame of the stream is either an
+//! end-of-stream frame or an error frame carrying the reason the listener
+//! aborted the stream. The end and error frames don't consume credits. A
+//! stream the listener closes without either of them is truncated, e.g., the
+//! listener crashed, which the dialer reports as an error.
+//!
+//! The dialer
+//!
+//! 1. Negotiates a new outbound substream, as for unary rpcs.
+//! 2. Sends the serialized request arguments.
+//! 3. Sends a credit frame with the initial window, i.e., the maximum number of
+//!    response frames the listener may send before hearing from the dialer.
+//! 4. Awaits response frames, granting more credits as the frames are consumed
+//!    by the rpc client. The dialer cancels the stream by half-closing (or
+//!    dropping) its output side before the listener half-closes.
+//! 5. Half-closes their output side once it received the end-of-stream frame.
+//!
+//! The listener
+//!
+//! 1. Awaits the serialized request arguments and the initial credit frame.
+//! 2. Hands the request to the higher layer rpc client, who sends the response
+//!    frames back down to the rpc layer.
+//! 3. Sends each response frame to the dialer, awaiting more credits whenever
+//!    it runs out of them.
+//! 4. Sends the end-of-stream frame once the higher layer completes the
+//!    stream, or an error frame if it fails or times out, and half-closes
+//!    their output side.
+//!
+//! Since the rpc client only observes the stream through bounded channels,
+//! back-pressure propagates from the client on the dialer side all the way to
+//! the client producing the responses on the listener side.
+//!
 //! Note: negotiated substreams are currently framed with the
 //! [muiltiformats unsigned varint length-prefix](https://github.com/multiformats/unsigned-varint)
 //!
@@ -65,20 +106,20 @@ use crate::{
     ProtocolId,
 };
 use bounded_executor::BoundedExecutor;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use channel;
 use error::RpcError;
 use futures::{
-    channel::oneshot,
+    channel::{mpsc, oneshot},
     compat::{Future01CompatExt, Sink01CompatExt},
-    future::{self, FutureExt, TryFutureExt},
+    future::{self, Future, FutureExt, TryFutureExt},
     io::{AsyncRead, AsyncReadExt, AsyncWrite},
     sink::SinkExt,
-    stream::StreamExt,
+    stream::{Stream, StreamExt},
     task::Context,
 };
 use logger::prelude::*;
-use std::{fmt::Debug, io, time::Duration};
+use std::{collections::HashSet, convert::TryFrom, fmt::Debug, io, sync::Arc, time::Duration};
 use tokio::{codec::Framed, prelude::FutureExt as Future01Ext, runtime::TaskExecutor};
 use types::PeerId;
 use unsigned_varint::codec::UviBytes;
@@ -134,12 +175,61 @@ pub struct OutboundRpcRequest {
     pub timeout: Duration,
 }
 
+/// A wrapper struct for an inbound streaming rpc request and its associated
+/// context.
+#[derive(Debug)]
+pub struct InboundStreamingRpcRequest {
+    /// Streaming rpc method identifier, e.g.,
+    /// `/libra/state_synchronizer/streaming-rpc/0.1.0`. This is used to
+    /// dispatch the request to the corresponding client handler.
+    pub protocol: ProtocolId,
+    /// The serialized request data received from the sender.
+    pub data: Bytes,
+    /// Channel over which the serialized response frames are sent from the
+    /// upper client layer to the rpc layer.
+    ///
+    /// The stream completes once the upper layer drops the sender. Sending an
+    /// [`RpcError`] aborts the stream. The rpc layer only pulls frames off this
+    /// channel as the remote peer grants credits, so sending blocks whenever the
+    /// remote is not keeping up. The upper client layer should be prepared for
+    /// the channel to be disconnected, as the remote might cancel the stream.
+    pub res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+}
+
+/// A wrapper struct for an outbound streaming rpc request and its associated
+/// context.
+#[derive(Debug)]
+pub struct OutboundStreamingRpcRequest {
+    /// Streaming rpc method identifier. This is the protocol we will negotiate
+    /// our outbound substream to.
+    pub protocol: ProtocolId,
+    /// The serialized request data to be sent to the receiver.
+    pub data: Bytes,
+    /// Channel over which the response frames are sent from the rpc layer to
+    /// the upper client layer. The channel is closed once the remote has sent
+    /// its last response frame.
+    ///
+    /// If there is an error while performing the rpc protocol, we will send an
+    /// [`RpcError`] over the channel before closing it. Cancellation is done by
+    /// the client dropping the receiver side of the channel.
+    pub res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+    /// The maximum number of response frames the remote may send ahead of the
+    /// client consuming them.
+    pub window: u32,
+    /// The timeout duration for receiving each response frame. If the timeout
+    /// elapses, the rpc layer will send an [`RpcError::TimedOut`] error over
+    /// the `res_tx` channel to the upper client layer.
+    pub timeout: Duration,
+}
+
 /// Events sent from the [`NetworkProvider`](crate::interface::NetworkProvider)
 /// actor to the [`Rpc`] actor.
 #[derive(Debug)]
 pub enum RpcRequest {
     /// Send an outbound rpc request to a remote peer.
     SendRpc(PeerId, OutboundRpcRequest),
+    /// Send an outbound streaming rpc request to a remote peer.
+    SendStreamingRpc(PeerId, OutboundStreamingRpcRequest),
 }
 
 /// Events sent from the [`Rpc`] actor to the
@@ -148,6 +238,8 @@ pub enum RpcRequest {
 pub enum RpcNotification {
     /// A new inbound rpc request has been received from a remote peer.
     RecvRpc(PeerId, InboundRpcRequest),
+    /// A new inbound streaming rpc request has been received from a remote peer.
+    RecvStreamingRpc(PeerId, InboundStreamingRpcRequest),
 }
 
 /// The rpc actor.
@@ -162,7 +254,10 @@ pub struct Rpc<TSubstream> {
     peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
     /// Channels to send notifictions to upstream actors.
     rpc_handler_tx: channel::Sender<RpcNotification>,
-    /// The timeout duration for inbound rpc calls.
+    /// Rpc protocols whose inbound substreams run the streaming rpc protocol.
+    streaming_rpc_protocols: HashSet<ProtocolId>,
+    /// The timeout duration for inbound rpc calls. For streaming rpcs, this is
+    /// the timeout for each step of the protocol.
     inbound_rpc_timeout: Duration,
     /// The maximum number of concurrent outbound rpc requests that we will
     /// service before back-pressure kicks in.
@@ -185,6 +280,7 @@ where
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         rpc_handler_tx: channel::Sender<RpcNotification>,
+        streaming_rpc_protocols: Vec<ProtocolId>,
         inbound_rpc_timeout: Duration,
         max_concurrent_outbound_rpcs: u32,
         max_concurrent_inbound_rpcs: u32,
@@ -195,6 +291,7 @@ where
             peer_mgr_notifs_rx,
             peer_mgr_reqs_tx,
             rpc_handler_tx,
+            streaming_rpc_protocols: streaming_rpc_protocols.into_iter().collect(),
             inbound_rpc_timeout,
             max_concurrent_outbound_rpcs,
             max_concurrent_inbound_rpcs,
@@ -209,6 +306,7 @@ where
         let peer_mgr_notifs_rx = self.peer_mgr_notifs_rx;
         let peer_mgr_reqs_tx = self.peer_mgr_reqs_tx;
         let rpc_handler_tx = self.rpc_handler_tx;
+        let streaming_rpc_protocols = Arc::new(self.streaming_rpc_protocols);
         let inbound_rpc_timeout = self.inbound_rpc_timeout;
         let max_concurrent_outbound_rpcs = self.max_concurrent_outbound_rpcs;
         let max_concurrent_inbound_rpcs = self.max_concurrent_inbound_rpcs;
@@ -227,6 +325,7 @@ where
             BoundedExecutor::new(max_concurrent_inbound_rpcs as usize, executor),
             peer_mgr_notifs_rx,
             rpc_handler_tx,
+            streaming_rpc_protocols,
             inbound_rpc_timeout,
         );
 
@@ -257,18 +356,35 @@ async fn handle_inbounds<TSubstream>(
     executor: BoundedExecutor,
     mut peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
     rpc_handler_tx: channel::Sender<RpcNotification>,
+    streaming_rpc_protocols: Arc<HashSet<ProtocolId>>,
     inbound_rpc_timeout: Duration,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static,
 {
     while let Some(notif) = peer_mgr_notifs_rx.next().await {
-        executor
-            .spawn(handle_inbound_substream(
-                rpc_handler_tx.clone(),
-                notif,
-                inbound_rpc_timeout,
-            ))
-            .await;
+        let is_streaming = match &notif {
+            PeerManagerNotification::NewInboundSubstream(_, substream) => {
+                streaming_rpc_protocols.contains(&substream.protocol)
+            }
+            _ => false,
+        };
+        if is_streaming {
+            executor
+                .spawn(handle_inbound_streaming_substream(
+                    rpc_handler_tx.clone(),
+                    notif,
+                    inbound_rpc_timeout,
+                ))
+                .await;
+        } else {
+            executor
+                .spawn(handle_inbound_substream(
+                    rpc_handler_tx.clone(),
+                    notif,
+                    inbound_rpc_timeout,
+                ))
+                .await;
+        }
     }
 }
 
@@ -333,6 +449,9 @@ async fn handle_outbound_rpc<TSubstream>(
                 },
             }
         }
+        RpcRequest::SendStreamingRpc(peer_id, req) => {
+            handle_outbound_streaming_rpc(peer_mgr_tx, peer_id, req).await;
+        }
     }
 }
 
@@ -473,3 +592,327 @@ where
 
     Ok(())
 }
+
+/// Handle an outbound streaming rpc request. Open a new substream then run the
+/// outbound streaming rpc protocol over the substream.
+///
+/// The response frames are forwarded to the rpc client through the
+/// [`req.res_tx`] channel, followed by an error if the protocol fails.
+/// Cancellation is done by the client dropping the receiver side of the
+/// [`req.res_tx`] channel, which is noticed when forwarding the next frame.
+///
+/// [`req.res_tx`]: OutboundStreamingRpcRequest::res_tx
+async fn handle_outbound_streaming_rpc<TSubstream>(
+    peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_id: PeerId,
+    req: OutboundStreamingRpcRequest,
+) where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let mut res_tx = req.res_tx;
+    let res = handle_outbound_streaming_rpc_inner(
+        peer_mgr_tx,
+        peer_id,
+        req.protocol,
+        req.data,
+        req.window,
+        req.timeout,
+        &mut res_tx,
+    )
+    .await;
+    match res {
+        Ok(()) => {}
+        Err(RpcError::UnexpectedResponseChannelCancel) => {
+            counters::RPC_REQUESTS_CANCELLED.inc();
+            debug!(
+                "Rpc client canceled outbound streaming rpc call to {}",
+                peer_id.short_str()
+            );
+        }
+        Err(err) => {
+            counters::RPC_REQUESTS_FAILED.inc();
+            warn!(
+                "Error making outbound streaming rpc request to {}: {:?}",
+                peer_id.short_str(),
+                err
+            );
+            // Propagate the error to the rpc client layer.
+            if res_tx.send(Err(err)).await.is_err() {
+                counters::RPC_REQUESTS_CANCELLED.inc();
+            }
+        }
+    }
+}
+
+async fn handle_outbound_streaming_rpc_inner<TSubstream>(
+    mut peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    req_data: Bytes,
+    window: u32,
+    timeout: Duration,
+    res_tx: &mut mpsc::Sender<Result<Bytes, RpcError>>,
+) -> Result<(), RpcError>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    // Request a new substream with the peer.
+    let substream = with_timeout(
+        async {
+            let substream = peer_mgr_tx.open_substream(peer_id, protocol).await?;
+            Ok::<_, RpcError>(substream)
+        },
+        timeout,
+    )
+    .await?;
+    // Rpc messages are length-prefixed.
+    let mut substream = Framed::new(substream.compat(), UviBytes::default()).sink_compat();
+    // Send the rpc request data, followed by the initial credits.
+    let req_len = req_data.len();
+    substream.buffered_send(req_data).await?;
+    substream.send(encode_credits(window)).await?;
+    counters::RPC_REQUESTS_SENT.inc();
+    counters::RPC_REQUEST_BYTES_SENT.inc_by(req_len as i64);
+
+    // Grant credits back in batches, once half of the window has been consumed.
+    let credit_batch = std::cmp::max(window / 2, 1);
+    let mut credits = window;
+    let mut consumed = 0;
+    // Receive response frames until the listener ends the stream.
+    loop {
+        let frame = match with_timeout(next_frame(&mut substream), timeout).await? {
+            Some(frame) => decode_stream_frame(frame)?,
+            // The listener half-closed their side without ending the stream.
+            None => return Err(RpcError::TruncatedStream),
+        };
+        let res_data = match frame {
+            StreamFrame::Data(res_data) => res_data,
+            StreamFrame::End => break,
+            StreamFrame::Error(reason) => return Err(RpcError::RemoteStreamError(reason)),
+        };
+        if credits == 0 {
+            // Remote should never send more frames than it has credits for; we'll
+            // consider this a protocol violation and abort the stream.
+            return Err(RpcError::UnexpectedRpcResponse);
+        }
+        credits -= 1;
+        // Forward the frame to the rpc client. This waits for the client to make
+        // room for it in the channel, which holds back the next credits.
+        res_tx
+            .send(Ok(res_data))
+            .await
+            .map_err(|_| RpcError::UnexpectedResponseChannelCancel)?;
+        consumed += 1;
+        if consumed == credit_batch {
+            substream.send(encode_credits(consumed)).await?;
+            credits += consumed;
+            consumed = 0;
+        }
+    }
+
+    // The stream is complete; half-close our side as well.
+    substream.close().await?;
+    Ok(())
+}
+
+/// Handle a new inbound substream of a streaming rpc protocol. Run the inbound
+/// streaming rpc protocol over the substream.
+async fn handle_inbound_streaming_substream<TSubstream>(
+    notification_tx: channel::Sender<RpcNotification>,
+    notif: PeerManagerNotification<TSubstream>,
+    timeout: Duration,
+) where
+    TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin,
+{
+    match notif {
+        PeerManagerNotification::NewInboundSubstream(peer_id, substream) => {
+            // Run the actual inbound streaming rpc protocol.
+            let res = handle_inbound_streaming_substream_inner(
+                notification_tx,
+                peer_id,
+                substream.protocol,
+                substream.substream,
+                timeout,
+            )
+            .await;
+
+            // Log any errors.
+            if let Err(err) = res {
+                counters::RPC_RESPONSES_FAILED.inc();
+                warn!(
+                    "Error handling inbound streaming rpc request from {}: {:?}",
+                    peer_id.short_str(),
+                    err
+                );
+            }
+        }
+        notif => unreachable!(
+            "Received unexpected event from PeerManager: {:?}, expected NewInboundSubstream",
+            notif
+        ),
+    }
+}
+
+async fn handle_inbound_streaming_substream_inner<TSubstream>(
+    mut notification_tx: channel::Sender<RpcNotification>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    substream: TSubstream,
+    timeout: Duration,
+) -> Result<(), RpcError>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    // Rpc messages are length-prefixed.
+    let mut substream = Framed::new(substream.compat(), UviBytes::default()).sink_compat();
+    // Read the rpc request data, followed by the initial credits.
+    let req_data = match with_timeout(next_frame(&mut substream), timeout).await? {
+        Some(req_data) => req_data,
+        None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    };
+    let mut credits = match with_timeout(next_frame(&mut substream), timeout).await? {
+        Some(credit_data) => decode_credits(&credit_data)?,
+        None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    };
+    counters::RPC_REQUESTS_RECEIVED.inc();
+
+    // Build the event and context we push up to upper layers for handling.
+    let (res_tx, mut res_rx) = mpsc::channel(1);
+    let notification = RpcNotification::RecvStreamingRpc(
+        peer_id,
+        InboundStreamingRpcRequest {
+            protocol,
+            data: req_data,
+            res_tx,
+        },
+    );
+    // TODO(philiphayes): impl correct shutdown process so this never panics
+    // Forward request to upper layer.
+    notification_tx.send(notification).await.unwrap();
+
+    // Send response frames until the upper layer drops its sender. Resolves to
+    // false if the dialer canceled the stream.
+    let res = async {
+        while let Some(res_data) =
+            with_timeout(async { Ok::<_, RpcError>(res_rx.next().await) }, timeout).await?
+        {
+            let res_data = res_data?;
+            while credits == 0 {
+                // Wait for the dialer to consume some of the frames we already sent.
+                match with_timeout(next_frame(&mut substream), timeout).await? {
+                    Some(credit_data) => credits = decode_credits(&credit_data)?,
+                    None => {
+                        // The dialer half-closed their side, canceling the stream.
+                        debug!(
+                            "Remote peer {} canceled inbound streaming rpc",
+                            peer_id.short_str()
+                        );
+                        return Ok(false);
+                    }
+                }
+            }
+            credits -= 1;
+            let res_len = res_data.len();
+            substream
+                .send(encode_stream_frame(STREAM_DATA_FRAME, &res_data))
+                .await?;
+            counters::RPC_RESPONSES_SENT.inc();
+            counters::RPC_RESPONSE_BYTES_SENT.inc_by(res_len as i64);
+        }
+        Ok::<_, RpcError>(true)
+    }
+        .await;
+
+    match res {
+        Ok(true) => {
+            substream
+                .send(encode_stream_frame(STREAM_END_FRAME, &[]))
+                .await?;
+        }
+        Ok(false) => return Ok(()),
+        Err(err) => {
+            // Let the dialer know the stream is incomplete. This is best effort,
+            // as the substream itself might be what failed.
+            let reason = err.to_string();
+            let _ = substream
+                .send(encode_stream_frame(STREAM_ERROR_FRAME, reason.as_bytes()))
+                .await;
+            let _ = substream.close().await;
+            return Err(err);
+        }
+    }
+
+    // We won't send anything else on this substream, so we can half-close our
+    // output. The dialer half-closes their side once they received everything.
+    substream.close().await?;
+    Ok(())
+}
+
+/// Read the next frame from the substream, if any.
+async fn next_frame<TStream>(substream: &mut TStream) -> Result<Option<Bytes>, RpcError>
+where
+    TStream: Stream<Item = Result<BytesMut, io::Error>> + Unpin,
+{
+    match substream.next().await {
+        Some(data) => Ok(Some(data?.freeze())),
+        None => Ok(None),
+    }
+}
+
+/// Run `f` with the given `timeout`, failing with [`RpcError::TimedOut`] if it
+/// elapses.
+async fn with_timeout<F, T>(f: F, timeout: Duration) -> Result<T, RpcError>
+where
+    F: Future<Output = Result<T, RpcError>> + Send,
+{
+    f.boxed()
+        .compat()
+        .timeout(timeout)
+        .compat()
+        // Convert tokio timeout::Error to RpcError
+        .map_err(Into::<RpcError>::into)
+        .await
+}
+
+/// Tags of the frames the listener sends on a streaming rpc substream.
+const STREAM_DATA_FRAME: u8 = 0;
+const STREAM_END_FRAME: u8 = 1;
+const STREAM_ERROR_FRAME: u8 = 2;
+
+/// A frame the listener sends on a streaming rpc substream.
+#[derive(Debug, PartialEq)]
+enum StreamFrame {
+    /// A response frame.
+    Data(Bytes),
+    /// The stream is complete.
+    End,
+    /// The listener aborted the stream for the given reason.
+    Error(String),
+}
+
+fn encode_stream_frame(tag: u8, data: &[u8]) -> Bytes {
+    let mut frame = Vec::with_capacity(1 + data.len());
+    frame.push(tag);
+    frame.extend_from_slice(data);
+    Bytes::from(frame)
+}
+
+fn decode_stream_frame(frame: Bytes) -> Result<StreamFrame, RpcError> {
+    match frame.first() {
+        Some(&STREAM_DATA_FRAME) => Ok(StreamFrame::Data(frame.slice_from(1))),
+        Some(&STREAM_END_FRAME) if frame.len() == 1 => Ok(StreamFrame::End),
+        Some(&STREAM_ERROR_FRAME) => Ok(StreamFrame::Error(
+            String::from_utf8_lossy(&frame[1..]).into_owned(),
+        )),
+        _ => Err(RpcError::InvalidRpcResponse),
+    }
+}
+
+fn encode_credits(credits: u32) -> Bytes {
+    Bytes::from(&credits.to_be_bytes()[..])
+}
+
+fn decode_credits(data: &[u8]) -> Result<u32, RpcError> {
+    let credits = <[u8; 4]>::try_from(data).map_err(|_| RpcError::InvalidCreditFrame)?;
+    Ok(u32::from_be_bytes(credits))
+}
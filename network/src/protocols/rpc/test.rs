@@ -78,6 +78,7 @@ fn upgrades() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -291,6 +292,7 @@ fn dialer_close_before_listener_send() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -623,6 +625,7 @@ fn rpc_protocol() {
         dialer_peer_mgr_notifs_rx,
         dialer_peer_mgr_reqs_tx,
         rpc_handler_tx,
+        vec![],
         Duration::from_millis(500),
         10,
         10,
@@ -674,6 +677,7 @@ fn rpc_protocol() {
         listener_peer_mgr_notifs_rx,
         listener_peer_mgr_reqs_tx,
         listener_rpc_notifs_tx,
+        vec![],
         Duration::from_millis(500),
         10,
         10,
@@ -702,6 +706,7 @@ fn rpc_protocol() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -713,3 +718,306 @@ fn rpc_protocol() {
     );
     rt.block_on(f.boxed().unit_error().compat()).unwrap();
 }
+
+// Test the streaming rpc substream upgrades.
+#[test]
+fn streaming_upgrades() {
+    ::logger::try_init_for_testing();
+
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    let protocol_id = b"/get_chunks/1.0.0";
+    let req_data = b"hello";
+    let res_frames: Vec<&'static [u8]> = vec![&b"one"[..], b"two", b"three", b"four", b"five"];
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+
+    // Fake the dialer NetworkProvider
+    let (dialer_peer_mgr_reqs_tx, dialer_peer_mgr_reqs_rx) = channel::new_test(8);
+    let dialer_peer_mgr_reqs_tx = PeerManagerRequestSender::new(dialer_peer_mgr_reqs_tx);
+    let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
+
+    // Fake the listener NetworkProvider
+    let (listener_rpc_notifs_tx, mut listener_rpc_notifs_rx) = channel::new_test(8);
+    let listener_res_frames = res_frames.clone();
+    let f_listener_network = async move {
+        // Handle the inbound streaming rpc request
+        match listener_rpc_notifs_rx.next().await.unwrap() {
+            RpcNotification::RecvStreamingRpc(peer_id, req) => {
+                assert_eq!(peer_id, dialer_peer_id);
+                assert_eq!(req.protocol.as_ref(), protocol_id);
+                assert_eq!(req.data.as_ref(), req_data);
+                let mut res_tx = req.res_tx;
+                for frame in listener_res_frames {
+                    res_tx.send(Ok(Bytes::from_static(frame))).await.unwrap();
+                }
+                // Dropping the sender completes the stream.
+            }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
+        }
+    };
+
+    let substream = NegotiatedSubstream {
+        protocol: ProtocolId::from_static(protocol_id),
+        substream: listener_substream,
+    };
+    let inbound_notif = PeerManagerNotification::NewInboundSubstream(dialer_peer_id, substream);
+
+    // Handle the inbound substream
+    let f_listener_upgrade = handle_inbound_streaming_substream(
+        listener_rpc_notifs_tx,
+        inbound_notif,
+        Duration::from_millis(500),
+    );
+
+    // Make an outbound streaming rpc request, with a window smaller than the
+    // number of response frames.
+    let f_dialer_upgrade = async move {
+        let (res_tx, res_rx) = mpsc::channel(1);
+        let req = OutboundStreamingRpcRequest {
+            protocol: ProtocolId::from_static(protocol_id),
+            data: Bytes::from_static(req_data),
+            res_tx,
+            window: 2,
+            timeout: Duration::from_secs(1),
+        };
+        let f_rpc = handle_outbound_streaming_rpc(dialer_peer_mgr_reqs_tx, listener_peer_id, req);
+        let (_, frames) = join(f_rpc, res_rx.collect::<Vec<_>>()).await;
+
+        // Check the rpc response frames
+        let frames: Vec<_> = frames.into_iter().map(Result::unwrap).collect();
+        let expected: Vec<_> = res_frames.into_iter().map(Bytes::from_static).collect();
+        assert_eq!(frames, expected);
+    };
+
+    let f = join4(
+        f_dialer_peer_mgr,
+        f_dialer_upgrade,
+        f_listener_network,
+        f_listener_upgrade,
+    );
+    Runtime::new()
+        .unwrap()
+        .block_on(f.boxed().unit_error().compat())
+        .unwrap();
+}
+
+// Test that dropping the response stream on the dialer side cancels the stream
+// on both ends.
+#[test]
+fn streaming_outbound_cancellation() {
+    ::logger::try_init_for_testing();
+
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    let protocol_id = b"/get_chunks/1.0.0";
+    let req_data = b"hello";
+    let res_data = b"chunk";
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+
+    // Fake the dialer NetworkProvider
+    let (dialer_peer_mgr_reqs_tx, dialer_peer_mgr_reqs_rx) = channel::new_test(8);
+    let dialer_peer_mgr_reqs_tx = PeerManagerRequestSender::new(dialer_peer_mgr_reqs_tx);
+    let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
+
+    // Fake the listener NetworkProvider, which keeps sending frames until the
+    // stream is canceled.
+    let (listener_rpc_notifs_tx, mut listener_rpc_notifs_rx) = channel::new_test(8);
+    let f_listener_network = async move {
+        match listener_rpc_notifs_rx.next().await.unwrap() {
+            RpcNotification::RecvStreamingRpc(_peer_id, req) => {
+                let mut res_tx = req.res_tx;
+                let mut num_frames = 0;
+                while res_tx.send(Ok(Bytes::from_static(res_data))).await.is_ok() {
+                    num_frames += 1;
+                    assert!(num_frames < 10, "listener: stream was not canceled");
+                }
+            }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
+        }
+    };
+
+    let substream = NegotiatedSubstream {
+        protocol: ProtocolId::from_static(protocol_id),
+        substream: listener_substream,
+    };
+    let inbound_notif = PeerManagerNotification::NewInboundSubstream(dialer_peer_id, substream);
+    let f_listener_upgrade = handle_inbound_streaming_substream(
+        listener_rpc_notifs_tx,
+        inbound_notif,
+        Duration::from_millis(500),
+    );
+
+    // Make an outbound streaming rpc request, but drop the response stream
+    // after the first frame.
+    let f_dialer_upgrade = async move {
+        let (res_tx, mut res_rx) = mpsc::channel(1);
+        let req = OutboundStreamingRpcRequest {
+            protocol: ProtocolId::from_static(protocol_id),
+            data: Bytes::from_static(req_data),
+            res_tx,
+            window: 1,
+            timeout: Duration::from_secs(1),
+        };
+        let f_rpc = handle_outbound_streaming_rpc(dialer_peer_mgr_reqs_tx, listener_peer_id, req);
+        let f_client = async move {
+            let frame = res_rx.next().await.unwrap().unwrap();
+            assert_eq!(frame.as_ref(), res_data);
+            drop(res_rx);
+        };
+        join(f_rpc, f_client).await;
+    };
+
+    let f = join4(
+        f_dialer_peer_mgr,
+        f_dialer_upgrade,
+        f_listener_network,
+        f_listener_upgrade,
+    );
+    Runtime::new()
+        .unwrap()
+        .block_on(f.boxed().unit_error().compat())
+        .unwrap();
+}
+
+// Test that an error in the listener's upper layer fails the stream on the
+// dialer side, rather than ending it as if it were complete.
+#[test]
+fn streaming_listener_error() {
+    ::logger::try_init_for_testing();
+
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    let protocol_id = b"/get_chunks/1.0.0";
+    let req_data = b"hello";
+    let res_data = b"chunk";
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+
+    // Fake the dialer NetworkProvider
+    let (dialer_peer_mgr_reqs_tx, dialer_peer_mgr_reqs_rx) = channel::new_test(8);
+    let dialer_peer_mgr_reqs_tx = PeerManagerRequestSender::new(dialer_peer_mgr_reqs_tx);
+    let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
+
+    // Fake the listener NetworkProvider, which fails after the first frame.
+    let (listener_rpc_notifs_tx, mut listener_rpc_notifs_rx) = channel::new_test(8);
+    let f_listener_network = async move {
+        match listener_rpc_notifs_rx.next().await.unwrap() {
+            RpcNotification::RecvStreamingRpc(_peer_id, req) => {
+                let mut res_tx = req.res_tx;
+                res_tx.send(Ok(Bytes::from_static(res_data))).await.unwrap();
+                res_tx
+                    .send(Err(RpcError::ApplicationError(::failure::format_err!(
+                        "db error"
+                    ))))
+                    .await
+                    .unwrap();
+            }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
+        }
+    };
+
+    let substream = NegotiatedSubstream {
+        protocol: ProtocolId::from_static(protocol_id),
+        substream: listener_substream,
+    };
+    let inbound_notif = PeerManagerNotification::NewInboundSubstream(dialer_peer_id, substream);
+    let f_listener_upgrade = handle_inbound_streaming_substream(
+        listener_rpc_notifs_tx,
+        inbound_notif,
+        Duration::from_millis(500),
+    );
+
+    let f_dialer_upgrade = async move {
+        let (res_tx, res_rx) = mpsc::channel(1);
+        let req = OutboundStreamingRpcRequest {
+            protocol: ProtocolId::from_static(protocol_id),
+            data: Bytes::from_static(req_data),
+            res_tx,
+            window: 2,
+            timeout: Duration::from_secs(1),
+        };
+        let f_rpc = handle_outbound_streaming_rpc(dialer_peer_mgr_reqs_tx, listener_peer_id, req);
+        let (_, mut frames) = join(f_rpc, res_rx.collect::<Vec<_>>()).await;
+
+        // The frame sent before the error is delivered, followed by the error.
+        assert_eq!(frames.len(), 2);
+        match frames.pop().unwrap() {
+            Err(RpcError::RemoteStreamError(_)) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert_eq!(frames.pop().unwrap().unwrap().as_ref(), res_data);
+    };
+
+    let f = join4(
+        f_dialer_peer_mgr,
+        f_dialer_upgrade,
+        f_listener_network,
+        f_listener_upgrade,
+    );
+    Runtime::new()
+        .unwrap()
+        .block_on(f.boxed().unit_error().compat())
+        .unwrap();
+}
+
+// Test that a stream the listener closes without an end-of-stream frame is
+// reported as truncated.
+#[test]
+fn streaming_truncated() {
+    ::logger::try_init_for_testing();
+
+    let listener_peer_id = PeerId::random();
+    let protocol_id = b"/get_chunks/1.0.0";
+    let req_data = b"hello";
+    let res_data = b"chunk";
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+
+    // Fake the dialer NetworkProvider
+    let (dialer_peer_mgr_reqs_tx, dialer_peer_mgr_reqs_rx) = channel::new_test(8);
+    let dialer_peer_mgr_reqs_tx = PeerManagerRequestSender::new(dialer_peer_mgr_reqs_tx);
+    let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
+
+    // Fake a listener that sends one data frame and then closes the substream.
+    let f_listener = async move {
+        let mut substream =
+            Framed::new(listener_substream.compat(), UviBytes::default()).sink_compat();
+        let req = next_frame(&mut substream).await.unwrap().unwrap();
+        assert_eq!(req.as_ref(), req_data);
+        let credits = next_frame(&mut substream).await.unwrap().unwrap();
+        assert_eq!(decode_credits(&credits).unwrap(), 2);
+        substream
+            .send(encode_stream_frame(STREAM_DATA_FRAME, res_data))
+            .await
+            .unwrap();
+        substream.close().await.unwrap();
+    };
+
+    let f_dialer_upgrade = async move {
+        let (res_tx, res_rx) = mpsc::channel(1);
+        let req = OutboundStreamingRpcRequest {
+            protocol: ProtocolId::from_static(protocol_id),
+            data: Bytes::from_static(req_data),
+            res_tx,
+            window: 2,
+            timeout: Duration::from_secs(1),
+        };
+        let f_rpc = handle_outbound_streaming_rpc(dialer_peer_mgr_reqs_tx, listener_peer_id, req);
+        let (_, mut frames) = join(f_rpc, res_rx.collect::<Vec<_>>()).await;
+
+        assert_eq!(frames.len(), 2);
+        match frames.pop().unwrap() {
+            Err(RpcError::TruncatedStream) => {}
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert_eq!(frames.pop().unwrap().unwrap().as_ref(), res_data);
+    };
+
+    let f = join3(f_dialer_peer_mgr, f_dialer_upgrade, f_listener);
+    Runtime::new()
+        .unwrap()
+        .block_on(f.boxed().unit_error().compat())
+        .unwrap();
+}
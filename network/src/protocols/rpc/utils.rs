@@ -1,9 +1,14 @@
 use crate::{
     interface::NetworkRequest,
-    protocols::rpc::{error::RpcError, OutboundRpcRequest},
+    protocols::rpc::{error::RpcError, OutboundRpcRequest, OutboundStreamingRpcRequest},
     ProtocolId,
 };
-use futures::{channel::oneshot, SinkExt};
+use bytes::Bytes;
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::Map,
+    SinkExt, StreamExt,
+};
 use protobuf::Message;
 use std::time::Duration;
 use types::PeerId;
@@ -36,3 +41,48 @@ pub async fn unary_rpc<T: Message>(
     let res_msg = ::protobuf::parse_from_bytes(res_data.as_ref())?;
     Ok(res_msg)
 }
+
+/// Stream of the deserialized response messages of a streaming rpc, as returned by
+/// [`streaming_rpc`]. The stream ends after the last response message, or after the first error.
+pub type RpcResponseStream<T> = Map<
+    mpsc::Receiver<Result<Bytes, RpcError>>,
+    fn(Result<Bytes, RpcError>) -> Result<T, RpcError>,
+>;
+
+/// Send a streaming rpc request to remote peer `recipient`. Handles serialization and
+/// deserialization of the message types, assuming that the request and responses all have the
+/// same message type.
+///
+/// At most `window` response messages are in flight before the returned stream is polled, and
+/// `timeout` applies to receiving each response message. Dropping the stream cancels the request.
+pub async fn streaming_rpc<T: Message>(
+    mut inner: channel::Sender<NetworkRequest>,
+    recipient: PeerId,
+    protocol: ProtocolId,
+    req_msg: T,
+    window: u32,
+    timeout: Duration,
+) -> Result<RpcResponseStream<T>, RpcError> {
+    // serialize request
+    let req_data = req_msg.write_to_bytes()?.into();
+
+    // ask network to fulfill streaming rpc request
+    let (res_tx, res_rx) = mpsc::channel(1);
+    let req = OutboundStreamingRpcRequest {
+        protocol,
+        data: req_data,
+        res_tx,
+        window,
+        timeout,
+    };
+    inner
+        .send(NetworkRequest::SendStreamingRpc(recipient, req))
+        .await?;
+    // deserialize responses as they arrive
+    Ok(res_rx.map(parse_response::<T> as fn(_) -> _))
+}
+
+fn parse_response<T: Message>(res_data: Result<Bytes, RpcError>) -> Result<T, RpcError> {
+    let res_msg = ::protobuf::parse_from_bytes(res_data?.as_ref())?;
+    Ok(res_msg)
+}
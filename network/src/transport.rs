@@ -2,17 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    common::NetworkPublicKeys,
+    common::{IdentityKeyRotations, NetworkPublicKeys},
     protocols::identity::{exchange_identity, Identity},
 };
 use crypto::{
-    x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    x25519::X25519StaticPublicKey,
     ValidKey,
 };
 use logger::prelude::*;
 use netcore::{
     multiplexing::{yamux::Yamux, StreamMultiplexer},
-    transport::{boxed, memory, tcp, TransportExt},
+    transport::{boxed, memory, quic, tcp, TransportExt},
 };
 use noise::NoiseConfig;
 use std::{
@@ -22,17 +23,48 @@ use std::{
     sync::{Arc, RwLock},
     time::Duration,
 };
+use tokio::runtime::TaskExecutor;
 use types::PeerId;
 
 /// A timeout for the connection to open and complete all of the upgrade steps.
 const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 
+// Identity keys rotated by peers take precedence over the ones in the trusted peer set.
+// The key rotations are always locked before the trusted peers (see the ConnectivityManager).
 fn identity_key_to_peer_id(
     trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
+    key_rotations: &RwLock<IdentityKeyRotations>,
     remote_static_key: &[u8],
 ) -> Option<PeerId> {
+    let key_rotations = key_rotations.read().unwrap();
     for (peer_id, public_keys) in trusted_peers.read().unwrap().iter() {
-        if public_keys.identity_public_key.to_bytes() == remote_static_key {
+        if key_rotations.is_valid_key(peer_id, &public_keys.identity_public_key, remote_static_key)
+        {
+            return Some(*peer_id);
+        }
+    }
+
+    None
+}
+
+// Trusted peer owning both the given signing and identity keys, if any. Identity keys rotated by
+// peers take precedence over the ones in the trusted peer set. Same lock order as
+// `identity_key_to_peer_id`.
+fn network_keys_to_peer_id(
+    trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
+    key_rotations: &RwLock<IdentityKeyRotations>,
+    remote_signing_key: &[u8],
+    remote_static_key: &[u8],
+) -> Option<PeerId> {
+    let key_rotations = key_rotations.read().unwrap();
+    for (peer_id, public_keys) in trusted_peers.read().unwrap().iter() {
+        if public_keys.signing_public_key.to_bytes()[..] == *remote_signing_key
+            && key_rotations.is_valid_key(
+                peer_id,
+                &public_keys.identity_public_key,
+                remote_static_key,
+            )
+        {
             return Some(*peer_id);
         }
     }
@@ -80,18 +112,20 @@ fn check_role(own_identity: &Identity, other_identity: Identity) -> Result<Ident
 
 pub fn build_memory_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let memory_transport = memory::MemoryTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
 
     memory_transport
         .and_then(move |socket, origin| {
             async move {
                 let (remote_static_key, socket) =
                     noise_config.upgrade_connection(socket, origin).await?;
-                if let Some(peer_id) = identity_key_to_peer_id(&trusted_peers, &remote_static_key) {
+                if let Some(peer_id) =
+                    identity_key_to_peer_id(&trusted_peers, &key_rotations, &remote_static_key)
+                {
                     Ok((peer_id, socket))
                 } else {
                     Err(io::Error::new(io::ErrorKind::Other, "Not a trusted peer"))
@@ -141,18 +175,20 @@ pub fn build_memory_transport(
 //TODO(bmwill) Maybe create an Either Transport so we can merge the building of Memory + Tcp
 pub fn build_tcp_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let tcp_transport = tcp::TcpTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
 
     tcp_transport
         .and_then(move |socket, origin| {
             async move {
                 let (remote_static_key, socket) =
                     noise_config.upgrade_connection(socket, origin).await?;
-                if let Some(peer_id) = identity_key_to_peer_id(&trusted_peers, &remote_static_key) {
+                if let Some(peer_id) =
+                    identity_key_to_peer_id(&trusted_peers, &key_rotations, &remote_static_key)
+                {
                     Ok((peer_id, socket))
                 } else {
                     security_log(SecurityEvent::InvalidNetworkPeer)
@@ -185,10 +221,9 @@ pub fn build_tcp_noise_transport(
 // Transport based on TCP + Noise, but permissionless -- i.e., any node is allowed to connect.
 pub fn build_permissionless_tcp_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let tcp_transport = tcp::TcpTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
     tcp_transport
         .and_then(move |socket, origin| {
             async move {
@@ -244,3 +279,87 @@ pub fn build_tcp_transport(
         .with_timeout(TRANSPORT_TIMEOUT)
         .boxed()
 }
+
+pub fn build_quic_transport(
+    own_identity: Identity,
+    signing_keys: (&Ed25519PrivateKey, &Ed25519PublicKey),
+    own_identity_public_key: &X25519StaticPublicKey,
+    trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
+    executor: TaskExecutor,
+) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
+    // Only connect to the trusted peers, identified by both their signing and identity keys.
+    let verify_peer: quic::PeerKeyVerifier = {
+        let trusted_peers = trusted_peers.clone();
+        let key_rotations = key_rotations.clone();
+        Arc::new(
+            move |signing_public_key: &[u8], identity_public_key: &[u8]| {
+                let trusted = network_keys_to_peer_id(
+                    &trusted_peers,
+                    &key_rotations,
+                    signing_public_key,
+                    identity_public_key,
+                )
+                .is_some();
+                if !trusted {
+                    security_log(SecurityEvent::InvalidNetworkPeer)
+                        .error("UntrustedPeer")
+                        .data(&trusted_peers)
+                        .data(&identity_public_key)
+                        .log();
+                }
+                trusted
+            },
+        )
+    };
+    let quic_transport = quic::QuicTransport::new(
+        executor,
+        &ed25519_pkcs8(signing_keys.0, signing_keys.1),
+        &own_identity_public_key.to_bytes(),
+        verify_peer,
+    )
+    .expect("Unable to create the QUIC transport");
+
+    // QUIC connections are multiplexed natively, so there is no need to upgrade them with Yamux.
+    quic_transport
+        .and_then(move |muxer, _origin| {
+            async move {
+                // Both sides verified each other's keys during the handshake, the peer is the
+                // trusted peer owning them.
+                let keys = muxer.peer_keys();
+                if let Some(peer_id) = network_keys_to_peer_id(
+                    &trusted_peers,
+                    &key_rotations,
+                    &keys.signing_public_key,
+                    &keys.static_public_key,
+                ) {
+                    Ok((peer_id, muxer))
+                } else {
+                    Err(io::Error::new(io::ErrorKind::Other, "Not a trusted peer"))
+                }
+            }
+        })
+        .and_then(move |(peer_id, muxer), origin| {
+            async move {
+                let (identity, muxer) = exchange_identity(&own_identity, muxer, origin).await?;
+                match_peer_id(identity, peer_id)
+                    .and_then(|identity| check_role(&own_identity, identity))
+                    .and_then(|identity| Ok((identity, muxer)))
+            }
+        })
+        .with_timeout(TRANSPORT_TIMEOUT)
+        .boxed()
+}
+
+/// PKCS#8 (v2) encoding of an Ed25519 key pair, which the QUIC transport issues its certificate
+/// for.
+fn ed25519_pkcs8(private_key: &Ed25519PrivateKey, public_key: &Ed25519PublicKey) -> Vec<u8> {
+    let mut pkcs8 = vec![
+        0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04,
+        0x20,
+    ];
+    pkcs8.extend_from_slice(&private_key.to_bytes());
+    pkcs8.extend_from_slice(&[0xa1, 0x23, 0x03, 0x21, 0x00]);
+    pkcs8.extend_from_slice(&public_key.to_bytes());
+    pkcs8
+}
@@ -6,7 +6,7 @@
 use crate::{
     error::NetworkError,
     interface::{NetworkNotification, NetworkRequest},
-    proto::{ConsensusMsg, RequestBlock, RespondBlock},
+    proto::{ConsensusMsg, RequestBatch, RequestBlock, RespondBatch, RespondBlock},
     protocols::{
         direct_send::Message,
         rpc::{self, error::RpcError},
@@ -16,6 +16,7 @@ use crate::{
 };
 use bytes::Bytes;
 use channel;
+use crypto::x25519::{X25519StaticPrivateKey, X25519StaticPublicKey};
 use futures::{
     stream::Map,
     task::{Context, Poll},
@@ -65,6 +66,15 @@ impl ConsensusNetworkEvents {
                 let req_msg = ::protobuf::parse_from_bytes(rpc_req.data.as_ref())?;
                 Ok(Event::RpcRequest((peer_id, req_msg, rpc_req.res_tx)))
             }
+            NetworkNotification::RecvStreamingRpc(peer_id, rpc_req) => {
+                // Consensus doesn't serve streaming rpcs; the upper layer rejects them.
+                let req_msg = ::protobuf::parse_from_bytes(rpc_req.data.as_ref())?;
+                Ok(Event::StreamingRpcRequest((
+                    peer_id,
+                    req_msg,
+                    rpc_req.res_tx,
+                )))
+            }
             NetworkNotification::RecvMessage(peer_id, msg) => {
                 let msg = ::protobuf::parse_from_bytes(msg.mdata.as_ref())?;
                 Ok(Event::Message((peer_id, msg)))
@@ -154,6 +164,38 @@ impl ConsensusNetworkSender {
         }
     }
 
+    /// Send a RequestBatch RPC request to remote peer `recipient`. Returns the
+    /// future `RespondBatch` returned by the remote peer.
+    ///
+    /// The rpc request can be canceled at any point by dropping the returned
+    /// future.
+    pub async fn request_batch(
+        &mut self,
+        recipient: PeerId,
+        req_msg: RequestBatch,
+        timeout: Duration,
+    ) -> Result<RespondBatch, RpcError> {
+        let protocol = ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL);
+        let mut req_msg_enum = ConsensusMsg::new();
+        req_msg_enum.set_request_batch(req_msg);
+        let mut res_msg_enum = rpc::utils::unary_rpc(
+            self.inner.clone(),
+            recipient,
+            protocol,
+            req_msg_enum,
+            timeout,
+        )
+        .await?;
+
+        if res_msg_enum.has_respond_batch() {
+            Ok(res_msg_enum.take_respond_batch())
+        } else {
+            Err(RpcError::InvalidRpcResponse)
+        }
+    }
+
+    /// Update the set of nodes eligible to join the network, e.g. when a new validator set
+    /// takes effect at an epoch boundary.
     pub async fn update_eligible_nodes(
         &mut self,
         validators: Vec<ValidatorPublicKeys>,
@@ -161,33 +203,38 @@ impl ConsensusNetworkSender {
         self.inner
             .send(NetworkRequest::UpdateEligibleNodes(
                 validators
-                    .into_iter()
-                    .map(|keys| {
-                        (
-                            *keys.account_address(),
-                            NetworkPublicKeys {
-                                identity_public_key: keys.network_identity_public_key().clone(),
-                                signing_public_key: keys.network_signing_public_key().clone(),
-                            },
-                        )
-                    })
+                    .iter()
+                    .map(|keys| (*keys.account_address(), NetworkPublicKeys::from(keys)))
                     .collect(),
             ))
             .await?;
         Ok(())
     }
+
+    /// Rotate the identity keypair of this node. The new public key is advertised to the other
+    /// peers through discovery before the node starts using it in Noise handshakes.
+    pub async fn rotate_identity_key(
+        &mut self,
+        keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send(NetworkRequest::RotateIdentityKey(keypair.0, keypair.1))
+            .await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{proto::Vote, protocols::rpc::InboundRpcRequest};
+    use crypto::{ed25519::compat, test_utils::TEST_SEED, x25519};
     use futures::{channel::oneshot, executor::block_on, future::try_join};
+    use rand::{rngs::StdRng, SeedableRng};
 
     fn new_test_vote() -> ConsensusMsg {
         let mut vote = Vote::new();
         vote.set_proposed_block_id(Bytes::new());
-        vote.set_executed_state_id(Bytes::new());
         vote.set_author(Bytes::new());
         vote.set_signature(Bytes::new());
 
@@ -327,4 +374,35 @@ mod tests {
         let (recv_res_msg, _) = block_on(try_join(f_res_msg, f_recv)).unwrap();
         assert_eq!(recv_res_msg, res_msg);
     }
+
+    // `ConsensusNetworkSender` should forward the network keys of the new validator set
+    #[test]
+    fn test_consensus_update_eligible_nodes() {
+        let (network_reqs_tx, mut network_reqs_rx) = channel::new_test(8);
+        let mut sender = ConsensusNetworkSender::new(network_reqs_tx);
+
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let (_, consensus_public_key) = compat::generate_keypair(&mut rng);
+        let (_, signing_public_key) = compat::generate_keypair(&mut rng);
+        let (_, identity_public_key) = x25519::compat::generate_keypair(&mut rng);
+        let peer_id = PeerId::random();
+        let validator = ValidatorPublicKeys::new(
+            peer_id,
+            consensus_public_key,
+            signing_public_key.clone(),
+            identity_public_key.clone(),
+        );
+
+        block_on(sender.update_eligible_nodes(vec![validator])).unwrap();
+
+        match block_on(network_reqs_rx.next()).unwrap() {
+            NetworkRequest::UpdateEligibleNodes(nodes) => {
+                assert_eq!(nodes.len(), 1);
+                let keys = nodes.get(&peer_id).unwrap();
+                assert_eq!(keys.signing_public_key, signing_public_key);
+                assert_eq!(keys.identity_public_key, identity_public_key);
+            }
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    }
 }
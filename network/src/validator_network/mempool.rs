@@ -60,7 +60,8 @@ impl MempoolNetworkEvents {
             .map::<_, fn(_) -> _>(|notification| match notification {
                 NetworkNotification::NewPeer(peer_id) => Ok(Event::NewPeer(peer_id)),
                 NetworkNotification::LostPeer(peer_id) => Ok(Event::LostPeer(peer_id)),
-                NetworkNotification::RecvRpc(_, _) => {
+                NetworkNotification::RecvRpc(_, _)
+                | NetworkNotification::RecvStreamingRpc(_, _) => {
                     unimplemented!("Mempool does not currently use RPC");
                 }
                 NetworkNotification::RecvMessage(peer_id, msg) => {
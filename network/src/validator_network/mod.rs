@@ -5,7 +5,7 @@
 
 pub use crate::protocols::rpc::error::RpcError;
 use bytes::Bytes;
-use futures::channel::oneshot;
+use futures::channel::{mpsc, oneshot};
 
 pub mod network_builder;
 
@@ -44,6 +44,11 @@ pub enum Event<TMessage> {
     /// serialized response `Bytes` over the `onshot::Sender`, where the network
     /// layer will handle sending the response over-the-wire.
     RpcRequest((PeerId, TMessage, oneshot::Sender<Result<Bytes, RpcError>>)),
+    /// New inbound streaming rpc request. The request is fulfilled by sending
+    /// the serialized response frames over the `mpsc::Sender`, and dropping the
+    /// sender after the last frame. The network layer will send the frames
+    /// over-the-wire as the remote peer grants credits for them.
+    StreamingRpcRequest((PeerId, TMessage, mpsc::Sender<Result<Bytes, RpcError>>)),
     /// Peer which we have a newly established connection with.
     NewPeer(PeerId),
     /// Peer with which we've lost our connection.
@@ -60,6 +65,10 @@ impl<TMessage: PartialEq> PartialEq for Event<TMessage> {
             (RpcRequest((pid1, msg1, _)), RpcRequest((pid2, msg2, _))) => {
                 pid1 == pid2 && msg1 == msg2
             }
+            // ignore mpsc::Sender in comparison
+            (StreamingRpcRequest((pid1, msg1, _)), StreamingRpcRequest((pid2, msg2, _))) => {
+                pid1 == pid2 && msg1 == msg2
+            }
             (NewPeer(pid1), NewPeer(pid2)) => pid1 == pid2,
             (LostPeer(pid1), LostPeer(pid2)) => pid1 == pid2,
             _ => false,
@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    common::NetworkPublicKeys,
+    common::{IdentityKeyRotations, NetworkPublicKeys},
     connectivity_manager::ConnectivityManager,
     counters,
     interface::{LibraNetworkProvider, NetworkProvider},
+    network_state::NetworkState,
     peer_manager::{PeerManager, PeerManagerRequestSender},
     proto::PeerInfo,
     protocols::{
@@ -26,6 +27,7 @@ use crypto::{
 };
 use futures::{compat::Compat01As03, FutureExt, StreamExt, TryFutureExt};
 use netcore::{multiplexing::StreamMultiplexer, transport::boxed::BoxedTransport};
+use noise::NoiseConfig;
 use parity_multiaddr::Multiaddr;
 use std::{
     collections::HashMap,
@@ -50,15 +52,18 @@ pub const PING_FAILURES_TOLERATED: u64 = 10;
 pub const MAX_CONCURRENT_NETWORK_REQS: u32 = 100;
 pub const MAX_CONCURRENT_NETWORK_NOTIFS: u32 = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 10 * 60 * 1000 /* 10 minutes */;
+pub const IDENTITY_KEY_ROTATION_OVERLAP_MS: u64 = 10 * 60 * 1000 /* 10 minutes */;
 
 /// The type of the transport layer, i.e., running on memory or TCP stream,
-/// with or without Noise encryption
+/// with or without Noise encryption, or on QUIC
+#[derive(Clone, Copy, Debug)]
 pub enum TransportType {
     Memory,
     MemoryNoise,
     Tcp,
     TcpNoise,
     PermissionlessTcpNoise,
+    Quic,
 }
 
 /// Build Network module with custom configuration values.
@@ -74,10 +79,13 @@ pub struct NetworkBuilder {
     advertised_address: Option<Multiaddr>,
     seed_peers: HashMap<PeerId, PeerInfo>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    key_rotations: Arc<RwLock<IdentityKeyRotations>>,
+    network_state: Arc<RwLock<NetworkState>>,
     transport: TransportType,
     channel_size: usize,
     direct_send_protocols: Vec<ProtocolId>,
     rpc_protocols: Vec<ProtocolId>,
+    streaming_rpc_protocols: Vec<ProtocolId>,
     discovery_interval_ms: u64,
     discovery_msg_timeout_ms: u64,
     ping_interval_ms: u64,
@@ -90,6 +98,7 @@ pub struct NetworkBuilder {
     max_concurrent_network_reqs: u32,
     max_concurrent_network_notifs: u32,
     max_connection_delay_ms: u64,
+    identity_key_rotation_overlap_ms: u64,
     signing_keys: Option<(Ed25519PrivateKey, Ed25519PublicKey)>,
     identity_keys: Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>,
 }
@@ -110,9 +119,12 @@ impl NetworkBuilder {
             advertised_address: None,
             seed_peers: HashMap::new(),
             trusted_peers: Arc::new(RwLock::new(HashMap::new())),
+            key_rotations: Arc::new(RwLock::new(IdentityKeyRotations::default())),
+            network_state: Arc::new(RwLock::new(NetworkState::default())),
             channel_size: NETWORK_CHANNEL_SIZE,
             direct_send_protocols: vec![],
             rpc_protocols: vec![],
+            streaming_rpc_protocols: vec![],
             transport: TransportType::Memory,
             discovery_interval_ms: DISCOVERY_INTERVAL_MS,
             discovery_msg_timeout_ms: DISOVERY_MSG_TIMEOUT_MS,
@@ -126,6 +138,7 @@ impl NetworkBuilder {
             max_concurrent_network_reqs: MAX_CONCURRENT_NETWORK_REQS,
             max_concurrent_network_notifs: MAX_CONCURRENT_NETWORK_NOTIFS,
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
+            identity_key_rotation_overlap_ms: IDENTITY_KEY_ROTATION_OVERLAP_MS,
             signing_keys: None,
             identity_keys: None,
         }
@@ -266,6 +279,16 @@ impl NetworkBuilder {
         self
     }
 
+    /// The duration (in milliseconds) for which both the old and the new identity key of a peer
+    /// are accepted after the peer rotated its identity key.
+    pub fn identity_key_rotation_overlap_ms(
+        &mut self,
+        identity_key_rotation_overlap_ms: u64,
+    ) -> &mut Self {
+        self.identity_key_rotation_overlap_ms = identity_key_rotation_overlap_ms;
+        self
+    }
+
     /// Set the size of the channels between different network actors.
     pub fn channel_size(&mut self, channel_size: usize) -> &mut Self {
         self.channel_size = channel_size;
@@ -284,10 +307,22 @@ impl NetworkBuilder {
         self
     }
 
+    /// Set the protocol IDs that RPC actor subscribes for streaming RPCs.
+    pub fn streaming_rpc_protocols(&mut self, protocols: Vec<ProtocolId>) -> &mut Self {
+        self.streaming_rpc_protocols = protocols;
+        self
+    }
+
+    /// Get a handle to the state of the network stack, which the network actors keep up to date.
+    pub fn network_state(&self) -> Arc<RwLock<NetworkState>> {
+        self.network_state.clone()
+    }
+
     fn supported_protocols(&self) -> Vec<ProtocolId> {
         self.direct_send_protocols
             .iter()
             .chain(&self.rpc_protocols)
+            .chain(&self.streaming_rpc_protocols)
             .chain(&vec![
                 ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
                 ProtocolId::from_static(PING_PROTOCOL_NAME),
@@ -302,23 +337,50 @@ impl NetworkBuilder {
         let identity = Identity::new(self.peer_id, self.supported_protocols(), self.role.clone());
         // Build network based on the transport type
         let own_identity_keys = self.identity_keys.take().expect("Identity keys not set");
+        let own_identity_public_key = own_identity_keys.1.clone();
+        let noise_config = Arc::new(NoiseConfig::new(own_identity_keys));
         let trusted_peers = self.trusted_peers.clone();
+        let key_rotations = self.key_rotations.clone();
         match self.transport {
-            TransportType::Memory => self.build_with_transport(build_memory_transport(identity)),
-            TransportType::MemoryNoise => self.build_with_transport(build_memory_noise_transport(
-                identity,
-                own_identity_keys,
-                trusted_peers,
-            )),
-            TransportType::Tcp => self.build_with_transport(build_tcp_transport(identity)),
-            TransportType::TcpNoise => self.build_with_transport(build_tcp_noise_transport(
-                identity,
-                own_identity_keys,
-                trusted_peers,
-            )),
+            TransportType::Memory => {
+                self.build_with_transport(build_memory_transport(identity), None)
+            }
+            TransportType::MemoryNoise => self.build_with_transport(
+                build_memory_noise_transport(
+                    identity,
+                    noise_config.clone(),
+                    trusted_peers,
+                    key_rotations,
+                ),
+                Some(noise_config),
+            ),
+            TransportType::Tcp => self.build_with_transport(build_tcp_transport(identity), None),
+            TransportType::TcpNoise => self.build_with_transport(
+                build_tcp_noise_transport(
+                    identity,
+                    noise_config.clone(),
+                    trusted_peers,
+                    key_rotations,
+                ),
+                Some(noise_config),
+            ),
             TransportType::PermissionlessTcpNoise => self.build_with_transport(
-                build_permissionless_tcp_noise_transport(identity, own_identity_keys),
+                build_permissionless_tcp_noise_transport(identity, noise_config.clone()),
+                Some(noise_config),
             ),
+            TransportType::Quic => {
+                let (signing_private_key, signing_public_key) =
+                    self.signing_keys.as_ref().expect("Signing keys not set");
+                let transport = build_quic_transport(
+                    identity,
+                    (signing_private_key, signing_public_key),
+                    &own_identity_public_key,
+                    trusted_peers,
+                    key_rotations,
+                    self.executor.clone(),
+                );
+                self.build_with_transport(transport, None)
+            }
         }
     }
 
@@ -330,6 +392,7 @@ impl NetworkBuilder {
             (Identity, impl StreamMultiplexer + 'static),
             impl ::std::error::Error + Send + Sync + 'static,
         >,
+        noise_config: Option<Arc<NoiseConfig>>,
     ) -> (Multiaddr, Box<dyn LibraNetworkProvider>) {
         // Setup communication channels.
         let (network_reqs_tx, network_reqs_rx) =
@@ -344,6 +407,8 @@ impl NetworkBuilder {
             self.channel_size,
             &counters::PENDING_CONNECTIVITY_MANAGER_REQUESTS,
         );
+        let (discovery_reqs_tx, discovery_reqs_rx) =
+            channel::new(self.channel_size, &counters::PENDING_DISCOVERY_REQUESTS);
         let (rpc_reqs_tx, rpc_reqs_rx) =
             channel::new(self.channel_size, &counters::PENDING_RPC_REQUESTS);
         let (rpc_net_notifs_tx, rpc_net_notifs_rx) =
@@ -383,6 +448,7 @@ impl NetworkBuilder {
         let rpc_handlers = self
             .rpc_protocols
             .iter()
+            .chain(&self.streaming_rpc_protocols)
             .map(|p| (p.clone(), pm_rpc_notifs_tx.clone()));
         let discovery_handler = vec![(
             ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
@@ -410,6 +476,7 @@ impl NetworkBuilder {
                 pm_ping_notifs_tx,
                 pm_discovery_notifs_tx,
             ],
+            self.network_state.clone(),
         );
         let listen_addr = peer_mgr.listen_addr().clone();
         self.executor
@@ -433,6 +500,7 @@ impl NetworkBuilder {
             pm_rpc_notifs_rx,
             PeerManagerRequestSender::new(pm_reqs_tx.clone()),
             rpc_net_notifs_tx,
+            self.streaming_rpc_protocols.clone(),
             Duration::from_millis(self.inbound_rpc_timeout_ms),
             self.max_concurrent_outbound_rpcs,
             self.max_concurrent_inbound_rpcs,
@@ -443,6 +511,7 @@ impl NetworkBuilder {
         // Initialize and start connectivity manager.
         let conn_mgr = ConnectivityManager::new(
             self.trusted_peers.clone(),
+            self.key_rotations.clone(),
             Compat01As03::new(Interval::new_interval(Duration::from_millis(
                 self.connectivity_check_interval_ms,
             )))
@@ -452,6 +521,7 @@ impl NetworkBuilder {
             conn_mgr_reqs_rx,
             ExponentialBackoff::from_millis(2).factor(1000 /* seconds */),
             self.max_connection_delay_ms,
+            self.network_state.clone(),
         );
         self.executor
             .spawn(conn_mgr.start().boxed().unit_error().compat());
@@ -477,7 +547,12 @@ impl NetworkBuilder {
             PeerManagerRequestSender::new(pm_reqs_tx.clone()),
             pm_discovery_notifs_rx,
             conn_mgr_reqs_tx.clone(),
+            discovery_reqs_rx,
+            noise_config,
+            self.key_rotations.clone(),
+            Duration::from_millis(self.identity_key_rotation_overlap_ms),
             Duration::from_millis(self.discovery_msg_timeout_ms),
+            self.network_state.clone(),
         );
         self.executor
             .spawn(discovery.start().boxed().unit_error().compat());
@@ -492,6 +567,7 @@ impl NetworkBuilder {
             pm_ping_notifs_rx,
             Duration::from_millis(self.ping_timeout_ms),
             self.ping_failures_tolerated,
+            self.network_state.clone(),
         );
         self.executor
             .spawn(health_checker.start().boxed().unit_error().compat());
@@ -503,6 +579,7 @@ impl NetworkBuilder {
             ds_reqs_tx,
             ds_net_notifs_rx,
             conn_mgr_reqs_tx.clone(),
+            discovery_reqs_tx,
             network_reqs_rx,
             network_reqs_tx,
             self.max_concurrent_network_reqs,
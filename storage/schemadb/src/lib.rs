@@ -203,6 +203,31 @@ impl DB {
         Ok(db)
     }
 
+    /// Opens an existing db at `path` in read-only mode with all the column families. Writes
+    /// fail, and the data written by a process holding the db open afterwards is not visible.
+    pub fn open_readonly<P: AsRef<Path>>(
+        path: P,
+        cf_opts_map: ColumnFamilyOptionsMap,
+    ) -> Result<Self> {
+        ensure!(
+            db_exists(path.as_ref()),
+            "No db found at {:?}",
+            path.as_ref()
+        );
+        let cfds: Vec<_> = cf_opts_map.into_iter().collect();
+        let inner = rocksdb::DB::open_cf_for_read_only(
+            DBOptions::new(),
+            path.as_ref().to_str().ok_or_else(|| {
+                format_err!("Path {:?} can not be converted to string.", path.as_ref())
+            })?,
+            cfds,
+            /* error_if_log_file_exist = */ false,
+        )
+        .map_err(convert_rocksdb_err)?;
+
+        Ok(DB { inner })
+    }
+
     fn open_cf<'a, P, T>(opts: DBOptions, path: P, cfds: Vec<T>) -> Result<DB>
     where
         P: AsRef<Path>,
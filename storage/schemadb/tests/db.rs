@@ -71,7 +71,11 @@ impl ValueCodec<TestSchema2> for TestField {
 }
 
 fn open_db(dir: &tempfile::TempDir) -> DB {
-    let cf_opts_map: ColumnFamilyOptionsMap = [
+    DB::open(&dir, cf_opts_map()).expect("Failed to open DB.")
+}
+
+fn cf_opts_map() -> ColumnFamilyOptionsMap {
+    [
         (DEFAULT_CF_NAME, ColumnFamilyOptions::default()),
         (
             TestSchema1::COLUMN_FAMILY_NAME,
@@ -84,8 +88,7 @@ fn open_db(dir: &tempfile::TempDir) -> DB {
     ]
     .iter()
     .cloned()
-    .collect();
-    DB::open(&dir, cf_opts_map).expect("Failed to open DB.")
+    .collect()
 }
 
 struct TestDB {
@@ -153,6 +156,24 @@ fn test_schema_put_get() {
     );
 }
 
+#[test]
+fn test_open_readonly() {
+    let tmpdir = tempfile::tempdir().expect("Failed to create temporary directory.");
+    assert!(DB::open_readonly(&tmpdir, cf_opts_map()).is_err());
+
+    {
+        let db = open_db(&tmpdir);
+        db.put::<TestSchema1>(&TestField(0), &TestField(1)).unwrap();
+    }
+
+    let db = DB::open_readonly(&tmpdir, cf_opts_map()).expect("Failed to open DB.");
+    assert_eq!(
+        db.get::<TestSchema1>(&TestField(0)).unwrap(),
+        Some(TestField(1)),
+    );
+    assert!(db.put::<TestSchema1>(&TestField(1), &TestField(1)).is_err());
+}
+
 fn collect_values<S: Schema>(db: &TestDB) -> Vec<(S::Key, S::Value)> {
     let mut iter = db
         .iter::<S>(Default::default())
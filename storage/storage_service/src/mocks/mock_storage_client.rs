@@ -12,7 +12,7 @@ use rand::{
     rngs::{OsRng, StdRng},
     Rng, SeedableRng,
 };
-use std::{collections::BTreeMap, pin::Pin};
+use std::{collections::BTreeMap, convert::TryFrom, pin::Pin};
 use storage_client::StorageRead;
 use storage_proto::StartupInfo;
 use types::{
@@ -25,13 +25,14 @@ use types::{
     proto::{
         account_state_blob::AccountStateWithProof,
         get_with_proof::{
-            GetAccountStateResponse, GetTransactionsResponse, RequestItem as ProtoRequestItem,
+            GetAccountStateResponse, GetAccountTransactionBySequenceNumberResponse,
+            GetTransactionsResponse, RequestItem as ProtoRequestItem,
             RequestItem_oneof_requested_items, ResponseItem as ProtoResponseItem,
             UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
         },
         ledger_info::LedgerInfoWithSignatures as ProtoLedgerInfoWithSignatures,
-        proof::AccumulatorProof,
-        transaction::TransactionListWithProof,
+        proof::{AccumulatorProof, SignedTransactionProof},
+        transaction::{SignedTransactionWithProof, TransactionListWithProof},
         transaction_info::TransactionInfo,
     },
     test_helpers::transaction_test_helpers::get_test_signed_txn,
@@ -205,8 +206,22 @@ fn get_mock_response_item(request_item: &ProtoRequestItem) -> Result<ProtoRespon
                 resp.set_account_state_with_proof(account_state_with_proof);
                 response_item.set_get_account_state_response(resp);
             }
-            RequestItem_oneof_requested_items::get_account_transaction_by_sequence_number_request(_request) => {
-                unimplemented!();
+            RequestItem_oneof_requested_items::get_account_transaction_by_sequence_number_request(request) => {
+                // Only the first transaction of every account is committed.
+                let mut resp = GetAccountTransactionBySequenceNumberResponse::new();
+                if request.sequence_number == 0 {
+                    let sender = AccountAddress::try_from(request.get_account())?;
+                    let (mut txns, mut infos) = get_mock_txn_data(sender, 0, 0);
+                    let mut proof = SignedTransactionProof::new();
+                    proof.set_ledger_info_to_transaction_info_proof(get_accumulator_proof());
+                    proof.set_transaction_info(infos.remove(0));
+                    let mut signed_transaction_with_proof = SignedTransactionWithProof::new();
+                    signed_transaction_with_proof.set_version(0);
+                    signed_transaction_with_proof.set_signed_transaction(txns.remove(0));
+                    signed_transaction_with_proof.set_proof(proof);
+                    resp.set_signed_transaction_with_proof(signed_transaction_with_proof);
+                }
+                response_item.set_get_account_transaction_by_sequence_number_response(resp);
             }
             RequestItem_oneof_requested_items::get_events_by_event_access_path_request(_request) => {
                 unimplemented!();
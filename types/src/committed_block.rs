@@ -0,0 +1,51 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The blocks committed by consensus, streamed to the external subscribers (e.g. the clients of
+//! admission control following the committed blocks).
+
+use crate::{
+    account_address::AccountAddress, crypto_proxies::LedgerInfoWithSignatures,
+    transaction::SignedTransaction,
+};
+use crypto::HashValue;
+use futures::Stream;
+use std::pin::Pin;
+
+pub use channel::publisher::SubscriberLagged;
+
+/// A block committed by consensus.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommittedBlock {
+    /// Id of the block.
+    pub block_id: HashValue,
+    /// Round of the block.
+    pub round: u64,
+    /// The validator that proposed the block, None for the NIL blocks.
+    pub proposer: Option<AccountAddress>,
+    /// Transactions of the block.
+    pub transactions: Vec<SignedTransaction>,
+    /// Proof of the commit: it certifies either this block or a descendant committed at the same
+    /// time.
+    pub ledger_info_with_sigs: LedgerInfoWithSignatures,
+}
+
+/// Notification about the commits, in the order of the commits.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CommitNotification {
+    /// A block committed by consensus.
+    Block(CommittedBlock),
+    /// The blocks committed up to the given LedgerInfo are not part of the stream, e.g. because
+    /// the node synchronized its state from its peers instead of committing them itself. The
+    /// subscriber has to catch up with the ledger by other means.
+    Gap(LedgerInfoWithSignatures),
+}
+
+/// Source of the commit notifications.
+pub trait CommittedBlockSource: Send + Sync {
+    /// Returns the stream of the commit notifications from now on. The stream ends with an error
+    /// in case the subscriber does not keep up with the commits.
+    fn subscribe(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = std::result::Result<CommitNotification, SubscriberLagged>> + Send>>;
+}
@@ -6,6 +6,7 @@
 use crate::{
     account_address::AccountAddress,
     transaction::Version,
+    validator_set::ValidatorSet,
     validator_verifier::{ValidatorVerifier, VerifyError},
 };
 use canonical_serialization::{CanonicalSerialize, CanonicalSerializer, SimpleSerializer};
@@ -40,9 +41,8 @@ use std::{
 /// LedgerInfo with the `version` being the latest version that will be committed if B gets 2f+1
 /// votes. It sets `consensus_data_hash` to represent B so that if those 2f+1 votes are gathered a
 /// QC is formed on B.
-#[derive(Clone, Debug, Eq, PartialEq, IntoProto, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(any(test, feature = "testing"), derive(Arbitrary))]
-#[ProtoType(crate::proto::ledger_info::LedgerInfo)]
 pub struct LedgerInfo {
     /// The version of latest transaction in the ledger.
     version: Version,
@@ -67,6 +67,9 @@ pub struct LedgerInfo {
     // they can be certain that their transaction will never be included in a block in the future
     // (assuming that their transaction has not yet been included)
     timestamp_usecs: u64,
+
+    /// If set, the validator set of the next epoch, which starts right after this version.
+    next_validator_set: Option<ValidatorSet>,
 }
 
 impl Display for LedgerInfo {
@@ -100,6 +103,7 @@ impl LedgerInfo {
             consensus_block_id,
             epoch_num,
             timestamp_usecs,
+            next_validator_set: None,
         }
     }
 
@@ -134,6 +138,15 @@ impl LedgerInfo {
         self.timestamp_usecs
     }
 
+    /// Returns the validator set of the next epoch in case this `LedgerInfo` ends the current one.
+    pub fn next_validator_set(&self) -> Option<&ValidatorSet> {
+        self.next_validator_set.as_ref()
+    }
+
+    pub fn set_next_validator_set(&mut self, next_validator_set: Option<ValidatorSet>) {
+        self.next_validator_set = next_validator_set;
+    }
+
     /// A ledger info is nominal if it's not certifying any real version.
     pub fn is_zero(&self) -> bool {
         self.version == 0
@@ -143,15 +156,39 @@ impl LedgerInfo {
 impl FromProto for LedgerInfo {
     type ProtoType = crate::proto::ledger_info::LedgerInfo;
 
-    fn from_proto(proto: Self::ProtoType) -> Result<Self> {
-        Ok(LedgerInfo::new(
+    fn from_proto(mut proto: Self::ProtoType) -> Result<Self> {
+        let mut ledger_info = LedgerInfo::new(
             proto.get_version(),
             HashValue::from_slice(proto.get_transaction_accumulator_hash())?,
             HashValue::from_slice(proto.get_consensus_data_hash())?,
             HashValue::from_slice(proto.get_consensus_block_id())?,
             proto.get_epoch_num(),
             proto.get_timestamp_usecs(),
-        ))
+        );
+        if proto.has_next_validator_set() {
+            ledger_info.set_next_validator_set(Some(ValidatorSet::from_proto(
+                proto.take_next_validator_set(),
+            )?));
+        }
+        Ok(ledger_info)
+    }
+}
+
+impl IntoProto for LedgerInfo {
+    type ProtoType = crate::proto::ledger_info::LedgerInfo;
+
+    fn into_proto(self) -> Self::ProtoType {
+        let mut proto = Self::ProtoType::new();
+        proto.set_version(self.version);
+        proto.set_transaction_accumulator_hash(self.transaction_accumulator_hash.to_vec());
+        proto.set_consensus_data_hash(self.consensus_data_hash.to_vec());
+        proto.set_consensus_block_id(self.consensus_block_id.to_vec());
+        proto.set_epoch_num(self.epoch_num);
+        proto.set_timestamp_usecs(self.timestamp_usecs);
+        if let Some(next_validator_set) = self.next_validator_set {
+            proto.set_next_validator_set(next_validator_set.into_proto());
+        }
+        proto
     }
 }
 
@@ -164,6 +201,11 @@ impl CanonicalSerialize for LedgerInfo {
             .encode_bytes(self.consensus_block_id.as_ref())?
             .encode_u64(self.epoch_num)?
             .encode_u64(self.timestamp_usecs)?;
+        // Only the ledger infos ending an epoch carry the next validator set, the encoding of the
+        // others doesn't change.
+        if let Some(next_validator_set) = &self.next_validator_set {
+            serializer.encode_struct(next_validator_set)?;
+        }
         Ok(())
     }
 }
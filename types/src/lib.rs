@@ -6,6 +6,7 @@ pub mod account_address;
 pub mod account_config;
 pub mod account_state_blob;
 pub mod byte_array;
+pub mod committed_block;
 pub mod contract_event;
 pub mod crypto_proxies;
 pub mod event;
@@ -1,8 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::account_address::AccountAddress;
-use crypto::*;
+use crate::{account_address::AccountAddress, validator_set::ValidatorSet};
+use crypto::{ed25519::*, *};
 use failure::prelude::*;
 use logger::prelude::*;
 use std::collections::HashMap;
@@ -229,6 +229,19 @@ impl<PublicKey: VerifyingKey> ValidatorVerifier<PublicKey> {
     }
 }
 
+impl From<&ValidatorSet> for ValidatorVerifier<Ed25519PublicKey> {
+    /// Builds a verifier for the consensus keys of a validator set, with the default quorum size.
+    fn from(validator_set: &ValidatorSet) -> Self {
+        ValidatorVerifier::new(
+            validator_set
+                .payload()
+                .iter()
+                .map(|keys| (*keys.account_address(), keys.consensus_public_key().clone()))
+                .collect(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{